@@ -0,0 +1,85 @@
+/*
+ * C API of shellcoder.
+ *
+ * Build the shared library using:
+ *
+ *     cargo rustc --release --features capi --crate-type cdylib
+ *
+ * All functions returning an `int` return `SHELLCODER_OK` on success, or a
+ * negative error code.
+ */
+
+#ifndef SHELLCODER_H
+#define SHELLCODER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Success. */
+#define SHELLCODER_OK 0
+
+/* A null pointer was given. */
+#define SHELLCODER_ERR_NULL_POINTER (-1)
+
+/* An invalid argument was given, e.g. an unsupported integer width. */
+#define SHELLCODER_ERR_INVALID_ARGUMENT (-2)
+
+/* The output buffer is too small, or the maximum length is exceeded. */
+#define SHELLCODER_ERR_BUFFER_TOO_SMALL (-3)
+
+/* An integer overflowed. */
+#define SHELLCODER_ERR_INTEGER_OVERFLOW (-4)
+
+/* Any other error. */
+#define SHELLCODER_ERR_OTHER (-5)
+
+/* A bad byte has been found. */
+#define SHELLCODER_ERR_BAD_BYTE (-6)
+
+/* A shellcoder backed by a dynamic buffer. */
+typedef struct shellcoder shellcoder_t;
+
+/* Instantiates a new shellcoder, to be freed using `shellcoder_free`. */
+shellcoder_t *shellcoder_new(void);
+
+/* Instantiates a new shellcoder with a maximum length in bytes. */
+shellcoder_t *shellcoder_new_with_max_len(size_t max_len);
+
+/* Frees a shellcoder. Null pointers are ignored. */
+void shellcoder_free(shellcoder_t *handle);
+
+/* Pushes an integer in little endian. `width` is 1, 2, 4 or 8. */
+int shellcoder_int_le(shellcoder_t *handle, uint64_t value, size_t width);
+
+/* Pushes an integer in big endian. `width` is 1, 2, 4 or 8. */
+int shellcoder_int_be(shellcoder_t *handle, uint64_t value, size_t width);
+
+/* Fills with `len` bytes of value `chr`. */
+int shellcoder_fill(shellcoder_t *handle, size_t len, uint8_t chr);
+
+/* Advances the cursor by `n` bytes, filling the gap with zeroes. */
+int shellcoder_advance(shellcoder_t *handle, size_t n);
+
+/* Pushes a buffer of `len` bytes. */
+int shellcoder_push_buffer(shellcoder_t *handle, const uint8_t *buffer, size_t len);
+
+/* Returns the number of bytes written so far. */
+size_t shellcoder_len(const shellcoder_t *handle);
+
+/*
+ * Copies the payload into a buffer of `out_len` bytes.
+ * If `written` is not null, it receives the size of the payload, even if the
+ * buffer is too small.
+ */
+int shellcoder_finalize(const shellcoder_t *handle, uint8_t *out, size_t out_len,
+                        size_t *written);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SHELLCODER_H */
@@ -0,0 +1,129 @@
+//! Procedural macros for [shellcoder](https://docs.rs/shellcoder).
+//!
+//! This crate is re-exported by `shellcoder` through its `macros` feature.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Error, Ident, LitByte, LitByteStr, LitInt, LitStr, Result, Token};
+
+/// A payload, evaluated at compile time.
+struct Payload(Vec<u8>);
+
+impl Payload {
+    /// Parses an integer statement, i.e. `<type> <value>`.
+    fn parse_int(input: ParseStream, little_endian: bool) -> Result<Vec<u8>> {
+        let ty: Ident = input.parse()?;
+        let lit: LitInt = input.parse()?;
+        let mut bytes = match ty.to_string().as_str() {
+            "u8" => lit.base10_parse::<u8>()?.to_be_bytes().to_vec(),
+            "u16" => lit.base10_parse::<u16>()?.to_be_bytes().to_vec(),
+            "u32" => lit.base10_parse::<u32>()?.to_be_bytes().to_vec(),
+            "u64" => lit.base10_parse::<u64>()?.to_be_bytes().to_vec(),
+            _ => {
+                return Err(Error::new(
+                    ty.span(),
+                    "expected one of `u8`, `u16`, `u32` or `u64`",
+                ))
+            }
+        };
+        if little_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+}
+
+impl Parse for Payload {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut payload = Vec::new();
+        while !input.is_empty() {
+            let statement: Ident = input.parse()?;
+            match statement.to_string().as_str() {
+                "le" => payload.extend(Self::parse_int(input, true)?),
+                "be" => payload.extend(Self::parse_int(input, false)?),
+                "advance" => {
+                    let n = input.parse::<LitInt>()?.base10_parse::<usize>()?;
+                    payload.resize(
+                        payload
+                            .len()
+                            .checked_add(n)
+                            .ok_or_else(|| Error::new(statement.span(), "payload is too large"))?,
+                        0,
+                    );
+                }
+                "fill" => {
+                    let len = input.parse::<LitInt>()?.base10_parse::<usize>()?;
+                    input.parse::<Token![,]>()?;
+                    let chr = if input.peek(LitByte) {
+                        input.parse::<LitByte>()?.value()
+                    } else {
+                        input.parse::<LitInt>()?.base10_parse::<u8>()?
+                    };
+                    payload.resize(
+                        payload
+                            .len()
+                            .checked_add(len)
+                            .ok_or_else(|| Error::new(statement.span(), "payload is too large"))?,
+                        chr,
+                    );
+                }
+                "bytes" => {
+                    if input.peek(LitStr) {
+                        payload.extend(input.parse::<LitStr>()?.value().into_bytes());
+                    } else {
+                        payload.extend(input.parse::<LitByteStr>()?.value());
+                    }
+                }
+                _ => {
+                    return Err(Error::new(
+                        statement.span(),
+                        "expected one of `le`, `be`, `advance`, `fill` or `bytes`",
+                    ))
+                }
+            }
+            input.parse::<Token![;]>()?;
+        }
+        Ok(Self(payload))
+    }
+}
+
+/// Builds a payload at compile time.
+///
+/// The macro accepts the same statements as `shellcoder::shellcode!`,
+/// except `op`, with literal values only. It expands to an array
+/// `[u8; N]`, thus it can be used to initialize constants and statics.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder_macros::payload;
+///
+/// const PAYLOAD: [u8; 14] = payload! {
+///     le u32 0xdeadbeef;
+///     advance 2;
+///     bytes b"/bin";
+///     fill 2, b'A';
+///     be u16 0x4243;
+/// };
+/// assert_eq!(&PAYLOAD, b"\xef\xbe\xad\xde\0\0/binAABC");
+/// ```
+///
+/// Integers that do not fit the given type are rejected.
+///
+/// ```compile_fail
+/// use shellcoder_macros::payload;
+///
+/// const PAYLOAD: [u8; 1] = payload! {
+///     le u8 0x100;
+/// };
+/// ```
+#[proc_macro]
+#[inline]
+pub fn payload(input: TokenStream) -> TokenStream {
+    let Payload(payload) = parse_macro_input!(input as Payload);
+    quote! {
+        [#(#payload),*]
+    }
+    .into()
+}
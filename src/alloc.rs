@@ -1,8 +1,45 @@
-//! Implementation of [`crate::Shellcoder`] using a dynamic buffer.
+//! Implementations of [`crate::Shellcoder`] using dynamic buffers.
 
+use alloc_crate::borrow::Cow;
+use alloc_crate::collections::BTreeMap;
 use core::borrow::Borrow;
+use core::ops::Range;
+use core::{iter, mem};
+use std::io::{self, IoSlice};
 
+use crate::analysis::{self, Digest, Occurrences};
+use crate::arch::Arch;
+use crate::bad_bytes::BadBytes;
+use crate::config::{Config, DebugPlacement, DebugStub, PointerWidth};
+use crate::ops::{Endianness, WriteCow};
 use crate::prelude::*;
+use crate::trace;
+use crate::transform::{Reverse, SwapPairs, Transform};
+use crate::IntoOp;
+use crate::Snapshot;
+
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// What to do when an operation replacing a region is shorter than the
+/// region, see [`Shellcoder::replace`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ReplacePolicy {
+    /// The operation must have exactly the length of the region.
+    Exact,
+
+    /// The rest of the region is filled with a byte.
+    PadWith(u8),
+}
+
+impl Default for ReplacePolicy {
+    /// Exact, so that nothing is silently padded.
+    #[inline]
+    fn default() -> Self {
+        Self::Exact
+    }
+}
 
 /// A shellcoder backed by a dynamic buffer.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
@@ -11,8 +48,16 @@ pub struct Shellcoder {
     /// Buffer containing the shellcode.
     stream: Vec<u8>,
 
-    /// A maximum length in bytes.
-    max_len: Option<usize>,
+    /// Configuration.
+    config: Config,
+
+    /// Number of operations that have been pushed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    n_ops: usize,
+
+    /// Regions of placeholders, by name, see [`Self::patch_placeholder`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    placeholders: BTreeMap<String, Range<usize>>,
 }
 
 impl Shellcoder {
@@ -27,18 +72,543 @@ impl Shellcoder {
     #[inline]
     #[must_use]
     pub fn new_with_max_len(max_len: usize) -> Self {
-        Self {
-            max_len: Some(max_len),
-            ..Self::default()
-        }
+        let mut shellcoder = Self::default();
+        shellcoder.config = shellcoder.config.with_max_len(Some(max_len));
+        shellcoder
     }
 
-    /// Consumes the [`Shellcoder`] by returning the underlying buffer.
+    /// Sets the configuration, see [`crate::config::ShellcoderBuilder`].
+    #[inline]
+    #[must_use]
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the bytes that must not be written.
+    ///
+    /// Pushing an operation that writes a bad byte fails with
+    /// [`Error::BadByteFound`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::error::Error;
+    /// use shellcoder::Shellcoder as _;
+    ///
+    /// let mut shellcoder = Shellcoder::new().with_bad_bytes(b"\0".iter().copied().collect());
+    /// let error = shellcoder.push_buffer(b"AA")
+    ///     .and_then(|shellcoder| shellcoder.int_le(0x4141_u16))
+    ///     .and_then(|shellcoder| shellcoder.int_le(0x41_u16))
+    ///     .unwrap_err();
+    /// assert!(matches!(error.root(), Error::BadByteFound { offset: 5, byte: 0 }));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_bad_bytes(mut self, bad_bytes: BadBytes) -> Self {
+        self.config = self.config.with_bad_bytes(bad_bytes);
+        self
+    }
+
+    /// Sets the default endianness, see [`crate::Shellcoder::endianness`].
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.config = self.config.with_endianness(endianness);
+        self
+    }
+
+    /// Returns the configuration.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Returns the shellcode.
     #[inline]
     #[must_use]
     pub fn as_bytes(&self) -> &[u8] {
         self.stream.as_ref()
     }
+
+    /// Returns the current state of the shellcoder, see [`Self::restore`].
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.stream.len(), self.n_ops)
+    }
+
+    /// Rewinds the shellcoder to a snapshot.
+    ///
+    /// Bytes written after the snapshot are discarded. Transforms of
+    /// regions preceding the snapshot, e.g. [`Self::reverse`], are not
+    /// undone.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::UnexpectedPosition`]: the shellcoder has been rewound past
+    /// the snapshot, e.g. by restoring an earlier one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.fill(4, b'A')?;
+    /// let prefix = shellcoder.snapshot();
+    /// let candidates = (0..=u8::MAX)
+    ///     .map(|byte| {
+    ///         shellcoder.restore(prefix)?.push(&[byte])?;
+    ///         Ok(shellcoder.as_bytes().to_vec())
+    ///     })
+    ///     .collect::<Result<Vec<_>>>()?;
+    /// assert_eq!(candidates[0x42], b"AAAAB");
+    /// assert_eq!(shellcoder.restore(prefix)?.as_bytes(), b"AAAA");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<&mut Self> {
+        snapshot.check_restore(self.stream.len())?;
+        self.truncate(snapshot.len());
+        self.n_ops = snapshot.n_ops();
+        Ok(self)
+    }
+
+    /// Returns an iterator over the offsets of a subsequence in the
+    /// shellcode, see [`crate::analysis::occurrences`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.fill(4, b'A')?.push(b"w00tw00t")?;
+    /// assert!(shellcoder.find(b"w00tw00t").eq([4]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn find<'needle>(&self, needle: &'needle [u8]) -> Occurrences<'_, 'needle> {
+        analysis::occurrences(&self.stream, needle)
+    }
+
+    /// Returns the hashes of the shellcode, see [`crate::analysis::digest`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.push(b"abc")?;
+    /// assert_eq!(shellcoder.digest().crc32(), 0x3524_41c2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn digest(&self) -> Digest {
+        analysis::digest(&self.stream)
+    }
+
+    /// Inserts an operation at `offset`, shifting the bytes that follow,
+    /// e.g. when a header turns out to be needed once most of the payload
+    /// has been written. On failure, the buffer is left untouched.
+    ///
+    /// The operation is padded and checked against the configuration as if
+    /// it was pushed at `offset`. Placeholders that follow are moved, and a
+    /// placeholder that the insertion splits is dropped. Other offsets
+    /// recorded so far are not fixed up, unless the operations are recorded
+    /// by a [`crate::plan::Recorder`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::OutputBufferTooSmall`]: `offset` is past the end of the
+    ///    shellcode.
+    ///  - [`Error::MaxLengthExceeded`]: the shellcode would exceed the
+    ///    maximum length.
+    ///  - Any error returned by the operation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::ops::WriteInteger;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.push(b"body")?;
+    /// shellcoder.insert_at(0, WriteInteger::new_be(4_u16))?;
+    /// assert_eq!(shellcoder.as_bytes(), b"\0\x04body");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn insert_at(&mut self, offset: usize, op: impl Op) -> Result<&mut Self> {
+        let index = self.n_ops;
+        let result = if offset > self.stream.len() {
+            Err(Error::buffer_too_small(offset))
+        } else {
+            op.check_at(self.config.position(offset))
+                .and_then(|()| self.config.render(&op, offset))
+                .and_then(|bytes| {
+                    self.config.check_len(self.stream.len(), bytes.len())?;
+                    Ok(bytes)
+                })
+        };
+        let bytes = result.map_err(|error| {
+            trace::failed(&op, index, offset, &error);
+            error.with_op(&op, index, offset)
+        })?;
+        trace::applied(&op, index, offset, bytes.len());
+        self.splice_placeholders(offset..offset, bytes.len());
+        self.stream.splice(offset..offset, bytes);
+        self.n_ops = index.saturating_add(1);
+        Ok(self)
+    }
+
+    /// Shortens the shellcode to `len` bytes. Nothing happens if the
+    /// shellcode is already shorter.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.push(b"/bin/sh")?.truncate(4).push(b"ash")?;
+    /// assert_eq!(shellcoder.as_bytes(), b"/binash");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn truncate(&mut self, len: usize) -> &mut Self {
+        self.stream.truncate(len);
+        self.placeholders.retain(|_name, region| region.end <= len);
+        self
+    }
+
+    /// Removes a region of the shellcode, shifting the bytes that follow.
+    ///
+    /// Placeholders that follow are moved, and placeholders that overlap the
+    /// region are dropped. Other offsets recorded so far are not fixed up.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`]: the region is out of the shellcode.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.push(b"AAAA\xcc\xccBBBB")?.remove_range(4..6)?;
+    /// assert_eq!(shellcoder.as_bytes(), b"AAAABBBB");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn remove_range(&mut self, range: Range<usize>) -> Result<&mut Self> {
+        self.region_mut(range.clone())?;
+        self.splice_placeholders(range.clone(), 0);
+        self.stream.drain(range);
+        Ok(self)
+    }
+
+    /// Fixes up the regions of placeholders once the bytes of `removed`
+    /// have been replaced by `inserted` bytes.
+    ///
+    /// Placeholders that follow are moved, and placeholders that overlap
+    /// `removed`, or that an insertion splits, are dropped.
+    fn splice_placeholders(&mut self, removed: Range<usize>, inserted: usize) {
+        let len = removed.len();
+        self.placeholders.retain(|_name, region| {
+            if region.end <= removed.start {
+                true
+            } else if region.start >= removed.end {
+                region.start = region.start.saturating_sub(len).saturating_add(inserted);
+                region.end = region.end.saturating_sub(len).saturating_add(inserted);
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Overwrites a region of the shellcode with an operation, without
+    /// shifting the bytes that follow, e.g. to patch a gadget address in a
+    /// chain that has already been laid out. On failure, the buffer is left
+    /// untouched.
+    ///
+    /// The operation is checked against the bad bytes. If it is shorter
+    /// than the region, `policy` tells whether to pad it or fail.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::OutputBufferTooSmall`]: the region is out of the
+    ///    shellcode.
+    ///  - [`Error::UnexpectedPosition`]: the operation does not end at the
+    ///    end of the region, and cannot be padded.
+    ///  - [`Error::BadByteFound`]: the operation writes a bad byte.
+    ///  - Any error returned by the operation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::{ReplacePolicy, Shellcoder};
+    /// use shellcoder::ops::WriteInteger;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.fill(8, b'A')?.push(b"tail")?;
+    /// shellcoder.replace(0..8, WriteInteger::new_le(0x401000_u64), ReplacePolicy::Exact)?;
+    /// assert_eq!(shellcoder.as_bytes(), b"\0\x10\x40\0\0\0\0\0tail");
+    /// assert!(shellcoder
+    ///     .replace(0..8, WriteInteger::new_le(0x401000_u32), ReplacePolicy::Exact)
+    ///     .is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn replace(
+        &mut self,
+        range: Range<usize>,
+        op: impl Op,
+        policy: ReplacePolicy,
+    ) -> Result<&mut Self> {
+        let index = self.n_ops;
+        let start = range.start;
+        let position = self.config.position(start);
+        let result = self.region_mut(range.clone()).and_then(|region| {
+            let len = region.len();
+            let mut bytes = Vec::with_capacity(len);
+            let n = op
+                .check_at(position)
+                .and_then(|()| op.write_to_io(&mut bytes))?;
+            match policy {
+                ReplacePolicy::PadWith(byte) if n <= len => bytes.resize(len, byte),
+                ReplacePolicy::Exact | ReplacePolicy::PadWith(_) if n == len => {}
+                ReplacePolicy::Exact | ReplacePolicy::PadWith(_) => {
+                    return Err(Error::UnexpectedPosition {
+                        expected: range.end,
+                        actual: start.saturating_add(n),
+                    });
+                }
+            }
+            Ok(bytes)
+        });
+        let bytes = result
+            .and_then(|bytes| {
+                self.config.bad_bytes().check(&bytes, start)?;
+                Ok(bytes)
+            })
+            .map_err(|error| {
+                trace::failed(&op, index, start, &error);
+                error.with_op(&op, index, start)
+            })?;
+        trace::applied(&op, index, start, bytes.len());
+        self.region_mut(range)?.copy_from_slice(&bytes);
+        self.n_ops = index.saturating_add(1);
+        Ok(self)
+    }
+
+    /// Returns the region of a placeholder, see [`crate::ops::Placeholder`].
+    #[inline]
+    #[must_use]
+    pub fn placeholder(&self, name: &str) -> Option<Range<usize>> {
+        self.placeholders.get(name).cloned()
+    }
+
+    /// Overwrites a placeholder with a value, e.g. a stack canary once it
+    /// has leaked, so that the layout of the payload can be final before
+    /// the values it depends on are known. See [`crate::ops::Placeholder`].
+    ///
+    /// Integers are encoded using the default endianness, and the value
+    /// must have exactly the length of the placeholder. Placeholders that
+    /// are pushed again with the same name are patched at their latest
+    /// offset, which follows [`Self::insert_at`] and [`Self::remove_range`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::UnknownSymbol`]: no placeholder has this name.
+    ///  - Any error returned by [`Self::replace`], e.g. a value that does
+    ///    not have the length of the placeholder.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::config::PointerWidth;
+    /// use shellcoder::ops::Placeholder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder
+    ///     .fill(8, b'A')?
+    ///     .add(Placeholder::canary(PointerWidth::Bits64))?
+    ///     .ptr(0x40_1136)?;
+    ///
+    /// // Once the canary has leaked.
+    /// shellcoder.patch_placeholder("canary", 0x6f4e_9a21_c3d8_b700_u64)?;
+    /// assert_eq!(&shellcoder.as_bytes()[8..16], b"\0\xb7\xd8\xc3\x21\x9a\x4e\x6f");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn patch_placeholder(&mut self, name: &str, value: impl IntoOp) -> Result<&mut Self> {
+        let region = self
+            .placeholder(name)
+            .ok_or_else(|| Error::UnknownSymbol(name.to_owned()))?;
+        let op = value.into_op(self.config.endianness());
+        self.replace(region, op, ReplacePolicy::Exact)
+    }
+
+    /// Transforms a region of the shellcode, right away. See
+    /// [`crate::transform::Transforms`] to transform regions once the
+    /// shellcode is complete.
+    ///
+    /// The transformed region is checked against the bad bytes of the
+    /// configuration. On failure, the shellcode is left untouched.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::OutputBufferTooSmall`]: the region is out of the
+    ///    shellcode.
+    ///  - [`Error::BadByteFound`]: the transformed region contains a bad
+    ///    byte.
+    #[inline]
+    pub fn transform(
+        &mut self,
+        range: Range<usize>,
+        transform: &(impl Transform + ?Sized),
+    ) -> Result<&mut Self> {
+        let mut bytes = self.region_mut(range.clone())?.to_vec();
+        transform.apply(&mut bytes);
+        self.config.bad_bytes().check(&bytes, range.start)?;
+        self.region_mut(range)?.copy_from_slice(&bytes);
+        Ok(self)
+    }
+
+    /// Reverses the order of the bytes of a region of the shellcode, see
+    /// [`Reverse`].
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`]: the region is out of the shellcode.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.push(b"AA")?;
+    /// let start = shellcoder.len();
+    /// shellcoder.push(b"/bin/sh")?;
+    /// shellcoder.reverse(start..shellcoder.len())?;
+    /// assert_eq!(shellcoder.as_bytes(), b"AAhs/nib/");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn reverse(&mut self, range: Range<usize>) -> Result<&mut Self> {
+        self.transform(range, &Reverse)
+    }
+
+    /// Swaps the bytes of each pair of a region of the shellcode, see
+    /// [`SwapPairs`].
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`]: the region is out of the shellcode.
+    #[inline]
+    pub fn swap_pairs(&mut self, range: Range<usize>) -> Result<&mut Self> {
+        self.transform(range, &SwapPairs)
+    }
+
+    /// Returns a region of the shellcode.
+    fn region_mut(&mut self, range: Range<usize>) -> Result<&mut [u8]> {
+        let end = range.end;
+        self.stream
+            .get_mut(range)
+            .ok_or_else(|| Error::buffer_too_small(end))
+    }
+
+    /// Consumes the [`Shellcoder`] by returning the underlying buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut shellcoder = Shellcoder::new();
+    /// shellcoder.fill(2, b'A')?.int_be(0x4243_u16)?;
+    /// assert_eq!(shellcoder.into_bytes(), b"AABC");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        mem::take(&mut self.stream)
+    }
+
+    /// Takes the underlying buffer, leaving the [`Shellcoder`] empty.
+    ///
+    /// The configuration is kept, so that the shellcoder can be reused to
+    /// build another shellcode.
+    #[inline]
+    #[must_use]
+    pub fn take(&mut self) -> Vec<u8> {
+        self.n_ops = 0;
+        self.placeholders.clear();
+        mem::take(&mut self.stream)
+    }
+
+    /// Overwrites the shellcode with zeroes and empties the shellcoder, e.g.
+    /// once a payload containing credentials has been delivered.
+    ///
+    /// Copies of the shellcode left behind when the buffer has been
+    /// reallocated are not overwritten, see [`crate::Shellcoder::reserve`].
+    #[cfg(feature = "zeroize")]
+    #[inline]
+    pub fn clear_sensitive(&mut self) {
+        self.zeroize();
+    }
 }
 
 impl crate::Shellcoder for Shellcoder {
@@ -47,15 +617,806 @@ impl crate::Shellcoder for Shellcoder {
     where
         O: Op,
     {
-        op.borrow()
-            .write_to_io(&mut self.stream)
-            .map_err(Error::from)
-            .and_then(|_| {
-                if self.max_len.map(|max_len| max_len < self.stream.len()) == Some(true) {
-                    Err(Error::buffer_too_small(self.stream.len()))
-                } else {
-                    Ok(self)
+        self.add_counted(op).map(|(_, shellcoder)| shellcoder)
+    }
+
+    /// Pushes an operation, followed by its padding if an alignment is
+    /// configured. On failure, the buffer is left untouched.
+    #[inline]
+    fn add_counted<O>(&mut self, op: impl Borrow<O>) -> Result<(usize, &mut Self)>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let start = self.stream.len();
+        let index = self.n_ops;
+        let mut stub = [0; 8];
+        let result = reserve_for(&self.config, &mut self.stream, start, operation.size())
+            .and_then(|()| self.config.write_entry_stub(start, &mut stub))
+            .and_then(|n| {
+                self.stream
+                    .extend_from_slice(stub.get(..n).unwrap_or_default());
+                let offset = self.stream.len();
+                operation
+                    .check_at(self.config.position(offset))
+                    .and_then(|()| operation.write_to_io(&mut self.stream))
+                    .map(|written| (offset, written))
+            })
+            .and_then(|(offset, written)| {
+                let padding = self.config.padding(self.stream.len());
+                self.stream
+                    .resize(self.stream.len().saturating_add(padding), 0);
+                self.config
+                    .check(offset, self.stream.get(offset..).unwrap_or_default())?;
+                Ok((offset, written))
+            });
+        let offset = match result {
+            Ok((offset, written)) => {
+                if let Some(name) = operation.placeholder() {
+                    self.placeholders
+                        .insert(name.to_owned(), offset..offset.saturating_add(written));
                 }
+                offset
+            }
+            Err(error) => {
+                self.stream.truncate(start);
+                trace::failed(operation, index, start, &error);
+                return Err(error.with_op(operation, index, start));
+            }
+        };
+        let n = self.stream.len().saturating_sub(offset);
+        trace::applied(operation, index, offset, n);
+        self.n_ops = index.saturating_add(1);
+        Ok((n, self))
+    }
+
+    /// Checks that an operation satisfies the configuration, e.g. its
+    /// maximum length or its bad bytes, by rendering it to a temporary
+    /// buffer.
+    #[inline]
+    fn try_add<O>(&self, op: impl Borrow<O>) -> Result<()>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let start = self.stream.len();
+        self.config
+            .write_entry_stub(start, &mut [0; 8])
+            .and_then(|stub| {
+                let offset = start.saturating_add(stub);
+                operation.check_at(self.config.position(offset))?;
+                self.config.render(operation, offset)
             })
+            .map(|_bytes| ())
+            .map_err(|error| error.with_op(operation, self.n_ops, start))
+    }
+
+    #[inline]
+    fn position(&self) -> usize {
+        self.config.position(self.stream.len())
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.stream.len()
+    }
+
+    #[inline]
+    fn endianness(&self) -> Endianness {
+        self.config.endianness()
+    }
+
+    #[inline]
+    fn pointer_width(&self) -> PointerWidth {
+        self.config.pointer_width()
+    }
+
+    #[inline]
+    fn arch(&self) -> Option<Arch> {
+        self.config.arch()
+    }
+
+    #[inline]
+    fn debug(&self) -> Option<(DebugStub, DebugPlacement)> {
+        self.config.debug()
+    }
+
+    /// Reserves capacity for at least `additional` more bytes, up to the
+    /// maximum length. Nothing is reserved if the allocation fails.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        let capped = self.config.max_len().map_or(additional, |limit| {
+            additional.min(limit.saturating_sub(self.stream.len()))
+        });
+        drop(self.stream.try_reserve(capped));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Shellcoder {
+    #[inline]
+    fn zeroize(&mut self) {
+        self.stream.zeroize();
+        self.n_ops = 0;
+        self.placeholders.clear();
+    }
+}
+
+/// The shellcode is overwritten with zeroes when the shellcoder is dropped.
+#[cfg(feature = "zeroize")]
+impl Drop for Shellcoder {
+    #[inline]
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for Shellcoder {}
+
+/// A shellcoder backed by a list of buffers, that are either borrowed or
+/// owned.
+///
+/// Operations are written to an owned buffer, except [`WriteCow`]
+/// operations pushed with [`Vectored::push_cow`], whose buffers are kept as
+/// is. The payload is then written to a stream using vectored I/O, so that
+/// large buffers are never copied.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::alloc::Vectored;
+/// use shellcoder::ops::WriteCow;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let stage = vec![0x90; 0x1000];
+/// let mut shellcoder = Vectored::new();
+/// shellcoder.int_le(0xdeadbeef_u32)?;
+/// shellcoder.push_cow(WriteCow::new(stage.as_slice()))?.fill(4, b'A')?;
+///
+/// let mut payload = Vec::new();
+/// assert_eq!(shellcoder.write_to_io(&mut payload)?, 0x1008);
+/// assert_eq!(&payload[..5], b"\xef\xbe\xad\xde\x90");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vectored<'buf> {
+    /// Buffers containing the beginning of the shellcode, in order.
+    segments: Vec<Cow<'buf, [u8]>>,
+
+    /// Buffer containing the end of the shellcode.
+    tail: Vec<u8>,
+
+    /// Number of bytes that have been written.
+    len: usize,
+
+    /// Number of operations that have been pushed.
+    n_ops: usize,
+
+    /// Configuration.
+    config: Config,
+}
+
+impl<'buf> Vectored<'buf> {
+    /// Instantiates a new shellcoder.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the configuration, see [`crate::config::ShellcoderBuilder`].
+    #[inline]
+    #[must_use]
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the default endianness, see [`crate::Shellcoder::endianness`].
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.config = self.config.with_endianness(endianness);
+        self
+    }
+
+    /// Returns the configuration.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Pushes a [`WriteCow`] operation, without copying its buffer.
+    ///
+    /// # Errors
+    ///
+    /// The buffer does not satisfy the configuration, see
+    /// [`crate::config::Config`].
+    #[inline]
+    pub fn push_cow(&mut self, op: WriteCow<'buf>) -> Result<&mut Self> {
+        let index = self.n_ops;
+        let buffer = op.into_inner();
+        let mut stub = [0; 8];
+        let result = self
+            .config
+            .write_entry_stub(self.len, &mut stub)
+            .and_then(|n| {
+                let offset = self.len.saturating_add(n);
+                let end = offset.saturating_add(buffer.len());
+                let padding = vec![0; self.config.padding(end)];
+                self.config
+                    .check(offset, &buffer)
+                    .and_then(|()| self.config.check(end, &padding))?;
+                Ok((n, padding))
+            });
+        let (n, padding) = result.map_err(|error| {
+            let operation = WriteCow::new(buffer.as_ref());
+            trace::failed(&operation, index, self.len, &error);
+            error.with_op(&operation, index, self.len)
+        })?;
+        let offset = self.len.saturating_add(n);
+        let size = buffer.len().saturating_add(padding.len());
+        trace::applied(&WriteCow::new(buffer.as_ref()), index, offset, size);
+        self.tail
+            .extend_from_slice(stub.get(..n).unwrap_or_default());
+        if !buffer.is_empty() {
+            if !self.tail.is_empty() {
+                self.segments.push(Cow::Owned(mem::take(&mut self.tail)));
+            }
+            self.segments.push(buffer);
+        }
+        self.tail.extend_from_slice(&padding);
+        self.len = offset.saturating_add(size);
+        self.n_ops = index.saturating_add(1);
+        Ok(self)
+    }
+
+    /// Returns the buffers containing the shellcode, in order.
+    #[inline]
+    pub fn segments(&self) -> impl Iterator<Item = &[u8]> + '_ {
+        self.segments
+            .iter()
+            .map(AsRef::as_ref)
+            .chain(iter::once(self.tail.as_slice()))
+            .filter(|segment| !segment.is_empty())
+    }
+
+    /// Returns the shellcode, concatenated into a single buffer.
+    #[inline]
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut shellcode = Vec::with_capacity(self.len);
+        for segment in self.segments() {
+            shellcode.extend_from_slice(segment);
+        }
+        shellcode
+    }
+
+    /// Writes the shellcode to a stream using vectored I/O, and returns the
+    /// number of bytes that have been written.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Io`]: an I/O error occurred.
+    #[inline]
+    pub fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let mut segments = self.segments().collect::<Vec<_>>();
+        let mut first = 0;
+        while first < segments.len() {
+            let slices = segments
+                .iter()
+                .skip(first)
+                .map(|segment| IoSlice::new(segment))
+                .collect::<Vec<_>>();
+            let mut n = match stream.write_vectored(&slices) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero).into()),
+                Ok(n) => n,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error.into()),
+            };
+            while let Some(segment) = segments.get_mut(first) {
+                if n < segment.len() {
+                    let rest = segment.get(n..).unwrap_or_default();
+                    *segment = rest;
+                    break;
+                }
+                n = n.saturating_sub(segment.len());
+                first = first.saturating_add(1);
+            }
+        }
+        Ok(self.len)
+    }
+}
+
+impl crate::Shellcoder for Vectored<'_> {
+    #[inline]
+    fn add<O>(&mut self, op: impl Borrow<O>) -> Result<&mut Self>
+    where
+        O: Op,
+    {
+        self.add_counted(op).map(|(_, shellcoder)| shellcoder)
+    }
+
+    #[inline]
+    fn add_counted<O>(&mut self, op: impl Borrow<O>) -> Result<(usize, &mut Self)>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let index = self.n_ops;
+        let start = self.tail.len();
+        let mut stub = [0; 8];
+        let result = reserve_for(&self.config, &mut self.tail, self.len, operation.size())
+            .and_then(|()| self.config.write_entry_stub(self.len, &mut stub))
+            .and_then(|n| {
+                self.tail
+                    .extend_from_slice(stub.get(..n).unwrap_or_default());
+                let offset = self.len.saturating_add(n);
+                operation
+                    .check_at(self.config.position(offset))
+                    .and_then(|()| operation.write_to_io(&mut self.tail))
+                    .map(|written| (offset, written))
+            })
+            .and_then(|(offset, written)| {
+                let padding = self.config.padding(offset.saturating_add(written));
+                self.tail.resize(self.tail.len().saturating_add(padding), 0);
+                self.config
+                    .check(self.len, self.tail.get(start..).unwrap_or_default())?;
+                Ok(offset)
+            });
+        let offset = match result {
+            Ok(offset) => offset,
+            Err(error) => {
+                self.tail.truncate(start);
+                trace::failed(operation, index, self.len, &error);
+                return Err(error.with_op(operation, index, self.len));
+            }
+        };
+        let end = self
+            .len
+            .saturating_add(self.tail.len().saturating_sub(start));
+        let n = end.saturating_sub(offset);
+        trace::applied(operation, index, offset, n);
+        self.len = end;
+        self.n_ops = index.saturating_add(1);
+        Ok((n, self))
+    }
+
+    /// Checks that an operation satisfies the configuration, e.g. its
+    /// maximum length or its bad bytes, by rendering it to a temporary
+    /// buffer, or by writing it to [`io::sink`] if there is nothing to
+    /// check it against.
+    #[inline]
+    fn try_add<O>(&self, op: impl Borrow<O>) -> Result<()>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let result = self
+            .config
+            .write_entry_stub(self.len, &mut [0; 8])
+            .and_then(|stub| {
+                let offset = self.len.saturating_add(stub);
+                operation.check_at(self.config.position(offset))?;
+                if stub == 0 && self.config.is_passthrough() {
+                    operation.write_to_io(&mut io::sink()).map(|_n| ())
+                } else {
+                    self.config.render(operation, offset).map(|_bytes| ())
+                }
+            });
+        result.map_err(|error| error.with_op(operation, self.n_ops, self.len))
+    }
+
+    #[inline]
+    fn position(&self) -> usize {
+        self.config.position(self.len)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn endianness(&self) -> Endianness {
+        self.config.endianness()
+    }
+
+    #[inline]
+    fn pointer_width(&self) -> PointerWidth {
+        self.config.pointer_width()
+    }
+
+    #[inline]
+    fn arch(&self) -> Option<Arch> {
+        self.config.arch()
+    }
+
+    #[inline]
+    fn debug(&self) -> Option<(DebugStub, DebugPlacement)> {
+        self.config.debug()
+    }
+
+    /// Reserves capacity for at least `additional` more bytes. Nothing is
+    /// reserved if the allocation fails.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        drop(self.tail.try_reserve(additional));
+    }
+}
+
+/// Reserves room in `buffer` for an operation of `size` bytes, if known,
+/// when `len` bytes have been written so far.
+///
+/// Unlike [`Vec::reserve`], a size that cannot be allocated is an error
+/// rather than a panic, as size hints may come from untrusted input, e.g.
+/// [`crate::plan::replay`].
+fn reserve_for(
+    config: &Config,
+    buffer: &mut Vec<u8>,
+    len: usize,
+    size: Option<usize>,
+) -> Result<()> {
+    size.map_or(Ok(()), |n| {
+        config.check_len(len, n)?;
+        buffer
+            .try_reserve(n)
+            .map_err(|error| io::Error::new(io::ErrorKind::OutOfMemory, error).into())
+    })
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::indexing_slicing,
+    clippy::panic,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::shadow_unrelated,
+    clippy::unnecessary_wraps,
+    clippy::unreadable_literal,
+    clippy::unwrap_used,
+    clippy::wildcard_enum_match_arm
+)]
+mod tests {
+    use std::error::Error as _;
+    use std::io;
+
+    use crate::alloc::{ReplacePolicy, Shellcoder, Vectored};
+    use crate::config::{PointerWidth, ShellcoderBuilder};
+    use crate::error::ErrorKind;
+    use crate::ops::{Fill, Placeholder, WriteBuffer, WriteCow};
+    use crate::transform::{Add, Xor};
+    use crate::Shellcoder as _;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_op_context() -> Result<()> {
+        let error = Shellcoder::new_with_max_len(8)
+            .int_le(0xdeadbeef_u32)
+            .and_then(|shellcoder| shellcoder.advance(2))
+            .and_then(|shellcoder| shellcoder.fill(4, b'A'))
+            .unwrap_err();
+        let context = match &error {
+            Error::Op(context) => context,
+            _ => panic!("unexpected error: {error:?}"),
+        };
+        assert_eq!(context.index(), 2);
+        assert_eq!(context.offset(), 6);
+        assert_eq!(context.op(), "Fill(4, 65)");
+        assert!(matches!(
+            error.root(),
+            Error::MaxLengthExceeded {
+                limit: 8,
+                attempted: 10
+            }
+        ));
+        assert_eq!(error.kind(), ErrorKind::MaxLengthExceeded);
+        assert!(matches!(
+            error
+                .source()
+                .and_then(|source| source.downcast_ref::<Error>()),
+            Some(Error::MaxLengthExceeded { .. })
+        ));
+        assert_eq!(
+            error.to_string(),
+            "operation #2 `Fill(4, 65)` at offset 0x6"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_op_index() -> Result<()> {
+        let mut shellcoder = Shellcoder::new_with_max_len(4);
+        shellcoder.fill(2, b'A')?;
+        assert!(shellcoder.assert_at(0).is_err());
+        assert!(shellcoder.fill(4, b'B').is_err());
+        assert!(shellcoder.insert_at(0, Fill::new(4, b'B')).is_err());
+        assert!(shellcoder
+            .replace(0..2, Fill::new(1, b'B'), ReplacePolicy::Exact)
+            .is_err());
+        assert_eq!(shellcoder.snapshot().n_ops(), 1);
+        let error = shellcoder
+            .fill(1, b'B')
+            .and_then(|next| next.fill(2, b'C'))
+            .unwrap_err();
+        let context = match &error {
+            Error::Op(context) => context,
+            _ => panic!("unexpected error: {error:?}"),
+        };
+        assert_eq!(context.index(), 2);
+        assert_eq!(shellcoder.snapshot().n_ops(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_huge_size_hint() -> Result<()> {
+        let mut shellcoder = Shellcoder::new();
+        let error = shellcoder.fill(usize::MAX, 0).unwrap_err();
+        assert!(
+            matches!(error.root(), Error::Io(io_error) if io_error.kind() == io::ErrorKind::OutOfMemory)
+        );
+        assert!(matches!(
+            Shellcoder::new_with_max_len(8)
+                .fill(usize::MAX, 0)
+                .unwrap_err()
+                .root(),
+            Error::MaxLengthExceeded { .. }
+        ));
+        let mut vectored = Vectored::new();
+        vectored.reserve(usize::MAX);
+        assert!(vectored.fill(usize::MAX, 0).is_err());
+        shellcoder.reserve(usize::MAX);
+        shellcoder.fill(2, b'A')?;
+        assert_eq!(shellcoder.as_bytes(), b"AA");
+        Ok(())
+    }
+
+    #[test]
+    fn test_try_add() -> Result<()> {
+        let mut shellcoder =
+            Shellcoder::new_with_max_len(4).with_bad_bytes(b"\n".iter().copied().collect());
+        shellcoder.fill(2, b'A')?;
+        assert!(shellcoder.try_add(Fill::new(2, b'B')).is_ok());
+        assert!(matches!(
+            shellcoder.try_add(Fill::new(3, b'B')).unwrap_err().root(),
+            Error::MaxLengthExceeded {
+                limit: 4,
+                attempted: 5
+            }
+        ));
+        assert!(matches!(
+            shellcoder
+                .try_add(WriteBuffer::new(b"B\n"))
+                .unwrap_err()
+                .root(),
+            Error::BadByteFound {
+                offset: 3,
+                byte: b'\n'
+            }
+        ));
+        assert_eq!(shellcoder.as_bytes(), b"AA");
+        Ok(())
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_clear_sensitive() -> Result<()> {
+        let mut shellcoder = Shellcoder::new_with_max_len(4);
+        shellcoder.fill(4, b'A')?;
+        shellcoder.clear_sensitive();
+        assert!(shellcoder.is_empty());
+        assert_eq!(shellcoder.fill(4, b'B')?.as_bytes(), b"BBBB");
+        Ok(())
+    }
+
+    #[test]
+    fn test_transforms() -> Result<()> {
+        let mut shellcoder = Shellcoder::new();
+        shellcoder.push(b"ABCDEF")?.swap_pairs(1..6)?;
+        assert_eq!(shellcoder.as_bytes(), b"ACBEDF");
+        shellcoder.reverse(0..0)?.reverse(4..6)?;
+        assert_eq!(shellcoder.as_bytes(), b"ACBEFD");
+        assert!(matches!(
+            shellcoder.reverse(4..7),
+            Err(Error::OutputBufferTooSmall(7))
+        ));
+        shellcoder.transform(0..2, &Add::new(1))?;
+        assert_eq!(shellcoder.as_bytes(), b"BDBEFD");
+
+        let mut shellcoder = Shellcoder::new().with_bad_bytes(b"\0".iter().copied().collect());
+        shellcoder.push(b"AB")?;
+        assert!(matches!(
+            shellcoder.transform(0..2, &Xor::new(b"AB")),
+            Err(Error::BadByteFound { .. })
+        ));
+        assert_eq!(shellcoder.as_bytes(), b"AB");
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace() -> Result<()> {
+        let mut shellcoder = Shellcoder::new().with_bad_bytes(b"\n".iter().copied().collect());
+        shellcoder.fill(6, b'A')?;
+        shellcoder.replace(1..4, Fill::new(2, b'B'), ReplacePolicy::PadWith(b'C'))?;
+        shellcoder.replace(4..6, WriteBuffer::new(b"DE"), ReplacePolicy::Exact)?;
+        assert_eq!(shellcoder.as_bytes(), b"ABBCDE");
+
+        let error = shellcoder
+            .replace(0..1, Fill::new(2, b'F'), ReplacePolicy::PadWith(0))
+            .unwrap_err();
+        assert!(matches!(
+            error.root(),
+            Error::UnexpectedPosition {
+                expected: 1,
+                actual: 2
+            }
+        ));
+        let error = shellcoder
+            .replace(0..1, Fill::new(1, b'\n'), ReplacePolicy::Exact)
+            .unwrap_err();
+        assert!(matches!(
+            error.root(),
+            Error::BadByteFound { offset: 0, .. }
+        ));
+        let error = shellcoder
+            .replace(4..8, Fill::new(4, b'F'), ReplacePolicy::Exact)
+            .unwrap_err();
+        assert!(matches!(error.root(), Error::OutputBufferTooSmall(8)));
+        assert_eq!(shellcoder.as_bytes(), b"ABBCDE");
+        Ok(())
+    }
+
+    #[test]
+    fn test_placeholder() -> Result<()> {
+        let mut shellcoder = ShellcoderBuilder::new()
+            .pointer_width(PointerWidth::Bits32)
+            .build_alloc();
+        shellcoder
+            .push(b"AAAA")?
+            .add(Placeholder::canary(PointerWidth::Bits32).with_filler(b'?'))?
+            .add(Placeholder::new("ret", 2))?;
+        assert_eq!(shellcoder.as_bytes(), b"AAAA????\0\0");
+        assert_eq!(shellcoder.placeholder("canary"), Some(4..8));
+
+        shellcoder
+            .patch_placeholder("canary", 0xdead_be00_u32)?
+            .patch_placeholder("ret", b"\x10\x40")?;
+        assert_eq!(shellcoder.as_bytes(), b"AAAA\0\xbe\xad\xde\x10\x40");
+        assert!(matches!(
+            shellcoder.patch_placeholder("canary", 0_u64),
+            Err(Error::Op(_))
+        ));
+        assert!(matches!(
+            shellcoder.patch_placeholder("cookie", 0_u32),
+            Err(Error::UnknownSymbol(name)) if name == "cookie"
+        ));
+
+        shellcoder.truncate(8);
+        assert_eq!(shellcoder.placeholder("ret"), None);
+        assert_eq!(shellcoder.placeholder("canary"), Some(4..8));
+        Ok(())
+    }
+
+    #[test]
+    fn test_placeholder_splice() -> Result<()> {
+        let mut shellcoder = Shellcoder::new();
+        shellcoder
+            .push(b"AA")?
+            .add(Placeholder::new("canary", 2))?
+            .push(b"BB")?
+            .add(Placeholder::new("ret", 2))?
+            .push(b"CC")?;
+        shellcoder.insert_at(0, WriteBuffer::new(b"HDR"))?;
+        assert_eq!(shellcoder.placeholder("canary"), Some(5..7));
+        shellcoder.remove_range(7..9)?;
+        assert_eq!(shellcoder.placeholder("ret"), Some(7..9));
+        shellcoder
+            .patch_placeholder("canary", b"\xca\xfe")?
+            .patch_placeholder("ret", b"\x10\x40")?;
+        assert_eq!(shellcoder.as_bytes(), b"HDRAA\xca\xfe\x10\x40CC");
+
+        shellcoder.insert_at(6, Fill::new(1, b'X'))?;
+        assert_eq!(shellcoder.placeholder("canary"), None);
+        assert_eq!(shellcoder.placeholder("ret"), Some(8..10));
+        shellcoder.remove_range(9..11)?;
+        assert_eq!(shellcoder.placeholder("ret"), None);
+        assert!(matches!(
+            shellcoder.patch_placeholder("ret", b"\x10\x40"),
+            Err(Error::UnknownSymbol(_))
+        ));
+        assert_eq!(shellcoder.as_bytes(), b"HDRAA\xcaX\xfe\x10C");
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove() -> Result<()> {
+        let mut shellcoder = Shellcoder::new();
+        shellcoder
+            .push(b"ABCDEF")?
+            .remove_range(1..1)?
+            .remove_range(4..6)?;
+        assert_eq!(shellcoder.truncate(8).as_bytes(), b"ABCD");
+        assert!(matches!(
+            shellcoder.remove_range(3..5),
+            Err(Error::OutputBufferTooSmall(5))
+        ));
+        assert!(shellcoder.truncate(0).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_at() -> Result<()> {
+        let mut shellcoder = Shellcoder::new_with_max_len(8);
+        shellcoder
+            .push(b"AD")?
+            .insert_at(1, WriteBuffer::new(b"BC"))?;
+        shellcoder.insert_at(4, Fill::new(1, b'E'))?;
+        assert_eq!(shellcoder.as_bytes(), b"ABCDE");
+        let error = shellcoder.insert_at(6, Fill::new(1, b'F')).unwrap_err();
+        assert!(matches!(error.root(), Error::OutputBufferTooSmall(6)));
+        let error = shellcoder.insert_at(0, Fill::new(4, b'F')).unwrap_err();
+        assert!(matches!(
+            error.root(),
+            Error::MaxLengthExceeded {
+                limit: 8,
+                attempted: 9
+            }
+        ));
+        assert_eq!(shellcoder.as_bytes(), b"ABCDE");
+        Ok(())
+    }
+
+    #[test]
+    fn test_take() -> Result<()> {
+        let mut shellcoder = Shellcoder::new_with_max_len(4);
+        shellcoder.fill(4, b'A')?;
+        assert_eq!(shellcoder.take(), b"AAAA");
+        assert!(shellcoder.is_empty());
+        shellcoder.fill(4, b'B')?;
+        assert!(shellcoder.fill(1, b'B').is_err());
+        assert_eq!(shellcoder.into_bytes(), b"BBBB");
+        Ok(())
+    }
+
+    /// A stream that accepts at most 3 bytes per write.
+    struct Trickle(Vec<u8>);
+
+    impl io::Write for Trickle {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_vectored() -> Result<()> {
+        let stage = b"/bin/sh\0".to_vec();
+        let mut shellcoder = Vectored::new();
+        shellcoder.int_be(0x4142_u16)?;
+        shellcoder
+            .push_cow(WriteCow::new(stage.as_slice()))?
+            .push_cow(WriteCow::new(vec![0x90; 2]))?
+            .push_cow(WriteCow::new(Vec::new()))?
+            .fill(2, b'C')?;
+        assert_eq!(shellcoder.len(), 14);
+        assert_eq!(shellcoder.segments().count(), 4);
+        assert_eq!(shellcoder.to_vec(), b"AB/bin/sh\0\x90\x90CC");
+        let mut stream = Trickle(Vec::new());
+        assert_eq!(shellcoder.write_to_io(&mut stream)?, 14);
+        assert_eq!(stream.0, shellcoder.to_vec());
+        Ok(())
     }
 }
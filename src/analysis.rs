@@ -0,0 +1,615 @@
+//! Analysis of payloads.
+//!
+//! [`stats`] computes statistics about the bytes of a payload, e.g. to spot
+//! accidental structure like a forgotten gap of null bytes, or to check
+//! how a payload would look to a naive entropy-based heuristic.
+//!
+//! [`occurrences`] and [`find`] search a payload for a subsequence, e.g. to
+//! check that an egg appears exactly once.
+//!
+//! [`sha256`], [`crc32`] and [`digest`] hash a payload, e.g. to log it and
+//! pin it for reproducibility checks.
+
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::Range;
+
+/// Initial hash value of SHA-256.
+const SHA256_INITIAL: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+/// Round constants of SHA-256.
+const SHA256_ROUNDS: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+/// Length of a SHA-256 block, in bytes.
+const SHA256_BLOCK_LEN: usize = 64;
+
+/// Reversed polynomial of CRC-32 (IEEE 802.3).
+const CRC32_POLYNOMIAL: u32 = 0xedb8_8320;
+
+/// A run of identical bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Run {
+    /// Offset of the first byte.
+    offset: usize,
+
+    /// Number of bytes.
+    len: usize,
+}
+
+impl Run {
+    /// Returns the offset of the first byte of the run.
+    #[inline]
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of bytes of the run.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the run is empty.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the offsets of the bytes of the run.
+    #[inline]
+    #[must_use]
+    pub const fn range(&self) -> Range<usize> {
+        self.offset..self.offset.saturating_add(self.len)
+    }
+}
+
+/// Statistics about the bytes of a payload, see [`stats`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stats {
+    /// Length of the payload.
+    len: usize,
+
+    /// Number of occurrences of each byte.
+    histogram: [usize; 256],
+
+    /// Longest run of each byte. The first one wins on ties.
+    runs: [Run; 256],
+}
+
+impl Stats {
+    /// Returns the length of the payload.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the payload is empty.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of occurrences of each byte.
+    #[inline]
+    #[must_use]
+    pub const fn histogram(&self) -> &[usize; 256] {
+        &self.histogram
+    }
+
+    /// Returns the number of occurrences of a byte.
+    #[inline]
+    #[must_use]
+    pub fn count(&self, byte: u8) -> usize {
+        self.histogram
+            .get(usize::from(byte))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of distinct bytes.
+    #[inline]
+    #[must_use]
+    pub fn distinct(&self) -> usize {
+        self.histogram.iter().filter(|&&count| count != 0).count()
+    }
+
+    /// Returns the longest run of a byte, if the byte occurs.
+    #[inline]
+    #[must_use]
+    pub fn longest_run_of(&self, byte: u8) -> Option<Run> {
+        self.runs
+            .get(usize::from(byte))
+            .copied()
+            .filter(|run| !run.is_empty())
+    }
+
+    /// Returns the longest run of identical bytes, along with the byte.
+    /// The first one wins on ties.
+    #[inline]
+    #[must_use]
+    pub fn longest_run(&self) -> Option<(u8, Run)> {
+        (0..=u8::MAX)
+            .filter_map(|byte| self.longest_run_of(byte).map(|run| (byte, run)))
+            .fold(
+                None,
+                |longest: Option<(u8, Run)>, (byte, run)| match longest {
+                    Some((_, longest_run))
+                        if (longest_run.len, run.offset) >= (run.len, longest_run.offset) =>
+                    {
+                        longest
+                    }
+                    Some(_) | None => Some((byte, run)),
+                },
+            )
+    }
+
+    /// Returns the Shannon entropy of the payload, in bits per byte, i.e.
+    /// between `0.0` for a single repeated byte and `8.0` for uniformly
+    /// distributed bytes.
+    #[cfg(feature = "std")]
+    #[inline]
+    #[must_use]
+    // Counts are converted to floats, which is lossy for huge payloads
+    // only.
+    #[allow(
+        clippy::float_arithmetic,
+        clippy::as_conversions,
+        clippy::cast_precision_loss
+    )]
+    pub fn entropy(&self) -> f64 {
+        if self.len == 0 {
+            return 0.0;
+        }
+        let len = self.len as f64;
+        -self
+            .histogram
+            .iter()
+            .filter(|&&count| count != 0)
+            .map(|&count| {
+                let probability = count as f64 / len;
+                probability * probability.log2()
+            })
+            .sum::<f64>()
+    }
+}
+
+/// Computes statistics about the bytes of a payload: their frequency,
+/// their entropy, and their longest runs.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::analysis::stats;
+///
+/// let stats = stats(b"\x90\x90\x90\x90\0\0\0\0\0\0/bin/sh");
+/// assert_eq!(stats.count(0x90), 4);
+/// let (byte, run) = stats.longest_run().unwrap();
+/// assert_eq!((byte, run.range()), (0, 4..10));
+/// # #[cfg(feature = "std")]
+/// assert!(stats.entropy() < 3.0);
+/// ```
+#[inline]
+#[must_use]
+pub fn stats(payload: &[u8]) -> Stats {
+    let mut stats = Stats {
+        len: payload.len(),
+        histogram: [0; 256],
+        runs: [Run::default(); 256],
+    };
+    let mut current: Option<(u8, Run)> = None;
+    for (offset, &byte) in payload.iter().enumerate() {
+        if let Some(count) = stats.histogram.get_mut(usize::from(byte)) {
+            *count = count.saturating_add(1);
+        }
+        let run = match current {
+            Some((previous, run)) if previous == byte => Run {
+                len: run.len.saturating_add(1),
+                ..run
+            },
+            Some(_) | None => Run { offset, len: 1 },
+        };
+        if let Some(longest) = stats.runs.get_mut(usize::from(byte)) {
+            if run.len > longest.len {
+                *longest = run;
+            }
+        }
+        current = Some((byte, run));
+    }
+    stats
+}
+
+/// Iterator over the offsets of a subsequence in a payload, see
+/// [`occurrences`].
+#[derive(Clone, Debug)]
+pub struct Occurrences<'payload, 'needle> {
+    /// The payload.
+    payload: &'payload [u8],
+
+    /// The subsequence.
+    needle: &'needle [u8],
+
+    /// Offset of the next window to compare.
+    offset: usize,
+}
+
+impl Iterator for Occurrences<'_, '_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.needle.is_empty() {
+            return None;
+        }
+        let found = self
+            .payload
+            .get(self.offset..)?
+            .windows(self.needle.len())
+            .position(|window| window == self.needle)
+            .map(|position| self.offset.saturating_add(position));
+        self.offset = found.map_or(self.payload.len(), |offset| offset.saturating_add(1));
+        found
+    }
+}
+
+impl FusedIterator for Occurrences<'_, '_> {}
+
+/// Returns an iterator over the offsets of a subsequence in a payload, in
+/// ascending order. Occurrences may overlap. An empty subsequence never
+/// occurs.
+#[inline]
+#[must_use]
+pub const fn occurrences<'payload, 'needle>(
+    payload: &'payload [u8],
+    needle: &'needle [u8],
+) -> Occurrences<'payload, 'needle> {
+    Occurrences {
+        payload,
+        needle,
+        offset: 0,
+    }
+}
+
+/// Returns the offsets of a subsequence in a payload, see [`occurrences`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::analysis::find;
+///
+/// assert_eq!(find(b"w00tw00tAAAAw00t", b"w00tw00t"), [0]);
+/// assert_eq!(find(b"AAAA", b"AA"), [0, 1, 2]);
+/// ```
+#[cfg(feature = "std")]
+#[inline]
+#[must_use]
+pub fn find(payload: &[u8], needle: &[u8]) -> Vec<usize> {
+    occurrences(payload, needle).collect()
+}
+
+/// Hashes of a payload, see [`digest`].
+///
+/// Hashes are displayed as the lowercase hexadecimal SHA-256.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Digest {
+    /// SHA-256 of the payload.
+    sha256: [u8; 32],
+
+    /// CRC-32 of the payload.
+    crc32: u32,
+}
+
+impl Digest {
+    /// Returns the SHA-256 of the payload.
+    #[inline]
+    #[must_use]
+    pub const fn sha256(&self) -> &[u8; 32] {
+        &self.sha256
+    }
+
+    /// Returns the CRC-32 of the payload.
+    #[inline]
+    #[must_use]
+    pub const fn crc32(&self) -> u32 {
+        self.crc32
+    }
+}
+
+impl fmt::Display for Digest {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.sha256
+            .iter()
+            .try_for_each(|byte| write!(fmt, "{byte:02x}"))
+    }
+}
+
+/// Computes the SHA-256 and the CRC-32 of a payload.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::analysis::digest;
+///
+/// let digest = digest(b"abc");
+/// assert_eq!(digest.crc32(), 0x3524_41c2);
+/// # #[cfg(feature = "std")]
+/// assert_eq!(
+///     digest.to_string(),
+///     "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+/// );
+/// ```
+#[inline]
+#[must_use]
+pub fn digest(payload: &[u8]) -> Digest {
+    Digest {
+        sha256: sha256(payload),
+        crc32: crc32(payload),
+    }
+}
+
+/// Processes a SHA-256 block.
+fn sha256_block(state: &mut [u32; 8], block: &[u8]) {
+    // The message schedule is a sliding window of 16 words.
+    let mut window: [u32; 16] = [0; 16];
+    for (word, chunk) in window.iter_mut().zip(block.chunks_exact(4)) {
+        *word = chunk.try_into().map(u32::from_be_bytes).unwrap_or_default();
+    }
+    let [mut x0, mut x1, mut x2, mut x3, mut x4, mut x5, mut x6, mut x7] = *state;
+    for constant in SHA256_ROUNDS {
+        let [w0, w1, _, _, _, _, _, _, _, w9, _, _, _, _, w14, _] = window;
+        let sum1 = x4.rotate_right(6) ^ x4.rotate_right(11) ^ x4.rotate_right(25);
+        let choice = (x4 & x5) ^ (!x4 & x6);
+        let temp1 = x7
+            .wrapping_add(sum1)
+            .wrapping_add(choice)
+            .wrapping_add(constant)
+            .wrapping_add(w0);
+        let sum0 = x0.rotate_right(2) ^ x0.rotate_right(13) ^ x0.rotate_right(22);
+        let majority = (x0 & x1) ^ (x0 & x2) ^ (x1 & x2);
+        let temp2 = sum0.wrapping_add(majority);
+        x7 = x6;
+        x6 = x5;
+        x5 = x4;
+        x4 = x3.wrapping_add(temp1);
+        x3 = x2;
+        x2 = x1;
+        x1 = x0;
+        x0 = temp1.wrapping_add(temp2);
+
+        let sigma0 = w1.rotate_right(7) ^ w1.rotate_right(18) ^ w1.wrapping_shr(3);
+        let sigma1 = w14.rotate_right(17) ^ w14.rotate_right(19) ^ w14.wrapping_shr(10);
+        window.rotate_left(1);
+        if let Some(last) = window.last_mut() {
+            *last = sigma1
+                .wrapping_add(w9)
+                .wrapping_add(sigma0)
+                .wrapping_add(w0);
+        }
+    }
+    for (word, mixed) in state.iter_mut().zip([x0, x1, x2, x3, x4, x5, x6, x7]) {
+        *word = word.wrapping_add(mixed);
+    }
+}
+
+/// Computes the SHA-256 of a payload.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::analysis::sha256;
+///
+/// assert_eq!(sha256(b"")[..4], [0xe3, 0xb0, 0xc4, 0x42]);
+/// ```
+#[inline]
+#[must_use]
+pub fn sha256(payload: &[u8]) -> [u8; 32] {
+    let mut state = SHA256_INITIAL;
+    let blocks = payload.chunks_exact(SHA256_BLOCK_LEN);
+    let remainder = blocks.remainder();
+    for block in blocks {
+        sha256_block(&mut state, block);
+    }
+
+    // The last bytes are followed by a one bit, zeroes, and the length of
+    // the payload in bits, in one or two blocks.
+    let mut tail: [u8; 2 * SHA256_BLOCK_LEN] = [0; 2 * SHA256_BLOCK_LEN];
+    let tail_len = if remainder.len() < SHA256_BLOCK_LEN - 8 {
+        SHA256_BLOCK_LEN
+    } else {
+        2 * SHA256_BLOCK_LEN
+    };
+    if let Some(bytes) = tail.get_mut(..remainder.len()) {
+        bytes.copy_from_slice(remainder);
+    }
+    if let Some(marker) = tail.get_mut(remainder.len()) {
+        *marker = 0x80;
+    }
+    let bits = u64::try_from(payload.len())
+        .unwrap_or(u64::MAX)
+        .wrapping_mul(8);
+    if let Some(bytes) = tail.get_mut(tail_len.saturating_sub(8)..tail_len) {
+        bytes.copy_from_slice(&bits.to_be_bytes());
+    }
+    for block in tail
+        .get(..tail_len)
+        .unwrap_or_default()
+        .chunks_exact(SHA256_BLOCK_LEN)
+    {
+        sha256_block(&mut state, block);
+    }
+
+    let mut hash: [u8; 32] = [0; 32];
+    for (bytes, word) in hash.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    hash
+}
+
+/// Computes the CRC-32 of a payload, as used by zlib, PNG or Ethernet.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::analysis::crc32;
+///
+/// assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+/// ```
+#[inline]
+#[must_use]
+pub fn crc32(payload: &[u8]) -> u32 {
+    !payload.iter().fold(u32::MAX, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |value: u32, _bit: u8| {
+            if value & 1 == 1 {
+                value.wrapping_shr(1) ^ CRC32_POLYNOMIAL
+            } else {
+                value.wrapping_shr(1)
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps
+)]
+mod tests {
+    use crate::analysis::{crc32, digest, occurrences, sha256, stats, Run};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let empty = stats(b"");
+        assert!(empty.is_empty());
+        assert_eq!(empty.distinct(), 0);
+        assert_eq!(empty.longest_run(), None);
+
+        let stats = stats(b"AABBBAAAC");
+        assert_eq!(stats.len(), 9);
+        assert_eq!(stats.count(b'A'), 5);
+        assert_eq!(stats.count(b'D'), 0);
+        assert_eq!(stats.distinct(), 3);
+        assert_eq!(stats.longest_run_of(b'A'), Some(Run { offset: 5, len: 3 }));
+        assert_eq!(stats.longest_run_of(b'D'), None);
+        assert_eq!(stats.longest_run(), Some((b'B', Run { offset: 2, len: 3 })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_occurrences() -> Result<()> {
+        assert!(occurrences(b"ABABA", b"ABA").eq([0, 2]));
+        assert_eq!(occurrences(b"ABABA", b"C").next(), None);
+        assert_eq!(occurrences(b"AB", b"ABC").next(), None);
+        assert_eq!(occurrences(b"AB", b"").next(), None);
+        assert_eq!(occurrences(b"", b"").next(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hashes() -> Result<()> {
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            *b"\x24\x8d\x6a\x61\xd2\x06\x38\xb8\xe5\xc0\x26\x93\x0c\x3e\x60\x39\
+               \xa3\x3c\xe4\x59\x64\xff\x21\x67\xf6\xec\xed\xd4\x19\xdb\x06\xc1"
+        );
+        let long = sha256(&[b'a'; 1000]);
+        assert_eq!(long[..4], [0x41, 0xed, 0xec, 0xe4]);
+        assert_eq!(crc32(b""), 0);
+        let digest = digest(b"The quick brown fox jumps over the lazy dog");
+        assert_eq!(digest.crc32(), 0x414f_a339);
+        assert_eq!(digest.sha256()[..4], [0xd7, 0xa8, 0xfb, 0xb3]);
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_entropy() -> Result<()> {
+        assert!(stats(b"").entropy().abs() < f64::EPSILON);
+        assert!(stats(b"AAAA").entropy().abs() < f64::EPSILON);
+        assert!((stats(b"ABAB").entropy() - 1.0).abs() < f64::EPSILON);
+        let all = (0..=u8::MAX).collect::<Vec<_>>();
+        assert!((stats(&all).entropy() - 8.0).abs() < f64::EPSILON);
+        Ok(())
+    }
+}
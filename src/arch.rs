@@ -0,0 +1,242 @@
+//! Architectures of targets.
+//!
+//! Some parts of a payload depend on the instruction set of the target
+//! rather than on its data layout: code addresses, e.g. the Thumb bit of
+//! ARM, instruction fills, e.g. NOP sleds, see [`crate::ops::Sled`], and
+//! stubs, see [`crate::ops::SyscallStub`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::arch::Arch;
+//! use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+//! use shellcoder::Shellcoder as _;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let mut buffer = [0u8; 8];
+//! let mut shellcoder = ShellcoderBuilder::new()
+//!     .arch(Arch::Thumb)
+//!     .pointer_width(PointerWidth::Bits32)
+//!     .build_static(&mut buffer);
+//! shellcoder.code_ptr(0x0001_0400)?;
+//! assert_eq!(shellcoder.get(), b"\x01\x04\x01\0");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::ops::Endianness;
+use crate::prelude::*;
+
+/// Architecture of a target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Arch {
+    /// 32-bit x86.
+    X86,
+
+    /// 64-bit x86.
+    X86_64,
+
+    /// 32-bit ARM, in the ARM (A32) instruction set.
+    Arm,
+
+    /// 32-bit ARM, in the Thumb (T32) instruction set.
+    Thumb,
+
+    /// 64-bit ARM.
+    Aarch64,
+
+    /// 32-bit MIPS, either big-endian or little-endian.
+    Mips,
+
+    /// 64-bit RISC-V, with the compressed (C) extension, e.g. RV64GC.
+    Riscv64,
+}
+
+impl Arch {
+    /// Returns the alignment of instructions, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn code_alignment(self) -> u64 {
+        match self {
+            Self::X86 | Self::X86_64 => 1,
+            Self::Thumb | Self::Riscv64 => 2,
+            Self::Arm | Self::Aarch64 | Self::Mips => 4,
+        }
+    }
+
+    /// Returns `true` if the instruction that follows a branch, i.e. its
+    /// delay slot, is executed before the branch is taken.
+    #[inline]
+    #[must_use]
+    pub const fn has_delay_slots(self) -> bool {
+        matches!(self, Self::Mips)
+    }
+
+    /// Returns the encoding of an instruction that does nothing, to fill
+    /// sleds.
+    ///
+    /// ARM and RISC-V instructions are little-endian, even on big-endian
+    /// targets: the endianness only matters to MIPS, whose canonical `nop`
+    /// is made of null bytes. It is replaced by an instruction that writes
+    /// to `$zero`, which is discarded, thus inert.
+    ///
+    /// With RISC-V, the instruction is compressed, so that a sled can be
+    /// entered at any instruction boundary: a 4-byte `nop` entered in its
+    /// middle would be decoded as something else. The canonical `c.nop` is
+    /// also replaced by a hint that writes to `zero`, free of null bytes.
+    #[inline]
+    #[must_use]
+    pub const fn nop(self, endianness: Endianness) -> &'static [u8] {
+        match (self, endianness) {
+            // nop
+            (Self::X86 | Self::X86_64, _) => b"\x90",
+            // mov r0, r0
+            (Self::Arm, _) => b"\x00\x00\xa0\xe1",
+            // mov r8, r8
+            (Self::Thumb, _) => b"\xc0\x46",
+            // nop
+            (Self::Aarch64, _) => b"\x1f\x20\x03\xd5",
+            // addiu $zero, $t1, 0x101
+            (Self::Mips, Endianness::Big) => b"\x25\x20\x01\x01",
+            (Self::Mips, Endianness::Little) => b"\x01\x01\x20\x25",
+            // c.addi zero, -31
+            (Self::Riscv64, _) => b"\x05\x10",
+        }
+    }
+
+    /// Returns the encoding of an instruction that performs a system call,
+    /// e.g. `syscall`, `svc #0` or `ecall`.
+    ///
+    /// With 32-bit x86, this is `int 0x80`. With MIPS, this is
+    /// `syscall 0x40404`, which is free of null bytes.
+    #[inline]
+    #[must_use]
+    pub const fn syscall(self, endianness: Endianness) -> &'static [u8] {
+        match (self, endianness) {
+            (Self::X86, _) => b"\xcd\x80",
+            (Self::X86_64, _) => b"\x0f\x05",
+            (Self::Arm, _) => b"\x00\x00\x00\xef",
+            (Self::Thumb, _) => b"\x00\xdf",
+            (Self::Aarch64, _) => b"\x01\x00\x00\xd4",
+            (Self::Mips, Endianness::Big) => b"\x01\x01\x01\x0c",
+            (Self::Mips, Endianness::Little) => b"\x0c\x01\x01\x01",
+            (Self::Riscv64, _) => b"\x73\0\0\0",
+        }
+    }
+
+    /// Returns the encoding of an instruction that returns to the caller,
+    /// e.g. `ret`, `bx lr` or `jr $ra`.
+    ///
+    /// With MIPS, the instruction is followed by a branch delay slot, see
+    /// [`Arch::has_delay_slots`].
+    #[inline]
+    #[must_use]
+    pub const fn ret(self, endianness: Endianness) -> &'static [u8] {
+        match (self, endianness) {
+            (Self::X86 | Self::X86_64, _) => b"\xc3",
+            (Self::Arm, _) => b"\x1e\xff\x2f\xe1",
+            (Self::Thumb, _) => b"\x70\x47",
+            (Self::Aarch64, _) => b"\xc0\x03\x5f\xd6",
+            (Self::Mips, Endianness::Big) => b"\x03\xe0\x00\x08",
+            (Self::Mips, Endianness::Little) => b"\x08\x00\xe0\x03",
+            // c.jr ra
+            (Self::Riscv64, _) => b"\x82\x80",
+        }
+    }
+
+    /// Returns the address a branch must target to execute the code at
+    /// `address` in this instruction set.
+    ///
+    /// With Thumb, the lowest bit of the address is set, so that
+    /// interworking branches (`bx`, `blx`, `pop {pc}`) switch to Thumb.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Misaligned`]: the address is not aligned on instructions,
+    /// e.g. a Thumb address on an ARM target.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::arch::Arch;
+    ///
+    /// assert_eq!(Arch::Thumb.code_address(0x1000).unwrap(), 0x1001);
+    /// assert_eq!(Arch::Thumb.code_address(0x1001).unwrap(), 0x1001);
+    /// assert!(Arch::Arm.code_address(0x1001).is_err());
+    /// ```
+    #[inline]
+    pub const fn code_address(self, address: u64) -> Result<u64> {
+        match self {
+            Self::Thumb => Ok(address | 1),
+            Self::X86 | Self::X86_64 | Self::Arm | Self::Aarch64 | Self::Mips | Self::Riscv64 => {
+                let alignment = self.code_alignment();
+                if address & alignment.saturating_sub(1) == 0 {
+                    Ok(address)
+                } else {
+                    Err(Error::Misaligned {
+                        value: address,
+                        alignment,
+                    })
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::arch::Arch;
+    use crate::ops::Endianness;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_arch() -> Result<()> {
+        assert_eq!(Arch::X86.code_address(0x0804_8001)?, 0x0804_8001);
+        assert_eq!(Arch::Aarch64.code_address(0x40_0000)?, 0x40_0000);
+        assert!(matches!(
+            Arch::Aarch64.code_address(0x40_0002),
+            Err(Error::Misaligned {
+                value: 0x40_0002,
+                alignment: 4
+            })
+        ));
+        for arch in [
+            Arch::X86,
+            Arch::X86_64,
+            Arch::Arm,
+            Arch::Thumb,
+            Arch::Aarch64,
+            Arch::Riscv64,
+        ] {
+            let nop = arch.nop(Endianness::Big);
+            assert_eq!(u64::try_from(nop.len())?, arch.code_alignment());
+            assert_eq!(nop, arch.nop(Endianness::Little));
+            assert!(!arch.has_delay_slots());
+        }
+
+        let nop = Arch::Mips.nop(Endianness::Big);
+        assert!(!nop.contains(&0));
+        assert_eq!(
+            u32::from_be_bytes(nop.try_into().unwrap()),
+            u32::from_le_bytes(Arch::Mips.nop(Endianness::Little).try_into().unwrap())
+        );
+        assert!(!Arch::Mips.syscall(Endianness::Little).contains(&0));
+        assert!(Arch::Mips.has_delay_slots());
+
+        assert!(!Arch::Riscv64.nop(Endianness::Little).contains(&0));
+        assert_eq!(Arch::Riscv64.code_address(0x1_0002)?, 0x1_0002);
+        assert!(Arch::Riscv64.code_address(0x1_0001).is_err());
+        Ok(())
+    }
+}
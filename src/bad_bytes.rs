@@ -0,0 +1,418 @@
+//! Bad bytes, i.e. bytes that must not appear in payloads.
+//!
+//! Bad bytes usually come from the way the payload is delivered, e.g. null
+//! bytes terminate strings copied with `strcpy`, and newlines terminate
+//! lines read with `fgets`.
+
+use core::fmt;
+
+use crate::config::PointerWidth;
+use crate::prelude::*;
+
+/// A set of bad bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::bad_bytes::BadBytes;
+/// use shellcoder::error::Error;
+///
+/// let bad_bytes: BadBytes = b"\0\n".iter().copied().collect();
+/// assert!(bad_bytes.check(b"/bin/sh", 0).is_ok());
+/// assert!(matches!(
+///     bad_bytes.check(b"/bin/sh\0", 0x10),
+///     Err(Error::BadByteFound { offset: 0x17, byte: 0 })
+/// ));
+/// ```
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BadBytes([u8; 32]);
+
+impl BadBytes {
+    /// Instantiates an empty set of bad bytes.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self([0; 32])
+    }
+
+    /// Returns the word and the mask of a byte in the bitmap.
+    fn locate(byte: u8) -> (usize, u8) {
+        (
+            usize::from(byte.wrapping_shr(3)),
+            u8::checked_shl(1, u32::from(byte & 7)).unwrap_or_default(),
+        )
+    }
+
+    /// Adds a bad byte.
+    #[inline]
+    pub fn insert(&mut self, byte: u8) -> &mut Self {
+        let (index, mask) = Self::locate(byte);
+        if let Some(word) = self.0.get_mut(index) {
+            *word |= mask;
+        }
+        self
+    }
+
+    /// Returns `true` if a byte is bad.
+    #[inline]
+    #[must_use]
+    pub fn contains(&self, byte: u8) -> bool {
+        let (index, mask) = Self::locate(byte);
+        self.0.get(index).map_or(false, |word| word & mask != 0)
+    }
+
+    /// Returns `true` if there is no bad byte.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    /// Returns an iterator over the bad bytes, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX).filter(|byte| self.contains(*byte))
+    }
+
+    /// Checks that some bytes, located at `offset` in the payload, do not
+    /// contain any bad byte.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::BadByteFound`]: a bad byte has been found. The offset is
+    /// relative to the payload.
+    #[inline]
+    pub fn check(&self, bytes: &[u8], offset: usize) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        bytes
+            .iter()
+            .position(|byte| self.contains(*byte))
+            .map_or(Ok(()), |position| {
+                Err(Error::BadByteFound {
+                    offset: offset.saturating_add(position),
+                    byte: bytes.get(position).copied().unwrap_or_default(),
+                })
+            })
+    }
+
+    /// Writes an address whose encoding may contain bad bytes, using
+    /// primitives that make the target compute it, see [`Primitives`].
+    ///
+    /// The address is written as is if its encoding is clean. Otherwise, it
+    /// is split into two clean values whose sum is the address, if the
+    /// primitives support additions, or whose XOR is the address. The
+    /// chosen reconstruction is returned, so that it can be reported.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::BadByteFound`]: the encoding of the address contains a
+    ///    bad byte, and it cannot be reconstructed. The offset is relative
+    ///    to the encoding, in little endian.
+    ///  - [`Error::IntegerOverflow`]: the address does not fit in a pointer.
+    ///  - Any error returned by the primitives.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::bad_bytes::{BadBytes, Primitives, Reconstruction};
+    /// use shellcoder::config::PointerWidth;
+    /// # use shellcoder::Result;
+    ///
+    /// /// Values popped by a ROP chain.
+    /// struct Pops(Vec<u64>);
+    ///
+    /// impl Primitives for Pops {
+    ///     fn write(&mut self, value: u64) -> Result<()> {
+    ///         // pop rax
+    ///         self.0.push(value);
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn write_xor(&mut self, lhs: u64, rhs: u64) -> Option<Result<()>> {
+    ///         // pop rax; pop rcx; xor rax, rcx
+    ///         self.0.extend([lhs, rhs]);
+    ///         Some(Ok(()))
+    ///     }
+    /// }
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let bad_bytes: BadBytes = b"\0\n".iter().copied().collect();
+    /// let mut pops = Pops(Vec::new());
+    /// let reconstruction = bad_bytes.materialize(0x0040_0a10, PointerWidth::Bits32, &mut pops)?;
+    /// assert_eq!(reconstruction, Reconstruction::Xor(0x0101_0101, 0x0141_0b11));
+    /// assert_eq!(reconstruction.to_string(), "0x1010101 ^ 0x1410b11");
+    /// assert_eq!(pops.0, [0x0101_0101, 0x0141_0b11]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn materialize(
+        &self,
+        address: u64,
+        pointer_width: PointerWidth,
+        primitives: &mut impl Primitives,
+    ) -> Result<Reconstruction> {
+        let bytes = address.to_le_bytes();
+        let (encoding, rest) = bytes.split_at(pointer_width.bytes().min(bytes.len()));
+        if rest.iter().any(|byte| *byte != 0) {
+            return Err(Error::IntegerOverflow);
+        }
+        let error = match self.check(encoding, 0) {
+            Ok(()) => {
+                primitives.write(address)?;
+                return Ok(Reconstruction::Direct(address));
+            }
+            Err(error) => error,
+        };
+        if let Some((lhs, rhs)) = self.split_add(encoding, false, &mut 0) {
+            if let Some(result) = primitives.write_add(lhs, rhs) {
+                result?;
+                return Ok(Reconstruction::Add(lhs, rhs));
+            }
+        }
+        if let Some((lhs, rhs)) = self.split_xor(encoding) {
+            if let Some(result) = primitives.write_xor(lhs, rhs) {
+                result?;
+                return Ok(Reconstruction::Xor(lhs, rhs));
+            }
+        }
+        Err(error)
+    }
+
+    /// Splits a little-endian encoding into two clean values, whose sum
+    /// plus `carry` is the encoded value, modulo its width.
+    ///
+    /// `failed` records the positions that cannot be split, for each carry,
+    /// so that they are not explored twice.
+    fn split_add(&self, encoding: &[u8], carry: bool, failed: &mut u32) -> Option<(u64, u64)> {
+        let (&byte, rest) = match encoding.split_first() {
+            Some(split) => split,
+            None => return Some((0, 0)),
+        };
+        let state = u32::try_from(encoding.len())
+            .unwrap_or_default()
+            .wrapping_shl(1)
+            | u32::from(carry);
+        let bit = u32::from(true).wrapping_shl(state);
+        if *failed & bit != 0 {
+            return None;
+        }
+        for lhs in (1..=u8::MAX).filter(|lhs| !self.contains(*lhs)) {
+            let rhs = byte.wrapping_sub(lhs).wrapping_sub(u8::from(carry));
+            if self.contains(rhs) {
+                continue;
+            }
+            let sum = u16::from(lhs)
+                .saturating_add(u16::from(rhs))
+                .saturating_add(u16::from(carry));
+            if let Some((high_lhs, high_rhs)) = self.split_add(rest, sum > 0xff, failed) {
+                return Some((
+                    high_lhs.wrapping_shl(8) | u64::from(lhs),
+                    high_rhs.wrapping_shl(8) | u64::from(rhs),
+                ));
+            }
+        }
+        *failed |= bit;
+        None
+    }
+
+    /// Splits a little-endian encoding into two clean values, whose XOR is
+    /// the encoded value.
+    fn split_xor(&self, encoding: &[u8]) -> Option<(u64, u64)> {
+        encoding
+            .iter()
+            .rev()
+            .try_fold((u64::MIN, u64::MIN), |(lhs, rhs), &byte| {
+                let key =
+                    (1..=u8::MAX).find(|key| !self.contains(*key) && !self.contains(byte ^ key))?;
+                Some((
+                    lhs.wrapping_shl(8) | u64::from(key),
+                    rhs.wrapping_shl(8) | u64::from(byte ^ key),
+                ))
+            })
+    }
+}
+
+/// Primitives that write values, and make the target compute an address
+/// out of them, e.g. ROP gadgets such as `pop rax; pop rcx; add rax, rcx`,
+/// see [`BadBytes::materialize`].
+pub trait Primitives {
+    /// Writes an address as is.
+    ///
+    /// # Errors
+    ///
+    /// Any error raised while writing.
+    fn write(&mut self, value: u64) -> Result<()>;
+
+    /// Writes two values, whose sum is the address, or returns `None` if
+    /// additions are not supported.
+    ///
+    /// Defaults to unsupported.
+    #[inline]
+    fn write_add(&mut self, _lhs: u64, _rhs: u64) -> Option<Result<()>> {
+        None
+    }
+
+    /// Writes two values, whose XOR is the address, or returns `None` if
+    /// XOR is not supported.
+    ///
+    /// Defaults to unsupported.
+    #[inline]
+    fn write_xor(&mut self, _lhs: u64, _rhs: u64) -> Option<Result<()>> {
+        None
+    }
+}
+
+/// How an address has been written, see [`BadBytes::materialize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Reconstruction {
+    /// The address, written as is.
+    Direct(u64),
+
+    /// Two values whose sum is the address.
+    Add(u64, u64),
+
+    /// Two values whose XOR is the address.
+    Xor(u64, u64),
+}
+
+impl Reconstruction {
+    /// Returns the address, as computed by the target.
+    #[inline]
+    #[must_use]
+    pub const fn address(self) -> u64 {
+        match self {
+            Self::Direct(address) => address,
+            Self::Add(lhs, rhs) => lhs.wrapping_add(rhs),
+            Self::Xor(lhs, rhs) => lhs ^ rhs,
+        }
+    }
+}
+
+impl fmt::Display for Reconstruction {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Direct(address) => write!(fmt, "{address:#x}"),
+            Self::Add(lhs, rhs) => write!(fmt, "{lhs:#x} + {rhs:#x}"),
+            Self::Xor(lhs, rhs) => write!(fmt, "{lhs:#x} ^ {rhs:#x}"),
+        }
+    }
+}
+
+impl fmt::Debug for BadBytes {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<u8> for BadBytes {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut bad_bytes = Self::new();
+        for byte in iter {
+            bad_bytes.insert(byte);
+        }
+        bad_bytes
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps
+)]
+mod tests {
+    use crate::bad_bytes::{BadBytes, Primitives, Reconstruction};
+    use crate::config::PointerWidth;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_bad_bytes() -> Result<()> {
+        let mut bad_bytes = BadBytes::new();
+        assert!(bad_bytes.is_empty());
+        bad_bytes.insert(0).insert(0x0a).insert(0xff);
+        assert!(!bad_bytes.is_empty());
+        assert!(bad_bytes.contains(0));
+        assert!(bad_bytes.contains(0x0a));
+        assert!(bad_bytes.contains(0xff));
+        assert!(!bad_bytes.contains(0x0b));
+        assert!(bad_bytes.iter().eq([0, 0x0a, 0xff]));
+        assert!(bad_bytes.check(b"AAAA", 0).is_ok());
+        assert!(matches!(
+            bad_bytes.check(b"AA\nA", 2),
+            Err(Error::BadByteFound {
+                offset: 4,
+                byte: 0x0a
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_materialize() -> Result<()> {
+        /// Primitives that support additions, and count what they write.
+        struct Adder(usize);
+
+        impl Primitives for Adder {
+            fn write(&mut self, _value: u64) -> Result<()> {
+                self.0 += 1;
+                Ok(())
+            }
+
+            fn write_add(&mut self, _lhs: u64, _rhs: u64) -> Option<Result<()>> {
+                self.0 += 2;
+                Some(Ok(()))
+            }
+        }
+
+        /// Primitives that only write values as is.
+        struct Writer;
+
+        impl Primitives for Writer {
+            fn write(&mut self, _value: u64) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        let bad_bytes: BadBytes = [0, 0x0a, 0xff].into_iter().collect();
+        let mut adder = Adder(0);
+        assert_eq!(
+            bad_bytes.materialize(0x4141, PointerWidth::Bits16, &mut adder)?,
+            Reconstruction::Direct(0x4141)
+        );
+        for address in [0x0040_0a10, 0x00ff_0000, 0x0100_0000, 0] {
+            let reconstruction =
+                bad_bytes.materialize(address, PointerWidth::Bits32, &mut adder)?;
+            assert!(matches!(reconstruction, Reconstruction::Add(..)));
+            assert_eq!(reconstruction.address() & 0xffff_ffff, address);
+            if let Reconstruction::Add(lhs, rhs) = reconstruction {
+                assert!(bad_bytes.check(&lhs.to_le_bytes()[..4], 0).is_ok());
+                assert!(bad_bytes.check(&rhs.to_le_bytes()[..4], 0).is_ok());
+            }
+        }
+        assert_eq!(adder.0, 9);
+        assert!(matches!(
+            bad_bytes.materialize(0x1_0000, PointerWidth::Bits16, &mut adder),
+            Err(Error::IntegerOverflow)
+        ));
+
+        // Without additions nor XOR, bad bytes cannot be avoided.
+        assert!(matches!(
+            bad_bytes.materialize(0x0a41, PointerWidth::Bits16, &mut Writer),
+            Err(Error::BadByteFound {
+                offset: 1,
+                byte: 0x0a
+            })
+        ));
+        Ok(())
+    }
+}
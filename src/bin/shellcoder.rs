@@ -0,0 +1,156 @@
+//! Builds payloads from recipes.
+//!
+//! ```text
+//! shellcoder [-f raw|hex|c|base64|gdb|url] [-a ADDRESS] [-o OUTPUT] RECIPE
+//! ```
+//!
+//! `ADDRESS` is the address the payload is written at by the `gdb` format.
+//!
+//! The syntax of the recipe is guessed from its extension: `.json`, `.yaml`,
+//! `.yml` or `.toml`.
+
+use core::fmt::Write as _;
+use std::env;
+use std::error::Error as _;
+use std::fs;
+use std::io::{self, Write as _};
+use std::path::Path;
+use std::process::ExitCode;
+
+use shellcoder::alloc::Shellcoder;
+use shellcoder::error::Error;
+use shellcoder::format::Format;
+use shellcoder::recipe::Recipe;
+
+/// Usage of the command.
+const USAGE: &str =
+    "usage: shellcoder [-f raw|hex|c|base64|gdb|url] [-a ADDRESS] [-o OUTPUT] RECIPE";
+
+/// Command line arguments.
+#[derive(Debug, Default)]
+struct Args {
+    /// Output format.
+    format: Format,
+
+    /// Output path. Standard output is used if missing.
+    output: Option<String>,
+
+    /// Path to the recipe.
+    recipe: String,
+}
+
+impl Args {
+    /// Parses the command line arguments.
+    ///
+    /// Returns `None` if the usage was requested.
+    #[allow(clippy::single_call_fn)]
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, String> {
+        let mut format = Format::default();
+        let mut address = 0;
+        let mut output = None;
+        let mut recipe = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-f" | "--format" => {
+                    let name = args.next().ok_or("missing value for `--format`")?;
+                    format = Format::from_name(&name)
+                        .ok_or_else(|| format!("unknown format `{name}`"))?;
+                }
+                "-a" | "--address" => {
+                    let value = args.next().ok_or("missing value for `--address`")?;
+                    address = parse_address(&value)
+                        .ok_or_else(|| format!("invalid address `{value}`"))?;
+                }
+                "-o" | "--output" => {
+                    output = Some(args.next().ok_or("missing value for `--output`")?);
+                }
+                "-h" | "--help" => return Ok(None),
+                _ if recipe.is_none() && !arg.starts_with('-') => recipe = Some(arg),
+                _ => return Err(format!("unexpected argument `{arg}`")),
+            }
+        }
+        Ok(Some(Self {
+            format: format.with_address(address),
+            output,
+            recipe: recipe.ok_or("missing recipe")?,
+        }))
+    }
+}
+
+/// Parses an address, either decimal or hexadecimal with a `0x` prefix.
+#[allow(clippy::single_call_fn)]
+fn parse_address(value: &str) -> Option<u64> {
+    value.strip_prefix("0x").map_or_else(
+        || value.parse().ok(),
+        |hex| u64::from_str_radix(hex, 16).ok(),
+    )
+}
+
+/// Loads a recipe, guessing its syntax from its extension.
+#[allow(clippy::single_call_fn)]
+fn load_recipe(path: &str) -> Result<Recipe, String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("cannot read `{path}`: {error}"))?;
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    match extension {
+        "yaml" | "yml" => Recipe::from_yaml(&content),
+        "toml" => Recipe::from_toml(&content),
+        _ => Recipe::from_json(&content),
+    }
+    .map_err(|error| format!("`{path}`: {}", report(&error)))
+}
+
+/// Formats an error followed by its sources, e.g. the error raised by the
+/// operation that failed.
+fn report(error: &Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        write!(message, ": {cause}").unwrap_or_default();
+        source = cause.source();
+    }
+    message
+}
+
+/// Runs the command.
+#[allow(clippy::single_call_fn)]
+fn run() -> Result<(), String> {
+    let args = match Args::parse(env::args().skip(1))? {
+        Some(args) => args,
+        None => {
+            return writeln!(io::stdout(), "{USAGE}")
+                .map_err(|error| format!("cannot write the usage: {error}"));
+        }
+    };
+    let recipe = load_recipe(&args.recipe)?;
+
+    let mut shellcoder = Shellcoder::new();
+    recipe
+        .apply(&mut shellcoder)
+        .map_err(|error| format!("cannot build the payload: {}", report(&error)))?;
+
+    let mut stream: Box<dyn io::Write> = match &args.output {
+        Some(path) => Box::new(
+            fs::File::create(path).map_err(|error| format!("cannot create `{path}`: {error}"))?,
+        ),
+        None => Box::new(io::stdout().lock()),
+    };
+    args.format
+        .write_to_io(shellcoder.as_bytes(), &mut stream)
+        .and_then(|()| stream.flush().map_err(Error::from))
+        .map_err(|error| format!("cannot write the payload: {}", report(&error)))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            // Nothing sensible can be done if stderr is not writable.
+            writeln!(io::stderr(), "{message}").unwrap_or_default();
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! C API.
+//!
+//! This module exposes the [`crate::alloc::Shellcoder`] to C and C++
+//! through a small and stable API. The corresponding header is
+//! `include/shellcoder.h`.
+//!
+//! A shared library can be built using the following command:
+//!
+//! ```shell
+//! $ cargo rustc --release --features capi --crate-type cdylib
+//! ```
+//!
+//! All functions returning an `int` return [`SHELLCODER_OK`] on success, or
+//! a negative error code.
+
+use core::ptr;
+use core::slice;
+use std::os::raw::c_int;
+
+use crate::alloc::Shellcoder;
+use crate::error::ErrorKind;
+use crate::ops::Endianness;
+use crate::prelude::*;
+use crate::Shellcoder as _;
+
+/// Success.
+pub const SHELLCODER_OK: c_int = 0;
+
+/// A null pointer was given.
+pub const SHELLCODER_ERR_NULL_POINTER: c_int = -1;
+
+/// An invalid argument was given, e.g. an unsupported integer width.
+pub const SHELLCODER_ERR_INVALID_ARGUMENT: c_int = -2;
+
+/// The output buffer is too small, or the maximum length is exceeded.
+pub const SHELLCODER_ERR_BUFFER_TOO_SMALL: c_int = -3;
+
+/// An integer overflowed.
+pub const SHELLCODER_ERR_INTEGER_OVERFLOW: c_int = -4;
+
+/// Any other error.
+pub const SHELLCODER_ERR_OTHER: c_int = -5;
+
+/// A bad byte has been found.
+pub const SHELLCODER_ERR_BAD_BYTE: c_int = -6;
+
+/// Converts a result into a status code.
+fn status<T>(result: &Result<T>) -> c_int {
+    match result.as_ref().map_err(Error::kind) {
+        Ok(_) => SHELLCODER_OK,
+        Err(ErrorKind::OutputBufferTooSmall | ErrorKind::MaxLengthExceeded) => {
+            SHELLCODER_ERR_BUFFER_TOO_SMALL
+        }
+        Err(ErrorKind::IntegerOverflow) => SHELLCODER_ERR_INTEGER_OVERFLOW,
+        Err(ErrorKind::InvalidBytecode | ErrorKind::Misaligned) => SHELLCODER_ERR_INVALID_ARGUMENT,
+        Err(ErrorKind::BadByteFound) => SHELLCODER_ERR_BAD_BYTE,
+        Err(
+            ErrorKind::Io
+            | ErrorKind::InvalidRecipe
+            | ErrorKind::UnexpectedPosition
+            | ErrorKind::Overlap
+            | ErrorKind::UnknownSymbol
+            | ErrorKind::UnknownArch
+            | ErrorKind::Unsupported
+            | ErrorKind::InvalidSymbolTable,
+        ) => SHELLCODER_ERR_OTHER,
+    }
+}
+
+/// Pushes an integer of a given width in bytes.
+fn push_int(
+    shellcoder: &mut Shellcoder,
+    value: u64,
+    width: usize,
+    little_endian: bool,
+) -> Result<&mut Shellcoder> {
+    let endianness = if little_endian {
+        Endianness::Little
+    } else {
+        Endianness::Big
+    };
+    match width {
+        1 => shellcoder.int_with(u8::try_from(value)?, endianness),
+        2 => shellcoder.int_with(u16::try_from(value)?, endianness),
+        4 => shellcoder.int_with(u32::try_from(value)?, endianness),
+        8 => shellcoder.int_with(value, endianness),
+        _ => Err(Error::IntegerOverflow),
+    }
+}
+
+/// Instantiates a new shellcoder.
+///
+/// The returned shellcoder must be freed using [`shellcoder_free`].
+#[no_mangle]
+#[must_use]
+pub extern "C" fn shellcoder_new() -> *mut Shellcoder {
+    Box::into_raw(Box::new(Shellcoder::new()))
+}
+
+/// Instantiates a new shellcoder with a maximum length in bytes.
+///
+/// The returned shellcoder must be freed using [`shellcoder_free`].
+#[no_mangle]
+#[must_use]
+pub extern "C" fn shellcoder_new_with_max_len(max_len: usize) -> *mut Shellcoder {
+    Box::into_raw(Box::new(Shellcoder::new_with_max_len(max_len)))
+}
+
+/// Frees a shellcoder. Null pointers are ignored.
+///
+/// # Safety
+///
+/// `handle` must be null, or have been returned by [`shellcoder_new`]
+/// or [`shellcoder_new_with_max_len`], and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_free(handle: *mut Shellcoder) {
+    if !handle.is_null() {
+        // SAFETY:
+        //
+        // The pointer has been allocated by `Box` in `shellcoder_new`.
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Pushes an integer in little endian. `width` is the size of the integer
+/// in bytes, i.e. 1, 2, 4 or 8.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid shellcoder.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_int_le(
+    handle: *mut Shellcoder,
+    value: u64,
+    width: usize,
+) -> c_int {
+    // SAFETY:
+    //
+    // The caller guarantees that the pointer is null or valid.
+    let shellcoder = match unsafe { handle.as_mut() } {
+        Some(shellcoder) => shellcoder,
+        None => return SHELLCODER_ERR_NULL_POINTER,
+    };
+    if !matches!(width, 1 | 2 | 4 | 8) {
+        return SHELLCODER_ERR_INVALID_ARGUMENT;
+    }
+    status(&push_int(shellcoder, value, width, true))
+}
+
+/// Pushes an integer in big endian. `width` is the size of the integer
+/// in bytes, i.e. 1, 2, 4 or 8.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid shellcoder.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_int_be(
+    handle: *mut Shellcoder,
+    value: u64,
+    width: usize,
+) -> c_int {
+    // SAFETY:
+    //
+    // The caller guarantees that the pointer is null or valid.
+    let shellcoder = match unsafe { handle.as_mut() } {
+        Some(shellcoder) => shellcoder,
+        None => return SHELLCODER_ERR_NULL_POINTER,
+    };
+    if !matches!(width, 1 | 2 | 4 | 8) {
+        return SHELLCODER_ERR_INVALID_ARGUMENT;
+    }
+    status(&push_int(shellcoder, value, width, false))
+}
+
+/// Fills with `len` bytes of value `chr`.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid shellcoder.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_fill(handle: *mut Shellcoder, len: usize, chr: u8) -> c_int {
+    // SAFETY:
+    //
+    // The caller guarantees that the pointer is null or valid.
+    let shellcoder = match unsafe { handle.as_mut() } {
+        Some(shellcoder) => shellcoder,
+        None => return SHELLCODER_ERR_NULL_POINTER,
+    };
+    status(&shellcoder.fill(len, chr))
+}
+
+/// Advances the cursor by `n` bytes, filling the gap with zeroes.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid shellcoder.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_advance(handle: *mut Shellcoder, n: usize) -> c_int {
+    // SAFETY:
+    //
+    // The caller guarantees that the pointer is null or valid.
+    let shellcoder = match unsafe { handle.as_mut() } {
+        Some(shellcoder) => shellcoder,
+        None => return SHELLCODER_ERR_NULL_POINTER,
+    };
+    status(&shellcoder.advance(n))
+}
+
+/// Pushes a buffer of `len` bytes.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid shellcoder, and `buffer` must point
+/// to `len` readable bytes. `buffer` may be null if `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_push_buffer(
+    handle: *mut Shellcoder,
+    buffer: *const u8,
+    len: usize,
+) -> c_int {
+    // SAFETY:
+    //
+    // The caller guarantees that the pointer is null or valid.
+    let shellcoder = match unsafe { handle.as_mut() } {
+        Some(shellcoder) => shellcoder,
+        None => return SHELLCODER_ERR_NULL_POINTER,
+    };
+    let bytes = if len == 0 {
+        &[]
+    } else if buffer.is_null() {
+        return SHELLCODER_ERR_NULL_POINTER;
+    } else {
+        // SAFETY:
+        //
+        // The caller guarantees that `buffer` points to `len` bytes.
+        unsafe { slice::from_raw_parts(buffer, len) }
+    };
+    status(&shellcoder.push_buffer(bytes))
+}
+
+/// Returns the number of bytes written so far, or zero if `handle` is
+/// null.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid shellcoder.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_len(handle: *const Shellcoder) -> usize {
+    // SAFETY:
+    //
+    // The caller guarantees that the pointer is null or valid.
+    unsafe { handle.as_ref() }.map_or(0, |shellcoder| shellcoder.as_bytes().len())
+}
+
+/// Copies the payload into a buffer of `out_len` bytes.
+///
+/// If `written` is not null, it receives the size of the payload, even if
+/// the buffer is too small.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid shellcoder, `out` must point to
+/// `out_len` writable bytes, and `written` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn shellcoder_finalize(
+    handle: *const Shellcoder,
+    out: *mut u8,
+    out_len: usize,
+    written: *mut usize,
+) -> c_int {
+    // SAFETY:
+    //
+    // The caller guarantees that the pointer is null or valid.
+    let shellcoder = match unsafe { handle.as_ref() } {
+        Some(shellcoder) => shellcoder,
+        None => return SHELLCODER_ERR_NULL_POINTER,
+    };
+    let payload = shellcoder.as_bytes();
+    if !written.is_null() {
+        // SAFETY:
+        //
+        // The caller guarantees that `written` is writable.
+        unsafe {
+            written.write(payload.len());
+        }
+    }
+    if payload.len() > out_len {
+        return SHELLCODER_ERR_BUFFER_TOO_SMALL;
+    }
+    if payload.is_empty() {
+        return SHELLCODER_OK;
+    }
+    if out.is_null() {
+        return SHELLCODER_ERR_NULL_POINTER;
+    }
+    // SAFETY:
+    //
+    // The caller guarantees that `out` points to `out_len` bytes, and
+    // `out_len` is greater than the length of the payload.
+    unsafe {
+        ptr::copy_nonoverlapping(payload.as_ptr(), out, payload.len());
+    }
+    SHELLCODER_OK
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::indexing_slicing,
+    clippy::multiple_unsafe_ops_per_block,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::undocumented_unsafe_blocks,
+    clippy::unnecessary_wraps,
+    clippy::unseparated_literal_suffix
+)]
+mod tests {
+    use core::ptr;
+
+    use crate::capi::{
+        shellcoder_advance, shellcoder_fill, shellcoder_finalize, shellcoder_free,
+        shellcoder_int_be, shellcoder_int_le, shellcoder_len, shellcoder_new,
+        shellcoder_push_buffer, SHELLCODER_ERR_BUFFER_TOO_SMALL, SHELLCODER_ERR_INTEGER_OVERFLOW,
+        SHELLCODER_ERR_INVALID_ARGUMENT, SHELLCODER_ERR_NULL_POINTER, SHELLCODER_OK,
+    };
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_capi() -> Result<()> {
+        unsafe {
+            let shellcoder = shellcoder_new();
+            assert_eq!(shellcoder_int_le(shellcoder, 0xdead, 2), SHELLCODER_OK);
+            assert_eq!(shellcoder_int_be(shellcoder, 0xbeef, 4), SHELLCODER_OK);
+            assert_eq!(
+                shellcoder_int_be(shellcoder, 0x100, 1),
+                SHELLCODER_ERR_INTEGER_OVERFLOW
+            );
+            assert_eq!(
+                shellcoder_int_be(shellcoder, 1, 3),
+                SHELLCODER_ERR_INVALID_ARGUMENT
+            );
+            assert_eq!(shellcoder_fill(shellcoder, 2, b'A'), SHELLCODER_OK);
+            assert_eq!(shellcoder_advance(shellcoder, 1), SHELLCODER_OK);
+            assert_eq!(
+                shellcoder_push_buffer(shellcoder, b"sh".as_ptr(), 2),
+                SHELLCODER_OK
+            );
+            assert_eq!(
+                shellcoder_push_buffer(shellcoder, ptr::null(), 0),
+                SHELLCODER_OK
+            );
+            assert_eq!(shellcoder_len(shellcoder), 11);
+
+            let mut written = 0;
+            let mut small = [0u8; 4];
+            assert_eq!(
+                shellcoder_finalize(shellcoder, small.as_mut_ptr(), small.len(), &mut written),
+                SHELLCODER_ERR_BUFFER_TOO_SMALL
+            );
+            assert_eq!(written, 11);
+
+            let mut out = [0u8; 16];
+            assert_eq!(
+                shellcoder_finalize(shellcoder, out.as_mut_ptr(), out.len(), &mut written),
+                SHELLCODER_OK
+            );
+            assert_eq!(&out[..written], b"\xad\xde\0\0\xbe\xefAA\0sh");
+            shellcoder_free(shellcoder);
+
+            assert_eq!(
+                shellcoder_fill(ptr::null_mut(), 1, 0),
+                SHELLCODER_ERR_NULL_POINTER
+            );
+            shellcoder_free(ptr::null_mut());
+        }
+        Ok(())
+    }
+}
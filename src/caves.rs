@@ -0,0 +1,297 @@
+//! Code caves.
+//!
+//! When patching a binary in place, there is rarely a single region large
+//! enough for the whole payload, but there are often several unused ones,
+//! i.e. caves, e.g. padding between functions or the end of a section.
+//! [`Caves`] splits a payload made of fragments across them, and connects
+//! the fragments with jumps, see [`crate::ops::Insn::JmpRel32`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::arch::Arch;
+//! use shellcoder::caves::Caves;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let mut image = [0xccu8; 0x40];
+//! let mut caves = Caves::new(Arch::X86_64);
+//! caves.add_cave(0x08, 0x0c)?.add_cave(0x30, 0x10)?;
+//! caves
+//!     .add_fragment(b"\x48\x31\xf6\x56")
+//!     .add_fragment(b"\x48\xbf/bin//sh");
+//!
+//! let solution = caves.patch(&mut image)?;
+//! assert_eq!(solution.fragment_offsets(), [0x08, 0x30]);
+//! // jmp 0x30
+//! assert_eq!(image[0x0c..0x11], *b"\xe9\x1f\0\0\0");
+//! assert_eq!(image[0x30..0x3a], *b"\x48\xbf/bin//sh");
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc_crate::collections::BTreeMap;
+
+use crate::arch::Arch;
+use crate::ops::{Endianness, Insn, WriteInsn};
+use crate::prelude::*;
+
+/// A set of caves, and the fragments of a payload to fit in them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Caves {
+    /// Architecture of the target.
+    arch: Arch,
+
+    /// Endianness of instructions.
+    endianness: Endianness,
+
+    /// Lengths of caves, by offset.
+    regions: BTreeMap<usize, usize>,
+
+    /// Fragments, in execution order.
+    fragments: Vec<Vec<u8>>,
+}
+
+/// Where the fragments of a payload have been fitted, see [`Caves::solve`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    /// Offsets of fragments, in execution order.
+    fragments: Vec<usize>,
+
+    /// Jumps connecting fragments, by offset.
+    jumps: Vec<(usize, WriteInsn)>,
+}
+
+impl Caves {
+    /// Instantiates a new empty set of caves, for a little-endian target.
+    #[inline]
+    #[must_use]
+    pub fn new(arch: Arch) -> Self {
+        Self {
+            arch,
+            endianness: Endianness::Little,
+            regions: BTreeMap::new(),
+            fragments: Vec::new(),
+        }
+    }
+
+    /// Sets the endianness of instructions.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Adds a cave of `len` bytes at `offset`.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Overlap`]: the cave overlaps another one.
+    ///  - [`Error::IntegerOverflow`]: the cave ends past the address space.
+    #[inline]
+    pub fn add_cave(&mut self, offset: usize, len: usize) -> Result<&mut Self> {
+        let end = offset.checked_add(len).ok_or(Error::IntegerOverflow)?;
+        let overlapped = self
+            .regions
+            .range(..end)
+            .next_back()
+            .filter(|(&start, &previous)| start.saturating_add(previous) > offset);
+        if let Some((&previous, _len)) = overlapped {
+            return Err(Error::Overlap { offset, previous });
+        }
+        self.regions.insert(offset, len);
+        Ok(self)
+    }
+
+    /// Adds a fragment of code, executed after the fragments added before.
+    #[inline]
+    pub fn add_fragment(&mut self, fragment: impl Into<Vec<u8>>) -> &mut Self {
+        self.fragments.push(fragment.into());
+        self
+    }
+
+    /// Fits the fragments in the caves.
+    ///
+    /// Fragments are placed in order: a fragment follows the previous one
+    /// in the same cave if there is enough room left, so that execution
+    /// falls through, or is placed in the first cave with enough room
+    /// otherwise, and the previous fragment is followed by a jump to it.
+    /// Room for that jump is reserved after each fragment but the last.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::MaxLengthExceeded`]: a fragment does not fit in any
+    ///    cave. `limit` is the room left in the largest one.
+    ///  - Any error raised while encoding a jump, e.g. a cave that is out of
+    ///    the range of branches of the architecture.
+    #[inline]
+    pub fn solve(&self) -> Result<Solution> {
+        let jump_len = self.jump(0, 0)?.size().ok_or(Error::IntegerOverflow)?;
+        // Offset of the room left in each cave, by cave offset.
+        let mut cursors = self
+            .regions
+            .keys()
+            .map(|&offset| (offset, offset))
+            .collect::<BTreeMap<_, _>>();
+        let mut solution = Solution {
+            fragments: Vec::with_capacity(self.fragments.len()),
+            jumps: Vec::new(),
+        };
+        // Cave and end of the previous fragment.
+        let mut previous: Option<(usize, usize)> = None;
+        for (index, fragment) in self.fragments.iter().enumerate() {
+            let needed = if index.saturating_add(1) < self.fragments.len() {
+                fragment.len().saturating_add(jump_len)
+            } else {
+                fragment.len()
+            };
+            let room = |cave: &usize| {
+                let end = cave.saturating_add(self.regions.get(cave).copied().unwrap_or_default());
+                cursors
+                    .get(cave)
+                    .map_or(0, |&cursor| end.saturating_sub(cursor))
+            };
+            let cave = previous
+                .map(|(cave, _end)| cave)
+                .filter(|cave| room(cave) >= needed)
+                .or_else(|| {
+                    self.regions
+                        .keys()
+                        .copied()
+                        .find(|cave| room(cave) >= needed)
+                })
+                .ok_or_else(|| Error::MaxLengthExceeded {
+                    limit: self.regions.keys().map(room).max().unwrap_or_default(),
+                    attempted: needed,
+                })?;
+            let offset = cursors.get(&cave).copied().unwrap_or(cave);
+            if let Some((from, end)) = previous.filter(|&(_cave, end)| end != offset) {
+                solution.jumps.push((end, self.jump(end, offset)?));
+                cursors.insert(from, end.saturating_add(jump_len));
+            }
+            let end = offset.saturating_add(fragment.len());
+            cursors.insert(cave, end);
+            solution.fragments.push(offset);
+            previous = Some((cave, end));
+        }
+        Ok(solution)
+    }
+
+    /// Fits the fragments in the caves, see [`Caves::solve`], and writes
+    /// them and the jumps connecting them to `image`.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::OutputBufferTooSmall`]: a cave ends past the image.
+    ///  - Any error returned by [`Caves::solve`].
+    #[inline]
+    pub fn patch(&self, mut out: impl AsMut<[u8]>) -> Result<Solution> {
+        let solution = self.solve()?;
+        let image = out.as_mut();
+        for (fragment, &offset) in self.fragments.iter().zip(&solution.fragments) {
+            let end = offset.saturating_add(fragment.len());
+            image
+                .get_mut(offset..end)
+                .ok_or_else(|| Error::buffer_too_small(end))?
+                .copy_from_slice(fragment);
+        }
+        for (offset, jump) in &solution.jumps {
+            jump.write_to(image.get_mut(*offset..).unwrap_or_default())?;
+        }
+        Ok(solution)
+    }
+
+    /// Returns a jump from `from` to `to`.
+    fn jump(&self, from: usize, to: usize) -> Result<WriteInsn> {
+        let offset = i64::try_from(to)?
+            .checked_sub(i64::try_from(from)?)
+            .ok_or(Error::IntegerOverflow)?;
+        Ok(
+            WriteInsn::new(self.arch, Insn::JmpRel32(i32::try_from(offset)?))
+                .with_endianness(self.endianness),
+        )
+    }
+}
+
+impl Solution {
+    /// Returns the offsets of fragments, in execution order.
+    #[inline]
+    #[must_use]
+    pub fn fragment_offsets(&self) -> &[usize] {
+        &self.fragments
+    }
+
+    /// Returns the jumps connecting fragments, and their offsets.
+    #[inline]
+    #[must_use]
+    pub fn jumps(&self) -> &[(usize, WriteInsn)] {
+        &self.jumps
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::indexing_slicing,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unseparated_literal_suffix
+)]
+mod tests {
+    use crate::arch::Arch;
+    use crate::caves::Caves;
+    use crate::ops::{Endianness, Insn};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_caves() -> Result<()> {
+        let mut caves = Caves::new(Arch::Mips).with_endianness(Endianness::Big);
+        caves
+            .add_cave(0x100, 0x10)?
+            .add_cave(0x20, 0x14)?
+            .add_fragment([0x11; 4])
+            .add_fragment([0x22; 4])
+            .add_fragment([0x33; 8])
+            .add_fragment([0x44; 4]);
+        assert!(matches!(
+            caves.add_cave(0x28, 8),
+            Err(Error::Overlap {
+                offset: 0x28,
+                previous: 0x20
+            })
+        ));
+
+        // The first two fragments fall through in the first cave, which
+        // has no room left for the third one and its jump.
+        let solution = caves.solve()?;
+        assert_eq!(solution.fragment_offsets(), [0x20, 0x24, 0x100, 0x108]);
+        assert_eq!(solution.jumps().len(), 1);
+        let (offset, jump) = solution.jumps()[0];
+        assert_eq!(offset, 0x28);
+        assert_eq!(jump.insn(), Insn::JmpRel32(0xd8));
+
+        let mut image = [0u8; 0x110];
+        caves.patch(&mut image)?;
+        assert_eq!(
+            image[0x24..0x30],
+            *b"\x22\x22\x22\x22\x10\x00\x00\x35\x25\x20\x01\x01"
+        );
+        assert_eq!(
+            image[0x100..0x10c],
+            [0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x33, 0x44, 0x44, 0x44, 0x44]
+        );
+        assert!(caves.patch([0u8; 0x100]).is_err());
+
+        caves.add_fragment([0x55; 8]);
+        assert!(matches!(
+            caves.solve(),
+            Err(Error::MaxLengthExceeded {
+                limit: 8,
+                attempted: 12
+            })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+//! Stream ciphers, used by [`crate::ops::Encrypt`].
+//!
+//! Ciphers are implemented in a straightforward way, so that their output
+//! matches the decoders found in stubs and agents. They are meant to keep
+//! payloads from appearing in cleartext on the wire, not to be a
+//! constant-time cryptographic library.
+
+/// Constants of the `ChaCha20` state, i.e. `"expand 32-byte k"`.
+const CHACHA20_CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// Length of a `ChaCha20` block, in bytes.
+const CHACHA20_BLOCK_LEN: usize = 64;
+
+/// Number of `ChaCha20` double rounds, i.e. half the number of rounds.
+const CHACHA20_DOUBLE_ROUNDS: usize = 10;
+
+/// A stream cipher, along with its key and nonce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Cipher<'key> {
+    /// RC4, with a key of 1 to 256 bytes.
+    Rc4(&'key [u8]),
+
+    /// `ChaCha20`, as specified by RFC 8439.
+    ChaCha20 {
+        /// The 256-bit key.
+        key: [u8; 32],
+
+        /// The 96-bit nonce.
+        nonce: [u8; 12],
+
+        /// The initial block counter.
+        counter: u32,
+    },
+}
+
+impl<'key> Cipher<'key> {
+    /// Instantiates RC4 with a key, that must be 1 to 256 bytes long.
+    #[inline]
+    #[must_use]
+    pub fn rc4(key: &'key [u8]) -> Option<Self> {
+        (1..=256).contains(&key.len()).then(|| Self::Rc4(key))
+    }
+
+    /// Instantiates `ChaCha20` with a key and a nonce, starting at block 0.
+    #[inline]
+    #[must_use]
+    pub const fn chacha20(key: [u8; 32], nonce: [u8; 12]) -> Self {
+        Self::ChaCha20 {
+            key,
+            nonce,
+            counter: 0,
+        }
+    }
+
+    /// Returns the key.
+    #[inline]
+    #[must_use]
+    pub const fn key(&self) -> &[u8] {
+        match self {
+            Self::Rc4(key) => key,
+            Self::ChaCha20 { key, .. } => key,
+        }
+    }
+
+    /// Returns the nonce, if the cipher has one.
+    #[inline]
+    #[must_use]
+    pub const fn nonce(&self) -> Option<&[u8]> {
+        match self {
+            Self::Rc4(_) => None,
+            Self::ChaCha20 { nonce, .. } => Some(nonce),
+        }
+    }
+
+    /// Encrypts or decrypts bytes in place, i.e. XORs them with the
+    /// keystream.
+    #[inline]
+    pub fn apply(&self, bytes: &mut [u8]) {
+        match *self {
+            Self::Rc4(key) => rc4(key, bytes),
+            Self::ChaCha20 {
+                key,
+                nonce,
+                counter,
+            } => chacha20(&key, &nonce, counter, bytes),
+        }
+    }
+}
+
+/// XORs bytes with the RC4 keystream.
+#[allow(clippy::single_call_fn)]
+fn rc4(key: &[u8], bytes: &mut [u8]) {
+    let mut state: [u8; 256] = [0; 256];
+    for (value, slot) in (0..=u8::MAX).zip(state.iter_mut()) {
+        *slot = value;
+    }
+    let mut mixer: u8 = 0;
+    for (index, key_byte) in (0..=u8::MAX).zip(key.iter().cycle()) {
+        mixer = mixer
+            .wrapping_add(state.get(usize::from(index)).copied().unwrap_or_default())
+            .wrapping_add(*key_byte);
+        state.swap(usize::from(index), usize::from(mixer));
+    }
+    let (mut index, mut other): (u8, u8) = (0, 0);
+    for byte in bytes {
+        index = index.wrapping_add(1);
+        other = other.wrapping_add(state.get(usize::from(index)).copied().unwrap_or_default());
+        state.swap(usize::from(index), usize::from(other));
+        let sum = state
+            .get(usize::from(index))
+            .copied()
+            .unwrap_or_default()
+            .wrapping_add(state.get(usize::from(other)).copied().unwrap_or_default());
+        *byte ^= state.get(usize::from(sum)).copied().unwrap_or_default();
+    }
+}
+
+/// The `ChaCha20` quarter round.
+fn quarter_round(first: &mut u32, second: &mut u32, third: &mut u32, fourth: &mut u32) {
+    *first = first.wrapping_add(*second);
+    *fourth = (*fourth ^ *first).rotate_left(16);
+    *third = third.wrapping_add(*fourth);
+    *second = (*second ^ *third).rotate_left(12);
+    *first = first.wrapping_add(*second);
+    *fourth = (*fourth ^ *first).rotate_left(8);
+    *third = third.wrapping_add(*fourth);
+    *second = (*second ^ *third).rotate_left(7);
+}
+
+/// Computes a `ChaCha20` block.
+#[allow(clippy::single_call_fn)]
+fn chacha20_block(state: &[u32; 16]) -> [u8; CHACHA20_BLOCK_LEN] {
+    let [mut x0, mut x1, mut x2, mut x3, mut x4, mut x5, mut x6, mut x7, mut x8, mut x9, mut x10, mut x11, mut x12, mut x13, mut x14, mut x15] =
+        *state;
+    for _double_round in 0..CHACHA20_DOUBLE_ROUNDS {
+        quarter_round(&mut x0, &mut x4, &mut x8, &mut x12);
+        quarter_round(&mut x1, &mut x5, &mut x9, &mut x13);
+        quarter_round(&mut x2, &mut x6, &mut x10, &mut x14);
+        quarter_round(&mut x3, &mut x7, &mut x11, &mut x15);
+        quarter_round(&mut x0, &mut x5, &mut x10, &mut x15);
+        quarter_round(&mut x1, &mut x6, &mut x11, &mut x12);
+        quarter_round(&mut x2, &mut x7, &mut x8, &mut x13);
+        quarter_round(&mut x3, &mut x4, &mut x9, &mut x14);
+    }
+    let mixed = [
+        x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, x12, x13, x14, x15,
+    ];
+    let mut block: [u8; CHACHA20_BLOCK_LEN] = [0; CHACHA20_BLOCK_LEN];
+    for ((chunk, word), initial) in block.chunks_exact_mut(4).zip(mixed).zip(state) {
+        chunk.copy_from_slice(&word.wrapping_add(*initial).to_le_bytes());
+    }
+    block
+}
+
+/// Reads little-endian words.
+fn le_words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words: [u32; N] = [0; N];
+    for (word, chunk) in words.iter_mut().zip(bytes.chunks_exact(4)) {
+        *word = chunk.try_into().map(u32::from_le_bytes).unwrap_or_default();
+    }
+    words
+}
+
+/// XORs bytes with the `ChaCha20` keystream.
+#[allow(clippy::single_call_fn)]
+fn chacha20(key: &[u8; 32], nonce: &[u8; 12], counter: u32, bytes: &mut [u8]) {
+    let [c0, c1, c2, c3] = CHACHA20_CONSTANTS;
+    let [k0, k1, k2, k3, k4, k5, k6, k7] = le_words::<8>(key);
+    let [n0, n1, n2] = le_words::<3>(nonce);
+    let mut state = [
+        c0, c1, c2, c3, k0, k1, k2, k3, k4, k5, k6, k7, counter, n0, n1, n2,
+    ];
+    for chunk in bytes.chunks_mut(CHACHA20_BLOCK_LEN) {
+        let keystream = chacha20_block(&state);
+        for (byte, key_byte) in chunk.iter_mut().zip(keystream) {
+            *byte ^= key_byte;
+        }
+        if let Some(block_counter) = state.get_mut(12) {
+            *block_counter = block_counter.wrapping_add(1);
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::cipher::Cipher;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_rc4() -> Result<()> {
+        let mut bytes = *b"Plaintext";
+        let cipher = Cipher::rc4(b"Key").unwrap();
+        cipher.apply(&mut bytes);
+        assert_eq!(bytes, *b"\xbb\xf3\x16\xe8\xd9\x40\xaf\x0a\xd3");
+        cipher.apply(&mut bytes);
+        assert_eq!(bytes, *b"Plaintext");
+        assert_eq!(cipher.key(), b"Key");
+        assert_eq!(cipher.nonce(), None);
+        assert!(Cipher::rc4(b"").is_none());
+        assert!(Cipher::rc4(&[0; 257]).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_chacha20() -> Result<()> {
+        // RFC 8439, section 2.4.2.
+        let mut key: [u8; 32] = [0; 32];
+        for (value, byte) in (0..).zip(key.iter_mut()) {
+            *byte = value;
+        }
+        let cipher = Cipher::ChaCha20 {
+            key,
+            nonce: *b"\0\0\0\0\0\0\0\x4a\0\0\0\0",
+            counter: 1,
+        };
+        let mut bytes = *b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        cipher.apply(&mut bytes);
+        assert_eq!(
+            bytes,
+            *b"\x6e\x2e\x35\x9a\x25\x68\xf9\x80\x41\xba\x07\x28\xdd\x0d\x69\x81\
+               \xe9\x7e\x7a\xec\x1d\x43\x60\xc2\x0a\x27\xaf\xcc\xfd\x9f\xae\x0b\
+               \xf9\x1b\x65\xc5\x52\x47\x33\xab\x8f\x59\x3d\xab\xcd\x62\xb3\x57\
+               \x16\x39\xd6\x24\xe6\x51\x52\xab\x8f\x53\x0c\x35\x9f\x08\x61\xd8\
+               \x07\xca\x0d\xbf\x50\x0d\x6a\x61\x56\xa3\x8e\x08\x8a\x22\xb6\x5e\
+               \x52\xbc\x51\x4d\x16\xcc\xf8\x06\x81\x8c\xe9\x1a\xb7\x79\x37\x36\
+               \x5a\xf9\x0b\xbf\x74\xa3\x5b\xe6\xb4\x0b\x8e\xed\xf2\x78\x5e\x42\
+               \x87\x4d"
+        );
+        assert_eq!(cipher.nonce().map(<[u8]>::len), Some(12));
+        Ok(())
+    }
+}
@@ -0,0 +1,604 @@
+//! Configuration of shellcoders.
+//!
+//! All backends share the same [`Config`], which is usually built using a
+//! [`ShellcoderBuilder`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+//! use shellcoder::ops::Endianness;
+//! use shellcoder::Shellcoder as _;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let builder = ShellcoderBuilder::new()
+//!     .max_len(0x20)
+//!     .endianness(Endianness::Big)
+//!     .pointer_width(PointerWidth::Bits32)
+//!     .bad_bytes(b"\n".iter().copied().collect())
+//!     .alignment(4);
+//!
+//! let mut buffer = [0u8; 0x20];
+//! let mut shellcoder = builder.build_static(&mut buffer);
+//! shellcoder.int(0xdead_u16)?.push(b"sh")?;
+//! assert_eq!(shellcoder.get(), b"\xde\xad\0\0sh\0\0");
+//! assert!(shellcoder.push(b"\n").is_err());
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use crate::alloc::{self, Vectored};
+use crate::arch::Arch;
+use crate::bad_bytes::BadBytes;
+#[cfg(feature = "std")]
+use crate::io::Shellcoder as Io;
+use crate::ops::{Endianness, Insn, WriteInsn};
+use crate::prelude::*;
+use crate::r#static;
+
+/// Width of pointers on the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum PointerWidth {
+    /// 16-bit pointers.
+    Bits16,
+
+    /// 32-bit pointers.
+    Bits32,
+
+    /// 64-bit pointers.
+    Bits64,
+}
+
+impl PointerWidth {
+    /// Returns the width of pointers in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn bytes(self) -> usize {
+        match self {
+            Self::Bits16 => 2,
+            Self::Bits32 => 4,
+            Self::Bits64 => 8,
+        }
+    }
+}
+
+impl Default for PointerWidth {
+    /// 64-bit pointers, as most targets have.
+    #[inline]
+    fn default() -> Self {
+        Self::Bits64
+    }
+}
+
+/// A stub written in debug mode, to attach a debugger to the target at a
+/// given point of the payload, see [`ShellcoderBuilder::debug`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum DebugStub {
+    /// A breakpoint, see [`Insn::Int3`].
+    Breakpoint,
+
+    /// An infinite loop, see [`Insn::InfiniteLoop`], e.g. when the target
+    /// cannot be started under a debugger.
+    InfiniteLoop,
+}
+
+impl DebugStub {
+    /// Returns the instruction of the stub.
+    #[inline]
+    #[must_use]
+    pub const fn insn(self) -> Insn {
+        match self {
+            Self::Breakpoint => Insn::Int3,
+            Self::InfiniteLoop => Insn::InfiniteLoop,
+        }
+    }
+}
+
+/// Where a [`DebugStub`] is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum DebugPlacement {
+    /// At the entry point, i.e. before the first byte of the payload.
+    ///
+    /// The stub is not part of the layout of the payload: positions, e.g.
+    /// [`crate::Shellcoder::assert_at`], and alignment are relative to the
+    /// end of the stub.
+    Entry,
+
+    /// At each marker, see [`crate::Shellcoder::debug_marker`].
+    Marker,
+}
+
+/// Configuration of a shellcoder.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Config {
+    /// A maximum length in bytes.
+    max_len: Option<usize>,
+
+    /// Default endianness.
+    endianness: Endianness,
+
+    /// Width of pointers on the target.
+    pointer_width: PointerWidth,
+
+    /// Bytes that must not be written.
+    bad_bytes: BadBytes,
+
+    /// Alignment of the cursor after each operation, in bytes.
+    alignment: usize,
+
+    /// Architecture of the target, if known.
+    arch: Option<Arch>,
+
+    /// Debug stub and where it is written, if in debug mode.
+    debug: Option<(DebugStub, DebugPlacement)>,
+}
+
+impl Config {
+    /// Returns the maximum length in bytes, if any.
+    #[inline]
+    #[must_use]
+    pub const fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    /// Returns the default endianness.
+    #[inline]
+    #[must_use]
+    pub const fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Returns the width of pointers on the target.
+    #[inline]
+    #[must_use]
+    pub const fn pointer_width(&self) -> PointerWidth {
+        self.pointer_width
+    }
+
+    /// Returns the bytes that must not be written.
+    #[inline]
+    #[must_use]
+    pub const fn bad_bytes(&self) -> &BadBytes {
+        &self.bad_bytes
+    }
+
+    /// Returns the alignment of the cursor after each operation, in bytes.
+    /// `0` and `1` both mean that operations are not aligned.
+    #[inline]
+    #[must_use]
+    pub const fn alignment(&self) -> usize {
+        self.alignment
+    }
+
+    /// Returns the architecture of the target, if known.
+    #[inline]
+    #[must_use]
+    pub const fn arch(&self) -> Option<Arch> {
+        self.arch
+    }
+
+    /// Returns the debug stub and where it is written, if in debug mode.
+    #[inline]
+    #[must_use]
+    pub const fn debug(&self) -> Option<(DebugStub, DebugPlacement)> {
+        self.debug
+    }
+
+    /// Sets the maximum length in bytes.
+    pub(crate) const fn with_max_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Sets the default endianness.
+    pub(crate) const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets the bytes that must not be written.
+    pub(crate) const fn with_bad_bytes(mut self, bad_bytes: BadBytes) -> Self {
+        self.bad_bytes = bad_bytes;
+        self
+    }
+
+    /// Returns the stub to write at the entry point before an operation,
+    /// when `len` bytes have been written so far.
+    fn entry_stub(&self, len: usize) -> Result<Option<WriteInsn>> {
+        match self.debug {
+            Some((stub, DebugPlacement::Entry)) if len == 0 => {
+                let arch = self.arch.ok_or(Error::UnknownArch)?;
+                Ok(Some(
+                    WriteInsn::new(arch, stub.insn()).with_endianness(self.endianness),
+                ))
+            }
+            Some(_) | None => Ok(None),
+        }
+    }
+
+    /// Writes the stub at the entry point to `out` if nothing has been
+    /// written so far, i.e. if `len` is zero, and returns its length.
+    pub(crate) fn write_entry_stub(&self, len: usize, out: &mut [u8]) -> Result<usize> {
+        self.entry_stub(len)?.map_or(Ok(0), |stub| {
+            let n = stub.write_to(&mut *out)?;
+            self.check(len, out.get(..n).unwrap_or_default())?;
+            Ok(n)
+        })
+    }
+
+    /// Returns the position of the cursor once `len` bytes have been
+    /// written, i.e. without the stub at the entry point, if any.
+    pub(crate) fn position(&self, len: usize) -> usize {
+        let stub = self
+            .entry_stub(0)
+            .ok()
+            .flatten()
+            .and_then(|stub| stub.size());
+        len.saturating_sub(stub.unwrap_or_default())
+    }
+
+    /// Returns the number of padding bytes needed to align the cursor,
+    /// when `offset` bytes have been written, see [`Self::position`].
+    pub(crate) fn padding(&self, offset: usize) -> usize {
+        match self.position(offset).checked_rem(self.alignment) {
+            Some(0) | None => 0,
+            Some(rem) => self.alignment.saturating_sub(rem),
+        }
+    }
+
+    /// Checks that `len` bytes can be written at `offset`.
+    pub(crate) const fn check_len(&self, offset: usize, len: usize) -> Result<()> {
+        let attempted = offset.saturating_add(len);
+        match self.max_len {
+            Some(limit) if limit < attempted => Err(Error::MaxLengthExceeded { limit, attempted }),
+            Some(_) | None => Ok(()),
+        }
+    }
+
+    /// Checks that `bytes` can be written at `offset`.
+    pub(crate) fn check(&self, offset: usize, bytes: &[u8]) -> Result<()> {
+        self.check_len(offset, bytes.len())?;
+        self.bad_bytes.check(bytes, offset)
+    }
+
+    /// Writes an operation to a new buffer, followed by its padding, and
+    /// checks it can be written at `offset`.
+    #[cfg(feature = "std")]
+    pub(crate) fn render(&self, op: &(impl Op + ?Sized), offset: usize) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(op.size().unwrap_or_default());
+        let n = op.write_to_io(&mut bytes)?;
+        let padding = self.padding(offset.saturating_add(n));
+        bytes.resize(n.saturating_add(padding), 0);
+        self.check(offset, &bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns `true` if operations can be written as is, i.e. without
+    /// being checked nor padded.
+    #[cfg(feature = "std")]
+    pub(crate) fn is_passthrough(&self) -> bool {
+        self.max_len.is_none() && self.bad_bytes.is_empty() && self.alignment <= 1
+    }
+}
+
+/// A builder for shellcoders.
+///
+/// The same configuration can build any backend.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ShellcoderBuilder {
+    /// The configuration being built.
+    config: Config,
+}
+
+impl ShellcoderBuilder {
+    /// Instantiates a new builder, with the default configuration.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a maximum length in bytes.
+    ///
+    /// Pushing an operation that exceeds it fails with
+    /// [`Error::MaxLengthExceeded`].
+    #[inline]
+    #[must_use]
+    pub const fn max_len(mut self, max_len: usize) -> Self {
+        self.config = self.config.with_max_len(Some(max_len));
+        self
+    }
+
+    /// Sets the default endianness, see [`crate::Shellcoder::endianness`].
+    #[inline]
+    #[must_use]
+    pub const fn endianness(mut self, endianness: Endianness) -> Self {
+        self.config = self.config.with_endianness(endianness);
+        self
+    }
+
+    /// Sets the width of pointers on the target.
+    #[inline]
+    #[must_use]
+    pub const fn pointer_width(mut self, pointer_width: PointerWidth) -> Self {
+        self.config.pointer_width = pointer_width;
+        self
+    }
+
+    /// Sets the bytes that must not be written.
+    ///
+    /// Pushing an operation that writes a bad byte fails with
+    /// [`Error::BadByteFound`].
+    #[inline]
+    #[must_use]
+    pub const fn bad_bytes(mut self, bad_bytes: BadBytes) -> Self {
+        self.config = self.config.with_bad_bytes(bad_bytes);
+        self
+    }
+
+    /// Sets the alignment of the cursor, in bytes: each operation is
+    /// followed by null bytes up to the next multiple of `alignment`.
+    #[inline]
+    #[must_use]
+    pub const fn alignment(mut self, alignment: usize) -> Self {
+        self.config.alignment = alignment;
+        self
+    }
+
+    /// Sets the architecture of the target, see
+    /// [`crate::Shellcoder::code_ptr`].
+    #[inline]
+    #[must_use]
+    pub const fn arch(mut self, arch: Arch) -> Self {
+        self.config.arch = Some(arch);
+        self
+    }
+
+    /// Enables the debug mode: a stub suited to the architecture of the
+    /// target, e.g. `int3` or `brk #0`, is written at `placement`, so that a
+    /// debugger can be attached right there without editing the payload.
+    ///
+    /// Writing the stub fails with [`Error::UnknownArch`] if the
+    /// architecture has not been set, see [`ShellcoderBuilder::arch`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::arch::Arch;
+    /// use shellcoder::config::{DebugPlacement, DebugStub, ShellcoderBuilder};
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = ShellcoderBuilder::new()
+    ///     .arch(Arch::X86_64)
+    ///     .debug(DebugStub::Breakpoint, DebugPlacement::Entry)
+    ///     .build_static(&mut buffer);
+    /// shellcoder.push(b"\x0f\x05")?;
+    /// assert_eq!(shellcoder.get(), b"\xcc\x0f\x05");
+    ///
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = ShellcoderBuilder::new()
+    ///     .arch(Arch::Aarch64)
+    ///     .debug(DebugStub::InfiniteLoop, DebugPlacement::Marker)
+    ///     .build_static(&mut buffer);
+    /// shellcoder
+    ///     .push(b"\x1f\x20\x03\xd5")?
+    ///     .debug_marker()?
+    ///     .push(b"\x01\0\0\xd4")?;
+    /// assert_eq!(&shellcoder.get()[4..8], b"\0\0\0\x14");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn debug(mut self, stub: DebugStub, placement: DebugPlacement) -> Self {
+        self.config.debug = Some((stub, placement));
+        self
+    }
+
+    /// Returns the configuration.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Builds a [`crate::alloc::Shellcoder`].
+    #[cfg(feature = "std")]
+    #[inline]
+    #[must_use]
+    pub fn build_alloc(self) -> alloc::Shellcoder {
+        alloc::Shellcoder::new().with_config(self.config)
+    }
+
+    /// Builds a [`crate::alloc::Vectored`].
+    #[cfg(feature = "std")]
+    #[inline]
+    #[must_use]
+    pub fn build_vectored<'buf>(self) -> Vectored<'buf> {
+        Vectored::new().with_config(self.config)
+    }
+
+    /// Builds a [`crate::io::Shellcoder`].
+    #[cfg(feature = "std")]
+    #[inline]
+    #[must_use]
+    pub fn build_io(self, stream: &mut impl io::Write) -> Io<'_> {
+        Io::new(stream).with_config(self.config)
+    }
+
+    /// Builds a [`Shellcoder`](crate::static::Shellcoder).
+    #[inline]
+    #[must_use]
+    pub fn build_static(self, buffer: &mut [u8]) -> r#static::Shellcoder<'_> {
+        r#static::Shellcoder::new(buffer).with_config(self.config)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::shadow_unrelated,
+    clippy::unnecessary_wraps,
+    clippy::unseparated_literal_suffix
+)]
+mod tests {
+    use crate::config::{PointerWidth, ShellcoderBuilder};
+    use crate::ops::Endianness;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_config() -> Result<()> {
+        let config = *ShellcoderBuilder::new().alignment(8).max_len(12).config();
+        assert_eq!(config.padding(0), 0);
+        assert_eq!(config.padding(3), 5);
+        assert_eq!(config.padding(16), 0);
+        assert!(config.check_len(8, 4).is_ok());
+        assert!(matches!(
+            config.check_len(8, 5),
+            Err(Error::MaxLengthExceeded {
+                limit: 12,
+                attempted: 13
+            })
+        ));
+        assert_eq!(config.endianness(), Endianness::Little);
+        assert_eq!(config.pointer_width(), PointerWidth::Bits64);
+        assert_eq!(PointerWidth::Bits32.bytes(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug() -> Result<()> {
+        use crate::arch::Arch;
+        use crate::config::{DebugPlacement, DebugStub};
+        use crate::Shellcoder as _;
+
+        let mut buffer = [0u8; 16];
+        let mut shellcoder = ShellcoderBuilder::new()
+            .arch(Arch::Mips)
+            .endianness(Endianness::Big)
+            .debug(DebugStub::Breakpoint, DebugPlacement::Entry)
+            .build_static(&mut buffer);
+        shellcoder.int(1_u16)?.debug_marker()?.int(2_u16)?;
+        assert_eq!(shellcoder.get(), b"\0\0\0\x0d\0\x01\0\x02");
+
+        let mut buffer = [0u8; 16];
+        let mut shellcoder = ShellcoderBuilder::new()
+            .arch(Arch::Riscv64)
+            .build_static(&mut buffer);
+        shellcoder.int(1_u16)?.debug_marker()?;
+        assert_eq!(shellcoder.get(), b"\x01\0");
+
+        let mut buffer = [0u8; 16];
+        let mut shellcoder = ShellcoderBuilder::new()
+            .debug(DebugStub::InfiniteLoop, DebugPlacement::Marker)
+            .build_static(&mut buffer);
+        shellcoder.int(1_u16)?;
+        assert!(matches!(shellcoder.debug_marker(), Err(Error::UnknownArch)));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_debug_entry() -> Result<()> {
+        use crate::arch::Arch;
+        use crate::config::{DebugPlacement, DebugStub};
+        use crate::ops::{Fill, WriteCow};
+        use crate::plan::{Plan, Region};
+        use crate::Shellcoder as _;
+
+        let builder = ShellcoderBuilder::new()
+            .arch(Arch::X86_64)
+            .alignment(2)
+            .debug(DebugStub::Breakpoint, DebugPlacement::Entry);
+
+        let mut buffer = [0u8; 16];
+        let mut shellcoder = builder.build_static(&mut buffer);
+        let empty = shellcoder.snapshot();
+        let (n, next) = shellcoder.add_counted(Fill::new(1, b'A'))?;
+        assert_eq!(n, 2);
+        assert_eq!(next.position(), 2);
+        shellcoder.restore(empty)?.assert_at(0)?.pad_to(3, b'B')?;
+        assert_eq!(shellcoder.get(), b"\xccBBB\0");
+        assert!(shellcoder.assert_at(4).is_ok());
+
+        let mut alloc = builder.build_alloc();
+        assert!(alloc.try_add(Fill::new(1, b'\xcc')).is_ok());
+        let plan = [Fill::new(1, b'A'), Fill::new(2, b'B')]
+            .into_iter()
+            .collect::<Plan<'_>>();
+        let layout = plan.apply_with_layout(&mut alloc)?;
+        let ranges = layout.regions().iter().map(Region::range);
+        assert_eq!(ranges.collect::<Vec<_>>(), [1..3, 3..5]);
+        assert_eq!(alloc.as_bytes(), b"\xccA\0BB");
+        assert_eq!(alloc.snapshot().n_ops(), 2);
+
+        let mut vectored = builder.build_vectored();
+        vectored.push_cow(WriteCow::new(&b"A"[..]))?.fill(2, b'B')?;
+        assert_eq!(vectored.to_vec(), alloc.as_bytes());
+
+        let mut stream = Vec::new();
+        let mut io = builder
+            .bad_bytes(b"\xcc".iter().copied().collect())
+            .build_io(&mut stream);
+        assert!(io.try_add(Fill::new(1, b'A')).is_err());
+        assert!(io.fill(1, b'A').is_err());
+        assert!(io.is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_backends() -> Result<()> {
+        use crate::ops::Fill;
+        use crate::Shellcoder as _;
+
+        let builder = ShellcoderBuilder::new()
+            .alignment(4)
+            .bad_bytes(b"B".iter().copied().collect())
+            .endianness(Endianness::Big);
+
+        let mut alloc = builder.build_alloc();
+        alloc.int(0x4141_u16)?.fill(1, b'C')?;
+        assert!(alloc.push(b"AB").is_err());
+        assert_eq!(alloc.as_bytes(), b"AA\0\0C\0\0\0");
+
+        let mut vectored = builder.build_vectored();
+        vectored.int(0x4141_u16)?.fill(1, b'C')?;
+        assert!(vectored.push(b"AB").is_err());
+        assert_eq!(vectored.to_vec(), alloc.as_bytes());
+
+        let mut stream = Vec::new();
+        let mut io = builder.build_io(&mut stream);
+        io.int(0x4141_u16)?.fill(1, b'C')?;
+        assert!(io.push(b"AB").is_err());
+        assert_eq!(io.len(), 8);
+        assert_eq!(stream, alloc.as_bytes());
+
+        let mut buffer = [0xccu8; 16];
+        let mut shellcoder = builder.build_static(&mut buffer);
+        shellcoder.int(0x4141_u16)?.fill(1, b'C')?;
+        assert!(shellcoder.push(b"AB").is_err());
+        assert!(shellcoder.try_add(Fill::new(1, b'B')).is_err());
+        assert_eq!(shellcoder.get(), alloc.as_bytes());
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+//! Comparison of payloads.
+//!
+//! [`diff`] reports the ranges of bytes that differ between two payloads.
+//! With the `std` feature, [`plans`] also attributes them to the
+//! operations of two plans.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::diff::diff;
+//!
+//! let ranges = diff(b"AAAABBBBCC", b"AAAXBBBBYYZ").collect::<Vec<_>>();
+//! assert_eq!(ranges, [3..4, 8..11]);
+//! ```
+
+#[cfg(feature = "std")]
+use core::fmt;
+use core::iter::FusedIterator;
+use core::ops::Range;
+
+#[cfg(feature = "std")]
+use crate::plan::{Plan, Region};
+#[cfg(feature = "std")]
+use crate::prelude::*;
+
+/// Iterator over the ranges of bytes that differ between two payloads,
+/// see [`diff`].
+#[derive(Clone, Debug)]
+pub struct Ranges<'left, 'right> {
+    /// The first payload.
+    left: &'left [u8],
+
+    /// The second payload.
+    right: &'right [u8],
+
+    /// Offset of the next byte to compare.
+    offset: usize,
+}
+
+impl Ranges<'_, '_> {
+    /// Returns `true` if the bytes at `offset` differ. Bytes past the end of
+    /// the shortest payload always differ.
+    fn differs(&self, offset: usize) -> bool {
+        self.left.get(offset) != self.right.get(offset)
+    }
+}
+
+impl Iterator for Ranges<'_, '_> {
+    type Item = Range<usize>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.left.len().max(self.right.len());
+        let start = (self.offset..len).find(|&offset| self.differs(offset))?;
+        let end = (start..len)
+            .find(|&offset| !self.differs(offset))
+            .unwrap_or(len);
+        self.offset = end;
+        Some(start..end)
+    }
+}
+
+impl FusedIterator for Ranges<'_, '_> {}
+
+/// Returns the ranges of bytes that differ between two payloads, in
+/// ascending order.
+///
+/// If the payloads have different lengths, the extra bytes of the longest
+/// one are part of the last range.
+#[inline]
+#[must_use]
+pub const fn diff<'left, 'right>(left: &'left [u8], right: &'right [u8]) -> Ranges<'left, 'right> {
+    Ranges {
+        left,
+        right,
+        offset: 0,
+    }
+}
+
+/// A range of bytes that differ between the payloads of two plans, along
+/// with the operations that wrote them.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+    /// The offsets of the bytes.
+    range: Range<usize>,
+
+    /// Regions of the first plan that overlap the range.
+    left: Vec<Region>,
+
+    /// Regions of the second plan that overlap the range.
+    right: Vec<Region>,
+}
+
+#[cfg(feature = "std")]
+impl Hunk {
+    /// Returns the offsets of the bytes that differ.
+    #[inline]
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the regions of the first plan that overlap the range.
+    #[inline]
+    #[must_use]
+    pub fn left(&self) -> &[Region] {
+        &self.left
+    }
+
+    /// Returns the regions of the second plan that overlap the range.
+    #[inline]
+    #[must_use]
+    pub fn right(&self) -> &[Region] {
+        &self.right
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Hunk {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(fmt, "{:#06x}..{:#06x}", self.range.start, self.range.end)?;
+        for region in &self.left {
+            writeln!(fmt, "- #{:<2} {}", region.index(), region.label())?;
+        }
+        for region in &self.right {
+            writeln!(fmt, "+ #{:<2} {}", region.index(), region.label())?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the payloads of two plans, and attributes the ranges of bytes
+/// that differ to the operations that wrote them.
+///
+/// # Errors
+///
+/// [`Error::Io`]: an I/O error occurred.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::diff::plans;
+/// use shellcoder::ops::{Fill, WriteInteger};
+/// use shellcoder::plan::Plan;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut before = Plan::new();
+/// before.push(Fill::new(4, b'A')).push(WriteInteger::new_le(0x10u16));
+/// let mut after = Plan::new();
+/// after.push(Fill::new(4, b'A')).push(WriteInteger::new_le(0x20u16));
+///
+/// let hunks = plans(&before, &after)?;
+/// assert_eq!(hunks.len(), 1);
+/// assert_eq!(
+///     hunks[0].to_string(),
+///     "0x0004..0x0005\n- #1  LittleEndian(16)\n+ #1  LittleEndian(32)\n",
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[inline]
+pub fn plans(left: &Plan<'_>, right: &Plan<'_>) -> Result<Vec<Hunk>> {
+    let (left_bytes, right_bytes) = (render(left)?, render(right)?);
+    let (left_layout, right_layout) = (left.layout(), right.layout());
+    Ok(diff(&left_bytes, &right_bytes)
+        .map(|range| Hunk {
+            left: left_layout.overlapping(range.clone()).cloned().collect(),
+            right: right_layout.overlapping(range.clone()).cloned().collect(),
+            range,
+        })
+        .collect())
+}
+
+/// Writes the payload of a plan to a new buffer.
+#[cfg(feature = "std")]
+fn render(plan: &Plan<'_>) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(plan.size().unwrap_or_default());
+    plan.write_to_io(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::indexing_slicing,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps
+)]
+mod tests {
+    use core::iter;
+
+    use crate::diff::diff;
+    #[cfg(feature = "std")]
+    use crate::diff::plans;
+    #[cfg(feature = "std")]
+    use crate::ops::{Fill, WriteBuffer};
+    #[cfg(feature = "std")]
+    use crate::plan::{Plan, Region};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_diff() -> Result<()> {
+        assert_eq!(diff(b"", b"").next(), None);
+        assert_eq!(diff(b"ABC", b"ABC").next(), None);
+        assert!(diff(b"ABCD", b"xBxx").eq([0..1, 2..4]));
+        assert!(diff(b"ABCD", b"AB").eq(iter::once(2..4)));
+        assert!(diff(b"", b"AB").eq(iter::once(0..2)));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_plans() -> Result<()> {
+        let mut left = Plan::new();
+        left.push(Fill::new(2, b'A'))
+            .push(WriteBuffer::new(b"sh"))
+            .push(Fill::new(2, b'B'));
+        let mut right = Plan::new();
+        right
+            .push(Fill::new(3, b'A'))
+            .push(WriteBuffer::new(b"h"))
+            .push(Fill::new(2, b'B'));
+        let hunks = plans(&left, &right)?;
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].range(), 2..3);
+        assert!(hunks[0].left().iter().map(Region::index).eq([1]));
+        assert!(hunks[0].right().iter().map(Region::index).eq([0]));
+        assert!(plans(&left, &left)?.is_empty());
+        Ok(())
+    }
+}
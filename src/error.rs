@@ -1,10 +1,14 @@
 //! Errors that may happen in this crate.
 
 use core::fmt;
+use core::num::TryFromIntError;
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
 #[cfg(feature = "std")]
 use std::io;
 
 /// Errors that may happen in this crate.
+#[allow(clippy::error_impl_error)]
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Error {
@@ -16,8 +20,187 @@ pub enum Error {
     /// Value corresponds to the minimum size it is expected.
     OutputBufferTooSmall(usize),
 
+    /// A maximum length has been exceeded.
+    MaxLengthExceeded {
+        /// The maximum length in bytes.
+        limit: usize,
+
+        /// The length in bytes that would have been reached.
+        attempted: usize,
+    },
+
+    /// A bad byte has been found.
+    BadByteFound {
+        /// Offset of the bad byte in the payload.
+        offset: usize,
+
+        /// The bad byte.
+        byte: u8,
+    },
+
     /// Integer overflow.
     IntegerOverflow,
+
+    /// Invalid bytecode.
+    /// Value corresponds to the offset of the faulty operation.
+    InvalidBytecode(usize),
+
+    /// The cursor is not where it is expected to be, see
+    /// [`crate::ops::AssertPosition`].
+    UnexpectedPosition {
+        /// The expected offset.
+        expected: usize,
+
+        /// The offset of the cursor.
+        actual: usize,
+    },
+
+    /// Two contents of a layout overlap, see [`crate::fit::Fit`].
+    Overlap {
+        /// Offset of the content that has been rejected.
+        offset: usize,
+
+        /// Offset of the content it overlaps.
+        previous: usize,
+    },
+
+    /// A symbol is missing from a table, see [`crate::symbols::Table`], or
+    /// a gadget from a catalog, see [`crate::rop::Catalog`].
+    #[cfg(feature = "std")]
+    UnknownSymbol(String),
+
+    /// Invalid symbol table.
+    /// Value corresponds to the line of the faulty entry, starting at 1.
+    InvalidSymbolTable(usize),
+
+    /// A value is not aligned as the target expects it, e.g. a code
+    /// address, see [`crate::arch::Arch::code_address`].
+    Misaligned {
+        /// The value.
+        value: u64,
+
+        /// The expected alignment.
+        alignment: u64,
+    },
+
+    /// The architecture of the target is needed but unknown, see
+    /// [`crate::config::ShellcoderBuilder::arch`].
+    UnknownArch,
+
+    /// The target does not support what has been asked, e.g. a technique
+    /// that has been patched in its version of glibc, see
+    /// [`crate::glibc`]. Value is the reason.
+    Unsupported(&'static str),
+
+    /// Invalid recipe.
+    /// The error of the parser is its source.
+    #[cfg(feature = "recipe")]
+    InvalidRecipe(Box<dyn StdError + Send + Sync>),
+
+    /// An operation pushed to a shellcoder failed.
+    /// The underlying error is its source, see [`OpContext`].
+    #[cfg(feature = "std")]
+    Op(Box<OpContext>),
+}
+
+/// Categories of [`Error`].
+///
+/// Unlike [`Error`], variants do not depend on features, and the context
+/// of operations is ignored, see [`Error::kind`].
+#[allow(clippy::module_name_repetitions)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// See [`Error::Io`].
+    Io,
+
+    /// See [`Error::OutputBufferTooSmall`].
+    OutputBufferTooSmall,
+
+    /// See [`Error::MaxLengthExceeded`].
+    MaxLengthExceeded,
+
+    /// See [`Error::BadByteFound`].
+    BadByteFound,
+
+    /// See [`Error::IntegerOverflow`].
+    IntegerOverflow,
+
+    /// See [`Error::InvalidBytecode`].
+    InvalidBytecode,
+
+    /// See [`Error::UnexpectedPosition`].
+    UnexpectedPosition,
+
+    /// See [`Error::InvalidRecipe`].
+    InvalidRecipe,
+
+    /// See [`Error::Overlap`].
+    Overlap,
+
+    /// See [`Error::UnknownSymbol`].
+    UnknownSymbol,
+
+    /// See [`Error::InvalidSymbolTable`].
+    InvalidSymbolTable,
+
+    /// See [`Error::Misaligned`].
+    Misaligned,
+
+    /// See [`Error::UnknownArch`].
+    UnknownArch,
+
+    /// See [`Error::Unsupported`].
+    Unsupported,
+}
+
+/// Context of an operation that failed, see [`Error::Op`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct OpContext {
+    /// Debug representation of the operation.
+    op: String,
+
+    /// Index of the operation, i.e. the number of operations that have been
+    /// pushed to the shellcoder before.
+    index: usize,
+
+    /// Offset of the cursor when the operation was pushed.
+    offset: usize,
+
+    /// The error.
+    error: Error,
+}
+
+#[cfg(feature = "std")]
+impl OpContext {
+    /// Returns the debug representation of the operation.
+    #[inline]
+    #[must_use]
+    pub fn op(&self) -> &str {
+        &self.op
+    }
+
+    /// Returns the index of the operation.
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the offset of the cursor when the operation was pushed.
+    #[inline]
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the error raised by the operation.
+    #[inline]
+    #[must_use]
+    pub const fn error(&self) -> &Error {
+        &self.error
+    }
 }
 
 impl fmt::Display for Error {
@@ -30,7 +213,69 @@ impl fmt::Display for Error {
                 fmt,
                 "output buffer error: too small (requires at least {len:#x} byte(s)"
             ),
+            Self::MaxLengthExceeded { limit, attempted } => write!(
+                fmt,
+                "maximum length exceeded: {attempted:#x} byte(s) for a limit of {limit:#x}"
+            ),
+            Self::BadByteFound { offset, byte } => {
+                write!(fmt, "bad byte {byte:#04x} found at offset {offset:#x}")
+            }
             Self::IntegerOverflow => write!(fmt, "integer overflow"),
+            Self::InvalidBytecode(offset) => {
+                write!(fmt, "invalid bytecode at offset {offset:#x}")
+            }
+            Self::UnexpectedPosition { expected, actual } => write!(
+                fmt,
+                "unexpected position: cursor at offset {actual:#x} instead of {expected:#x}"
+            ),
+            Self::Overlap { offset, previous } => write!(
+                fmt,
+                "overlap: content at offset {offset:#x} overlaps content at offset {previous:#x}"
+            ),
+            #[cfg(feature = "std")]
+            Self::UnknownSymbol(name) => write!(fmt, "unknown symbol `{name}`"),
+            Self::InvalidSymbolTable(line) => {
+                write!(fmt, "invalid symbol table at line {line}")
+            }
+            Self::Misaligned { value, alignment } => write!(
+                fmt,
+                "misaligned value {value:#x}: expected a multiple of {alignment:#x}"
+            ),
+            Self::UnknownArch => write!(fmt, "unknown architecture"),
+            Self::Unsupported(reason) => write!(fmt, "unsupported: {reason}"),
+            #[cfg(feature = "recipe")]
+            Self::InvalidRecipe(_) => write!(fmt, "invalid recipe"),
+            #[cfg(feature = "std")]
+            Self::Op(context) => write!(
+                fmt,
+                "operation #{} `{}` at offset {:#x}",
+                context.index, context.op, context.offset
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for Error {
+    #[inline]
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            #[cfg(feature = "recipe")]
+            Self::InvalidRecipe(error) => Some(error.as_ref()),
+            Self::Op(context) => Some(&context.error),
+            Self::OutputBufferTooSmall(_)
+            | Self::MaxLengthExceeded { .. }
+            | Self::BadByteFound { .. }
+            | Self::IntegerOverflow
+            | Self::InvalidBytecode(_)
+            | Self::UnexpectedPosition { .. }
+            | Self::Overlap { .. }
+            | Self::UnknownSymbol(_)
+            | Self::InvalidSymbolTable(_)
+            | Self::Misaligned { .. }
+            | Self::UnknownArch
+            | Self::Unsupported(_) => None,
         }
     }
 }
@@ -43,18 +288,166 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<TryFromIntError> for Error {
+    #[inline]
+    fn from(_error: TryFromIntError) -> Self {
+        Self::IntegerOverflow
+    }
+}
+
 impl Error {
     /// Instantiates an [`Error::OutputBufferTooSmall`] variant.
     pub(super) const fn buffer_too_small(n: usize) -> Self {
         Self::OutputBufferTooSmall(n)
     }
 
-    /// Returns the underlying I/O error if suitable.
+    /// Instantiates an [`Error::InvalidRecipe`] variant.
+    #[cfg(any(feature = "json", feature = "yaml", feature = "toml"))]
+    #[allow(clippy::single_call_fn)]
+    pub(crate) fn invalid_recipe(error: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self::InvalidRecipe(error.into())
+    }
+
+    /// Shifts the size of an [`Error::OutputBufferTooSmall`] by `offset`
+    /// bytes, for operations written at `offset` in a larger buffer.
+    // Errors that own values cannot be dropped in const functions.
+    #[cfg_attr(not(feature = "std"), allow(clippy::missing_const_for_fn))]
+    pub(crate) fn shifted(self, offset: usize) -> Self {
+        if let Self::OutputBufferTooSmall(n) = self {
+            Self::OutputBufferTooSmall(n.saturating_add(offset))
+        } else {
+            self
+        }
+    }
+
+    /// Formats the error followed by its sources, for bindings whose errors
+    /// only carry a message.
+    #[cfg(any(feature = "python", feature = "wasm"))]
+    pub(crate) fn report(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = self.source();
+        while let Some(cause) = source {
+            message = format!("{message}: {cause}");
+            source = cause.source();
+        }
+        message
+    }
+
+    /// Attaches the context of the operation that raised the error.
+    ///
+    /// Context is only kept with the `std` feature.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_op(self, op: &impl fmt::Debug, index: usize, offset: usize) -> Self {
+        Self::Op(Box::new(OpContext {
+            op: format!("{op:?}"),
+            index,
+            offset,
+            error: self,
+        }))
+    }
+
+    /// Attaches the context of the operation that raised the error.
+    ///
+    /// Context is only kept with the `std` feature.
+    #[cfg(not(feature = "std"))]
+    pub(crate) const fn with_op(
+        self,
+        _op: &impl fmt::Debug,
+        _index: usize,
+        _offset: usize,
+    ) -> Self {
+        self
+    }
+
+    /// Returns the error without the context of operations, i.e. the error
+    /// that has originally been raised.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::error::Error;
+    /// use shellcoder::Shellcoder as _;
+    ///
+    /// let mut buffer = [0u8; 4];
+    /// let error = Shellcoder::new(&mut buffer)
+    ///     .advance(2)
+    ///     .and_then(|shellcoder| shellcoder.int_le(0xdeadbeef_u32))
+    ///     .unwrap_err();
+    /// assert!(matches!(error.root(), Error::OutputBufferTooSmall(4)));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn root(&self) -> &Self {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Op(context) => context.error.root(),
+            _ => self,
+        }
+    }
+
+    /// Returns the category of the error, ignoring the context of
+    /// operations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::error::ErrorKind;
+    /// use shellcoder::Shellcoder as _;
+    ///
+    /// let mut buffer = [0u8; 2];
+    /// let error = Shellcoder::new(&mut buffer).advance(4).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::OutputBufferTooSmall);
+    /// ```
+    #[inline]
+    #[must_use]
+    #[cfg_attr(not(feature = "std"), allow(clippy::missing_const_for_fn))]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Op(context) => context.error.kind(),
+            #[cfg(feature = "std")]
+            Self::Io(_) => ErrorKind::Io,
+            Self::OutputBufferTooSmall(_) => ErrorKind::OutputBufferTooSmall,
+            Self::MaxLengthExceeded { .. } => ErrorKind::MaxLengthExceeded,
+            Self::BadByteFound { .. } => ErrorKind::BadByteFound,
+            Self::IntegerOverflow => ErrorKind::IntegerOverflow,
+            Self::InvalidBytecode(_) => ErrorKind::InvalidBytecode,
+            Self::UnexpectedPosition { .. } => ErrorKind::UnexpectedPosition,
+            Self::Overlap { .. } => ErrorKind::Overlap,
+            #[cfg(feature = "std")]
+            Self::UnknownSymbol(_) => ErrorKind::UnknownSymbol,
+            Self::InvalidSymbolTable(_) => ErrorKind::InvalidSymbolTable,
+            Self::Misaligned { .. } => ErrorKind::Misaligned,
+            Self::UnknownArch => ErrorKind::UnknownArch,
+            Self::Unsupported(_) => ErrorKind::Unsupported,
+            #[cfg(feature = "recipe")]
+            Self::InvalidRecipe(_) => ErrorKind::InvalidRecipe,
+        }
+    }
+
+    /// Returns the underlying I/O error if suitable, ignoring the context of
+    /// operations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io;
+    ///
+    /// use shellcoder::io::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    ///
+    /// let mut buffer = [0u8; 2];
+    /// let mut stream = &mut buffer[..];
+    /// let error = Shellcoder::new(&mut stream).advance(4).unwrap_err();
+    /// assert_eq!(error.io().map(io::Error::kind), Some(io::ErrorKind::WriteZero));
+    /// ```
     #[cfg(feature = "std")]
     #[must_use]
     #[inline]
     pub const fn io(&self) -> Option<&io::Error> {
-        if let Self::Io(err) = self {
+        if let Self::Io(err) = self.root() {
             Some(err)
         } else {
             None
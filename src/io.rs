@@ -4,10 +4,26 @@ use core::borrow::Borrow;
 use core::fmt;
 use std::io;
 
+use crate::arch::Arch;
+use crate::config::{Config, DebugPlacement, DebugStub, PointerWidth};
+use crate::ops::Endianness;
 use crate::prelude::*;
+use crate::trace;
 
 /// A shellcoder backed by an IO object.
-pub struct Shellcoder<'io>(&'io mut dyn io::Write);
+pub struct Shellcoder<'io> {
+    /// The stream.
+    stream: &'io mut dyn io::Write,
+
+    /// Number of bytes that have been written.
+    offset: usize,
+
+    /// Number of operations that have been pushed.
+    n_ops: usize,
+
+    /// Configuration.
+    config: Config,
+}
 
 impl fmt::Debug for Shellcoder<'_> {
     #[inline]
@@ -21,20 +37,139 @@ impl<'io> Shellcoder<'io> {
     #[inline]
     #[must_use]
     pub fn new(stream: &'io mut impl io::Write) -> Self {
-        Self(stream)
+        Self {
+            stream,
+            offset: 0,
+            n_ops: 0,
+            config: Config::default(),
+        }
+    }
+
+    /// Sets the configuration, see [`crate::config::ShellcoderBuilder`].
+    #[inline]
+    #[must_use]
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the default endianness, see [`crate::Shellcoder::endianness`].
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.config = self.config.with_endianness(endianness);
+        self
+    }
+
+    /// Returns the configuration.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.config
     }
 }
 
 impl crate::Shellcoder for Shellcoder<'_> {
-    /// Pushes an operation.
     #[inline]
     fn add<O>(&mut self, op: impl Borrow<O>) -> Result<&mut Self>
     where
         O: Op,
     {
-        op.borrow()
-            .write_to_io(self.0)
-            .map_err(Error::from)
-            .map(|_| self)
+        self.add_counted(op).map(|(_, shellcoder)| shellcoder)
+    }
+
+    /// Pushes an operation, and returns the number of bytes that have been
+    /// written.
+    ///
+    /// Unless the configuration is the default one, the operation is first
+    /// written to a temporary buffer to be checked, so that nothing is
+    /// written to the stream on failure.
+    #[inline]
+    fn add_counted<O>(&mut self, op: impl Borrow<O>) -> Result<(usize, &mut Self)>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let index = self.n_ops;
+        let start = self.offset;
+        let mut stub = [0; 8];
+        let result = self
+            .config
+            .write_entry_stub(start, &mut stub)
+            .and_then(|n| {
+                let offset = start.saturating_add(n);
+                operation.check_at(self.config.position(offset))?;
+                if n == 0 && self.config.is_passthrough() {
+                    return operation
+                        .write_to_io(&mut *self.stream)
+                        .map(|written| (offset, written));
+                }
+                let mut bytes = stub.get(..n).unwrap_or_default().to_vec();
+                bytes.extend(self.config.render(operation, offset)?);
+                self.stream.write_all(&bytes)?;
+                Ok((offset, bytes.len().saturating_sub(n)))
+            });
+        let (offset, n) = result.map_err(|error| {
+            trace::failed(operation, index, start, &error);
+            error.with_op(operation, index, start)
+        })?;
+        trace::applied(operation, index, offset, n);
+        self.offset = offset.saturating_add(n);
+        self.n_ops = index.saturating_add(1);
+        Ok((n, self))
+    }
+
+    /// Checks that an operation can be pushed, by writing it to
+    /// [`io::sink`], or to a temporary buffer if it has to be checked
+    /// against the configuration.
+    #[inline]
+    fn try_add<O>(&self, op: impl Borrow<O>) -> Result<()>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let result = self
+            .config
+            .write_entry_stub(self.offset, &mut [0; 8])
+            .and_then(|stub| {
+                let offset = self.offset.saturating_add(stub);
+                operation.check_at(self.config.position(offset))?;
+                if stub == 0 && self.config.is_passthrough() {
+                    operation.write_to_io(&mut io::sink()).map(|_n| ())
+                } else {
+                    self.config.render(operation, offset).map(|_bytes| ())
+                }
+            });
+        result.map_err(|error| error.with_op(operation, self.n_ops, self.offset))
+    }
+
+    #[inline]
+    fn position(&self) -> usize {
+        self.config.position(self.offset)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.offset
+    }
+
+    #[inline]
+    fn endianness(&self) -> Endianness {
+        self.config.endianness()
+    }
+
+    #[inline]
+    fn pointer_width(&self) -> PointerWidth {
+        self.config.pointer_width()
+    }
+
+    #[inline]
+    fn arch(&self) -> Option<Arch> {
+        self.config.arch()
+    }
+
+    #[inline]
+    fn debug(&self) -> Option<(DebugStub, DebugPlacement)> {
+        self.config.debug()
     }
 }
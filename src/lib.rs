@@ -8,6 +8,14 @@ use core::result::Result as CoreResult;
 #[cfg(feature = "std")]
 use std::io as std_io;
 
+#[cfg(feature = "macros")]
+#[allow(clippy::useless_attribute, clippy::pub_use)]
+pub use shellcoder_macros::payload;
+
+// The `alloc` crate is renamed, as it would be shadowed by [`alloc`].
+#[cfg(feature = "std")]
+extern crate alloc as alloc_crate;
+
 #[allow(unused_imports)]
 use prelude::*;
 
@@ -16,12 +24,56 @@ pub type Result<T> = CoreResult<T, Error>;
 
 #[cfg(feature = "std")]
 pub mod alloc;
+pub mod analysis;
+pub mod arch;
+pub mod bad_bytes;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "std")]
+pub mod caves;
+#[cfg(feature = "encrypt")]
+pub mod cipher;
+pub mod config;
+pub mod diff;
 pub mod error;
 #[cfg(feature = "std")]
+pub mod fit;
+#[cfg(feature = "std")]
+pub mod format;
+pub mod glibc;
+#[cfg(feature = "std")]
 pub mod io;
+#[cfg(feature = "mac")]
+pub mod mac;
+pub mod macho;
+mod macros;
+pub mod observer;
 pub mod ops;
+pub mod pack;
+#[cfg(feature = "peb")]
+pub mod peb;
+pub mod plan;
 mod prelude;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "recipe")]
+pub mod recipe;
+#[cfg(feature = "std")]
+pub mod rop;
+#[cfg(feature = "std")]
+pub mod stages;
 pub mod r#static;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "std")]
+pub mod symbols;
+mod trace;
+pub mod transform;
+pub mod unpack;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watermark;
+pub mod windows;
 
 /// Generic interface for operations.
 ///
@@ -54,7 +106,7 @@ pub trait Op: fmt::Debug {
     ///     .write(true)
     ///     .truncate(true)
     ///     .create(true)
-    ///     .open("op.bin")?;
+    ///     .open(std::env::temp_dir().join("op.bin"))?;
     ///
     /// Advance::new(42)
     ///     .write_to_io(&mut file)?;
@@ -85,7 +137,8 @@ pub trait Op: fmt::Debug {
     /// # Errors
     ///
     /// [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
-    /// to contain the result of the operation.
+    /// to contain the result of the operation. The value is the number of
+    /// bytes the operation needs, and the buffer is left untouched.
     ///
     /// # Examples
     ///
@@ -124,6 +177,93 @@ pub trait Op: fmt::Debug {
     /// # }
     /// ```
     fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize>;
+
+    /// Returns the number of bytes the operation writes, if it is known
+    /// without writing it.
+    ///
+    /// This is a hint: shellcoders use it to check or reserve capacity
+    /// ahead of writing.
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Checks that the operation can be written at `offset` in the payload.
+    ///
+    /// Shellcoders call it before writing the operation. This is how
+    /// [`ops::AssertPosition`] checks the position of the cursor.
+    ///
+    /// # Errors
+    ///
+    /// Any error, that is returned by [`Shellcoder::add`].
+    #[inline]
+    fn check_at(&self, _offset: usize) -> Result<()> {
+        Ok(())
+    }
+
+    /// Returns the name of the region written by the operation, if it is
+    /// a placeholder to patch later, see [`ops::Placeholder`].
+    #[inline]
+    fn placeholder(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl<O> Op for &O
+where
+    O: Op + ?Sized,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn std_io::Write) -> Result<usize> {
+        (**self).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        (**self).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        (**self).size()
+    }
+
+    #[inline]
+    fn check_at(&self, offset: usize) -> Result<()> {
+        (**self).check_at(offset)
+    }
+
+    #[inline]
+    fn placeholder(&self) -> Option<&str> {
+        (**self).placeholder()
+    }
+}
+
+/// Conversion into an operation.
+///
+/// This is implemented for operations themselves, for integers, which are
+/// converted into [`ops::WriteInteger`], and for byte strings, which are
+/// converted into [`ops::WriteBuffer`]. See [`Shellcoder::push`].
+pub trait IntoOp {
+    /// The operation.
+    type Op: Op;
+
+    /// Converts into an operation. Integers are encoded using the given
+    /// endianness.
+    fn into_op(self, endianness: ops::Endianness) -> Self::Op;
+}
+
+impl<O> IntoOp for O
+where
+    O: Op,
+{
+    type Op = Self;
+
+    #[inline]
+    fn into_op(self, _endianness: ops::Endianness) -> Self::Op {
+        self
+    }
 }
 
 /// Generic interface for shellcoders.
@@ -187,25 +327,316 @@ pub trait Op: fmt::Debug {
 /// # }
 /// ```
 pub trait Shellcoder: fmt::Debug {
-    /// Pushes an operation, and returns the number of bytes that have been
-    /// written.
+    /// Pushes an operation.
     ///
     /// # Errors
     ///
     ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
     ///    to contain the result of the operation.
-    ///  - [`Error:Io`]: an I/O error occurred.
+    ///  - [`Error::Io`]: an I/O error occurred.
     fn add<O>(&mut self, op: impl Borrow<O>) -> Result<&mut Self>
     where
         O: Op;
 
+    /// Pushes an operation, and returns the number of bytes that have been
+    /// written.
+    ///
+    /// This is useful for recording the offsets of fields while building
+    /// a payload. In debug mode, the stub written at the entry point is not
+    /// counted, see [`config::DebugPlacement::Entry`].
+    ///
+    /// Defaults to how much [`Shellcoder::len`] grows when the operation is
+    /// pushed with [`Shellcoder::add`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::WriteBuffer;
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// let (n, shellcoder) = shellcoder.add_counted(WriteBuffer::new(b"/bin/sh\0"))?;
+    /// assert_eq!(n, 8);
+    /// shellcoder.int_le(u8::try_from(n)?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn add_counted<O>(&mut self, op: impl Borrow<O>) -> Result<(usize, &mut Self)>
+    where
+        O: Op,
+    {
+        let start = self.len();
+        let shellcoder = self.add(op)?;
+        Ok((shellcoder.len().saturating_sub(start), shellcoder))
+    }
+
+    /// Pushes anything that can be converted into an operation, such as an
+    /// integer, a byte string or a [`plan::Plan`].
+    ///
+    /// Integers are encoded using [`Shellcoder::endianness`].
+    ///
+    /// # Errors
+    ///
+    /// Any error [`Shellcoder::add`] would raise.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 12];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.push(0xdeadbeef_u32)?.push(b"/bin/sh\0")?;
+    /// assert_eq!(shellcoder.get(), b"\xef\xbe\xad\xde/bin/sh\0");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn push<T>(&mut self, value: T) -> Result<&mut Self>
+    where
+        T: IntoOp,
+    {
+        let op = value.into_op(self.endianness());
+        self.add(op)
+    }
+
+    /// Returns the endianness integers are encoded with by
+    /// [`Shellcoder::push`] and [`Shellcoder::int`].
+    ///
+    /// Defaults to little-endian.
+    #[inline]
+    fn endianness(&self) -> ops::Endianness {
+        ops::Endianness::default()
+    }
+
+    /// Returns the width of pointers pushed by [`Shellcoder::ptr`].
+    ///
+    /// Defaults to 64-bit.
+    #[inline]
+    fn pointer_width(&self) -> config::PointerWidth {
+        config::PointerWidth::default()
+    }
+
+    /// Returns the architecture of the target, used by
+    /// [`Shellcoder::code_ptr`].
+    ///
+    /// Defaults to an unknown architecture.
+    #[inline]
+    fn arch(&self) -> Option<arch::Arch> {
+        None
+    }
+
+    /// Returns the debug stub and where it is written, see
+    /// [`config::ShellcoderBuilder::debug`].
+    ///
+    /// Defaults to no debug stub.
+    #[inline]
+    fn debug(&self) -> Option<(config::DebugStub, config::DebugPlacement)> {
+        None
+    }
+
+    /// Returns the operation writing the debug stub at `placement`, if any.
+    ///
+    /// # Errors
+    ///
+    /// [`error::Error::UnknownArch`]: the architecture of the target is
+    /// unknown.
+    #[inline]
+    fn debug_stub(&self, placement: config::DebugPlacement) -> Result<Option<ops::WriteInsn>> {
+        match self.debug() {
+            Some((stub, at)) if at == placement => {
+                let arch = self.arch().ok_or(Error::UnknownArch)?;
+                Ok(Some(
+                    ops::WriteInsn::new(arch, stub.insn()).with_endianness(self.endianness()),
+                ))
+            }
+            Some(_) | None => Ok(None),
+        }
+    }
+
+    /// Marks where the debug stub is written in debug mode, see
+    /// [`config::ShellcoderBuilder::debug`]. Outside of debug mode, nothing
+    /// is written.
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::UnknownArch`]: the architecture of the target is
+    ///    unknown.
+    ///  - Any error [`Shellcoder::add`] would raise.
+    #[inline]
+    fn debug_marker(&mut self) -> Result<&mut Self> {
+        match self.debug_stub(config::DebugPlacement::Marker)? {
+            Some(stub) => self.add(stub),
+            None => Ok(self),
+        }
+    }
+
+    /// Pushes a sequence of operations, e.g. a slice of [`ops::AnyOp`].
+    ///
+    /// # Errors
+    ///
+    /// Any error [`Shellcoder::add`] would raise. Operations that precede
+    /// the faulty one have already been pushed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::{AnyOp, Fill, WriteInteger};
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let chain = [
+    ///     AnyOp::from(WriteInteger::new_le(0x401000_u32)),
+    ///     AnyOp::from(Fill::new(4, b'A')),
+    ///     AnyOp::from(WriteInteger::new_le(0x401337_u32)),
+    /// ];
+    /// let mut buffer = [0u8; 12];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.extend(&chain)?;
+    /// assert_eq!(shellcoder.get(), b"\x00\x10\x40\x00AAAA\x37\x13\x40\x00");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn extend<I>(&mut self, ops: I) -> Result<&mut Self>
+    where
+        I: IntoIterator,
+        I::Item: Op,
+    {
+        for op in ops {
+            self.add(op)?;
+        }
+        Ok(self)
+    }
+
+    /// Checks that an operation can be pushed, without pushing it. The
+    /// shellcoder is left untouched.
+    ///
+    /// Defaults to checking the position of the cursor only, see
+    /// [`Op::check_at`]. Backends of this crate override it to also check
+    /// their own constraints.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::UnexpectedPosition`]: the operation asserts a position
+    ///    other than the one of the cursor.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::Fill;
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    ///
+    /// let mut buffer = [0u8; 4];
+    /// let shellcoder = Shellcoder::new(&mut buffer);
+    /// assert!(shellcoder.try_add(Fill::new(4, b'A')).is_ok());
+    /// assert!(shellcoder.try_add(Fill::new(5, b'A')).is_err());
+    /// assert!(shellcoder.get().is_empty());
+    /// ```
+    #[inline]
+    fn try_add<O>(&self, op: impl Borrow<O>) -> Result<()>
+    where
+        O: Op,
+    {
+        op.borrow().check_at(self.position())
+    }
+
+    /// Returns the number of bytes that have been written so far.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.fill(4, b'A')?.int_le(0xdeadbeef_u32)?;
+    /// assert_eq!(shellcoder.len(), 8);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn len(&self) -> usize;
+
+    /// Returns `true` if nothing has been written so far.
+    #[inline]
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the position of the cursor, i.e. the offset at which the next
+    /// operation will be written.
+    ///
+    /// This is the same as [`Shellcoder::len`], and reads better when
+    /// computing offsets of fields, except in debug mode where the stub at
+    /// the entry point is not counted, see
+    /// [`config::DebugPlacement::Entry`].
+    #[inline]
+    fn position(&self) -> usize {
+        self.len()
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    ///
+    /// This is a hint: shellcoders that are not backed by a growable buffer
+    /// ignore it.
+    #[inline]
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Checks that the cursor is at `offset`, see [`ops::AssertPosition`].
+    ///
+    /// # Errors
+    ///
+    /// [`error::Error::UnexpectedPosition`]: the cursor is elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::error::ErrorKind;
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.fill(6, b'A')?.assert_at(6)?.int_le(0xdead_u16)?;
+    /// let error = shellcoder.assert_at(12).unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::UnexpectedPosition);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn assert_at(&mut self, offset: usize) -> Result<&mut Self> {
+        self.add(ops::AssertPosition::new(offset))
+    }
+
     /// Advances the cursor by n bytes, filling gaps with zeroes.
     ///
     /// # Errors
     ///
     ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
     ///    to contain the result of the operation.
-    ///  - [`Error:Io`]: an I/O error occurred.
+    ///  - [`Error::Io`]: an I/O error occurred.
     #[inline]
     fn advance(&mut self, n: usize) -> Result<&mut Self> {
         self.add(ops::Advance::new(n))
@@ -217,19 +648,133 @@ pub trait Shellcoder: fmt::Debug {
     ///
     ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
     ///    to contain the result of the operation.
-    ///  - [`Error:Io`]: an I/O error occurred.
+    ///  - [`Error::Io`]: an I/O error occurred.
     #[inline]
     fn fill(&mut self, len: usize, chr: u8) -> Result<&mut Self> {
         self.add(ops::Fill::new(len, chr))
     }
 
+    /// Fills with a byte until the cursor is at `offset`, e.g. up to a
+    /// saved return address.
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::UnexpectedPosition`]: the cursor is already past
+    ///    `offset`.
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.push(b"sh\0")?.pad_to(8, b'A')?.int_le(0xdead_u16)?;
+    /// assert_eq!(shellcoder.get(), b"sh\0AAAAA\xad\xde");
+    /// assert!(shellcoder.pad_to(8, b'A').is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn pad_to(&mut self, offset: usize, chr: u8) -> Result<&mut Self> {
+        let position = self.position();
+        let len = offset
+            .checked_sub(position)
+            .ok_or(Error::UnexpectedPosition {
+                expected: offset,
+                actual: position,
+            })?;
+        self.fill(len, chr)
+    }
+
+    /// Pushes the first `len` bytes of a cyclic pattern, see
+    /// [`ops::Pattern`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    #[inline]
+    fn pattern(&mut self, len: usize) -> Result<&mut Self> {
+        self.add(ops::Pattern::new(len))
+    }
+
+    /// Pushes `len` bytes of a cyclic pattern, starting at `start` in the
+    /// pattern, so that a pattern can be resumed in another field.
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.pattern(6)?.int_le(0_u16)?.pattern_at(4, 6)?;
+    /// assert_eq!(shellcoder.get(), b"aaaaba\0\0aaca");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn pattern_at(&mut self, len: usize, start: usize) -> Result<&mut Self> {
+        self.add(ops::Pattern::new(len).with_start(start))
+    }
+
+    /// Advances the cursor to the next multiple of `alignment`, filling the
+    /// gap with zeroes. `0` and `1` both mean that nothing is written.
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.push(b"abc")?.align(4)?.align(4)?.push(b"d")?;
+    /// assert_eq!(shellcoder.get(), b"abc\0d");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn align(&mut self, alignment: usize) -> Result<&mut Self> {
+        let len = match self.position().checked_rem(alignment) {
+            Some(0) | None => 0,
+            Some(rem) => alignment.saturating_sub(rem),
+        };
+        self.advance(len)
+    }
+
     /// Pushes an integer in big endian.
     ///
     /// # Errors
     ///
     ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
     ///    to contain the result of the operation.
-    ///  - [`Error:Io`]: an I/O error occurred.
+    ///  - [`Error::Io`]: an I/O error occurred.
     #[inline]
     fn int_be<I>(&mut self, i: I) -> Result<&mut Self>
     where
@@ -238,13 +783,161 @@ pub trait Shellcoder: fmt::Debug {
         self.add(ops::WriteInteger::<I>::new_be(i))
     }
 
+    /// Pushes an integer in the endianness of the shellcoder, see
+    /// [`Shellcoder::endianness`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::Endianness;
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 4];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer).with_endianness(Endianness::Big);
+    /// shellcoder.int(0xdeadbeef_u32)?;
+    /// assert_eq!(shellcoder.get(), b"\xde\xad\xbe\xef");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn int<I>(&mut self, i: I) -> Result<&mut Self>
+    where
+        I: ops::EncodableInteger + IntoOp,
+    {
+        self.push(i)
+    }
+
+    /// Pushes an integer with a given endianness, e.g. one read from a
+    /// target configuration at runtime.
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::Endianness;
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 4];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder
+    ///     .int_with(0x4142_u16, Endianness::Big)?
+    ///     .int_with(0x4142_u16, Endianness::Little)?;
+    /// assert_eq!(shellcoder.get(), b"ABBA");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn int_with<I>(&mut self, i: I, endianness: ops::Endianness) -> Result<&mut Self>
+    where
+        I: ops::EncodableInteger,
+    {
+        self.add(ops::WriteInteger::<I>::new(i, endianness))
+    }
+
+    /// Pushes a pointer, using the default endianness and the width of
+    /// pointers on the target, see [`config::ShellcoderBuilder::pointer_width`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::IntegerOverflow`]: the address does not fit in a
+    ///    pointer, e.g. a 64-bit address on a 32-bit target.
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 8];
+    /// let mut shellcoder = ShellcoderBuilder::new()
+    ///     .pointer_width(PointerWidth::Bits32)
+    ///     .build_static(&mut buffer);
+    /// shellcoder.ptr(0x0804_8000)?;
+    /// assert_eq!(shellcoder.get(), b"\0\x80\x04\x08");
+    /// assert!(shellcoder.ptr(0x7fff_ffff_e000).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn ptr(&mut self, address: u64) -> Result<&mut Self> {
+        let endianness = self.endianness();
+        match self.pointer_width() {
+            config::PointerWidth::Bits16 => self.int_with(u16::try_from(address)?, endianness),
+            config::PointerWidth::Bits32 => self.int_with(u32::try_from(address)?, endianness),
+            config::PointerWidth::Bits64 => self.int_with(address, endianness),
+        }
+    }
+
+    /// Pushes a code pointer, e.g. a return address, as a branch to the
+    /// code at `address` expects it on the target, see
+    /// [`arch::Arch::code_address`].
+    ///
+    /// On an unknown architecture, this is the same as [`Shellcoder::ptr`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::Misaligned`]: the address is not aligned on
+    ///    instructions.
+    ///  - Any error returned by [`Shellcoder::ptr`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::arch::Arch;
+    /// use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 8];
+    /// let mut shellcoder = ShellcoderBuilder::new()
+    ///     .arch(Arch::Arm)
+    ///     .pointer_width(PointerWidth::Bits32)
+    ///     .build_static(&mut buffer);
+    /// shellcoder.code_ptr(0x0001_0400)?;
+    /// assert_eq!(shellcoder.get(), b"\0\x04\x01\0");
+    /// assert!(shellcoder.code_ptr(0x0001_0401).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn code_ptr(&mut self, address: u64) -> Result<&mut Self> {
+        let target = match self.arch() {
+            Some(arch) => arch.code_address(address)?,
+            None => address,
+        };
+        self.ptr(target)
+    }
+
     /// Pushes an integer in little endian.
     ///
     /// # Errors
     ///
     ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
     ///    to contain the result of the operation.
-    ///  - [`Error:Io`]: an I/O error occurred.
+    ///  - [`Error::Io`]: an I/O error occurred.
     #[inline]
     fn int_le<I>(&mut self, i: I) -> Result<&mut Self>
     where
@@ -259,9 +952,126 @@ pub trait Shellcoder: fmt::Debug {
     ///
     ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
     ///    to contain the result of the operation.
-    ///  - [`error::Error:Io`]: an I/O error occurred.
+    ///  - [`error::Error::Io`]: an I/O error occurred.
     #[inline]
     fn push_buffer(&mut self, buffer: impl AsRef<[u8]>) -> Result<&mut Self> {
         self.add(ops::WriteBuffer::new(&buffer))
     }
+
+    /// Pushes a null-terminated string, see [`ops::WriteCString`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`error::Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.push_cstr("/bin/sh")?.push_cstr("-c")?;
+    /// assert_eq!(shellcoder.get(), b"/bin/sh\0-c\0");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn push_cstr(&mut self, string: impl AsRef<[u8]>) -> Result<&mut Self> {
+        self.add(ops::WriteCString::new(&string))
+    }
+
+    /// Pushes a null-terminated UTF-16 string, using the default
+    /// endianness, see [`ops::WriteUtf16`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`error::Error::OutputBufferTooSmall`]: the provided output buffer is too small
+    ///    to contain the result of the operation.
+    ///  - [`error::Error::Io`]: an I/O error occurred.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 16];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.push_utf16("ntdll")?;
+    /// assert_eq!(shellcoder.get(), b"n\0t\0d\0l\0l\0\0\0");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn push_utf16(&mut self, string: &str) -> Result<&mut Self> {
+        let endianness = self.endianness();
+        self.add(ops::WriteUtf16::new(string).with_endianness(endianness))
+    }
+}
+
+/// The state of a shellcoder at some point, see
+/// [`Shellcoder::snapshot`](crate::static::Shellcoder::snapshot) and its
+/// `alloc` counterpart.
+///
+/// Restoring a snapshot rewinds the shellcoder to that point, e.g. to try
+/// many values for a byte without rebuilding the common prefix each time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    /// Number of bytes that had been written.
+    len: usize,
+
+    /// Number of operations that had been pushed.
+    n_ops: usize,
+}
+
+impl Snapshot {
+    /// Instantiates a new [`Snapshot`].
+    #[inline]
+    #[allow(clippy::single_call_fn)]
+    pub(crate) const fn new(len: usize, n_ops: usize) -> Self {
+        Self { len, n_ops }
+    }
+
+    /// Returns the number of bytes that had been written.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing had been written.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of operations that had been pushed.
+    #[inline]
+    #[must_use]
+    pub const fn n_ops(&self) -> usize {
+        self.n_ops
+    }
+
+    /// Checks that a snapshot can be restored by a shellcoder which has
+    /// written `len` bytes.
+    #[inline]
+    pub(crate) const fn check_restore(&self, len: usize) -> Result<()> {
+        if self.len > len {
+            Err(Error::UnexpectedPosition {
+                expected: self.len,
+                actual: len,
+            })
+        } else {
+            Ok(())
+        }
+    }
 }
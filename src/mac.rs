@@ -0,0 +1,134 @@
+//! Message authentication codes, used by [`crate::ops::Authenticate`].
+//!
+//! Like [`crate::cipher`], codes are implemented in a straightforward way,
+//! so that their output matches the verifiers found in agents. They let an
+//! agent check that a stage comes from its operator before executing it.
+
+/// Length of a `SipHash` key, in bytes.
+pub const SIPHASH_KEY_LEN: usize = 16;
+
+/// Length of a `SipHash` tag, in bytes.
+pub const SIPHASH_TAG_LEN: usize = 8;
+
+/// Initial `SipHash` state, i.e. `"somepseudorandomlygeneratedbytes"`.
+const SIPHASH_INITIAL: [u64; 4] = [
+    0x736f_6d65_7073_6575,
+    0x646f_7261_6e64_6f6d,
+    0x6c79_6765_6e65_7261,
+    0x7465_6462_7974_6573,
+];
+
+/// Number of compression rounds of `SipHash-2-4`.
+const SIPHASH_C_ROUNDS: usize = 2;
+
+/// Number of finalization rounds of `SipHash-2-4`.
+const SIPHASH_D_ROUNDS: usize = 4;
+
+/// Applies a `SipRound` to a `SipHash` state.
+fn sip_round(state: &mut [u64; 4]) {
+    let [mut v0, mut v1, mut v2, mut v3] = *state;
+    v0 = v0.wrapping_add(v1);
+    v1 = v1.rotate_left(13) ^ v0;
+    v0 = v0.rotate_left(32);
+    v2 = v2.wrapping_add(v3);
+    v3 = v3.rotate_left(16) ^ v2;
+    v0 = v0.wrapping_add(v3);
+    v3 = v3.rotate_left(21) ^ v0;
+    v2 = v2.wrapping_add(v1);
+    v1 = v1.rotate_left(17) ^ v2;
+    v2 = v2.rotate_left(32);
+    *state = [v0, v1, v2, v3];
+}
+
+/// Absorbs a word in a `SipHash` state.
+fn sip_absorb(state: &mut [u64; 4], word: u64) {
+    let [_, _, _, v3] = state;
+    *v3 ^= word;
+    for _ in 0..SIPHASH_C_ROUNDS {
+        sip_round(state);
+    }
+    let [v0, _, _, _] = state;
+    *v0 ^= word;
+}
+
+/// Computes the `SipHash-2-4` of a payload under a key, as specified by
+/// Aumasson and Bernstein.
+///
+/// The tag is usually written in little endian.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::mac::siphash24;
+///
+/// let key = *b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f";
+/// assert_eq!(siphash24(&key, b""), 0x726f_db47_dd0e_0e31);
+/// ```
+#[inline]
+#[must_use]
+pub fn siphash24(key: &[u8; SIPHASH_KEY_LEN], payload: &[u8]) -> u64 {
+    let mut halves = key
+        .chunks_exact(8)
+        .map(|half| half.try_into().map(u64::from_le_bytes).unwrap_or_default());
+    let k0 = halves.next().unwrap_or_default();
+    let k1 = halves.next().unwrap_or_default();
+    let [c0, c1, c2, c3] = SIPHASH_INITIAL;
+    let mut state = [c0 ^ k0, c1 ^ k1, c2 ^ k0, c3 ^ k1];
+
+    let words = payload.chunks_exact(8);
+    let remainder = words.remainder();
+    for word in words {
+        sip_absorb(
+            &mut state,
+            word.try_into().map(u64::from_le_bytes).unwrap_or_default(),
+        );
+    }
+
+    // The last bytes are followed by zeroes, and the length of the payload
+    // modulo 256 in the most significant byte.
+    let mut last = [0; 8];
+    if let Some(bytes) = last.get_mut(..remainder.len()) {
+        bytes.copy_from_slice(remainder);
+    }
+    if let Some(len) = last.last_mut() {
+        *len = payload
+            .len()
+            .to_le_bytes()
+            .first()
+            .copied()
+            .unwrap_or_default();
+    }
+    sip_absorb(&mut state, u64::from_le_bytes(last));
+
+    if let Some(v2) = state.get_mut(2) {
+        *v2 ^= 0xff;
+    }
+    for _ in 0..SIPHASH_D_ROUNDS {
+        sip_round(&mut state);
+    }
+    let [v0, v1, v2, v3] = state;
+    v0 ^ v1 ^ v2 ^ v3
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_test_prefix, clippy::unseparated_literal_suffix)]
+mod tests {
+    use crate::mac::siphash24;
+
+    const KEY: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    #[test]
+    fn test_siphash24() {
+        // Test vectors of the reference implementation.
+        let mut message = [0u8; 64];
+        for (byte, value) in message.iter_mut().zip(0u8..) {
+            *byte = value;
+        }
+        assert_eq!(siphash24(&KEY, &message[..0]), 0x726f_db47_dd0e_0e31);
+        assert_eq!(siphash24(&KEY, &message[..1]), 0x74f8_39c5_93dc_67fd);
+        assert_eq!(siphash24(&KEY, &message[..7]), 0xab02_00f5_8b01_d137);
+        assert_eq!(siphash24(&KEY, &message[..8]), 0x93f5_f579_9a93_2462);
+        assert_eq!(siphash24(&KEY, &message[..15]), 0xa129_ca61_49be_45e5);
+        assert_eq!(siphash24(&KEY, &message[..63]), 0x958a_324c_eb06_4572);
+    }
+}
@@ -0,0 +1,367 @@
+//! Mach-O wrapper for raw payloads.
+//!
+//! [`MachO`] is an operation that wraps a raw payload into a minimal
+//! 64-bit Mach-O executable for macOS.
+//!
+//! The emitted file only contains the following load commands:
+//!
+//!  - a `__PAGEZERO` segment,
+//!  - a `__TEXT` segment with a single `__text` section holding the payload,
+//!  - a `LC_UNIXTHREAD` command setting the program counter to the payload.
+//!
+//! It has no code signature. Apple Silicon kernels refuse to run unsigned
+//! arm64 code, thus an [`Arch::Arm64`] file must be ad-hoc signed before it
+//! runs, e.g. with `codesign -s - FILE`.
+
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::prelude::*;
+use crate::r#static::Shellcoder as StaticShellcoder;
+use crate::Shellcoder as _;
+
+/// Magic of 64-bit Mach-O files.
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+
+/// File type of executables.
+const MH_EXECUTE: u32 = 0x2;
+
+/// Flag telling that the object file has no undefined references.
+const MH_NOUNDEFS: u32 = 0x1;
+
+/// Load command of a 64-bit segment.
+const LC_SEGMENT_64: u32 = 0x19;
+
+/// Load command of a thread with a stack.
+const LC_UNIXTHREAD: u32 = 0x5;
+
+/// Read and execute memory protections.
+const VM_PROT_READ_EXECUTE: u32 = 0x1 | 0x4;
+
+/// `S_ATTR_PURE_INSTRUCTIONS | S_ATTR_SOME_INSTRUCTIONS`.
+const TEXT_SECTION_FLAGS: u32 = 0x8000_0400;
+
+/// Virtual address of the `__TEXT` segment, right after `__PAGEZERO`.
+const TEXT_ADDRESS: u64 = 0x1_0000_0000;
+
+/// Size of a `mach_header_64`.
+const HEADER_LEN: usize = 0x20;
+
+/// Size of a `segment_command_64`.
+const SEGMENT_LEN: u32 = 0x48;
+
+/// Size of a `segment_command_64` followed by a `section_64`.
+const SEGMENT_WITH_SECTION_LEN: u32 = 0x98;
+
+/// Maximum size of the headers and load commands, i.e. the arm64 ones.
+const MAX_HEADERS_LEN: usize = 0x220;
+
+/// Target architecture of a Mach-O file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Arch {
+    /// Intel 64-bit.
+    X86_64,
+
+    /// ARM 64-bit. The file must be ad-hoc signed before it runs, see
+    /// [the module documentation](self).
+    Arm64,
+}
+
+impl Arch {
+    /// Returns the CPU type.
+    const fn cpu_type(self) -> u32 {
+        match self {
+            Self::X86_64 => 0x0100_0007,
+            Self::Arm64 => 0x0100_000c,
+        }
+    }
+
+    /// Returns the CPU subtype.
+    const fn cpu_subtype(self) -> u32 {
+        match self {
+            Self::X86_64 => 0x3,
+            Self::Arm64 => 0x0,
+        }
+    }
+
+    /// Returns the page size minus one.
+    const fn page_mask(self) -> usize {
+        match self {
+            Self::X86_64 => 0xfff,
+            Self::Arm64 => 0x3fff,
+        }
+    }
+
+    /// Returns the thread state flavor.
+    const fn thread_flavor(self) -> u32 {
+        match self {
+            Self::X86_64 => 0x4,
+            Self::Arm64 => 0x6,
+        }
+    }
+
+    /// Returns the size of the thread state in 32-bit words.
+    const fn thread_state_count(self) -> u32 {
+        match self {
+            Self::X86_64 => 0x2a,
+            Self::Arm64 => 0x44,
+        }
+    }
+
+    /// Returns the size of the `LC_UNIXTHREAD` command.
+    const fn thread_command_len(self) -> u32 {
+        match self {
+            Self::X86_64 => 0xb8,
+            Self::Arm64 => 0x120,
+        }
+    }
+
+    /// Returns the number of bytes before and after the program counter in
+    /// the thread state.
+    const fn pc_surroundings(self) -> (usize, usize) {
+        match self {
+            Self::X86_64 => (0x80, 0x20),
+            Self::Arm64 => (0x100, 0x8),
+        }
+    }
+
+    /// Returns the size of the load commands.
+    const fn commands_len(self) -> u32 {
+        match self {
+            Self::X86_64 => 0x198,
+            Self::Arm64 => 0x200,
+        }
+    }
+
+    /// Returns the offset of the payload in the file, i.e. the size of the
+    /// headers aligned to 16 bytes.
+    const fn payload_offset(self) -> usize {
+        match self {
+            Self::X86_64 => 0x1c0,
+            Self::Arm64 => 0x220,
+        }
+    }
+}
+
+/// An operation that wraps a payload into a minimal Mach-O executable.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MachO<'buf> {
+    /// Target architecture.
+    arch: Arch,
+
+    /// Raw payload.
+    payload: &'buf [u8],
+}
+
+impl<'buf> MachO<'buf> {
+    /// Instantiates a new [`MachO`] wrapping the given payload.
+    #[inline]
+    #[must_use]
+    pub fn new(arch: Arch, payload: &'buf (impl AsRef<[u8]> + 'buf)) -> Self {
+        Self {
+            arch,
+            payload: payload.as_ref(),
+        }
+    }
+
+    /// Returns the offset of the payload in the file.
+    #[inline]
+    #[must_use]
+    pub const fn payload_offset(&self) -> usize {
+        self.arch.payload_offset()
+    }
+
+    /// Returns the virtual address of the payload, i.e. the entry point.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: the address does not fit.
+    #[inline]
+    pub fn entry_point(&self) -> Result<u64> {
+        TEXT_ADDRESS
+            .checked_add(u64::try_from(self.payload_offset())?)
+            .ok_or(Error::IntegerOverflow)
+    }
+
+    /// Writes the headers and the load commands to a buffer, including the
+    /// padding up to the payload.
+    fn write_headers(&self, out: &mut [u8; MAX_HEADERS_LEN]) -> Result<usize> {
+        let arch = self.arch;
+        let payload_offset = arch.payload_offset();
+        let file_len = payload_offset
+            .checked_add(self.payload.len())
+            .ok_or(Error::IntegerOverflow)?;
+        let vm_len = file_len
+            .checked_add(arch.page_mask())
+            .ok_or(Error::IntegerOverflow)?
+            & !arch.page_mask();
+        let entry_point = self.entry_point()?;
+        let (pc_before, pc_after) = arch.pc_surroundings();
+
+        let mut shellcoder = StaticShellcoder::new(out);
+        shellcoder
+            .int_le(MH_MAGIC_64)?
+            .int_le(arch.cpu_type())?
+            .int_le(arch.cpu_subtype())?
+            .int_le(MH_EXECUTE)?
+            .int_le::<u32>(3)?
+            .int_le(arch.commands_len())?
+            .int_le(MH_NOUNDEFS)?
+            .advance(4)?;
+
+        // `__PAGEZERO`.
+        shellcoder
+            .int_le(LC_SEGMENT_64)?
+            .int_le(SEGMENT_LEN)?
+            .push_buffer(b"__PAGEZERO")?
+            .advance(6)?
+            .int_le::<u64>(0)?
+            .int_le(TEXT_ADDRESS)?
+            .advance(0x20)?;
+
+        // `__TEXT` and its `__text` section.
+        shellcoder
+            .int_le(LC_SEGMENT_64)?
+            .int_le(SEGMENT_WITH_SECTION_LEN)?
+            .push_buffer(b"__TEXT")?
+            .advance(10)?
+            .int_le(TEXT_ADDRESS)?
+            .int_le(u64::try_from(vm_len)?)?
+            .int_le::<u64>(0)?
+            .int_le(u64::try_from(file_len)?)?
+            .int_le(VM_PROT_READ_EXECUTE)?
+            .int_le(VM_PROT_READ_EXECUTE)?
+            .int_le::<u32>(1)?
+            .int_le::<u32>(0)?
+            .push_buffer(b"__text")?
+            .advance(10)?
+            .push_buffer(b"__TEXT")?
+            .advance(10)?
+            .int_le(entry_point)?
+            .int_le(u64::try_from(self.payload.len())?)?
+            .int_le(u32::try_from(payload_offset)?)?
+            .int_le::<u32>(4)?
+            .advance(8)?
+            .int_le(TEXT_SECTION_FLAGS)?
+            .advance(12)?;
+
+        // `LC_UNIXTHREAD`.
+        shellcoder
+            .int_le(LC_UNIXTHREAD)?
+            .int_le(arch.thread_command_len())?
+            .int_le(arch.thread_flavor())?
+            .int_le(arch.thread_state_count())?
+            .advance(pc_before)?
+            .int_le(entry_point)?
+            .advance(pc_after)?;
+
+        let padding = payload_offset
+            .checked_sub(HEADER_LEN)
+            .and_then(|n| n.checked_sub(usize::try_from(arch.commands_len()).ok()?))
+            .ok_or(Error::IntegerOverflow)?;
+        shellcoder.advance(padding)?;
+        Ok(shellcoder.into_bytes().len())
+    }
+}
+
+impl Op for MachO<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let mut headers = [0; MAX_HEADERS_LEN];
+        let n = self.write_headers(&mut headers)?;
+        stream.write_all(headers.get(..n).ok_or(Error::IntegerOverflow)?)?;
+        stream.write_all(self.payload)?;
+        n.checked_add(self.payload.len())
+            .ok_or(Error::IntegerOverflow)
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let mut headers = [0; MAX_HEADERS_LEN];
+        let n = self.write_headers(&mut headers)?;
+        let total = n
+            .checked_add(self.payload.len())
+            .ok_or(Error::IntegerOverflow)?;
+        let output = out
+            .as_mut()
+            .get_mut(..total)
+            .ok_or_else(|| Error::buffer_too_small(total))?;
+        let mut shellcoder = StaticShellcoder::new(output);
+        shellcoder
+            .push_buffer(headers.get(..n).ok_or(Error::IntegerOverflow)?)?
+            .push_buffer(self.payload)?;
+        Ok(shellcoder.into_bytes().len())
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::unnecessary_wraps,
+    clippy::unseparated_literal_suffix,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::macho::{Arch, MachO};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_x86_64() -> Result<()> {
+        let payload = [0xcc_u8; 5];
+        let macho = MachO::new(Arch::X86_64, &payload);
+        let mut buffer = [0u8; 0x1c5];
+        assert_eq!(macho.write_to(&mut buffer).unwrap(), 0x1c5);
+        assert_eq!(&buffer[..4], &[0xcf, 0xfa, 0xed, 0xfe]);
+        assert_eq!(&buffer[4..8], &[0x07, 0x00, 0x00, 0x01]);
+        assert_eq!(&buffer[0x10..0x14], &[3, 0, 0, 0]);
+        assert_eq!(&buffer[0x14..0x18], &[0x98, 0x01, 0, 0]);
+        assert_eq!(&buffer[0x70..0x76], b"__TEXT");
+        assert_eq!(&buffer[0xd0..0xd8], &0x1_0000_01c0_u64.to_le_bytes());
+        assert_eq!(&buffer[0x190..0x198], &0x1_0000_01c0_u64.to_le_bytes());
+        assert_eq!(&buffer[0x1c0..], &payload);
+        assert_eq!(macho.entry_point().unwrap(), 0x1_0000_01c0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_arm64() -> Result<()> {
+        let payload = [0x1f_u8, 0x20, 0x03, 0xd5];
+        let macho = MachO::new(Arch::Arm64, &payload);
+        let mut buffer = [0u8; 0x224];
+        assert_eq!(macho.write_to(&mut buffer).unwrap(), 0x224);
+        assert_eq!(&buffer[4..8], &[0x0c, 0x00, 0x00, 0x01]);
+        assert_eq!(&buffer[0x14..0x18], &[0, 0x02, 0, 0]);
+        assert_eq!(&buffer[0x210..0x218], &0x1_0000_0220_u64.to_le_bytes());
+        assert_eq!(&buffer[0x220..], &payload);
+        Ok(())
+    }
+
+    #[test]
+    fn test_buffer_too_small() -> Result<()> {
+        let macho = MachO::new(Arch::X86_64, b"AAAA");
+        let mut buffer = [0u8; 0x1c3];
+        let err = macho.write_to(&mut buffer).unwrap_err();
+        assert!(matches!(err, Error::OutputBufferTooSmall(0x1c4)));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_io() -> Result<()> {
+        let macho = MachO::new(Arch::Arm64, b"AAAA");
+        let mut stream = Vec::new();
+        assert_eq!(macho.write_to_io(&mut stream).unwrap(), 0x224);
+        let mut buffer = [0u8; 0x224];
+        macho.write_to(&mut buffer).unwrap();
+        assert_eq!(stream.as_slice(), &buffer);
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+//! Macros.
+
+/// Writes a payload using a compact DSL.
+///
+/// The following statements are available, each of them being terminated by
+/// a semicolon:
+///
+///  - `le <type> <value>`: pushes an integer in little endian (see [`crate::Shellcoder::int_le`]).
+///  - `be <type> <value>`: pushes an integer in big endian (see [`crate::Shellcoder::int_be`]).
+///  - `advance <n>`: advances the cursor (see [`crate::Shellcoder::advance`]).
+///  - `fill <len>, <byte>`: fills with a byte (see [`crate::Shellcoder::fill`]).
+///  - `bytes <buffer>`: pushes a buffer (see [`crate::Shellcoder::push_buffer`]).
+///  - `op <op>`: pushes any operation (see [`crate::Shellcoder::add`]).
+///
+/// The type of integers is part of the statement, thus a value that does
+/// not fit is caught at compile time.
+///
+/// When a shellcoder is given using `<shellcoder> => <statements>`, the
+/// macro evaluates to a [`crate::Result`] of the mutable reference to the
+/// shellcoder. Otherwise, a new [`crate::alloc::Shellcoder`] is built and
+/// returned (requires feature `std`).
+///
+/// # Examples
+///
+/// Builds a payload using a new [`crate::alloc::Shellcoder`].
+///
+/// ```rust
+/// # #[cfg(feature = "std")]
+/// # {
+/// use shellcoder::shellcode;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let shellcoder = shellcode! {
+///     le u64 0xdeadbeef;
+///     advance 8;
+///     bytes b"/bin/sh\0";
+///     fill 4, 0x41;
+/// }?;
+/// assert_eq!(
+///     shellcoder.as_bytes(),
+///     b"\xef\xbe\xad\xde\0\0\0\0\0\0\0\0\0\0\0\0/bin/sh\0AAAA"
+/// );
+/// # Ok(())
+/// # }
+/// # main().unwrap();
+/// # }
+/// ```
+///
+/// Builds a payload using a static shellcoder.
+///
+/// ```rust
+/// use shellcoder::ops::Fill;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::shellcode;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcode! {
+///     &mut shellcoder =>
+///     be u16 0x4142;
+///     op Fill::new(2, b'C');
+/// }?;
+/// # Ok(())
+/// # }
+/// # main().unwrap();
+/// ```
+///
+/// Integers that do not fit the given type are rejected at compile time.
+///
+/// ```compile_fail
+/// # use shellcoder::r#static::Shellcoder;
+/// # use shellcoder::shellcode;
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// let _ = shellcode! {
+///     &mut shellcoder =>
+///     le u8 0x100;
+/// };
+/// ```
+#[macro_export]
+macro_rules! shellcode {
+    (@chain $acc:expr;) => {
+        $acc
+    };
+    (@chain $acc:expr; le $ty:ident $value:expr; $($rest:tt)*) => {
+        $crate::shellcode!(@chain $acc.and_then(|shellcoder| {
+            $crate::Shellcoder::int_le::<$ty>(shellcoder, $value)
+        }); $($rest)*)
+    };
+    (@chain $acc:expr; be $ty:ident $value:expr; $($rest:tt)*) => {
+        $crate::shellcode!(@chain $acc.and_then(|shellcoder| {
+            $crate::Shellcoder::int_be::<$ty>(shellcoder, $value)
+        }); $($rest)*)
+    };
+    (@chain $acc:expr; advance $n:expr; $($rest:tt)*) => {
+        $crate::shellcode!(@chain $acc.and_then(|shellcoder| {
+            $crate::Shellcoder::advance(shellcoder, $n)
+        }); $($rest)*)
+    };
+    (@chain $acc:expr; fill $len:expr, $chr:expr; $($rest:tt)*) => {
+        $crate::shellcode!(@chain $acc.and_then(|shellcoder| {
+            $crate::Shellcoder::fill(shellcoder, $len, $chr)
+        }); $($rest)*)
+    };
+    (@chain $acc:expr; bytes $buffer:expr; $($rest:tt)*) => {
+        $crate::shellcode!(@chain $acc.and_then(|shellcoder| {
+            $crate::Shellcoder::push_buffer(shellcoder, $buffer)
+        }); $($rest)*)
+    };
+    (@chain $acc:expr; op $op:expr; $($rest:tt)*) => {
+        $crate::shellcode!(@chain $acc.and_then(|shellcoder| {
+            $crate::Shellcoder::add(shellcoder, $op)
+        }); $($rest)*)
+    };
+    ($shellcoder:expr => $($body:tt)*) => {
+        $crate::shellcode!(@chain $crate::Result::Ok($shellcoder); $($body)*)
+    };
+    ($($body:tt)*) => {{
+        let mut shellcoder = $crate::alloc::Shellcoder::new();
+        let result = $crate::shellcode!(&mut shellcoder => $($body)*).map(|_| ());
+        result.map(|()| shellcoder)
+    }};
+}
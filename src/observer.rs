@@ -0,0 +1,306 @@
+//! Observers, i.e. hooks invoked after each operation.
+//!
+//! Any shellcoder can be wrapped into an [`Observed`] shellcoder, that
+//! notifies an [`Observer`] each time an operation has been written, e.g.
+//! to report the progress of a huge payload streamed over a slow link, or
+//! to validate the payload as it is being built.
+
+use core::borrow::Borrow;
+use core::fmt;
+
+use crate::arch::Arch;
+use crate::config::{DebugPlacement, DebugStub, PointerWidth};
+use crate::ops::Endianness;
+use crate::prelude::*;
+
+/// Information about an operation that has been written.
+#[derive(Clone, Copy)]
+pub struct OpInfo<'op> {
+    /// The operation.
+    op: &'op dyn fmt::Debug,
+
+    /// Index of the operation.
+    index: usize,
+
+    /// Offset of the operation in the payload.
+    offset: usize,
+
+    /// Number of bytes that have been written.
+    size: usize,
+}
+
+impl<'op> OpInfo<'op> {
+    /// Returns the operation.
+    #[inline]
+    #[must_use]
+    pub const fn op(&self) -> &'op dyn fmt::Debug {
+        self.op
+    }
+
+    /// Returns the index of the operation, i.e. the number of operations
+    /// that have been pushed before it.
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the offset of the operation in the payload.
+    #[inline]
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the number of bytes that have been written.
+    #[inline]
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl fmt::Debug for OpInfo<'_> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("OpInfo")
+            .field("op", &self.op)
+            .field("index", &self.index)
+            .field("offset", &self.offset)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// A hook invoked after each operation.
+///
+/// Closures taking an [`OpInfo`] are observers.
+pub trait Observer {
+    /// Invoked after an operation has been written.
+    ///
+    /// # Errors
+    ///
+    /// Any error, that is returned by [`crate::Shellcoder::add`]. The
+    /// operation has already been written.
+    fn on_op(&mut self, info: &OpInfo<'_>) -> Result<()>;
+}
+
+impl<F> Observer for F
+where
+    F: FnMut(&OpInfo<'_>) -> Result<()>,
+{
+    #[inline]
+    fn on_op(&mut self, info: &OpInfo<'_>) -> Result<()> {
+        self(info)
+    }
+}
+
+/// A shellcoder that notifies an [`Observer`] after each operation.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::observer::{Observed, OpInfo};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 8];
+/// let mut progress = 0;
+/// let mut shellcoder = Observed::new(Shellcoder::new(&mut buffer), |info: &OpInfo<'_>| {
+///     progress = info.offset() + info.size();
+///     Ok(())
+/// });
+/// shellcoder.fill(4, b'A')?.int_le(0xdead_u16)?;
+/// drop(shellcoder);
+/// assert_eq!(progress, 6);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Observed<S, F> {
+    /// The observed shellcoder.
+    shellcoder: S,
+
+    /// The observer.
+    observer: F,
+
+    /// Number of operations that have been pushed.
+    n_ops: usize,
+}
+
+impl<S, F> Observed<S, F>
+where
+    S: crate::Shellcoder,
+    F: Observer,
+{
+    /// Wraps a shellcoder, so that `observer` is notified after each
+    /// operation.
+    #[inline]
+    #[must_use]
+    pub const fn new(shellcoder: S, observer: F) -> Self {
+        Self {
+            shellcoder,
+            observer,
+            n_ops: 0,
+        }
+    }
+
+    /// Returns the observed shellcoder.
+    #[inline]
+    #[must_use]
+    pub const fn get_ref(&self) -> &S {
+        &self.shellcoder
+    }
+
+    /// Returns the observed shellcoder, mutably. Operations pushed directly
+    /// to it are not observed.
+    #[inline]
+    #[must_use]
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.shellcoder
+    }
+
+    /// Consumes the [`Observed`] shellcoder and returns the observed one.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.shellcoder
+    }
+}
+
+impl<S, F> fmt::Debug for Observed<S, F>
+where
+    S: fmt::Debug,
+{
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Observed")
+            .field("shellcoder", &self.shellcoder)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, F> crate::Shellcoder for Observed<S, F>
+where
+    S: crate::Shellcoder,
+    F: Observer,
+{
+    #[inline]
+    fn add<O>(&mut self, op: impl Borrow<O>) -> Result<&mut Self>
+    where
+        O: Op,
+    {
+        self.add_counted(op).map(|(_, shellcoder)| shellcoder)
+    }
+
+    #[inline]
+    fn add_counted<O>(&mut self, op: impl Borrow<O>) -> Result<(usize, &mut Self)>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let index = self.n_ops;
+        let (size, shellcoder) = self.shellcoder.add_counted::<O>(operation)?;
+        let offset = shellcoder.len().saturating_sub(size);
+        self.n_ops = index.saturating_add(1);
+        self.observer
+            .on_op(&OpInfo {
+                op: operation,
+                index,
+                offset,
+                size,
+            })
+            .map_err(|error| error.with_op(operation, index, offset))?;
+        Ok((size, self))
+    }
+
+    #[inline]
+    fn try_add<O>(&self, op: impl Borrow<O>) -> Result<()>
+    where
+        O: Op,
+    {
+        self.shellcoder.try_add(op)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.shellcoder.len()
+    }
+
+    #[inline]
+    fn position(&self) -> usize {
+        self.shellcoder.position()
+    }
+
+    #[inline]
+    fn endianness(&self) -> Endianness {
+        self.shellcoder.endianness()
+    }
+
+    #[inline]
+    fn pointer_width(&self) -> PointerWidth {
+        self.shellcoder.pointer_width()
+    }
+
+    #[inline]
+    fn arch(&self) -> Option<Arch> {
+        self.shellcoder.arch()
+    }
+
+    #[inline]
+    fn debug(&self) -> Option<(DebugStub, DebugPlacement)> {
+        self.shellcoder.debug()
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.shellcoder.reserve(additional);
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::unseparated_literal_suffix,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::observer::{Observed, OpInfo};
+    use crate::ops::Fill;
+    use crate::r#static::Shellcoder;
+    use crate::Shellcoder as _;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_observed() -> Result<()> {
+        let mut buffer = [0u8; 16];
+        let mut sizes = [0usize; 4];
+        let mut shellcoder = Observed::new(Shellcoder::new(&mut buffer), |info: &OpInfo<'_>| {
+            if info.offset() >= 8 {
+                return Err(Error::MaxLengthExceeded {
+                    limit: 8,
+                    attempted: info.offset(),
+                });
+            }
+            if let Some(size) = sizes.get_mut(info.index()) {
+                *size = info.size();
+            }
+            Ok(())
+        });
+        shellcoder.fill(2, b'A')?.int_be(0x4243_u16)?.advance(4)?;
+        assert!(shellcoder.try_add(Fill::new(8, b'C')).is_ok());
+        assert!(matches!(
+            shellcoder.fill(1, b'C').unwrap_err().root(),
+            Error::MaxLengthExceeded { .. }
+        ));
+        assert_eq!(shellcoder.len(), 9);
+        assert_eq!(shellcoder.into_inner().get(), b"AABC\0\0\0\0C");
+        assert_eq!(sizes, [2, 2, 4, 0]);
+        Ok(())
+    }
+}
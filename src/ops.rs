@@ -1,10 +1,39 @@
 //! All operations available for writing shellcodes.
 
+#[cfg(feature = "std")]
+use alloc_crate::borrow::Cow;
 use core::fmt;
+use core::iter;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Range;
+use core::slice;
 #[cfg(feature = "std")]
 use std::io;
 
+#[cfg(feature = "compress")]
+use miniz_oxide::deflate;
+
+#[cfg(feature = "encrypt")]
+use crate::cipher::Cipher;
+#[cfg(feature = "mac")]
+use crate::mac::{self, SIPHASH_KEY_LEN, SIPHASH_TAG_LEN};
+
+use crate::arch::Arch;
+use crate::config::PointerWidth;
 use crate::prelude::*;
+use crate::IntoOp;
+
+/// Maximum length generated by [`arbitrary::Arbitrary`] for operations that
+/// take a length, so that fuzzers do not spend their time allocating.
+#[cfg(feature = "arbitrary")]
+pub const ARBITRARY_MAX_LEN: usize = 0x1000;
+
+/// Generates an arbitrary length, up to [`ARBITRARY_MAX_LEN`].
+#[cfg(feature = "arbitrary")]
+fn arbitrary_len(unstructured: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<usize> {
+    unstructured.int_in_range(0..=ARBITRARY_MAX_LEN)
+}
 
 #[cfg(feature = "serde")]
 pub trait WithOrWithoutSerde: Serialize + for<'de> Deserialize<'de> {}
@@ -20,7 +49,8 @@ impl<T> WithOrWithoutSerde for T {}
 /// The gap will be filled by zeroes.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Advance(usize);
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Advance(#[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_len))] usize);
 
 impl Advance {
     /// Instantiates a new [`Advance`] to move the cursor ahead by n bytes.
@@ -29,6 +59,13 @@ impl Advance {
     pub const fn new(n: usize) -> Self {
         Self(n)
     }
+
+    /// Returns the number of bytes to move the cursor ahead by.
+    #[inline]
+    #[must_use]
+    pub const fn n(&self) -> usize {
+        self.0
+    }
 }
 
 impl Op for Advance {
@@ -42,12 +79,97 @@ impl Op for Advance {
     fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
         Fill::new(self.0, 0).write_to(out)
     }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.0)
+    }
+}
+
+/// An operation that writes nothing, but fails if the cursor is not at an
+/// expected offset, e.g. to catch off-by-padding errors when building the
+/// payload rather than on the target.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::AssertPosition;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.fill(8, b'A')?.add(AssertPosition::new(8))?;
+/// assert!(shellcoder.add(AssertPosition::new(12)).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct AssertPosition(usize);
+
+impl AssertPosition {
+    /// Instantiates a new [`AssertPosition`] expecting the cursor at
+    /// `offset`.
+    #[inline]
+    #[must_use]
+    pub const fn new(offset: usize) -> Self {
+        Self(offset)
+    }
+
+    /// Returns the expected offset.
+    #[inline]
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.0
+    }
+}
+
+impl Op for AssertPosition {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, _stream: &mut dyn io::Write) -> Result<usize> {
+        Ok(0)
+    }
+
+    #[inline]
+    fn write_to(&self, _out: impl AsMut<[u8]>) -> Result<usize> {
+        Ok(0)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    #[inline]
+    fn check_at(&self, offset: usize) -> Result<()> {
+        if offset == self.0 {
+            Ok(())
+        } else {
+            Err(Error::UnexpectedPosition {
+                expected: self.0,
+                actual: offset,
+            })
+        }
+    }
 }
 
+/// Length of the chunks [`Fill`] writes to streams.
+#[cfg(feature = "std")]
+const FILL_CHUNK_LEN: usize = 0x1000;
+
 /// An operation that fills with a value.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct Fill(usize, u8);
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Fill(
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_len))] usize,
+    u8,
+);
 
 impl Fill {
     /// Instantiates a new [`Fill`].
@@ -56,16 +178,39 @@ impl Fill {
     pub const fn new(len: usize, chr: u8) -> Self {
         Self(len, chr)
     }
+
+    /// Returns the number of bytes to fill.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.0
+    }
+
+    /// Returns `true` if there is nothing to fill.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the value to fill with.
+    #[inline]
+    #[must_use]
+    pub const fn chr(&self) -> u8 {
+        self.1
+    }
 }
 
 impl Op for Fill {
     #[cfg(feature = "std")]
     #[inline]
     fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
-        use core::slice;
-        let rchr = slice::from_ref(&self.1);
-        for _ in 0..self.0 {
-            stream.write_all(rchr)?;
+        let chunk = [self.1; FILL_CHUNK_LEN];
+        let mut remaining = self.0;
+        while remaining > 0 {
+            let n = remaining.min(FILL_CHUNK_LEN);
+            stream.write_all(chunk.get(..n).unwrap_or_default())?;
+            remaining = remaining.saturating_sub(n);
         }
         Ok(self.0)
     }
@@ -78,468 +223,5229 @@ impl Op for Fill {
             .fill(self.1);
         Ok(self.0)
     }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.0)
+    }
 }
 
-/// An integer that is encodable.
-pub trait EncodableInteger:
-    Copy + Clone + Sized + fmt::Debug + PartialEq + Eq + Send + Sync + WithOrWithoutSerde
-{
-    /// Returns the number of bytes needed to encode the integer.
-    fn n(self) -> usize;
+/// Advances a `SplitMix64` generator and returns its next output.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut mixed = *state;
+    mixed = (mixed ^ mixed.wrapping_shr(30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    mixed = (mixed ^ mixed.wrapping_shr(27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    mixed ^ mixed.wrapping_shr(31)
+}
 
-    /// Writes in big endian.
-    ///
-    /// # Errors
-    ///
-    /// An I/O error may be raised here.
-    #[cfg(feature = "std")]
-    fn write_be_io(self, stream: &mut dyn io::Write) -> Result<()>;
+/// An operation that fills with pseudorandom bytes, reproducible from a
+/// seed.
+///
+/// Junk padding varies from one payload to the other, e.g. to defeat
+/// signatures, yet a crash can be traced back to the exact payload that
+/// caused it, as long as the seed is recorded. The bytes are the
+/// little-endian outputs of a `SplitMix64` generator: this is not suitable
+/// for cryptography.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::RandomFill;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut first = [0u8; 8];
+/// Shellcoder::new(&mut first).add(RandomFill::new(8, 0x1337))?;
+/// let mut second = [0u8; 8];
+/// Shellcoder::new(&mut second).add(RandomFill::new(8, 0x1337))?;
+/// assert_eq!(first, second);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct RandomFill {
+    /// Number of bytes to write.
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_len))]
+    len: usize,
 
-    /// Writes in little endian.
-    ///
-    /// # Errors
-    ///
-    /// An I/O error may be raised here.
-    #[cfg(feature = "std")]
-    fn write_le_io(self, stream: &mut dyn io::Write) -> Result<()>;
+    /// Seed of the generator.
+    seed: u64,
+}
 
-    /// Writes in big endian.
-    ///
-    /// # Errors
-    ///
-    /// [`Error::OutputBufferTooSmall`] is raised if `out` cannot contain the encoded
-    /// integer.
-    fn write_be(self, out: impl AsMut<[u8]>) -> Result<()>;
+impl RandomFill {
+    /// Instantiates a new [`RandomFill`].
+    #[inline]
+    #[must_use]
+    pub const fn new(len: usize, seed: u64) -> Self {
+        Self { len, seed }
+    }
 
-    /// Writes in little endian.
-    ///
-    /// # Errors
-    ///
-    /// [`Error::OutputBufferTooSmall`] is raised if `out` cannot contain the encoded
-    /// integer.
-    fn write_le(self, out: impl AsMut<[u8]>) -> Result<()>;
-}
+    /// Returns the number of bytes to write.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
 
-/// Implements [`EncodableInteger`] for a given type.
-macro_rules! impl_encodable_integer_for {
-    ($i:ident) => {
-        impl EncodableInteger for $i {
-            #[inline]
-            #[must_use]
-            fn n(self) -> usize {
-                ($i::BITS >> 3).try_into().expect("unreachable")
-            }
+    /// Returns `true` if there is nothing to write.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 
-            #[cfg(feature = "std")]
-            #[inline]
-            fn write_be_io(self, stream: &mut dyn io::Write) -> Result<()> {
-                stream.write_all(&self.to_be_bytes()).map_err(Error::from)
-            }
+    /// Returns the seed of the generator.
+    #[inline]
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
 
-            #[cfg(feature = "std")]
-            #[inline]
-            fn write_le_io(self, stream: &mut dyn io::Write) -> Result<()> {
-                stream.write_all(&self.to_le_bytes()).map_err(Error::from)
-            }
+    /// Returns the operation writing the bytes.
+    fn op(&self) -> Generate<impl Iterator<Item = u8> + Clone + fmt::Debug> {
+        let bytes = iter::repeat(())
+            .scan(self.seed, |state, ()| Some(splitmix64(state).to_le_bytes()))
+            .flatten();
+        Generate::with_iter(self.len, bytes)
+    }
+}
 
-            #[inline]
-            fn write_be(self, mut out: impl AsMut<[u8]>) -> Result<()> {
-                let n = self.n();
-                let out = out
-                    .as_mut()
-                    .get_mut(..n)
-                    .ok_or(Error::buffer_too_small(n))?;
-                // SAFETY:
-                //
-                // Length of `out` has been checked previously.
-                unsafe {
-                    out.as_mut_ptr().copy_from(self.to_be_bytes().as_ptr(), n);
-                }
-                Ok(())
-            }
+impl Op for RandomFill {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op().write_to_io(stream)
+    }
 
-            #[inline]
-            fn write_le(self, mut out: impl AsMut<[u8]>) -> Result<()> {
-                let n = self.n();
-                let out = out
-                    .as_mut()
-                    .get_mut(..n)
-                    .ok_or(Error::buffer_too_small(n))?;
-                // SAFETY:
-                //
-                // Length of `out` has been checked previously.
-                unsafe {
-                    out.as_mut_ptr().copy_from(self.to_le_bytes().as_ptr(), n);
-                }
-                Ok(())
-            }
-        }
-    };
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op().write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.len)
+    }
 }
 
-impl_encodable_integer_for!(u8);
-impl_encodable_integer_for!(u16);
-impl_encodable_integer_for!(u32);
-impl_encodable_integer_for!(u64);
+/// Byte-frequency profile of English prose, per mille, e.g. for
+/// [`ShapedFill`].
+#[rustfmt::skip]
+pub const ENGLISH_TEXT: [usize; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    180, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 9, 1, 9, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 2, 1, 1, 0, 0, 0, 0, 1, 3, 0, 0, 0, 1, 0, 0,
+    0, 0, 0, 2, 3, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 65, 12, 22, 34, 102, 18, 16, 50, 57, 1, 6, 33, 20, 57, 62,
+    15, 1, 50, 53, 75, 23, 8, 17, 1, 14, 1, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
 
-/// An operation that writes an integer.
-/// The cursor will be moved ahead by n bytes, n depending on the integer's
-/// encoded size.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-#[non_exhaustive]
-pub enum WriteInteger<I>
-where
-    I: EncodableInteger + WithOrWithoutSerde,
-{
-    /// The integer's value, to encode in big-endian.
-    BigEndian(I),
+/// An operation that fills with pseudorandom bytes following a
+/// byte-frequency profile, reproducible from a seed.
+///
+/// The profile weighs each byte value, e.g. [`ENGLISH_TEXT`] for padding
+/// that looks like prose, or the histogram of a sample, see
+/// [`crate::analysis::Stats::histogram`], for padding whose distribution,
+/// and thus entropy, matches the sample. This helps to assess detectors
+/// based on entropy or on byte statistics.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::analysis;
+/// use shellcoder::ops::{ShapedFill, ENGLISH_TEXT};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 64];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(ShapedFill::new(64, &ENGLISH_TEXT, 0x1337))?;
+/// assert!(shellcoder.get().iter().all(|byte| ENGLISH_TEXT[usize::from(*byte)] != 0));
+///
+/// let sample = analysis::stats(b"\xde\xad\xbe\xef");
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(ShapedFill::new(16, sample.histogram(), 0x1337))?;
+/// assert!(shellcoder.get().iter().all(|byte| b"\xde\xad\xbe\xef".contains(byte)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ShapedFill<'profile> {
+    /// Number of bytes to write.
+    len: usize,
 
-    /// The integer's value, to encode in little-endian.
-    LittleEndian(I),
+    /// Weight of each byte value.
+    profile: &'profile [usize; 256],
+
+    /// Seed of the generator.
+    seed: u64,
 }
 
-impl<I> WriteInteger<I>
-where
-    I: EncodableInteger,
-{
-    /// Instantiates a new [`WriteInteger`] to write a big-endian encoded integer.
+impl<'profile> ShapedFill<'profile> {
+    /// Instantiates a new [`ShapedFill`].
     #[inline]
     #[must_use]
-    pub const fn new_be(value: I) -> Self {
-        Self::BigEndian(value)
+    pub const fn new(len: usize, profile: &'profile [usize; 256], seed: u64) -> Self {
+        Self { len, profile, seed }
     }
 
-    /// Instantiates a new [`WriteInteger`] to write a little-endian encoded integer.
+    /// Returns the number of bytes to write.
     #[inline]
     #[must_use]
-    pub const fn new_le(value: I) -> Self {
-        Self::LittleEndian(value)
+    pub const fn len(&self) -> usize {
+        self.len
     }
-}
 
-impl<I> Op for WriteInteger<I>
-where
-    I: EncodableInteger,
-{
-    #[cfg(feature = "std")]
+    /// Returns `true` if there is nothing to write.
     #[inline]
-    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
-        match self {
-            Self::BigEndian(n) => n.write_be_io(stream).map(|()| n.n()),
-            Self::LittleEndian(n) => n.write_le_io(stream).map(|()| n.n()),
-        }
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
+    /// Returns the profile.
     #[inline]
-    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
-        match self {
-            Self::BigEndian(n) => n.write_be(out).map(|()| n.n()),
-            Self::LittleEndian(n) => n.write_le(out).map(|()| n.n()),
-        }
+    #[must_use]
+    pub const fn profile(&self) -> &'profile [usize; 256] {
+        self.profile
     }
-}
-
-/// An operation that writes a buffer.
-/// The cursor will be moved ahead by the length in bytes of the given buffer.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct WriteBuffer<'buf>(&'buf [u8]);
 
-impl<'buf> WriteBuffer<'buf> {
-    /// Instantiates a new [`WriteBuffer`].
+    /// Returns the seed of the generator.
     #[inline]
     #[must_use]
-    pub fn new(buffer: &'buf (impl AsRef<[u8]> + 'buf)) -> Self {
-        Self(buffer.as_ref())
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the operation writing the bytes.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Unsupported`]: all the weights are zero.
+    ///  - [`Error::IntegerOverflow`]: the sum of the weights overflows.
+    fn op(&self) -> Result<Generate<impl Iterator<Item = u8> + Clone + fmt::Debug + 'profile>> {
+        let total = self
+            .profile
+            .iter()
+            .try_fold(u64::MIN, |total, &weight| {
+                total.checked_add(u64::try_from(weight).ok()?)
+            })
+            .ok_or(Error::IntegerOverflow)?;
+        if total == 0 {
+            return Err(Error::Unsupported("byte profiles need a non-zero weight"));
+        }
+        let profile = self.profile;
+        let bytes = iter::repeat(()).scan(self.seed, move |state, ()| {
+            let mut target = splitmix64(state).checked_rem(total).unwrap_or_default();
+            Some(
+                (0..=u8::MAX)
+                    .zip(profile.iter())
+                    .find(|&(_, &count)| {
+                        let weight = u64::try_from(count).unwrap_or(u64::MAX);
+                        if target < weight {
+                            true
+                        } else {
+                            target = target.saturating_sub(weight);
+                            false
+                        }
+                    })
+                    .map_or(u8::MAX, |(byte, _)| byte),
+            )
+        });
+        Ok(Generate::with_iter(self.len, bytes))
     }
 }
 
-impl Op for WriteBuffer<'_> {
+impl Op for ShapedFill<'_> {
     #[cfg(feature = "std")]
     #[inline]
     fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
-        stream
-            .write_all(self.0)
-            .map(|()| self.0.len())
-            .map_err(Error::from)
+        self.op()?.write_to_io(stream)
     }
 
     #[inline]
-    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
-        let n = self.0.len();
-        let out_slice = out
-            .as_mut()
-            .get_mut(..n)
-            .ok_or_else(|| Error::buffer_too_small(n))?;
-        // SAFETY:
-        //
-        // Length of `out` has been checked previously.
-        unsafe {
-            out_slice.as_mut_ptr().copy_from(self.0.as_ptr(), n);
-        }
-        Ok(n)
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.len)
     }
 }
 
-#[cfg(test)]
-mod tests {
+/// An operation that reserves a region tagged with a name, so that it can
+/// be patched once its value is known, e.g. a stack canary that is leaked
+/// while the rest of the payload is already laid out.
+///
+/// The region is filled with a byte, null by default. Shellcoders that
+/// support it remember where it has been written, see
+/// [`crate::alloc::Shellcoder::patch_placeholder`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::config::PointerWidth;
+/// use shellcoder::ops::Placeholder;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .fill(4, b'A')?
+///     .add(Placeholder::canary(PointerWidth::Bits32).with_filler(b'?'))?;
+/// assert_eq!(shellcoder.get(), b"AAAA????");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Placeholder<'name> {
+    /// Name of the placeholder.
+    name: &'name str,
+
+    /// Length of the placeholder, in bytes.
+    len: usize,
+
+    /// Value to fill with until the placeholder is patched.
+    filler: u8,
+}
+
+impl<'name> Placeholder<'name> {
+    /// Instantiates a new [`Placeholder`] of `len` bytes, filled with null
+    /// bytes.
+    #[inline]
+    #[must_use]
+    pub const fn new(name: &'name str, len: usize) -> Self {
+        Self {
+            name,
+            len,
+            filler: 0,
+        }
+    }
+
+    /// Sets the value to fill with until the placeholder is patched, e.g.
+    /// when null bytes are bad bytes.
+    #[inline]
+    #[must_use]
+    pub const fn with_filler(mut self, filler: u8) -> Self {
+        self.filler = filler;
+        self
+    }
+
+    /// Returns the name of the placeholder.
+    #[inline]
+    #[must_use]
+    pub const fn name(&self) -> &'name str {
+        self.name
+    }
+
+    /// Returns the length of the placeholder, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the placeholder is empty.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Instantiates a new pointer-wide [`Placeholder`] named `canary`.
+    #[inline]
+    #[must_use]
+    pub const fn canary(pointer_width: PointerWidth) -> Placeholder<'static> {
+        Placeholder::new("canary", pointer_width.bytes())
+    }
+}
+
+impl Op for Placeholder<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        Fill::new(self.len, self.filler).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        Fill::new(self.len, self.filler).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.len)
+    }
+
+    #[inline]
+    fn placeholder(&self) -> Option<&str> {
+        Some(self.name)
+    }
+}
+
+/// An operation that writes a NOP sled, i.e. instructions that do nothing,
+/// so that a jump anywhere in the sled reaches the code that follows it.
+///
+/// The length of the sled must be a multiple of the length of the NOP
+/// instruction of the target, e.g. 2 bytes with Thumb or 4 bytes with ARM,
+/// so that the code that follows it stays aligned.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::arch::Arch;
+/// use shellcoder::ops::Sled;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(Sled::new(Arch::Thumb, 6))?;
+/// assert_eq!(shellcoder.get(), b"\xc0\x46\xc0\x46\xc0\x46");
+/// assert!(shellcoder.add(Sled::new(Arch::Thumb, 1)).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sled {
+    /// Architecture of the target.
+    arch: Arch,
+
+    /// Length of the sled, in bytes.
+    len: usize,
+
+    /// Endianness of instructions.
+    endianness: Endianness,
+}
+
+impl Sled {
+    /// Instantiates a new [`Sled`] of `len` bytes, for a little-endian
+    /// target.
+    #[inline]
+    #[must_use]
+    pub const fn new(arch: Arch, len: usize) -> Self {
+        Self {
+            arch,
+            len,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// Sets the endianness of instructions.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Returns the architecture of the target.
+    #[inline]
+    #[must_use]
+    pub const fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns the length of the sled, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the sled is empty.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the operation writing the sled.
+    fn op(&self) -> Result<Generate<iter::Cycle<iter::Copied<slice::Iter<'static, u8>>>>> {
+        let nop = self.arch.nop(self.endianness);
+        if self.len.checked_rem(nop.len()) == Some(0) {
+            Ok(Generate::with_iter(self.len, nop.iter().copied().cycle()))
+        } else {
+            Err(Error::Misaligned {
+                value: u64::try_from(self.len)?,
+                alignment: u64::try_from(nop.len())?,
+            })
+        }
+    }
+}
+
+impl Op for Sled {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op()?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.op().ok().map(|op| op.len())
+    }
+}
+
+/// Maximum length of the instructions of [`Junk`].
+#[cfg(feature = "polymorphic")]
+const JUNK_INSN_MAX_LEN: usize = 5;
+
+/// An instruction of [`Junk`], and its length.
+#[cfg(feature = "polymorphic")]
+type JunkInsn = ([u8; JUNK_INSN_MAX_LEN], usize);
+
+/// Returns an inert x86 or x86-64 instruction of at most `room` bytes,
+/// picked from a random value.
+#[allow(clippy::single_call_fn)]
+#[cfg(feature = "polymorphic")]
+fn junk_insn(x86_64: bool, random: u64, room: usize) -> JunkInsn {
+    let reg = u8::try_from(random.wrapping_shr(32) & 7).unwrap_or_default();
+    let same = 0xc0 | reg.wrapping_shl(3) | reg;
+    // `[esp + disp8]` takes a SIB byte: `lea esp, [esp]` is left out.
+    let lea_reg = if reg == 4 { 0 } else { reg };
+    let lea = 0x40 | lea_reg.wrapping_shl(3) | lea_reg;
+    let common = [
+        ([0x90, 0, 0, 0, 0], 1),
+        ([0x66, 0x90, 0, 0, 0], 2),
+        ([0xeb, 0, 0, 0, 0], 2),
+        ([0x86, same, 0, 0, 0], 2),
+        ([0x88, same, 0, 0, 0], 2),
+        ([0x8a, same, 0, 0, 0], 2),
+        ([0x66, 0x87, same, 0, 0], 3),
+        ([0x66, 0x89, same, 0, 0], 3),
+        ([0x66, 0x8b, same, 0, 0], 3),
+        ([0x0f, 0x1f, 0, 0, 0], 3),
+        ([0x0f, 0x1f, 0x40, 0, 0], 4),
+        ([0x0f, 0x1f, 0x44, 0, 0], 5),
+    ];
+    // 32-bit operations zero-extend their destination on x86-64, hence the
+    // REX.W prefixes.
+    let x86 = [
+        ([0x87, same, 0, 0, 0], 2),
+        ([0x89, same, 0, 0, 0], 2),
+        ([0x8b, same, 0, 0, 0], 2),
+        ([0x8d, lea, 0, 0, 0], 3),
+    ];
+    let amd64 = [
+        ([0x48, 0x87, same, 0, 0], 3),
+        ([0x48, 0x89, same, 0, 0], 3),
+        ([0x48, 0x8b, same, 0, 0], 3),
+        ([0x4d, 0x87, same, 0, 0], 3),
+        ([0x4d, 0x89, same, 0, 0], 3),
+        ([0x48, 0x8d, lea, 0, 0], 4),
+        ([0x4d, 0x8d, lea, 0, 0], 4),
+    ];
+    let specific = if x86_64 { &amd64[..] } else { &x86[..] };
+    let candidates = common
+        .iter()
+        .chain(specific)
+        .filter(|&&(_, len)| len <= room);
+    let count = u64::try_from(candidates.clone().count()).unwrap_or(1);
+    let index = usize::try_from(random.checked_rem(count).unwrap_or_default()).unwrap_or_default();
+    candidates
+        .copied()
+        .nth(index)
+        .unwrap_or(([0x90, 0, 0, 0, 0], 1))
+}
+
+/// The bytes of [`Junk`].
+#[cfg(feature = "polymorphic")]
+#[derive(Clone, Debug)]
+struct JunkInsns {
+    /// Whether instructions are x86-64 ones.
+    x86_64: bool,
+
+    /// State of the generator.
+    state: u64,
+
+    /// Number of bytes left after the current instruction.
+    remaining: usize,
+
+    /// The current instruction.
+    insn: JunkInsn,
+
+    /// Index of the next byte of the current instruction.
+    position: usize,
+}
+
+#[cfg(feature = "polymorphic")]
+impl Iterator for JunkInsns {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        if self.position >= self.insn.1 {
+            if self.remaining == 0 {
+                return None;
+            }
+            self.insn = junk_insn(self.x86_64, splitmix64(&mut self.state), self.remaining);
+            self.remaining = self.remaining.saturating_sub(self.insn.1);
+            self.position = 0;
+        }
+        let byte = self.insn.0.get(self.position).copied();
+        self.position = self.position.saturating_add(1);
+        byte
+    }
+}
+
+/// An operation that writes junk x86 or x86-64 instructions, i.e.
+/// randomized instructions that do nothing, reproducible from a seed.
+///
+/// Padding of code regions is made of instructions that leave registers,
+/// flags and memory untouched, e.g. `xchg rax, rax`, `lea ecx, [ecx]` or
+/// multi-byte NOPs, so that it does not match the runs of `0x90` that
+/// signatures look for.
+///
+/// Unlike [`Sled`], the padding must be entered at its start: a jump in the
+/// middle of an instruction executes something else.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::arch::Arch;
+/// use shellcoder::ops::Junk;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(Junk::new(Arch::X86_64, 16, 0x1337))?;
+/// assert_ne!(shellcoder.get(), [0x90; 16]);
+/// assert!(shellcoder.add(Junk::new(Arch::Arm, 4, 0)).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "polymorphic")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Junk {
+    /// Architecture of the target.
+    arch: Arch,
+
+    /// Length of the padding, in bytes.
+    len: usize,
+
+    /// Seed of the generator.
+    seed: u64,
+}
+
+#[cfg(feature = "polymorphic")]
+impl Junk {
+    /// Instantiates a new [`Junk`] of `len` bytes.
+    #[inline]
+    #[must_use]
+    pub const fn new(arch: Arch, len: usize, seed: u64) -> Self {
+        Self { arch, len, seed }
+    }
+
+    /// Returns the architecture of the target.
+    #[inline]
+    #[must_use]
+    pub const fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns the length of the padding, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the padding is empty.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the seed of the generator.
+    #[inline]
+    #[must_use]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the operation writing the padding.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Unsupported`]: the target is neither x86 nor x86-64.
+    const fn op(&self) -> Result<Generate<JunkInsns>> {
+        let x86_64 = match self.arch {
+            Arch::X86 => false,
+            Arch::X86_64 => true,
+            Arch::Arm | Arch::Thumb | Arch::Aarch64 | Arch::Mips | Arch::Riscv64 => {
+                return Err(Error::Unsupported(
+                    "junk instructions are generated for x86 and x86-64 only",
+                ))
+            }
+        };
+        let insns = JunkInsns {
+            x86_64,
+            state: self.seed,
+            remaining: self.len,
+            insn: ([0; JUNK_INSN_MAX_LEN], 0),
+            position: 0,
+        };
+        Ok(Generate::with_iter(self.len, insns))
+    }
+}
+
+#[cfg(feature = "polymorphic")]
+impl Op for Junk {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op()?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// An operation that writes a system call stub: a system call instruction,
+/// optionally followed by a return to the caller, e.g. to call it from a
+/// ROP chain or from a hooked function.
+///
+/// The stub may also load the number of the system call in the register
+/// of the calling convention of Linux: `eax` with x86, `r7` with ARM, `x8`
+/// with 64-bit ARM, `$v0` with MIPS and `a7` with RISC-V.
+///
+/// On architectures with branch delay slots, i.e. MIPS, the return is
+/// followed by an inert instruction (see [`Arch::nop`]), so that whatever
+/// follows the stub is not executed before returning.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::arch::Arch;
+/// use shellcoder::ops::{Endianness, SyscallStub};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(
+///     SyscallStub::new(Arch::Mips)
+///         .with_endianness(Endianness::Big)
+///         .with_return(true),
+/// )?;
+/// // syscall 0x40404; jr $ra; addiu $zero, $t1, 0x101
+/// assert_eq!(
+///     shellcoder.get(),
+///     b"\x01\x01\x01\x0c\x03\xe0\x00\x08\x25\x20\x01\x01"
+/// );
+///
+/// // li a7, 93 (exit); ecall
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(SyscallStub::new(Arch::Riscv64).with_number(93))?;
+/// assert_eq!(shellcoder.get(), b"\x93\x08\xd0\x05\x73\0\0\0");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SyscallStub {
+    /// Architecture of the target.
+    arch: Arch,
+
+    /// Endianness of instructions.
+    endianness: Endianness,
+
+    /// Number of the system call to load, if any.
+    number: Option<u32>,
+
+    /// Whether the stub returns to the caller.
+    ret: bool,
+}
+
+impl SyscallStub {
+    /// Instantiates a new [`SyscallStub`] for a little-endian target, that
+    /// does not return.
+    #[inline]
+    #[must_use]
+    pub const fn new(arch: Arch) -> Self {
+        Self {
+            arch,
+            endianness: Endianness::Little,
+            number: None,
+            ret: false,
+        }
+    }
+
+    /// Sets the endianness of instructions.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets the number of the system call to load before performing it.
+    ///
+    /// The number must fit in the immediate of the instruction loading it:
+    /// 8 bits with Thumb, 11 bits with RISC-V, 15 bits with MIPS and 16
+    /// bits with both 32-bit and 64-bit ARM.
+    #[inline]
+    #[must_use]
+    pub const fn with_number(mut self, number: u32) -> Self {
+        self.number = Some(number);
+        self
+    }
+
+    /// Sets whether the stub returns to the caller after the system call.
+    #[inline]
+    #[must_use]
+    pub const fn with_return(mut self, ret: bool) -> Self {
+        self.ret = ret;
+        self
+    }
+
+    /// Returns the architecture of the target.
+    #[inline]
+    #[must_use]
+    pub const fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns the number of the system call loaded by the stub, if any.
+    #[inline]
+    #[must_use]
+    pub const fn number(&self) -> Option<u32> {
+        self.number
+    }
+
+    /// Returns the encoding of the instruction loading the number of the
+    /// system call, and its length.
+    fn load(self) -> Result<([u8; 5], usize)> {
+        let number = match self.number {
+            Some(number) => number,
+            None => return Ok(([0; 5], 0)),
+        };
+        let (word, limit) = match self.arch {
+            Arch::X86 | Arch::X86_64 => {
+                // mov eax, number
+                let [b0, b1, b2, b3] = number.to_le_bytes();
+                return Ok(([0xb8, b0, b1, b2, b3], 5));
+            }
+            Arch::Thumb => {
+                // movs r7, #number
+                let imm = u8::try_from(number)?;
+                return Ok(([imm, 0x27, 0, 0, 0], 2));
+            }
+            // movw r7, #number
+            Arch::Arm => (
+                0xe300_7000 | number.wrapping_shr(12).wrapping_shl(16) | (number & 0xfff),
+                0xffff,
+            ),
+            // movz x8, #number
+            Arch::Aarch64 => (0xd280_0008 | number.wrapping_shl(5), 0xffff),
+            // addiu $v0, $zero, number
+            Arch::Mips => (0x2402_0000 | number, 0x7fff),
+            // addi a7, zero, number
+            Arch::Riscv64 => (0x0000_0893 | number.wrapping_shl(20), 0x7ff),
+        };
+        if number > limit {
+            return Err(Error::IntegerOverflow);
+        }
+        let [b0, b1, b2, b3] = match (self.arch, self.endianness) {
+            (Arch::Mips, Endianness::Big) => word.to_be_bytes(),
+            _ => word.to_le_bytes(),
+        };
+        Ok(([b0, b1, b2, b3, 0], 4))
+    }
+
+    /// Returns the operations writing the instructions of the stub.
+    const fn ops(
+        self,
+    ) -> (
+        WriteBuffer<'static>,
+        WriteBuffer<'static>,
+        WriteBuffer<'static>,
+    ) {
+        let arch = self.arch;
+        let ret: &[u8] = if self.ret {
+            arch.ret(self.endianness)
+        } else {
+            &[]
+        };
+        let delay_slot: &[u8] = if self.ret && arch.has_delay_slots() {
+            arch.nop(self.endianness)
+        } else {
+            &[]
+        };
+        (
+            WriteBuffer(arch.syscall(self.endianness)),
+            WriteBuffer(ret),
+            WriteBuffer(delay_slot),
+        )
+    }
+}
+
+impl Op for SyscallStub {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let (load, len) = self.load()?;
+        (WriteBuffer(load.get(..len).unwrap_or_default()), self.ops()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        let (load, len) = self.load()?;
+        (WriteBuffer(load.get(..len).unwrap_or_default()), self.ops()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        let (_load, len) = self.load().ok()?;
+        self.ops().size()?.checked_add(len)
+    }
+}
+
+/// An instruction with a known encoding on every architecture, for glue
+/// code that does not need an assembler, see [`WriteInsn`].
+///
+/// Offsets of branches are relative to the address of the instruction
+/// itself, whatever the architecture: an offset of 0 targets the branch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Insn {
+    /// Jumps to an offset, e.g. `jmp rel32`, `b` or `jal zero`.
+    JmpRel32(i32),
+
+    /// Calls the function at an offset, e.g. `call rel32`, `bl` or
+    /// `jal ra`.
+    CallRel32(i32),
+
+    /// Breaks into the debugger, e.g. `int3`, `bkpt`, `brk` or `ebreak`.
+    Int3,
+
+    /// Jumps to itself forever, e.g. to attach a debugger to a process
+    /// after it reached a given point.
+    InfiniteLoop,
+}
+
+/// An operation that writes an [`Insn`] for a given architecture.
+///
+/// With MIPS, branches are followed by an inert instruction in their delay
+/// slot, see [`Arch::nop`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::arch::Arch;
+/// use shellcoder::ops::{Insn, WriteInsn};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .add(WriteInsn::new(Arch::X86_64, Insn::CallRel32(0x100)))?
+///     .add(WriteInsn::new(Arch::Aarch64, Insn::JmpRel32(-8)))?
+///     .add(WriteInsn::new(Arch::Thumb, Insn::InfiniteLoop))?;
+/// assert_eq!(
+///     shellcoder.get(),
+///     b"\xe8\xfb\0\0\0\xfe\xff\xff\x17\xfe\xe7"
+/// );
+/// assert!(shellcoder
+///     .add(WriteInsn::new(Arch::Arm, Insn::JmpRel32(2)))
+///     .is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WriteInsn {
+    /// Architecture of the target.
+    arch: Arch,
+
+    /// Instruction to write.
+    insn: Insn,
+
+    /// Endianness of instructions.
+    endianness: Endianness,
+}
+
+impl WriteInsn {
+    /// Instantiates a new [`WriteInsn`] for a little-endian target.
+    #[inline]
+    #[must_use]
+    pub const fn new(arch: Arch, insn: Insn) -> Self {
+        Self {
+            arch,
+            insn,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// Sets the endianness of instructions.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Returns the architecture of the target.
+    #[inline]
+    #[must_use]
+    pub const fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns the instruction to write.
+    #[inline]
+    #[must_use]
+    pub const fn insn(&self) -> Insn {
+        self.insn
+    }
+
+    /// Returns the encoding of the instruction, and its length.
+    fn bytes(self) -> Result<([u8; 8], usize)> {
+        let (jump, call) = match self.insn {
+            Insn::JmpRel32(offset) => (Some(offset), None),
+            Insn::CallRel32(offset) => (None, Some(offset)),
+            Insn::Int3 | Insn::InfiniteLoop => (None, None),
+        };
+        let offset = jump.or(call);
+        let word = match (self.arch, self.insn) {
+            (Arch::X86 | Arch::X86_64, Insn::Int3) => return Ok(([0xcc, 0, 0, 0, 0, 0, 0, 0], 1)),
+            (Arch::X86 | Arch::X86_64, Insn::InfiniteLoop) => {
+                return Ok(([0xeb, 0xfe, 0, 0, 0, 0, 0, 0], 2));
+            }
+            (Arch::X86 | Arch::X86_64, _) => {
+                let [b0, b1, b2, b3] = displacement(offset, 5, 1, 32)?.to_le_bytes();
+                let opcode = if call.is_some() { 0xe8 } else { 0xe9 };
+                return Ok(([opcode, b0, b1, b2, b3, 0, 0, 0], 5));
+            }
+            (Arch::Thumb, Insn::Int3) => return Ok(([0x00, 0xbe, 0, 0, 0, 0, 0, 0], 2)),
+            (Arch::Thumb, Insn::InfiniteLoop) => return Ok(([0xfe, 0xe7, 0, 0, 0, 0, 0, 0], 2)),
+            (Arch::Thumb, _) => {
+                // b.w or bl, made of two halfwords: S:I1:I2:imm10:imm11 is
+                // spread over both, with J1 and J2 derived from I1 and I2.
+                let imm = displacement(offset, 4, 2, 24)?;
+                let sign = imm.wrapping_shr(23) & 1;
+                let j1 = (imm.wrapping_shr(22) ^ sign ^ 1) & 1;
+                let j2 = (imm.wrapping_shr(21) ^ sign ^ 1) & 1;
+                let high = 0xf000 | sign.wrapping_shl(10) | (imm.wrapping_shr(11) & 0x3ff);
+                let low = if call.is_some() { 0xd000 } else { 0x9000 }
+                    | j1.wrapping_shl(13)
+                    | j2.wrapping_shl(11)
+                    | (imm & 0x7ff);
+                let [h0, h1, _, _] = high.to_le_bytes();
+                let [l0, l1, _, _] = low.to_le_bytes();
+                return Ok(([h0, h1, l0, l1, 0, 0, 0, 0], 4));
+            }
+            (Arch::Riscv64, Insn::Int3) => return Ok(([0x02, 0x90, 0, 0, 0, 0, 0, 0], 2)),
+            (Arch::Riscv64, Insn::InfiniteLoop) => {
+                return Ok(([0x01, 0xa0, 0, 0, 0, 0, 0, 0], 2));
+            }
+            (Arch::Riscv64, _) => {
+                // jal zero or jal ra, with imm[20|10:1|11|19:12].
+                let imm = displacement(offset, 0, 2, 20)?;
+                let rd = u32::from(call.is_some());
+                (imm.wrapping_shr(19) & 1).wrapping_shl(31)
+                    | (imm & 0x3ff).wrapping_shl(21)
+                    | (imm.wrapping_shr(10) & 1).wrapping_shl(20)
+                    | (imm.wrapping_shr(11) & 0xff).wrapping_shl(12)
+                    | rd.wrapping_shl(7)
+                    | 0x6f
+            }
+            // bkpt #0
+            (Arch::Arm, Insn::Int3) => 0xe120_0070,
+            // b .
+            (Arch::Arm, Insn::InfiniteLoop) => 0xeaff_fffe,
+            (Arch::Arm, _) => {
+                let opcode = if call.is_some() {
+                    0xeb00_0000
+                } else {
+                    0xea00_0000
+                };
+                opcode | displacement(offset, 8, 4, 24)?
+            }
+            // brk #0
+            (Arch::Aarch64, Insn::Int3) => 0xd420_0000,
+            // b .
+            (Arch::Aarch64, Insn::InfiniteLoop) => 0x1400_0000,
+            (Arch::Aarch64, _) => {
+                let opcode = if call.is_some() {
+                    0x9400_0000
+                } else {
+                    0x1400_0000
+                };
+                opcode | displacement(offset, 0, 4, 26)?
+            }
+            // break
+            (Arch::Mips, Insn::Int3) => 0x0000_000d,
+            // b .
+            (Arch::Mips, Insn::InfiniteLoop) => 0x1000_ffff,
+            (Arch::Mips, _) => {
+                // b, i.e. beq $zero, $zero, or bal, i.e. bgezal $zero.
+                let opcode = if call.is_some() {
+                    0x0411_0000
+                } else {
+                    0x1000_0000
+                };
+                opcode | displacement(offset, 4, 4, 16)?
+            }
+        };
+        let [b0, b1, b2, b3] = match (self.arch, self.endianness) {
+            (Arch::Mips, Endianness::Big) => word.to_be_bytes(),
+            _ => word.to_le_bytes(),
+        };
+        match (self.arch.has_delay_slots(), self.insn) {
+            (true, Insn::JmpRel32(_) | Insn::CallRel32(_) | Insn::InfiniteLoop) => {
+                let [n0, n1, n2, n3] = self
+                    .arch
+                    .nop(self.endianness)
+                    .try_into()
+                    .unwrap_or_default();
+                Ok(([b0, b1, b2, b3, n0, n1, n2, n3], 8))
+            }
+            _ => Ok(([b0, b1, b2, b3, 0, 0, 0, 0], 4)),
+        }
+    }
+}
+
+impl Op for WriteInsn {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer(bytes.get(..len).unwrap_or_default()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer(bytes.get(..len).unwrap_or_default()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.bytes().ok().map(|(_bytes, len)| len)
+    }
+}
+
+/// Encodes the offset of a branch as the immediate of the instruction.
+///
+/// The offset is relative to the instruction, whereas the immediate is
+/// relative to `bias` bytes after it, and is counted in units of
+/// `alignment` bytes. Only the lowest `width` bits are returned.
+fn displacement(branch: Option<i32>, bias: i32, alignment: i32, width: u32) -> Result<u32> {
+    let offset = branch.unwrap_or_default();
+    let relative = offset.checked_sub(bias).ok_or(Error::IntegerOverflow)?;
+    let imm = relative
+        .checked_div(alignment)
+        .ok_or(Error::IntegerOverflow)?;
+    if imm.checked_mul(alignment) != Some(relative) {
+        return Err(Error::Misaligned {
+            value: u64::from(offset.unsigned_abs()),
+            alignment: u64::from(alignment.unsigned_abs()),
+        });
+    }
+    let unused = u32::BITS.saturating_sub(width);
+    if imm.wrapping_shl(unused).wrapping_shr(unused) != imm {
+        return Err(Error::IntegerOverflow);
+    }
+    Ok(u32::from_le_bytes(imm.to_le_bytes()) & u32::MAX.wrapping_shr(unused))
+}
+
+/// An operation that fills a region with generated bytes, e.g. a ramp, a
+/// checkerboard, or a structure-aware fuzzing pattern.
+///
+/// Bytes are either computed from their index in the region, see
+/// [`Generate::new`], or taken from an iterator, see
+/// [`Generate::with_iter`]. They are written as they are generated,
+/// without an intermediate buffer.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::Generate;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .add(Generate::new(4, |index| if index % 2 == 0 { 0xaa } else { 0x55 }))?
+///     .add(Generate::with_iter(4, b"AB".iter().copied().cycle()))?;
+/// assert_eq!(shellcoder.get(), b"\xaa\x55\xaa\x55ABAB");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Generate<I> {
+    /// Number of bytes to write.
+    len: usize,
+
+    /// The bytes, cloned each time the operation is written.
+    iter: I,
+}
+
+impl<F> Generate<iter::Map<Range<usize>, F>>
+where
+    F: Fn(usize) -> u8 + Clone,
+{
+    /// Instantiates a new [`Generate`] writing `len` bytes, each computed
+    /// from its index in the region.
+    #[inline]
+    #[must_use]
+    pub fn new(len: usize, generator: F) -> Self {
+        Self {
+            len,
+            iter: (0..len).map(generator),
+        }
+    }
+}
+
+impl<I> Generate<I>
+where
+    I: Iterator<Item = u8> + Clone,
+{
+    /// Instantiates a new [`Generate`] writing the first `len` bytes of an
+    /// iterator. If the iterator is shorter, the remaining bytes are
+    /// zeroes.
+    #[inline]
+    #[must_use]
+    pub const fn with_iter(len: usize, iter: I) -> Self {
+        Self { len, iter }
+    }
+
+    /// Returns the number of bytes to write.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there is nothing to write.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the bytes to write.
+    fn bytes(&self) -> impl Iterator<Item = u8> {
+        self.iter.clone().chain(iter::repeat(0)).take(self.len)
+    }
+}
+
+impl<I> Op for Generate<I>
+where
+    I: Iterator<Item = u8> + Clone + fmt::Debug,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let mut chunk = [0; FILL_CHUNK_LEN];
+        let mut bytes = self.bytes().peekable();
+        while bytes.peek().is_some() {
+            let n = chunk
+                .iter_mut()
+                .zip(bytes.by_ref())
+                .map(|(slot, byte)| *slot = byte)
+                .count();
+            stream.write_all(chunk.get(..n).unwrap_or_default())?;
+        }
+        Ok(self.len)
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let region = out
+            .as_mut()
+            .get_mut(..self.len)
+            .ok_or_else(|| Error::buffer_too_small(self.len))?;
+        for (slot, byte) in region.iter_mut().zip(self.bytes()) {
+            *slot = byte;
+        }
+        Ok(self.len)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// Letters of the cyclic pattern, see [`Pattern`].
+const PATTERN_ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// Length of the unique subsequences of the cyclic pattern.
+const PATTERN_WINDOW: usize = 4;
+
+/// The De Bruijn sequence of order [`PATTERN_WINDOW`] over
+/// [`PATTERN_ALPHABET`], i.e. the concatenation, in lexicographic order, of
+/// the Lyndon words whose length divides the order.
+#[derive(Clone, Debug)]
+struct DeBruijn {
+    /// The current Lyndon word, as indices in the alphabet.
+    word: [u8; PATTERN_WINDOW],
+
+    /// Length of the current Lyndon word, `0` once the sequence is over.
+    len: usize,
+
+    /// Index of the next letter of the current word.
+    position: usize,
+}
+
+impl DeBruijn {
+    /// Instantiates a new [`DeBruijn`], starting at the word `a`.
+    const fn new() -> Self {
+        Self {
+            word: [0; PATTERN_WINDOW],
+            len: 1,
+            position: 0,
+        }
+    }
+
+    /// Moves to the next Lyndon word, see Duval's algorithm.
+    fn next_word(&mut self) {
+        let period = self.len;
+        for index in period..PATTERN_WINDOW {
+            let letter = self
+                .word
+                .get(index.wrapping_sub(period))
+                .copied()
+                .unwrap_or_default();
+            if let Some(slot) = self.word.get_mut(index) {
+                *slot = letter;
+            }
+        }
+        self.len = PATTERN_WINDOW;
+        while self
+            .word
+            .get(self.len.wrapping_sub(1))
+            .map_or(false, |&letter| {
+                PATTERN_ALPHABET
+                    .get(usize::from(letter).saturating_add(1))
+                    .is_none()
+            })
+        {
+            self.len = self.len.saturating_sub(1);
+        }
+        if let Some(letter) = self.word.get_mut(self.len.wrapping_sub(1)) {
+            *letter = letter.wrapping_add(1);
+        }
+        self.position = 0;
+    }
+}
+
+impl Iterator for DeBruijn {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        while self.len != 0 {
+            if self.position < self.len && PATTERN_WINDOW.checked_rem(self.len) == Some(0) {
+                let letter = self.word.get(self.position).copied().unwrap_or_default();
+                self.position = self.position.saturating_add(1);
+                return PATTERN_ALPHABET.get(usize::from(letter)).copied();
+            }
+            self.next_word();
+        }
+        None
+    }
+}
+
+/// An operation that writes a cyclic pattern, i.e. a De Bruijn sequence in
+/// which each 4-byte subsequence is unique.
+///
+/// This tells which bytes of a payload overwrote e.g. a saved register,
+/// see [`Pattern::offset_of`]. The pattern is the same as the one of
+/// pwntools' `cyclic`. It repeats after 456,976 bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::Pattern;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 32];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(Pattern::new(12))?;
+/// assert_eq!(shellcoder.get(), b"aaaabaaacaaa");
+/// assert_eq!(Pattern::offset_of(*b"caaa"), Some(8));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pattern {
+    /// Number of bytes to write.
+    len: usize,
+
+    /// Offset in the pattern of the first byte to write.
+    start: usize,
+}
+
+impl Pattern {
+    /// Instantiates a new [`Pattern`] writing the first `len` bytes of the
+    /// pattern.
+    #[inline]
+    #[must_use]
+    pub const fn new(len: usize) -> Self {
+        Self { len, start: 0 }
+    }
+
+    /// Starts at an offset in the pattern, e.g. to resume it in another
+    /// field while keeping its subsequences unique.
+    #[inline]
+    #[must_use]
+    pub const fn with_start(mut self, start: usize) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Returns the number of bytes to write.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there is nothing to write.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the offset of a subsequence in the pattern, if any.
+    #[inline]
+    #[must_use]
+    pub fn offset_of(subsequence: [u8; PATTERN_WINDOW]) -> Option<usize> {
+        let mut window = [0; PATTERN_WINDOW];
+        DeBruijn::new().enumerate().find_map(|(index, letter)| {
+            window.rotate_left(1);
+            if let Some(last) = window.last_mut() {
+                *last = letter;
+            }
+            let start = index.checked_sub(PATTERN_WINDOW.saturating_sub(1))?;
+            (window == subsequence).then(|| start)
+        })
+    }
+
+    /// Returns the operation writing the pattern.
+    fn op(self) -> Generate<iter::Skip<iter::Cycle<DeBruijn>>> {
+        Generate::with_iter(self.len, DeBruijn::new().cycle().skip(self.start))
+    }
+}
+
+impl Op for Pattern {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op().write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op().write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// An integer that is encodable.
+pub trait EncodableInteger:
+    Copy + Clone + Sized + fmt::Debug + PartialEq + Eq + Send + Sync + WithOrWithoutSerde
+{
+    /// Returns the number of bytes needed to encode the integer.
+    fn n(self) -> usize;
+
+    /// Writes in big endian.
+    ///
+    /// # Errors
+    ///
+    /// An I/O error may be raised here.
+    #[cfg(feature = "std")]
+    fn write_be_io(self, stream: &mut dyn io::Write) -> Result<()>;
+
+    /// Writes in little endian.
+    ///
+    /// # Errors
+    ///
+    /// An I/O error may be raised here.
+    #[cfg(feature = "std")]
+    fn write_le_io(self, stream: &mut dyn io::Write) -> Result<()>;
+
+    /// Writes in big endian.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`] is raised if `out` cannot contain the encoded
+    /// integer.
+    fn write_be(self, out: impl AsMut<[u8]>) -> Result<()>;
+
+    /// Writes in little endian.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`] is raised if `out` cannot contain the encoded
+    /// integer.
+    fn write_le(self, out: impl AsMut<[u8]>) -> Result<()>;
+}
+
+/// Implements [`EncodableInteger`] for a given type.
+macro_rules! impl_encodable_integer_for {
+    ($i:ident) => {
+        impl EncodableInteger for $i {
+            #[inline]
+            fn n(self) -> usize {
+                ($i::BITS >> 3).try_into().expect("unreachable")
+            }
+
+            #[cfg(feature = "std")]
+            #[inline]
+            fn write_be_io(self, stream: &mut dyn io::Write) -> Result<()> {
+                stream.write_all(&self.to_be_bytes()).map_err(Error::from)
+            }
+
+            #[cfg(feature = "std")]
+            #[inline]
+            fn write_le_io(self, stream: &mut dyn io::Write) -> Result<()> {
+                stream.write_all(&self.to_le_bytes()).map_err(Error::from)
+            }
+
+            #[inline]
+            fn write_be(self, mut out: impl AsMut<[u8]>) -> Result<()> {
+                let n = self.n();
+                let out = out
+                    .as_mut()
+                    .get_mut(..n)
+                    .ok_or(Error::buffer_too_small(n))?;
+                // SAFETY:
+                //
+                // Length of `out` has been checked previously.
+                unsafe {
+                    out.as_mut_ptr().copy_from(self.to_be_bytes().as_ptr(), n);
+                }
+                Ok(())
+            }
+
+            #[inline]
+            fn write_le(self, mut out: impl AsMut<[u8]>) -> Result<()> {
+                let n = self.n();
+                let out = out
+                    .as_mut()
+                    .get_mut(..n)
+                    .ok_or(Error::buffer_too_small(n))?;
+                // SAFETY:
+                //
+                // Length of `out` has been checked previously.
+                unsafe {
+                    out.as_mut_ptr().copy_from(self.to_le_bytes().as_ptr(), n);
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_encodable_integer_for!(u8);
+impl_encodable_integer_for!(u16);
+impl_encodable_integer_for!(u32);
+impl_encodable_integer_for!(u64);
+
+/// Endianness of integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Endianness {
+    /// Little-endian, i.e. least significant byte first.
+    Little,
+
+    /// Big-endian, i.e. most significant byte first.
+    Big,
+}
+
+impl Default for Endianness {
+    /// Little-endian, as most targets are.
+    #[inline]
+    fn default() -> Self {
+        Self::Little
+    }
+}
+
+/// An operation that writes an integer.
+/// The cursor will be moved ahead by n bytes, n depending on the integer's
+/// encoded size.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    // Bounds are already implied by `WithOrWithoutSerde`.
+    serde(bound = "")
+)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum WriteInteger<I>
+where
+    I: EncodableInteger + WithOrWithoutSerde,
+{
+    /// The integer's value, to encode in big-endian.
+    BigEndian(I),
+
+    /// The integer's value, to encode in little-endian.
+    LittleEndian(I),
+}
+
+impl<I> WriteInteger<I>
+where
+    I: EncodableInteger,
+{
+    /// Instantiates a new [`WriteInteger`] to write an integer with a given
+    /// endianness, e.g. one read from a target configuration at runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::{Endianness, WriteInteger};
+    ///
+    /// let endianness = Endianness::Big;
+    /// let integer = WriteInteger::new(0x1337_u16, endianness);
+    /// assert_eq!(integer, WriteInteger::new_be(0x1337_u16));
+    /// assert_eq!(integer.endianness(), endianness);
+    /// assert_eq!(integer.value(), 0x1337);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn new(value: I, endianness: Endianness) -> Self {
+        match endianness {
+            Endianness::Little => Self::LittleEndian(value),
+            Endianness::Big => Self::BigEndian(value),
+        }
+    }
+
+    /// Instantiates a new [`WriteInteger`] to write a big-endian encoded integer.
+    #[inline]
+    #[must_use]
+    pub const fn new_be(value: I) -> Self {
+        Self::BigEndian(value)
+    }
+
+    /// Instantiates a new [`WriteInteger`] to write a little-endian encoded integer.
+    #[inline]
+    #[must_use]
+    pub const fn new_le(value: I) -> Self {
+        Self::LittleEndian(value)
+    }
+
+    /// Returns the integer's value.
+    #[inline]
+    #[must_use]
+    pub const fn value(&self) -> I {
+        match *self {
+            Self::BigEndian(value) | Self::LittleEndian(value) => value,
+        }
+    }
+
+    /// Returns the endianness the integer is encoded with.
+    #[inline]
+    #[must_use]
+    pub const fn endianness(&self) -> Endianness {
+        match self {
+            Self::BigEndian(_) => Endianness::Big,
+            Self::LittleEndian(_) => Endianness::Little,
+        }
+    }
+}
+
+impl<I> Op for WriteInteger<I>
+where
+    I: EncodableInteger,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        match self {
+            Self::BigEndian(n) => n.write_be_io(stream).map(|()| n.n()),
+            Self::LittleEndian(n) => n.write_le_io(stream).map(|()| n.n()),
+        }
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        match self {
+            Self::BigEndian(n) => n.write_be(out).map(|()| n.n()),
+            Self::LittleEndian(n) => n.write_le(out).map(|()| n.n()),
+        }
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        match self {
+            Self::BigEndian(n) | Self::LittleEndian(n) => Some(n.n()),
+        }
+    }
+}
+
+/// An operation that writes a sequence of incrementing integers, e.g.
+/// `00 01 02 03`, to find out which offsets of a payload the target
+/// consumes.
+///
+/// Integers wrap around on overflow. Their width is the width of their
+/// type, and they are little-endian unless stated otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Counter, Endianness};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 10];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .add(Counter::new(0xfe_u8, 4))?
+///     .add(Counter::new(0_u16, 3).with_step(0x10).with_endianness(Endianness::Big))?;
+/// assert_eq!(shellcoder.get(), b"\xfe\xff\x00\x01\x00\x00\x00\x10\x00\x20");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Counter<I> {
+    /// The first integer.
+    start: I,
+
+    /// The difference between two consecutive integers.
+    step: u64,
+
+    /// Number of integers.
+    count: usize,
+
+    /// Endianness of the integers.
+    endianness: Endianness,
+}
+
+impl<I> Counter<I>
+where
+    I: EncodableInteger + Into<u64>,
+{
+    /// Instantiates a new [`Counter`] writing `count` little-endian
+    /// integers, starting at `start` and incremented by one.
+    #[inline]
+    #[must_use]
+    pub const fn new(start: I, count: usize) -> Self {
+        Self {
+            start,
+            step: 1,
+            count,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// Sets the difference between two consecutive integers.
+    #[inline]
+    #[must_use]
+    pub fn with_step(mut self, step: I) -> Self {
+        self.step = step.into();
+        self
+    }
+
+    /// Sets the endianness of the integers.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Returns the number of integers.
+    #[inline]
+    #[must_use]
+    pub const fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the encoded integers, as the bytes of a 64-bit integer and
+    /// the range of the bytes to write.
+    fn elements(&self) -> impl Iterator<Item = ([u8; 8], Range<usize>)> {
+        let width = self.start.n();
+        let endianness = self.endianness;
+        let step = self.step;
+        iter::successors(Some(self.start.into()), move |value: &u64| {
+            Some(value.wrapping_add(step))
+        })
+        .take(self.count)
+        .map(move |value| match endianness {
+            Endianness::Little => (value.to_le_bytes(), 0..width),
+            Endianness::Big => (
+                value.to_be_bytes(),
+                mem::size_of::<u64>().saturating_sub(width)..mem::size_of::<u64>(),
+            ),
+        })
+    }
+}
+
+impl<I> Op for Counter<I>
+where
+    I: EncodableInteger + Into<u64>,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let mut n: usize = 0;
+        for (bytes, range) in self.elements() {
+            let element = bytes.get(range).unwrap_or_default();
+            stream.write_all(element)?;
+            n = n.saturating_add(element.len());
+        }
+        Ok(n)
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let size = self.size().ok_or(Error::IntegerOverflow)?;
+        let region = out
+            .as_mut()
+            .get_mut(..size)
+            .ok_or_else(|| Error::buffer_too_small(size))?;
+        for (slot, (bytes, range)) in region.chunks_exact_mut(self.start.n()).zip(self.elements()) {
+            slot.copy_from_slice(bytes.get(range).unwrap_or_default());
+        }
+        Ok(size)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.count.checked_mul(self.start.n())
+    }
+}
+
+/// An alternative encoding of integers, see [`WriteEncodedInteger`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum IntegerEncoding {
+    /// Zigzag encoding, as used by protobuf for signed integers: `0`, `-1`,
+    /// `1`, `-2`, ... are encoded as `0`, `1`, `2`, `3`, ...
+    ZigZag,
+
+    /// Excess-K encoding, i.e. the value plus `K`. Offset binary is the
+    /// excess-2^(n-1) encoding of n-bit integers.
+    Excess(i64),
+
+    /// Packed binary-coded decimal, i.e. one decimal digit per nibble.
+    Bcd,
+}
+
+impl IntegerEncoding {
+    /// Encodes a value.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: the value cannot be encoded, e.g. a
+    /// negative value in binary-coded decimal.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::IntegerEncoding;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// assert_eq!(IntegerEncoding::ZigZag.encode(-3)?, 5);
+    /// assert_eq!(IntegerEncoding::Excess(0x80).encode(-1)?, 0x7f);
+    /// assert_eq!(IntegerEncoding::Bcd.encode(1337)?, 0x1337);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn encode(self, value: i64) -> Result<u64> {
+        match self {
+            Self::ZigZag => {
+                let encoded = value.wrapping_shl(1) ^ value.wrapping_shr(i64::BITS - 1);
+                Ok(u64::from_le_bytes(encoded.to_le_bytes()))
+            }
+            Self::Excess(excess) => Ok(u64::try_from(
+                value.checked_add(excess).ok_or(Error::IntegerOverflow)?,
+            )?),
+            Self::Bcd => {
+                let mut remaining = u64::try_from(value)?;
+                let mut encoded: u64 = 0;
+                let mut shift: u32 = 0;
+                while remaining != 0 {
+                    let digit = remaining.checked_rem(10).unwrap_or_default();
+                    encoded |= digit
+                        .checked_shl(shift)
+                        .filter(|_digit| shift < u64::BITS)
+                        .ok_or(Error::IntegerOverflow)?;
+                    remaining = remaining.checked_div(10).unwrap_or_default();
+                    shift = shift.saturating_add(4);
+                }
+                Ok(encoded)
+            }
+        }
+    }
+}
+
+/// An operation that writes an integer with an alternative encoding, e.g.
+/// for protobuf-like parsers or industrial protocols.
+///
+/// The encoded integer is written as an integer of type `I`,
+/// little-endian unless stated otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Endianness, IntegerEncoding, WriteEncodedInteger};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 3];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .add(WriteEncodedInteger::<u8>::new(-64, IntegerEncoding::ZigZag))?
+///     .add(
+///         WriteEncodedInteger::<u16>::new(2024, IntegerEncoding::Bcd)
+///             .with_endianness(Endianness::Big),
+///     )?;
+/// assert_eq!(shellcoder.get(), b"\x7f\x20\x24");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WriteEncodedInteger<I> {
+    /// The value to encode.
+    value: i64,
+
+    /// The encoding.
+    encoding: IntegerEncoding,
+
+    /// Endianness of the encoded integer.
+    endianness: Endianness,
+
+    /// Type of the encoded integer.
+    integer: PhantomData<I>,
+}
+
+impl<I> WriteEncodedInteger<I>
+where
+    I: EncodableInteger + TryFrom<u64>,
+{
+    /// Instantiates a new [`WriteEncodedInteger`], writing a little-endian
+    /// integer.
+    #[inline]
+    #[must_use]
+    pub const fn new(value: i64, encoding: IntegerEncoding) -> Self {
+        Self {
+            value,
+            encoding,
+            endianness: Endianness::Little,
+            integer: PhantomData,
+        }
+    }
+
+    /// Sets the endianness of the encoded integer.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Returns the operation writing the encoded integer.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: the value cannot be encoded, or the
+    /// encoded integer does not fit in `I`.
+    fn op(&self) -> Result<WriteInteger<I>> {
+        let encoded = I::try_from(self.encoding.encode(self.value)?)
+            .map_err(|_error| Error::IntegerOverflow)?;
+        Ok(WriteInteger::new(encoded, self.endianness))
+    }
+}
+
+impl<I> Op for WriteEncodedInteger<I>
+where
+    I: EncodableInteger + TryFrom<u64>,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op()?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.op().ok()?.size()
+    }
+}
+
+/// Implements [`IntoOp`] for an integer type.
+macro_rules! impl_into_op_for_integer {
+    ($i:ident) => {
+        impl IntoOp for $i {
+            type Op = WriteInteger<$i>;
+
+            #[inline]
+            fn into_op(self, endianness: Endianness) -> Self::Op {
+                WriteInteger::new(self, endianness)
+            }
+        }
+    };
+}
+
+impl_into_op_for_integer!(u8);
+impl_into_op_for_integer!(u16);
+impl_into_op_for_integer!(u32);
+impl_into_op_for_integer!(u64);
+
+/// An operation that writes a buffer.
+/// The cursor will be moved ahead by the length in bytes of the given buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WriteBuffer<'buf>(&'buf [u8]);
+
+impl<'buf> WriteBuffer<'buf> {
+    /// Instantiates a new [`WriteBuffer`].
+    #[inline]
+    #[must_use]
+    pub fn new(buffer: &'buf (impl AsRef<[u8]> + ?Sized + 'buf)) -> Self {
+        Self(buffer.as_ref())
+    }
+
+    /// Returns the buffer to write.
+    #[inline]
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'buf [u8] {
+        self.0
+    }
+}
+
+impl Op for WriteBuffer<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        stream
+            .write_all(self.0)
+            .map(|()| self.0.len())
+            .map_err(Error::from)
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let n = self.0.len();
+        let out_slice = out
+            .as_mut()
+            .get_mut(..n)
+            .ok_or_else(|| Error::buffer_too_small(n))?;
+        // SAFETY:
+        //
+        // Length of `out` has been checked previously.
+        unsafe {
+            out_slice.as_mut_ptr().copy_from(self.0.as_ptr(), n);
+        }
+        Ok(n)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+impl<'buf> IntoOp for &'buf [u8] {
+    type Op = WriteBuffer<'buf>;
+
+    #[inline]
+    fn into_op(self, _endianness: Endianness) -> Self::Op {
+        WriteBuffer::new(self)
+    }
+}
+
+impl<'buf, const N: usize> IntoOp for &'buf [u8; N] {
+    type Op = WriteBuffer<'buf>;
+
+    #[inline]
+    fn into_op(self, _endianness: Endianness) -> Self::Op {
+        WriteBuffer::new(self)
+    }
+}
+
+impl<'buf> IntoOp for &'buf str {
+    type Op = WriteBuffer<'buf>;
+
+    #[inline]
+    fn into_op(self, _endianness: Endianness) -> Self::Op {
+        WriteBuffer::new(self)
+    }
+}
+
+/// An operation that writes a null-terminated string, as expected by C
+/// functions.
+///
+/// The string is written as is, followed by a null byte: a null byte inside
+/// the string ends it early on the target.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::WriteCString;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(WriteCString::new("/bin/sh"))?;
+/// assert_eq!(shellcoder.get(), b"/bin/sh\0");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct WriteCString<'buf>(&'buf [u8]);
+
+impl<'buf> WriteCString<'buf> {
+    /// Instantiates a new [`WriteCString`].
+    #[inline]
+    #[must_use]
+    pub fn new(string: &'buf (impl AsRef<[u8]> + ?Sized + 'buf)) -> Self {
+        Self(string.as_ref())
+    }
+
+    /// Returns the string to write, without its null terminator.
+    #[inline]
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'buf [u8] {
+        self.0
+    }
+
+    /// Returns the operations writing the string and its null terminator.
+    const fn ops(&self) -> (WriteBuffer<'buf>, Fill) {
+        (WriteBuffer(self.0), Fill(1, 0))
+    }
+}
+
+impl Op for WriteCString<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.ops().write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.ops().write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.0.len().checked_add(1)
+    }
+}
+
+/// An operation that writes a UTF-16 string, also known as a wide string,
+/// as expected by Windows APIs, e.g. `LoadLibraryW`.
+///
+/// The string is little-endian and followed by a null code unit, unless
+/// stated otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Endianness, WriteUtf16};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .add(WriteUtf16::new("é"))?
+///     .add(WriteUtf16::new("A").with_endianness(Endianness::Big).with_terminator(false))?;
+/// assert_eq!(shellcoder.get(), b"\xe9\0\0\0\0A");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WriteUtf16<'buf> {
+    /// The string to write.
+    string: &'buf str,
+
+    /// Endianness of the code units.
+    endianness: Endianness,
+
+    /// Whether the string is followed by a null code unit.
+    terminator: bool,
+}
+
+impl<'buf> WriteUtf16<'buf> {
+    /// Instantiates a new little-endian, null-terminated [`WriteUtf16`].
+    #[inline]
+    #[must_use]
+    pub const fn new(string: &'buf str) -> Self {
+        Self {
+            string,
+            endianness: Endianness::Little,
+            terminator: true,
+        }
+    }
+
+    /// Sets the endianness of the code units.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets whether the string is followed by a null code unit.
+    #[inline]
+    #[must_use]
+    pub const fn with_terminator(mut self, terminator: bool) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Returns the string to write.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'buf str {
+        self.string
+    }
+
+    /// Returns the operation writing the code units. The null code unit,
+    /// if any, is the zero padding of [`Generate`].
+    fn op(&self) -> Option<Generate<impl Iterator<Item = u8> + Clone + fmt::Debug + 'buf>> {
+        let units = self
+            .string
+            .encode_utf16()
+            .count()
+            .checked_add(usize::from(self.terminator))?;
+        let endianness = self.endianness;
+        let bytes = self
+            .string
+            .encode_utf16()
+            .flat_map(move |unit| match endianness {
+                Endianness::Little => unit.to_le_bytes(),
+                Endianness::Big => unit.to_be_bytes(),
+            });
+        Some(Generate::with_iter(units.checked_mul(2)?, bytes))
+    }
+}
+
+impl Op for WriteUtf16<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op().ok_or(Error::IntegerOverflow)?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op().ok_or(Error::IntegerOverflow)?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.op()?.size()
+    }
+}
+
+/// An operation that writes a length-prefixed string, also known as a
+/// Pascal string, as found in legacy protocols and serialized formats.
+///
+/// The length is written as an integer of type `L`, e.g. [`u8`], [`u16`]
+/// or [`u32`], little-endian unless stated otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Endianness, WritePascalString};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .add(WritePascalString::<u8>::new("abc"))?
+///     .add(WritePascalString::<u16>::new("de").with_endianness(Endianness::Big))?;
+/// assert_eq!(shellcoder.get(), b"\x03abc\x00\x02de");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WritePascalString<'buf, L> {
+    /// The string to write.
+    string: &'buf [u8],
+
+    /// Endianness of the length.
+    endianness: Endianness,
+
+    /// Type of the length.
+    prefix: PhantomData<L>,
+}
+
+impl<'buf, L> WritePascalString<'buf, L>
+where
+    L: EncodableInteger + TryFrom<usize>,
+{
+    /// Instantiates a new [`WritePascalString`], with a little-endian
+    /// length.
+    #[inline]
+    #[must_use]
+    pub fn new(string: &'buf (impl AsRef<[u8]> + ?Sized + 'buf)) -> Self {
+        Self {
+            string: string.as_ref(),
+            endianness: Endianness::Little,
+            prefix: PhantomData,
+        }
+    }
+
+    /// Sets the endianness of the length.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Returns the string to write, without its length.
+    #[inline]
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'buf [u8] {
+        self.string
+    }
+
+    /// Returns the operations writing the length and the string.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: the length does not fit in `L`.
+    fn ops(&self) -> Result<(WriteInteger<L>, WriteBuffer<'buf>)> {
+        let len = L::try_from(self.string.len()).map_err(|_error| Error::IntegerOverflow)?;
+        let prefix = match self.endianness {
+            Endianness::Little => WriteInteger::new_le(len),
+            Endianness::Big => WriteInteger::new_be(len),
+        };
+        Ok((prefix, WriteBuffer(self.string)))
+    }
+}
+
+impl<L> Op for WritePascalString<'_, L>
+where
+    L: EncodableInteger + TryFrom<usize>,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.ops()?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.ops()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.ops().ok()?.size()
+    }
+}
+
+/// Maximum length of a label of a DNS name, see [`DnsName`].
+pub const DNS_LABEL_MAX_LEN: usize = 63;
+
+/// Maximum length of a DNS name in wire format, see [`DnsName`].
+pub const DNS_NAME_MAX_LEN: usize = 255;
+
+/// An operation that writes a DNS name in wire format, as found in DNS
+/// queries, e.g. to exfiltrate data or to reach a resolver through a
+/// server-side request forgery.
+///
+/// Each label is prefixed by its length, and the name ends with the empty
+/// label of the root. A trailing dot is ignored, thus the root itself is
+/// either `"."` or `""`.
+///
+/// The case of letters can be randomized from a seed, also known as DNS
+/// 0x20 encoding, so that queries for the same name differ.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::DnsName;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::{Op as _, Shellcoder as _};
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 19];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(DnsName::new("exfil.example.com"))?;
+/// assert_eq!(shellcoder.get(), b"\x05exfil\x07example\x03com\0");
+///
+/// let name = DnsName::new("exfil.example.com").with_case_randomization(0x20);
+/// let mut randomized = [0u8; 19];
+/// name.write_to(&mut randomized)?;
+/// assert!(randomized.eq_ignore_ascii_case(shellcoder.get()));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DnsName<'name> {
+    /// The name, with labels separated by dots.
+    name: &'name str,
+
+    /// Seed of the case randomization, if any.
+    seed: Option<u64>,
+}
+
+impl<'name> DnsName<'name> {
+    /// Instantiates a new [`DnsName`], written as is.
+    #[inline]
+    #[must_use]
+    pub const fn new(name: &'name str) -> Self {
+        Self { name, seed: None }
+    }
+
+    /// Randomizes the case of letters, from a seed.
+    #[inline]
+    #[must_use]
+    pub const fn with_case_randomization(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Returns the name.
+    #[inline]
+    #[must_use]
+    pub const fn as_str(&self) -> &'name str {
+        self.name
+    }
+
+    /// Returns the operation writing the name.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Unsupported`]: the name has an empty label.
+    ///  - [`Error::MaxLengthExceeded`]: a label or the name is too long.
+    fn op(&self) -> Result<Generate<impl Iterator<Item = u8> + Clone + fmt::Debug + 'name>> {
+        let name = self.name.strip_suffix('.').unwrap_or(self.name);
+        let labels = (!name.is_empty())
+            .then(|| name.split('.'))
+            .into_iter()
+            .flatten();
+        let mut len: usize = 1;
+        for label in labels.clone() {
+            if label.is_empty() {
+                return Err(Error::Unsupported("empty DNS label"));
+            }
+            if label.len() > DNS_LABEL_MAX_LEN {
+                return Err(Error::MaxLengthExceeded {
+                    limit: DNS_LABEL_MAX_LEN,
+                    attempted: label.len(),
+                });
+            }
+            len = len.saturating_add(label.len()).saturating_add(1);
+        }
+        if len > DNS_NAME_MAX_LEN {
+            return Err(Error::MaxLengthExceeded {
+                limit: DNS_NAME_MAX_LEN,
+                attempted: len,
+            });
+        }
+        let bytes = labels
+            .flat_map(|label| {
+                iter::once(u8::try_from(label.len()).unwrap_or_default()).chain(label.bytes())
+            })
+            .scan(self.seed, |state, byte| {
+                let flip = state.as_mut().map_or(false, |generator| {
+                    byte.is_ascii_alphabetic() && splitmix64(generator) & 1 == 1
+                });
+                Some(if flip { byte ^ 0x20 } else { byte })
+            });
+        Ok(Generate::with_iter(len, bytes))
+    }
+}
+
+impl Op for DnsName<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op()?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.op().ok()?.size()
+    }
+}
+
+/// Byte order of a UUID, see [`WriteUuid`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum UuidLayout {
+    /// Big-endian, i.e. the wire order of RFC 4122.
+    Rfc4122,
+
+    /// Mixed-endian, i.e. the layout of a Windows `GUID`: the first three
+    /// fields are little-endian, the last eight bytes are kept as is.
+    Guid,
+}
+
+/// An operation that writes a UUID, either in the RFC 4122 wire order, or
+/// as a Windows `GUID`, e.g. to fake COM or ALPC structures.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{UuidLayout, WriteUuid};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // IID_IUnknown.
+/// let iid = WriteUuid::parse("{00000000-0000-0000-C000-000000000046}").unwrap();
+/// let mut buffer = [0u8; 16];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(iid.with_layout(UuidLayout::Guid))?;
+/// assert_eq!(shellcoder.get(), b"\0\0\0\0\0\0\0\0\xc0\0\0\0\0\0\0\x46");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WriteUuid {
+    /// The UUID, as read in its textual representation.
+    uuid: u128,
+
+    /// Byte order of the UUID.
+    layout: UuidLayout,
+}
+
+impl WriteUuid {
+    /// Instantiates a new [`WriteUuid`] in the RFC 4122 wire order, from
+    /// the integer read in its textual representation, e.g.
+    /// `0x00000000_0000_0000_c000_000000000046`.
+    #[inline]
+    #[must_use]
+    pub const fn new(uuid: u128) -> Self {
+        Self {
+            uuid,
+            layout: UuidLayout::Rfc4122,
+        }
+    }
+
+    /// Parses a UUID, e.g. `00000000-0000-0000-c000-000000000046`, with or
+    /// without hyphens and braces. Returns `None` if the UUID is malformed.
+    #[inline]
+    #[must_use]
+    pub fn parse(uuid: &str) -> Option<Self> {
+        let unbraced = uuid
+            .strip_prefix('{')
+            .and_then(|inner| inner.strip_suffix('}'))
+            .unwrap_or(uuid);
+        let hyphenated = match unbraced.len() {
+            32 => false,
+            36 => true,
+            _ => return None,
+        };
+        unbraced
+            .char_indices()
+            .try_fold(0, |value: u128, (index, chr)| {
+                if hyphenated && matches!(index, 8 | 13 | 18 | 23) {
+                    return (chr == '-').then(|| value);
+                }
+                chr.to_digit(16)
+                    .map(|digit| value.wrapping_shl(4) | u128::from(digit))
+            })
+            .map(Self::new)
+    }
+
+    /// Sets the byte order of the UUID.
+    #[inline]
+    #[must_use]
+    pub const fn with_layout(mut self, layout: UuidLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Returns the UUID, as read in its textual representation.
+    #[inline]
+    #[must_use]
+    pub const fn uuid(&self) -> u128 {
+        self.uuid
+    }
+
+    /// Returns the bytes to write.
+    #[inline]
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = self.uuid.to_be_bytes();
+        match self.layout {
+            UuidLayout::Rfc4122 => {}
+            UuidLayout::Guid => {
+                for field in [0..4, 4..6, 6..8] {
+                    if let Some(field_bytes) = bytes.get_mut(field) {
+                        field_bytes.reverse();
+                    }
+                }
+            }
+        }
+        bytes
+    }
+}
+
+impl Op for WriteUuid {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        WriteBuffer(&self.to_bytes()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        WriteBuffer(&self.to_bytes()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(mem::size_of::<u128>())
+    }
+}
+
+/// An operation that overwrites only the low bytes of a pointer.
+///
+/// With ASLR, the low bits of an address are usually known, e.g. its offset
+/// in a page, while the high bits are not. Overwriting the low bytes of a
+/// pointer that is already in memory redirects it within the same region,
+/// guessing only the unknown bits that have been overwritten, see
+/// [`PartialPointer::brute_force_bits`].
+///
+/// Bytes are written in the order they have in memory, i.e. first with a
+/// little-endian target. With a big-endian target, the low bytes are the
+/// last bytes of the pointer: the operation must then be written at the end
+/// of the pointer.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::PartialPointer;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // The saved return address is `0x5555_5555_51d3`, and `win` is at
+/// // offset `0x1189` of the same binary.
+/// let win = PartialPointer::new(0x1189, 2);
+/// assert_eq!(win.brute_force_bits(), 4);
+///
+/// let mut buffer = [0u8; 2];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(win)?;
+/// assert_eq!(shellcoder.get(), b"\x89\x11");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PartialPointer {
+    /// The address, whose low bytes are written.
+    address: u64,
+
+    /// Number of bytes to write.
+    len: usize,
+
+    /// Endianness of the target.
+    endianness: Endianness,
+
+    /// Number of low bits of the address that do not depend on ASLR.
+    known_bits: u32,
+}
+
+impl PartialPointer {
+    /// Instantiates a new [`PartialPointer`] that writes the `len` low
+    /// bytes of an address, for a little-endian target whose low 12 bits
+    /// do not depend on ASLR, i.e. with 4 KiB pages.
+    #[inline]
+    #[must_use]
+    pub const fn new(address: u64, len: usize) -> Self {
+        Self {
+            address,
+            len,
+            endianness: Endianness::Little,
+            known_bits: 12,
+        }
+    }
+
+    /// Sets the endianness of the target.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Sets the number of low bits of the address that do not depend on
+    /// ASLR, e.g. 16 with 64 KiB pages.
+    #[inline]
+    #[must_use]
+    pub const fn with_known_bits(mut self, known_bits: u32) -> Self {
+        self.known_bits = known_bits;
+        self
+    }
+
+    /// Returns the address, whose low bytes are written.
+    #[inline]
+    #[must_use]
+    pub const fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Returns the number of bytes to write.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there is no byte to write.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of overwritten bits that depend on ASLR, i.e.
+    /// that have to be brute-forced: the exploit succeeds once every
+    /// `2^bits` attempts.
+    #[inline]
+    #[must_use]
+    pub fn brute_force_bits(&self) -> u32 {
+        u32::try_from(self.len)
+            .map_or(u32::MAX, |len| len.saturating_mul(u8::BITS))
+            .min(u64::BITS)
+            .saturating_sub(self.known_bits)
+    }
+
+    /// Returns the bytes of the address, and the range of its low bytes.
+    fn bytes(&self) -> Result<([u8; 8], Range<usize>)> {
+        let width = mem::size_of::<u64>();
+        let start = width.checked_sub(self.len).ok_or(Error::IntegerOverflow)?;
+        Ok(match self.endianness {
+            Endianness::Little => (self.address.to_le_bytes(), 0..self.len),
+            Endianness::Big => (self.address.to_be_bytes(), start..width),
+        })
+    }
+}
+
+impl Op for PartialPointer {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let (bytes, range) = self.bytes()?;
+        WriteBuffer(bytes.get(range).unwrap_or_default()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        let (bytes, range) = self.bytes()?;
+        WriteBuffer(bytes.get(range).unwrap_or_default()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        (self.len <= mem::size_of::<u64>()).then(|| self.len)
+    }
+}
+
+/// A target-specific transform of pointers, applied before they are
+/// written, see [`WriteMangledPointer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Mangling {
+    /// glibc safe-linking of the `next` pointers of tcache and fastbin
+    /// chunks, i.e. `pointer ^ (position >> 12)`.
+    SafeLinking {
+        /// Address the pointer is stored at.
+        position: u64,
+    },
+
+    /// glibc `PTR_MANGLE` on `x86_64`, i.e. `rol(pointer ^ cookie, 0x11)`,
+    /// e.g. for `setjmp` buffers and `atexit` handlers.
+    PtrMangle {
+        /// The pointer guard, usually leaked from the thread control block.
+        cookie: u64,
+    },
+
+    /// `rol((pointer & and_mask) ^ xor_mask, rotation)`, e.g. to research
+    /// pointer authentication with some bits stripped.
+    Custom {
+        /// Mask of the bits of the pointer that are kept.
+        and_mask: u64,
+
+        /// Mask of the bits of the pointer that are flipped.
+        xor_mask: u64,
+
+        /// Number of bits the pointer is rotated to the left by.
+        rotation: u32,
+    },
+}
+
+impl Mangling {
+    /// Rotation of glibc `PTR_MANGLE` on `x86_64`.
+    const PTR_MANGLE_ROTATION: u32 = 0x11;
+
+    /// Shift of glibc safe-linking, i.e. the size of a page in bits.
+    const SAFE_LINKING_SHIFT: u32 = 12;
+
+    /// Mangles a pointer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::Mangling;
+    ///
+    /// let tcache = Mangling::SafeLinking { position: 0x5555_5555_92a0 };
+    /// assert_eq!(tcache.mangle(0x5555_5555_92c0), 0x5550_0000_c799);
+    /// assert_eq!(tcache.demangle(0x5550_0000_c799), 0x5555_5555_92c0);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn mangle(&self, pointer: u64) -> u64 {
+        match *self {
+            Self::SafeLinking { position } => {
+                pointer ^ position.wrapping_shr(Self::SAFE_LINKING_SHIFT)
+            }
+            Self::PtrMangle { cookie } => (pointer ^ cookie).rotate_left(Self::PTR_MANGLE_ROTATION),
+            Self::Custom {
+                and_mask,
+                xor_mask,
+                rotation,
+            } => ((pointer & and_mask) ^ xor_mask).rotate_left(rotation),
+        }
+    }
+
+    /// Demangles a pointer, e.g. a leaked one.
+    ///
+    /// Bits cleared by the `and_mask` of [`Mangling::Custom`] are lost.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::Mangling;
+    ///
+    /// // Knowing a mangled pointer and its value, e.g. `_dl_fini` in the
+    /// // `atexit` handlers, reveals the pointer guard.
+    /// let leaked = Mangling::PtrMangle { cookie: 0 }.demangle(0x2e05_8b7c_d5f1_e42b);
+    /// let cookie = leaked ^ 0x7fff_f7fc_9040;
+    /// let guard = Mangling::PtrMangle { cookie };
+    /// assert_eq!(guard.mangle(0x7fff_f7fc_9040), 0x2e05_8b7c_d5f1_e42b);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn demangle(&self, value: u64) -> u64 {
+        match *self {
+            Self::SafeLinking { .. } => self.mangle(value),
+            Self::PtrMangle { cookie } => value.rotate_right(Self::PTR_MANGLE_ROTATION) ^ cookie,
+            Self::Custom {
+                and_mask,
+                xor_mask,
+                rotation,
+            } => (value.rotate_right(rotation) ^ xor_mask) & and_mask,
+        }
+    }
+}
+
+/// An operation that writes a 64-bit pointer, mangled as the target
+/// expects it, see [`Mangling`].
+///
+/// The pointer is little-endian unless stated otherwise.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Mangling, WriteMangledPointer};
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // Poisons the `next` pointer of a freed tcache chunk at 0x4052a0.
+/// let next = Mangling::SafeLinking { position: 0x40_52a0 };
+/// let mut buffer = [0u8; 8];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(WriteMangledPointer::new(0x40_4040, next))?;
+/// assert_eq!(shellcoder.get(), b"\x45\x44\x40\0\0\0\0\0");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WriteMangledPointer {
+    /// The pointer, before it is mangled.
+    pointer: u64,
+
+    /// The mangling.
+    mangling: Mangling,
+
+    /// Endianness of the pointer.
+    endianness: Endianness,
+}
+
+impl WriteMangledPointer {
+    /// Instantiates a new [`WriteMangledPointer`].
+    #[inline]
+    #[must_use]
+    pub const fn new(pointer: u64, mangling: Mangling) -> Self {
+        Self {
+            pointer,
+            mangling,
+            endianness: Endianness::Little,
+        }
+    }
+
+    /// Sets the endianness of the pointer.
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Returns the pointer, before it is mangled.
+    #[inline]
+    #[must_use]
+    pub const fn pointer(&self) -> u64 {
+        self.pointer
+    }
+
+    /// Returns the mangled pointer, i.e. the value that is written.
+    #[inline]
+    #[must_use]
+    pub const fn value(&self) -> u64 {
+        self.mangling.mangle(self.pointer)
+    }
+
+    /// Returns the operation writing the mangled pointer.
+    const fn op(&self) -> WriteInteger<u64> {
+        WriteInteger::new(self.value(), self.endianness)
+    }
+}
+
+impl Op for WriteMangledPointer {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.op().write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.op().write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(mem::size_of::<u64>())
+    }
+}
+
+/// An operation that writes a buffer that is either borrowed or owned.
+///
+/// Unlike [`WriteBuffer`], the buffer may be owned, and
+/// [`crate::alloc::Vectored`] keeps it as is instead of copying it.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteCow<'buf>(Cow<'buf, [u8]>);
+
+#[cfg(feature = "std")]
+impl<'buf> WriteCow<'buf> {
+    /// Instantiates a new [`WriteCow`].
+    #[inline]
+    #[must_use]
+    pub fn new(buffer: impl Into<Cow<'buf, [u8]>>) -> Self {
+        Self(buffer.into())
+    }
+
+    /// Returns the buffer to write.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Consumes the operation and returns the buffer.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Cow<'buf, [u8]> {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl Op for WriteCow<'_> {
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        WriteBuffer::new(self.as_bytes()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        WriteBuffer::new(self.as_bytes()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// An operation that delegates the encoding to a closure.
+///
+/// The closure is given a buffer of `len` bytes to write to, and returns
+/// the number of bytes it has written, at most `len`. This is handy for
+/// one-off encodings that do not deserve their own operation.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::FnOp;
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // A 24-bit big-endian integer.
+/// let int24 = FnOp::new(3, |out: &mut [u8]| {
+///     out.copy_from_slice(&0x123456_u32.to_be_bytes()[1..]);
+///     Ok(3)
+/// });
+/// let mut buffer = [0u8; 4];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder.add(int24)?;
+/// assert_eq!(shellcoder.get(), b"\x12\x34\x56");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy)]
+pub struct FnOp<F>
+where
+    F: Fn(&mut [u8]) -> Result<usize>,
+{
+    /// Length of the buffer given to the closure.
+    len: usize,
+
+    /// The closure.
+    encode: F,
+}
+
+impl<F> FnOp<F>
+where
+    F: Fn(&mut [u8]) -> Result<usize>,
+{
+    /// Instantiates a new [`FnOp`], whose closure is given a buffer of `len`
+    /// bytes.
+    #[inline]
+    #[must_use]
+    pub const fn new(len: usize, encode: F) -> Self {
+        Self { len, encode }
+    }
+
+    /// Calls the closure, and checks the number of bytes it has written.
+    fn call(&self, buffer: &mut [u8]) -> Result<usize> {
+        let n = (self.encode)(buffer)?;
+        if n > self.len {
+            return Err(Error::buffer_too_small(n));
+        }
+        Ok(n)
+    }
+}
+
+impl<F> fmt::Debug for FnOp<F>
+where
+    F: Fn(&mut [u8]) -> Result<usize>,
+{
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("FnOp")
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F> Op for FnOp<F>
+where
+    F: Fn(&mut [u8]) -> Result<usize>,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let mut buffer = vec![0; self.len];
+        let n = self.call(&mut buffer)?;
+        WriteBuffer::new(buffer.get(..n).unwrap_or_default()).write_to_io(stream)
+    }
+
+    /// Calls the closure with the first `len` bytes of `out`. The closure
+    /// may have written to `out` even if it fails.
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let buffer = out
+            .as_mut()
+            .get_mut(..self.len)
+            .ok_or_else(|| Error::buffer_too_small(self.len))?;
+        self.call(buffer)
+    }
+}
+
+/// Default compression level of [`Compress`], between `0` (no compression)
+/// and `10`.
+#[cfg(feature = "compress")]
+const DEFAULT_LEVEL: u8 = 6;
+
+/// Compression format of [`Compress`].
+#[cfg(feature = "compress")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Compression {
+    /// Raw deflate stream (RFC 1951).
+    Deflate,
+
+    /// Deflate stream with a zlib header and checksum (RFC 1950).
+    Zlib,
+}
+
+/// An operation that compresses the output of another operation, e.g. a
+/// stage that is decompressed on the target.
+///
+/// The compressed bytes may be preceded by their length, as a 32-bit
+/// integer.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::alloc::Shellcoder;
+/// use shellcoder::ops::{Compress, Compression, Endianness, Fill};
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let stage = Compress::new(Fill::new(0x1000, 0x90))
+///     .with_format(Compression::Zlib)
+///     .with_length_prefix(Endianness::Little);
+///
+/// let mut shellcoder = Shellcoder::new();
+/// shellcoder.push(&stage)?;
+/// let (prefix, compressed) = shellcoder.as_bytes().split_at(4);
+/// assert_eq!(prefix, (compressed.len() as u32).to_le_bytes());
+/// assert!(compressed.len() < 0x40);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "compress")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compress<O> {
+    /// The operation to compress.
+    op: O,
+
+    /// Compression format.
+    format: Compression,
+
+    /// Compression level.
+    level: u8,
+
+    /// Endianness of the length prefix, if any.
+    length_prefix: Option<Endianness>,
+}
+
+#[cfg(feature = "compress")]
+impl<O> Compress<O>
+where
+    O: Op,
+{
+    /// Instantiates a new [`Compress`] operation, producing a raw deflate
+    /// stream with the default compression level, and no length prefix.
+    #[inline]
+    #[must_use]
+    pub const fn new(op: O) -> Self {
+        Self {
+            op,
+            format: Compression::Deflate,
+            level: DEFAULT_LEVEL,
+            length_prefix: None,
+        }
+    }
+
+    /// Sets the compression format.
+    #[inline]
+    #[must_use]
+    pub const fn with_format(mut self, format: Compression) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets the compression level, between `0` (no compression) and `10`
+    /// (best compression). Higher levels are treated as `10`.
+    #[inline]
+    #[must_use]
+    pub const fn with_level(mut self, level: u8) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Precedes the compressed bytes with their length, as a 32-bit integer.
+    #[inline]
+    #[must_use]
+    pub const fn with_length_prefix(mut self, endianness: Endianness) -> Self {
+        self.length_prefix = Some(endianness);
+        self
+    }
+
+    /// Returns the bytes written by the operation, i.e. the compressed bytes
+    /// preceded by their length if requested.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::IntegerOverflow`]: the length does not fit in 32 bits.
+    ///  - Any error returned by the inner operation.
+    #[inline]
+    pub fn compress(&self) -> Result<Vec<u8>> {
+        let mut uncompressed = Vec::with_capacity(self.op.size().unwrap_or_default());
+        self.op.write_to_io(&mut uncompressed)?;
+        let compressed = match self.format {
+            Compression::Deflate => deflate::compress_to_vec(&uncompressed, self.level),
+            Compression::Zlib => deflate::compress_to_vec_zlib(&uncompressed, self.level),
+        };
+        let endianness = match self.length_prefix {
+            Some(endianness) => endianness,
+            None => return Ok(compressed),
+        };
+        let len = u32::try_from(compressed.len())?;
+        let mut bytes = Vec::with_capacity(compressed.len().saturating_add(4));
+        match endianness {
+            Endianness::Little => bytes.extend_from_slice(&len.to_le_bytes()),
+            Endianness::Big => bytes.extend_from_slice(&len.to_be_bytes()),
+        }
+        bytes.extend_from_slice(&compressed);
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "compress")]
+impl<O> Op for Compress<O>
+where
+    O: Op,
+{
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let bytes = self.compress()?;
+        stream.write_all(&bytes)?;
+        Ok(bytes.len())
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let bytes = self.compress()?;
+        out.as_mut()
+            .get_mut(..bytes.len())
+            .ok_or_else(|| Error::buffer_too_small(bytes.len()))?
+            .copy_from_slice(&bytes);
+        Ok(bytes.len())
+    }
+}
+
+/// An operation that encrypts the output of another operation with a
+/// stream cipher, e.g. a stage that must not appear in cleartext on the
+/// wire.
+///
+/// The ciphertext has the length of the plaintext. The key and the nonce,
+/// that the decoder stub or the agent must be configured with, are
+/// returned by [`Encrypt::cipher`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::cipher::Cipher;
+/// use shellcoder::ops::{Encrypt, WriteBuffer};
+/// use shellcoder::Op as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let stage = Encrypt::new(WriteBuffer::new(b"Plaintext"), Cipher::rc4(b"Key").unwrap());
+///
+/// let mut buffer = [0u8; 9];
+/// stage.write_to(&mut buffer)?;
+/// assert_eq!(buffer, *b"\xbb\xf3\x16\xe8\xd9\x40\xaf\x0a\xd3");
+/// assert_eq!(stage.cipher().key(), b"Key");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "encrypt")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encrypt<'key, O> {
+    /// The operation to encrypt.
+    op: O,
+
+    /// The cipher.
+    cipher: Cipher<'key>,
+}
+
+#[cfg(feature = "encrypt")]
+impl<'key, O> Encrypt<'key, O>
+where
+    O: Op,
+{
+    /// Instantiates a new [`Encrypt`] operation.
+    #[inline]
+    #[must_use]
+    pub const fn new(op: O, cipher: Cipher<'key>) -> Self {
+        Self { op, cipher }
+    }
+
+    /// Returns the cipher, along with its key and nonce.
+    #[inline]
+    #[must_use]
+    pub const fn cipher(&self) -> &Cipher<'key> {
+        &self.cipher
+    }
+
+    /// Returns the bytes written by the operation, i.e. the ciphertext.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by the inner operation.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn encrypt(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(self.op.size().unwrap_or_default());
+        self.op.write_to_io(&mut bytes)?;
+        self.cipher.apply(&mut bytes);
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "encrypt")]
+impl<O> Op for Encrypt<'_, O>
+where
+    O: Op,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let bytes = self.encrypt()?;
+        stream.write_all(&bytes)?;
+        Ok(bytes.len())
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let buffer = out.as_mut();
+        let len = self.op.write_to(&mut *buffer)?;
+        self.cipher.apply(
+            buffer
+                .get_mut(..len)
+                .ok_or_else(|| Error::buffer_too_small(len))?,
+        );
+        Ok(len)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.op.size()
+    }
+}
+
+/// An operation that writes the output of another operation, followed by
+/// its `SipHash-2-4` under a key, e.g. a stage that the agent
+/// authenticates before executing it.
+///
+/// The tag is 8 bytes long, little-endian, see [`crate::mac::siphash24`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::mac;
+/// use shellcoder::ops::{Authenticate, WriteBuffer};
+/// use shellcoder::Op as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let key = [0x42; 16];
+/// let stage = Authenticate::new(WriteBuffer::new(b"\xcc\xc3"), key);
+///
+/// let mut buffer = [0u8; 10];
+/// assert_eq!(stage.write_to(&mut buffer)?, 10);
+/// let (payload, tag) = buffer.split_at(2);
+/// assert_eq!(tag, mac::siphash24(&key, payload).to_le_bytes());
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "mac")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Authenticate<O> {
+    /// The operation to authenticate.
+    op: O,
+
+    /// The key.
+    key: [u8; SIPHASH_KEY_LEN],
+}
+
+#[cfg(feature = "mac")]
+impl<O> Authenticate<O>
+where
+    O: Op,
+{
+    /// Instantiates a new [`Authenticate`] operation.
+    #[inline]
+    #[must_use]
+    pub const fn new(op: O, key: [u8; SIPHASH_KEY_LEN]) -> Self {
+        Self { op, key }
+    }
+
+    /// Returns the key, that the agent must be configured with.
+    #[inline]
+    #[must_use]
+    pub const fn key(&self) -> &[u8; SIPHASH_KEY_LEN] {
+        &self.key
+    }
+}
+
+#[cfg(feature = "mac")]
+impl<O> Op for Authenticate<O>
+where
+    O: Op,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let mut bytes = Vec::with_capacity(self.size().unwrap_or_default());
+        self.op.write_to_io(&mut bytes)?;
+        let tag = mac::siphash24(&self.key, &bytes);
+        bytes.extend_from_slice(&tag.to_le_bytes());
+        stream.write_all(&bytes)?;
+        Ok(bytes.len())
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let buffer = out.as_mut();
+        let len = self.op.write_to(&mut *buffer)?;
+        let total = len
+            .checked_add(SIPHASH_TAG_LEN)
+            .ok_or(Error::IntegerOverflow)?;
+        let (payload, rest) = buffer.split_at_mut(len);
+        let tag = mac::siphash24(&self.key, payload);
+        rest.get_mut(..SIPHASH_TAG_LEN)
+            .ok_or_else(|| Error::buffer_too_small(total))?
+            .copy_from_slice(&tag.to_le_bytes());
+        Ok(total)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.op.size()?.checked_add(SIPHASH_TAG_LEN)
+    }
+}
+
+/// A primitive operation of this module, i.e. [`Advance`], [`Fill`], a
+/// [`WriteInteger`] of `u8`, `u16`, `u32` or `u64`, or a [`WriteBuffer`].
+///
+/// This is useful for storing heterogeneous sequences of operations, and
+/// for serializing them. With the `serde` feature, an operation is
+/// serialized as an object with two fields: `op`, the name of the variant,
+/// and `args`, the operation itself.
+///
+/// Other operations are not covered, as they are generic or hold closures,
+/// iterators or owned buffers: push them with [`crate::Shellcoder::add`].
+/// With the `serde` feature, most of them derive `Serialize` and
+/// `Deserialize` on their own, except [`ShapedFill`], [`Placeholder`],
+/// [`FnOp`], `Generate`, `WriteCow` and the operations that wrap other
+/// operations, such as `Compress`.
+///
+/// Buffers of [`AnyOp::WriteBuffer`] are borrowed, thus they can only be
+/// deserialized from formats that support borrowed bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(tag = "op", content = "args")
+)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[non_exhaustive]
+pub enum AnyOp<'buf> {
+    /// See [`Advance`].
+    Advance(Advance),
+
+    /// See [`Fill`].
+    Fill(Fill),
+
+    /// See [`WriteInteger`].
+    WriteU8(WriteInteger<u8>),
+
+    /// See [`WriteInteger`].
+    WriteU16(WriteInteger<u16>),
+
+    /// See [`WriteInteger`].
+    WriteU32(WriteInteger<u32>),
+
+    /// See [`WriteInteger`].
+    WriteU64(WriteInteger<u64>),
+
+    /// See [`WriteBuffer`].
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    WriteBuffer(WriteBuffer<'buf>),
+}
+
+/// Implements [`From`] for each variant of [`AnyOp`].
+macro_rules! impl_from_for_any_op {
+    ($variant:ident, $op:ty) => {
+        impl<'buf> From<$op> for AnyOp<'buf> {
+            #[inline]
+            fn from(op: $op) -> Self {
+                Self::$variant(op)
+            }
+        }
+    };
+}
+
+impl_from_for_any_op!(Advance, Advance);
+impl_from_for_any_op!(Fill, Fill);
+impl_from_for_any_op!(WriteU8, WriteInteger<u8>);
+impl_from_for_any_op!(WriteU16, WriteInteger<u16>);
+impl_from_for_any_op!(WriteU32, WriteInteger<u32>);
+impl_from_for_any_op!(WriteU64, WriteInteger<u64>);
+impl_from_for_any_op!(WriteBuffer, WriteBuffer<'buf>);
+
+impl Op for AnyOp<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        match self {
+            Self::Advance(op) => op.write_to_io(stream),
+            Self::Fill(op) => op.write_to_io(stream),
+            Self::WriteU8(op) => op.write_to_io(stream),
+            Self::WriteU16(op) => op.write_to_io(stream),
+            Self::WriteU32(op) => op.write_to_io(stream),
+            Self::WriteU64(op) => op.write_to_io(stream),
+            Self::WriteBuffer(op) => op.write_to_io(stream),
+        }
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        match self {
+            Self::Advance(op) => op.write_to(out),
+            Self::Fill(op) => op.write_to(out),
+            Self::WriteU8(op) => op.write_to(out),
+            Self::WriteU16(op) => op.write_to(out),
+            Self::WriteU32(op) => op.write_to(out),
+            Self::WriteU64(op) => op.write_to(out),
+            Self::WriteBuffer(op) => op.write_to(out),
+        }
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        match self {
+            Self::Advance(op) => op.size(),
+            Self::Fill(op) => op.size(),
+            Self::WriteU8(op) => op.size(),
+            Self::WriteU16(op) => op.size(),
+            Self::WriteU32(op) => op.size(),
+            Self::WriteU64(op) => op.size(),
+            Self::WriteBuffer(op) => op.size(),
+        }
+    }
+}
+
+/// Checks a member of a sequence of operations, when it is written at
+/// `offset`, and moves `offset` to the next member, if its size is known.
+///
+/// Placeholders are rejected, as their region would be the whole sequence.
+fn check_member<O>(op: &O, offset: &mut Option<usize>) -> Result<()>
+where
+    O: Op + ?Sized,
+{
+    if op.placeholder().is_some() {
+        return Err(Error::Unsupported(
+            "placeholders in a sequence of operations",
+        ));
+    }
+    if let Some(start) = *offset {
+        op.check_at(start)?;
+        #[cfg(feature = "std")]
+        let size = op.size().or_else(|| op.write_to_io(&mut io::sink()).ok());
+        #[cfg(not(feature = "std"))]
+        let size = op.size();
+        *offset = size.and_then(|n| start.checked_add(n));
+    }
+    Ok(())
+}
+
+/// A slice of operations is an operation that writes them, in order.
+impl<O> Op for [O]
+where
+    O: Op,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.iter().try_fold(0, |n: usize, op| {
+            let written = op.write_to_io(stream)?;
+            n.checked_add(written).ok_or(Error::IntegerOverflow)
+        })
+    }
+
+    #[inline]
+    fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+        let out_slice = out.as_mut();
+        if let Some(size) = self.size() {
+            if size > out_slice.len() {
+                return Err(Error::buffer_too_small(size));
+            }
+        }
+        self.iter().try_fold(0, |offset: usize, op| {
+            op.write_to(out_slice.get_mut(offset..).unwrap_or_default())
+                .map(|written| offset.saturating_add(written))
+                .map_err(|error| error.shifted(offset))
+        })
+    }
+
+    /// Returns the sum of the sizes of the operations, or [`None`] if one
+    /// of them is unknown or if it overflows.
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.iter()
+            .try_fold(0, |size: usize, op| size.checked_add(op.size()?))
+    }
+
+    /// Checks each operation at the offset it is written at. Operations
+    /// following one whose size is unknown are not checked.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Unsupported`]: one of the operations is a placeholder.
+    ///  - Any error returned by [`Op::check_at`].
+    #[inline]
+    fn check_at(&self, offset: usize) -> Result<()> {
+        let mut next = Some(offset);
+        self.iter().try_for_each(|op| check_member(op, &mut next))
+    }
+}
+
+impl<O, const N: usize> Op for [O; N]
+where
+    O: Op,
+{
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.as_slice().write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.as_slice().write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.as_slice().size()
+    }
+
+    #[inline]
+    fn check_at(&self, offset: usize) -> Result<()> {
+        self.as_slice().check_at(offset)
+    }
+}
+
+/// Implements [`Op`] for a tuple of operations, that writes them in order.
+macro_rules! impl_op_for_tuple {
+    ($($op:ident: $ty:ident),+) => {
+        impl<$($ty),+> Op for ($($ty,)+)
+        where
+            $($ty: Op),+
+        {
+            #[cfg(feature = "std")]
+            #[inline]
+            fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+                let ($($op,)+) = self;
+                let mut n: usize = 0;
+                $(
+                    n = n
+                        .checked_add($op.write_to_io(stream)?)
+                        .ok_or(Error::IntegerOverflow)?;
+                )+
+                Ok(n)
+            }
+
+            #[inline]
+            fn write_to(&self, mut out: impl AsMut<[u8]>) -> Result<usize> {
+                let out_slice = out.as_mut();
+                if let Some(size) = self.size() {
+                    if size > out_slice.len() {
+                        return Err(Error::buffer_too_small(size));
+                    }
+                }
+                let ($($op,)+) = self;
+                let mut offset: usize = 0;
+                $(
+                    offset = $op
+                        .write_to(out_slice.get_mut(offset..).unwrap_or_default())
+                        .map(|written| offset.saturating_add(written))
+                        .map_err(|error| error.shifted(offset))?;
+                )+
+                Ok(offset)
+            }
+
+            #[inline]
+            fn size(&self) -> Option<usize> {
+                let ($($op,)+) = self;
+                let mut size: usize = 0;
+                $(
+                    size = size.checked_add($op.size()?)?;
+                )+
+                Some(size)
+            }
+
+            #[inline]
+            fn check_at(&self, offset: usize) -> Result<()> {
+                let ($($op,)+) = self;
+                let mut next = Some(offset);
+                $(
+                    check_member($op, &mut next)?;
+                )+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_op_for_tuple!(op0: O0);
+impl_op_for_tuple!(op0: O0, op1: O1);
+impl_op_for_tuple!(op0: O0, op1: O1, op2: O2);
+impl_op_for_tuple!(op0: O0, op1: O1, op2: O2, op3: O3);
+impl_op_for_tuple!(op0: O0, op1: O1, op2: O2, op3: O3, op4: O4);
+impl_op_for_tuple!(op0: O0, op1: O1, op2: O2, op3: O3, op4: O4, op5: O5);
+impl_op_for_tuple!(op0: O0, op1: O1, op2: O2, op3: O3, op4: O4, op5: O5, op6: O6);
+impl_op_for_tuple!(op0: O0, op1: O1, op2: O2, op3: O3, op4: O4, op5: O5, op6: O6, op7: O7);
+
+#[cfg(test)]
+#[allow(
+    clippy::absolute_paths,
+    clippy::arithmetic_side_effects,
+    clippy::assertions_on_result_states,
+    clippy::cognitive_complexity,
+    clippy::default_numeric_fallback,
+    clippy::indexing_slicing,
+    clippy::integer_division_remainder_used,
+    clippy::map_err_ignore,
+    clippy::min_ident_chars,
+    clippy::panic,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::shadow_reuse,
+    clippy::shadow_unrelated,
+    clippy::unnecessary_wraps,
+    clippy::unreadable_literal,
+    clippy::unseparated_literal_suffix,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used,
+    clippy::wildcard_enum_match_arm
+)]
+mod tests {
+    #[cfg(feature = "std")]
     mod advance {
         use crate::ops::Advance;
 
         use crate::prelude::*;
 
-        #[cfg(feature = "std")]
         #[test]
-        fn test_io() -> Result<()> {
-            {
-                let mut stream = Vec::new();
-                let advance = Advance::new(0);
-                assert_eq!(advance.write_to_io(&mut stream).unwrap(), 0);
-                assert!(stream.is_empty());
-            }
-            {
-                let mut stream = Vec::new();
-                let advance = Advance::new(42);
-                assert_eq!(advance.write_to_io(&mut stream).unwrap(), 42);
-                assert_eq!(stream.len(), 42);
-                assert_eq!(
-                    stream.as_slice(),
-                    &[
-                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    ]
-                );
-            }
-            {
-                let mut stream = vec![42u8; 2];
-                let advance = Advance::new(42);
-                assert_eq!(advance.write_to_io(&mut stream).unwrap(), 42);
-                assert_eq!(stream.len(), 44);
-                assert_eq!(
-                    stream.as_slice(),
-                    &[
-                        42, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                    ]
-                );
-            }
+        fn test_io() -> Result<()> {
+            {
+                let mut stream = Vec::new();
+                let advance = Advance::new(0);
+                assert_eq!(advance.write_to_io(&mut stream).unwrap(), 0);
+                assert!(stream.is_empty());
+            }
+            {
+                let mut stream = Vec::new();
+                let advance = Advance::new(42);
+                assert_eq!(advance.write_to_io(&mut stream).unwrap(), 42);
+                assert_eq!(stream.len(), 42);
+                assert_eq!(
+                    stream.as_slice(),
+                    &[
+                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    ]
+                );
+            }
+            {
+                let mut stream = vec![42u8; 2];
+                let advance = Advance::new(42);
+                assert_eq!(advance.write_to_io(&mut stream).unwrap(), 42);
+                assert_eq!(stream.len(), 44);
+                assert_eq!(
+                    stream.as_slice(),
+                    &[
+                        42, 42, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                    ]
+                );
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test() -> Result<()> {
+            {
+                let mut stream = vec![0u8; 10];
+                let advance = Advance::new(10);
+                assert_eq!(advance.write_to(&mut stream).unwrap(), 10);
+                assert_eq!(stream.len(), 10);
+                assert_eq!(stream.as_slice(), &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0,]);
+            }
+            {
+                let mut stream = vec![0x41u8; 9];
+                let advance = Advance::new(10);
+                let err = advance.write_to(&mut stream).unwrap_err();
+                assert!(matches!(err, Error::OutputBufferTooSmall(10)));
+                assert_eq!(stream.len(), 9);
+                assert_eq!(
+                    stream.as_slice(),
+                    &[0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41,]
+                );
+            }
+            Ok(())
+        }
+    }
+
+    mod assert_position {
+        #[cfg(feature = "std")]
+        use crate::alloc::{Shellcoder as Alloc, Vectored};
+        use crate::error::ErrorKind;
+        #[cfg(feature = "std")]
+        use crate::io::Shellcoder as Io;
+        use crate::ops::AssertPosition;
+        use crate::r#static::Shellcoder;
+        use crate::Shellcoder as _;
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let mut buffer = [0u8; 8];
+            let mut shellcoder = Shellcoder::new(&mut buffer);
+            shellcoder.assert_at(0)?.fill(4, b'A')?;
+            assert!(shellcoder.try_add(AssertPosition::new(4)).is_ok());
+            assert_eq!(
+                shellcoder
+                    .try_add(AssertPosition::new(5))
+                    .unwrap_err()
+                    .kind(),
+                ErrorKind::UnexpectedPosition
+            );
+            assert!(matches!(
+                shellcoder.assert_at(2).unwrap_err().root(),
+                Error::UnexpectedPosition {
+                    expected: 2,
+                    actual: 4
+                }
+            ));
+            assert_eq!(shellcoder.len(), 4);
+            assert_eq!(shellcoder.add_counted(AssertPosition::new(4))?.0, 0);
+            Ok(())
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_backends() -> Result<()> {
+            let mut shellcoder = Alloc::new();
+            shellcoder.fill(2, b'A')?.assert_at(2)?;
+            assert!(shellcoder.assert_at(3).is_err());
+            assert_eq!(shellcoder.as_bytes(), b"AA");
+
+            let mut stream = Vec::new();
+            let mut io = Io::new(&mut stream);
+            io.fill(3, b'B')?.assert_at(3)?;
+            assert!(io.assert_at(0).is_err());
+
+            let mut vectored = Vectored::new();
+            vectored.fill(1, b'C')?.assert_at(1)?;
+            assert!(vectored.assert_at(2).is_err());
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    mod fill {
+        use crate::ops::Fill;
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test_io() -> Result<()> {
+            {
+                let mut stream = Vec::new();
+                let fill = Fill::new(0, 0x41);
+                assert_eq!(fill.write_to_io(&mut stream).unwrap(), 0);
+                assert_eq!(stream.len(), 0);
+            }
+            {
+                let mut stream = Vec::new();
+                let fill = Fill::new(42, 0x41);
+                assert_eq!(fill.write_to_io(&mut stream).unwrap(), 42);
+                assert_eq!(stream.len(), 42);
+                assert_eq!(
+                    String::from_utf8(stream).as_deref(),
+                    Ok("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")
+                );
+            }
+            {
+                let mut stream = vec![0x42u8; 4];
+                let fill = Fill::new(42, 0x41);
+                assert_eq!(fill.write_to_io(&mut stream).unwrap(), 42);
+                assert_eq!(stream.len(), 46);
+                assert_eq!(
+                    String::from_utf8(stream).as_deref(),
+                    Ok("BBBBAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")
+                );
+            }
+            {
+                let mut stream = Vec::new();
+                let fill = Fill::new(0x2345, 0x41);
+                assert_eq!(fill.write_to_io(&mut stream).unwrap(), 0x2345);
+                assert_eq!(stream.len(), 0x2345);
+                assert!(stream.iter().all(|byte| *byte == 0x41));
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test() -> Result<()> {
+            {
+                let mut stream = vec![0u8; 10];
+                let fill = Fill::new(10, 0x41);
+                assert_eq!(fill.write_to(&mut stream).unwrap(), 10);
+                assert_eq!(stream.len(), 10);
+                assert_eq!(String::from_utf8(stream).as_deref(), Ok("AAAAAAAAAA"));
+            }
+
+            {
+                let mut stream = vec![0u8; 9];
+                let fill = Fill::new(10, 0x41);
+                let err = fill.write_to(&mut stream).unwrap_err();
+                assert!(matches!(err, Error::OutputBufferTooSmall(10)));
+                assert_eq!(stream.len(), 9);
+                assert_eq!(stream.as_slice(), &[0, 0, 0, 0, 0, 0, 0, 0, 0,]);
+            }
+            Ok(())
+        }
+    }
+
+    mod random_fill {
+        use crate::ops::RandomFill;
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let fill = RandomFill::new(12, 0);
+            assert_eq!(fill.len(), 12);
+            assert!(!fill.is_empty());
+            assert_eq!(fill.seed(), 0);
+            assert_eq!(fill.size(), Some(12));
+
+            let mut buffer = [0u8; 13];
+            assert_eq!(fill.write_to(&mut buffer)?, 12);
+            assert_eq!(buffer[..8], 0xe220_a839_7b1d_cdaf_u64.to_le_bytes());
+            assert_eq!(buffer[8..12], 0x6e78_9e6a_a1b9_65f4_u64.to_le_bytes()[..4]);
+            assert_eq!(buffer[12], 0);
+
+            let mut other = [0u8; 12];
+            RandomFill::new(12, 1).write_to(&mut other)?;
+            assert_ne!(buffer[..12], other);
+            assert!(matches!(
+                fill.write_to(&mut [0u8; 11]),
+                Err(Error::OutputBufferTooSmall(12))
+            ));
+            Ok(())
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_io() -> Result<()> {
+            let fill = RandomFill::new(0x1001, 0x1337);
+            let mut stream = Vec::new();
+            assert_eq!(fill.write_to_io(&mut stream)?, 0x1001);
+            let mut buffer = vec![0u8; 0x1001];
+            fill.write_to(&mut buffer)?;
+            assert_eq!(stream, buffer);
+            Ok(())
+        }
+    }
+
+    mod shaped_fill {
+        use crate::analysis;
+        use crate::ops::{ShapedFill, ENGLISH_TEXT};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let fill = ShapedFill::new(0x1000, &ENGLISH_TEXT, 42);
+            assert_eq!(fill.len(), 0x1000);
+            assert_eq!(fill.seed(), 42);
+            assert_eq!(fill.profile(), &ENGLISH_TEXT);
+            assert_eq!(fill.size(), Some(0x1000));
+
+            let mut buffer = [0u8; 0x1000];
+            assert_eq!(fill.write_to(&mut buffer)?, 0x1000);
+            let stats = analysis::stats(&buffer);
+            assert!(buffer
+                .iter()
+                .all(|&byte| ENGLISH_TEXT[usize::from(byte)] != 0));
+            assert!(stats.count(b' ') > stats.count(b'e'));
+            assert!(stats.count(b'e') > stats.count(b'k'));
+
+            let mut again = [0u8; 0x1000];
+            ShapedFill::new(0x1000, &ENGLISH_TEXT, 42).write_to(&mut again)?;
+            assert_eq!(buffer, again);
+            Ok(())
+        }
+
+        #[test]
+        fn test_profile() -> Result<()> {
+            let mut profile = [0; 256];
+            profile[0x41] = 1;
+            profile[0xff] = 3;
+            let mut buffer = [0u8; 0x400];
+            ShapedFill::new(0x400, &profile, 7).write_to(&mut buffer)?;
+            let stats = analysis::stats(&buffer);
+            assert_eq!(stats.distinct(), 2);
+            assert!(stats.count(0xff) > 2 * stats.count(0x41));
+
+            assert!(matches!(
+                ShapedFill::new(1, &[0; 256], 0).write_to(&mut [0u8; 1]),
+                Err(Error::Unsupported(_))
+            ));
+            Ok(())
+        }
+    }
+
+    mod placeholder {
+        use crate::config::PointerWidth;
+        use crate::ops::Placeholder;
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let placeholder = Placeholder::canary(PointerWidth::Bits16).with_filler(b'C');
+            assert_eq!(placeholder.name(), "canary");
+            assert_eq!(placeholder.placeholder(), Some("canary"));
+            assert_eq!(placeholder.size(), Some(2));
+
+            let mut buffer = [0u8; 3];
+            assert_eq!(placeholder.write_to(&mut buffer)?, 2);
+            assert_eq!(&buffer, b"CC\0");
+            assert!(Placeholder::new("cookie", 4).write_to(&mut buffer).is_err());
+            Ok(())
+        }
+    }
+
+    mod integers {
+        use crate::ops::{EncodableInteger, WriteInteger};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test_constructors() -> Result<()> {
+            assert_eq!(WriteInteger::new_be(1u8), WriteInteger::BigEndian(1u8));
+            assert_eq!(<_ as EncodableInteger>::n(1u8), 1);
+            assert_eq!(WriteInteger::new_le(1u8), WriteInteger::LittleEndian(1u8));
+            assert_eq!(WriteInteger::new_be(1u16), WriteInteger::BigEndian(1u16));
+            assert_eq!(<_ as EncodableInteger>::n(1u16), 2);
+            assert_eq!(WriteInteger::new_le(1u16), WriteInteger::LittleEndian(1u16));
+            assert_eq!(WriteInteger::new_be(1u32), WriteInteger::BigEndian(1u32));
+            assert_eq!(<_ as EncodableInteger>::n(1u32), 4);
+            assert_eq!(WriteInteger::new_le(1u32), WriteInteger::LittleEndian(1u32));
+            assert_eq!(WriteInteger::new_be(1u64), WriteInteger::BigEndian(1u64));
+            assert_eq!(<_ as EncodableInteger>::n(1u64), 8);
+            assert_eq!(WriteInteger::new_le(1u64), WriteInteger::LittleEndian(1u64));
+            Ok(())
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_encodable_integer_io() -> Result<()> {
+            {
+                let mut stream = Vec::new();
+                assert_eq!(
+                    WriteInteger::new_be(1u8).write_to_io(&mut stream).unwrap(),
+                    1
+                );
+                assert_eq!(stream.len(), 1);
+                assert_eq!(
+                    WriteInteger::new_le(2u8).write_to_io(&mut stream).unwrap(),
+                    1
+                );
+                assert_eq!(stream.len(), 2);
+                assert_eq!(stream.as_slice(), &[1, 2]);
+            }
+
+            {
+                let mut stream = vec![2u8; 1];
+                assert_eq!(
+                    WriteInteger::new_be(1u8).write_to_io(&mut stream).unwrap(),
+                    1
+                );
+                assert_eq!(stream.len(), 2);
+                assert_eq!(stream.as_slice(), &[2u8, 1u8]);
+            }
+
+            {
+                let mut stream = Vec::new();
+                assert_eq!(
+                    WriteInteger::new_be(0xdeadu16)
+                        .write_to_io(&mut stream)
+                        .unwrap(),
+                    2
+                );
+                assert_eq!(stream.len(), 2);
+                assert_eq!(stream.as_slice(), &[0xde, 0xad]);
+            }
+
+            {
+                let mut stream = Vec::new();
+                assert_eq!(
+                    WriteInteger::new_le(0xdeadu16)
+                        .write_to_io(&mut stream)
+                        .unwrap(),
+                    2
+                );
+                assert_eq!(stream.len(), 2);
+                assert_eq!(stream.as_slice(), &[0xad, 0xde]);
+            }
+
+            {
+                let mut stream = Vec::new();
+                assert_eq!(
+                    WriteInteger::new_be(0xdeadbeefu32)
+                        .write_to_io(&mut stream)
+                        .unwrap(),
+                    4
+                );
+                assert_eq!(stream.len(), 4);
+                assert_eq!(stream.as_slice(), &[0xde, 0xad, 0xbe, 0xef]);
+            }
+
+            {
+                let mut stream = Vec::new();
+                assert_eq!(
+                    WriteInteger::new_le(0xdeadbeefu32)
+                        .write_to_io(&mut stream)
+                        .unwrap(),
+                    4
+                );
+                assert_eq!(stream.len(), 4);
+                assert_eq!(stream.as_slice(), &[0xef, 0xbe, 0xad, 0xde]);
+            }
+
+            {
+                let mut stream = Vec::new();
+                assert_eq!(
+                    WriteInteger::new_be(0xdeadbeefcafebabeu64)
+                        .write_to_io(&mut stream)
+                        .unwrap(),
+                    8
+                );
+                assert_eq!(stream.len(), 8);
+                assert_eq!(
+                    stream.as_slice(),
+                    &[0xde, 0xad, 0xbe, 0xef, 0xca, 0xfe, 0xba, 0xbe]
+                );
+            }
+
+            {
+                let mut stream = Vec::new();
+                assert_eq!(
+                    WriteInteger::new_le(0xdeadbeefcafebabeu64)
+                        .write_to_io(&mut stream)
+                        .unwrap(),
+                    8
+                );
+                assert_eq!(stream.len(), 8);
+                assert_eq!(
+                    stream.as_slice(),
+                    &[0xbe, 0xba, 0xfe, 0xca, 0xef, 0xbe, 0xad, 0xde]
+                );
+            }
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "std")]
+    mod fn_op {
+        use crate::ops::FnOp;
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let op = FnOp::new(4, |out: &mut [u8]| {
+                out[..2].copy_from_slice(b"AB");
+                Ok(2)
+            });
+            let mut stream = Vec::new();
+            assert_eq!(op.write_to_io(&mut stream).unwrap(), 2);
+            assert_eq!(stream, b"AB");
+
+            let mut buffer = [0u8; 3];
+            assert!(matches!(
+                op.write_to(&mut buffer),
+                Err(Error::OutputBufferTooSmall(4))
+            ));
+
+            let liar = FnOp::new(1, |_: &mut [u8]| Ok(2));
+            assert!(matches!(
+                liar.write_to_io(&mut stream),
+                Err(Error::OutputBufferTooSmall(2))
+            ));
+            assert_eq!(format!("{liar:?}"), "FnOp { len: 1, .. }");
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "compress")]
+    mod compress {
+        use miniz_oxide::inflate::{decompress_to_vec, decompress_to_vec_zlib};
+
+        use crate::ops::{Compress, Compression, Endianness, Fill, WriteBuffer};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test_compress() -> Result<()> {
+            let stage = b"/bin/sh\0".repeat(64);
+            let deflate = Compress::new(WriteBuffer::new(&stage)).compress()?;
+            assert!(deflate.len() < stage.len());
+            assert_eq!(decompress_to_vec(&deflate).unwrap(), stage);
+
+            let zlib = Compress::new(WriteBuffer::new(&stage))
+                .with_format(Compression::Zlib)
+                .with_level(10)
+                .with_length_prefix(Endianness::Big);
+            let bytes = zlib.compress()?;
+            assert_eq!(bytes[..4], u32::try_from(bytes.len() - 4)?.to_be_bytes());
+            assert_eq!(decompress_to_vec_zlib(&bytes[4..]).unwrap(), stage);
+
+            let mut buffer = [0u8; 4];
+            assert!(matches!(
+                Compress::new(Fill::new(0x100, 0)).write_to(&mut buffer),
+                Err(Error::OutputBufferTooSmall(_))
+            ));
+            assert_eq!(buffer, [0; 4]);
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "encrypt")]
+    mod encrypt {
+        use crate::cipher::Cipher;
+        use crate::ops::{Encrypt, Fill, WriteBuffer};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test_encrypt() -> Result<()> {
+            let cipher = Cipher::chacha20([0x41; 32], [0x42; 12]);
+            let stage = Encrypt::new(WriteBuffer::new(b"/bin/sh\0"), cipher);
+            assert_eq!(stage.size(), Some(8));
+
+            let mut buffer = [0u8; 9];
+            assert_eq!(stage.write_to(&mut buffer)?, 8);
+            assert_ne!(buffer[..8], *b"/bin/sh\0");
+            assert_eq!(buffer[8], 0);
+            cipher.apply(&mut buffer[..8]);
+            assert_eq!(buffer[..8], *b"/bin/sh\0");
+            assert_eq!(stage.cipher().nonce(), Some(&[0x42; 12][..]));
+
+            #[cfg(feature = "std")]
+            {
+                let mut stream = vec![];
+                assert_eq!(stage.write_to_io(&mut stream)?, 8);
+                assert_eq!(stream, stage.encrypt()?);
+                cipher.apply(&mut stream);
+                assert_eq!(stream, b"/bin/sh\0");
+            }
+
+            let mut small = [0u8; 4];
+            assert!(matches!(
+                Encrypt::new(Fill::new(8, 0), cipher).write_to(&mut small),
+                Err(Error::OutputBufferTooSmall(_))
+            ));
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "mac")]
+    mod authenticate {
+        use crate::mac::siphash24;
+        use crate::ops::{Authenticate, Fill, WriteBuffer};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test_authenticate() -> Result<()> {
+            let key = [7; 16];
+            let stage = Authenticate::new(WriteBuffer::new(b"/bin/sh\0"), key);
+            assert_eq!(stage.size(), Some(16));
+            assert_eq!(stage.key(), &key);
+
+            let mut buffer = [0u8; 17];
+            assert_eq!(stage.write_to(&mut buffer)?, 16);
+            assert_eq!(buffer[..8], *b"/bin/sh\0");
+            assert_eq!(buffer[8..16], siphash24(&key, b"/bin/sh\0").to_le_bytes());
+            assert_eq!(buffer[16], 0);
+
+            #[cfg(feature = "std")]
+            {
+                let mut stream = vec![];
+                assert_eq!(stage.write_to_io(&mut stream)?, 16);
+                assert_eq!(stream, buffer[..16]);
+            }
+
+            assert!(matches!(
+                Authenticate::new(Fill::new(8, 0), key).write_to(&mut [0u8; 12]),
+                Err(Error::OutputBufferTooSmall(16))
+            ));
+            Ok(())
+        }
+    }
+
+    mod generate {
+        use crate::ops::Generate;
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let ramp = Generate::new(0x300, |index| index.to_le_bytes()[0]);
+            assert_eq!(ramp.size(), Some(0x300));
+            let mut buffer = [0u8; 0x300];
+            assert_eq!(ramp.write_to(&mut buffer)?, 0x300);
+            assert_eq!(buffer[0xff..0x102], [0xff, 0, 1]);
+
+            let short = Generate::with_iter(4, b"AB".iter().copied());
+            let mut small = [0xffu8; 5];
+            assert_eq!(short.write_to(&mut small)?, 4);
+            assert_eq!(small, *b"AB\0\0\xff");
+            assert!(matches!(
+                short.write_to(&mut [0u8; 3]),
+                Err(Error::OutputBufferTooSmall(4))
+            ));
+            assert!(Generate::with_iter(0, core::iter::empty()).is_empty());
+            Ok(())
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_io() -> Result<()> {
+            let pattern = Generate::new(0x2345, |index| (index % 251).to_le_bytes()[0]);
+            let mut stream = Vec::new();
+            assert_eq!(pattern.write_to_io(&mut stream)?, 0x2345);
+            let mut buffer = vec![0u8; 0x2345];
+            pattern.write_to(&mut buffer)?;
+            assert_eq!(stream, buffer);
+            Ok(())
+        }
+    }
+
+    mod pattern {
+        #[cfg(feature = "std")]
+        use std::collections::HashSet;
+
+        #[cfg(feature = "std")]
+        use crate::ops::DeBruijn;
+        use crate::ops::Pattern;
+
+        use crate::prelude::*;
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_de_bruijn() {
+            let sequence = DeBruijn::new().collect::<Vec<_>>();
+            assert_eq!(sequence.len(), 26 * 26 * 26 * 26);
+            assert!(sequence
+                .starts_with(b"aaaabaaacaaadaaaeaaafaaagaaahaaaiaaajaaakaaalaaamaaanaaaoaaa"));
+            assert!(sequence.ends_with(b"yyzzyzyzzzz"));
+            let windows = sequence.windows(4).collect::<HashSet<_>>();
+            assert_eq!(windows.len(), sequence.len() - 3);
+        }
+
+        #[test]
+        fn test() -> Result<()> {
+            let mut buffer = [0u8; 8];
+            let pattern = Pattern::new(8).with_start(0x20);
+            assert_eq!(pattern.write_to(&mut buffer)?, 8);
+            assert_eq!(buffer, *b"iaaajaaa");
+            #[cfg(feature = "std")]
+            {
+                let mut stream = Vec::new();
+                assert_eq!(Pattern::new(0x20).write_to_io(&mut stream)?, 0x20);
+                assert_eq!(stream, b"aaaabaaacaaadaaaeaaafaaagaaahaaa");
+            }
+
+            assert_eq!(Pattern::offset_of(*b"aaaa"), Some(0));
+            assert_eq!(Pattern::offset_of(*b"jaaa"), Some(0x24));
+            assert_eq!(Pattern::offset_of(*b"zzzz"), Some(456_976 - 4));
+            assert_eq!(Pattern::offset_of(*b"AAAA"), None);
+            Ok(())
+        }
+    }
+
+    mod counter {
+        use crate::ops::{Counter, Endianness};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let counter = Counter::new(u32::MAX - 1, 3)
+                .with_step(1)
+                .with_endianness(Endianness::Big);
+            assert_eq!(counter.size(), Some(12));
+            let mut buffer = [0u8; 12];
+            assert_eq!(counter.write_to(&mut buffer)?, 12);
+            assert_eq!(buffer, *b"\xff\xff\xff\xfe\xff\xff\xff\xff\0\0\0\0");
+            assert!(matches!(
+                counter.write_to(&mut [0u8; 8]),
+                Err(Error::OutputBufferTooSmall(12))
+            ));
+
+            let descending = Counter::new(3_u64, 2).with_step(u64::MAX);
+            let mut buffer = [0u8; 16];
+            descending.write_to(&mut buffer)?;
+            assert_eq!(buffer, *b"\x03\0\0\0\0\0\0\0\x02\0\0\0\0\0\0\0");
+            assert_eq!(
+                Counter::new(0_u8, usize::MAX).with_step(2).size(),
+                Some(usize::MAX)
+            );
+            assert_eq!(Counter::new(0_u16, usize::MAX).size(), None);
+            Ok(())
+        }
+
+        #[cfg(feature = "std")]
+        #[test]
+        fn test_io() -> Result<()> {
+            let counter = Counter::new(0_u16, 0x200).with_endianness(Endianness::Big);
+            let mut stream = Vec::new();
+            assert_eq!(counter.write_to_io(&mut stream)?, 0x400);
+            assert_eq!(stream[0x1fe..0x202], [0, 0xff, 1, 0]);
+            Ok(())
+        }
+
+        #[cfg(feature = "json")]
+        #[test]
+        fn test_serde() {
+            let counter = Counter::new(0x41_u8, 4).with_step(2);
+            let json = serde_json::to_string(&counter).unwrap();
+            assert_eq!(serde_json::from_str::<Counter<u8>>(&json).unwrap(), counter);
+        }
+    }
+
+    mod write_pascal_string {
+        use crate::ops::{Endianness, WritePascalString};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let string = WritePascalString::<u32>::new(b"/bin/sh").with_endianness(Endianness::Big);
+            assert_eq!(string.size(), Some(11));
+            assert_eq!(string.as_bytes(), b"/bin/sh");
+            let mut buffer = [0u8; 11];
+            assert_eq!(string.write_to(&mut buffer)?, 11);
+            assert_eq!(buffer, *b"\0\0\0\x07/bin/sh");
+            assert!(matches!(
+                string.write_to(&mut [0u8; 6]),
+                Err(Error::OutputBufferTooSmall(11))
+            ));
+
+            let long = [b'A'; 0x100];
+            let too_long = WritePascalString::<u8>::new(&long);
+            assert_eq!(too_long.size(), None);
+            assert!(matches!(
+                too_long.write_to(&mut [0u8; 0x101]),
+                Err(Error::IntegerOverflow)
+            ));
+            Ok(())
+        }
+    }
+
+    mod dns_name {
+        use crate::ops::{DnsName, DNS_LABEL_MAX_LEN};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let name = DnsName::new("a.bc.");
+            assert_eq!(name.as_str(), "a.bc.");
+            assert_eq!(name.size(), Some(6));
+            let mut buffer = [0xffu8; 7];
+            assert_eq!(name.write_to(&mut buffer)?, 6);
+            assert_eq!(buffer, *b"\x01a\x02bc\0\xff");
+
+            for name in ["", "."] {
+                let mut root = [0xffu8; 1];
+                assert_eq!(DnsName::new(name).write_to(&mut root)?, 1);
+                assert_eq!(root, [0]);
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn test_case_randomization() -> Result<()> {
+            let plain = "abcdefghijklmnopqrstuvwxyz.example-0.com";
+            let mut first = [0u8; 42];
+            let mut second = [0u8; 42];
+            DnsName::new(plain)
+                .with_case_randomization(1)
+                .write_to(&mut first)?;
+            DnsName::new(plain)
+                .with_case_randomization(2)
+                .write_to(&mut second)?;
+            assert_ne!(first, second);
+            assert!(first.eq_ignore_ascii_case(&second));
+            assert!(first.iter().any(u8::is_ascii_uppercase));
+            assert_eq!(first[0], 26);
+            assert!(first[37..].eq_ignore_ascii_case(b"\x03com\0"));
+
+            let mut again = [0u8; 42];
+            DnsName::new(plain)
+                .with_case_randomization(1)
+                .write_to(&mut again)?;
+            assert_eq!(first, again);
+            Ok(())
+        }
+
+        #[test]
+        fn test_invalid() -> Result<()> {
+            for name in ["a..b", "a..", ".a", ".."] {
+                assert!(matches!(
+                    DnsName::new(name).write_to(&mut [0u8; 8]),
+                    Err(Error::Unsupported(_))
+                ));
+            }
+
+            let label = [b'a'; DNS_LABEL_MAX_LEN + 1];
+            let long = core::str::from_utf8(&label).map_err(|_| Error::IntegerOverflow)?;
+            assert_eq!(DnsName::new(long).size(), None);
+            assert!(matches!(
+                DnsName::new(long).write_to(&mut [0u8; 0x80]),
+                Err(Error::MaxLengthExceeded {
+                    limit: 63,
+                    attempted: 64
+                })
+            ));
+
+            let mut labels = [b'a'; 0x140];
+            for dot in labels
+                .iter_mut()
+                .skip(DNS_LABEL_MAX_LEN)
+                .step_by(DNS_LABEL_MAX_LEN + 1)
+            {
+                *dot = b'.';
+            }
+            let name = core::str::from_utf8(&labels).map_err(|_| Error::IntegerOverflow)?;
+            assert!(matches!(
+                DnsName::new(name).write_to(&mut [0u8; 0x200]),
+                Err(Error::MaxLengthExceeded { limit: 255, .. })
+            ));
+            Ok(())
+        }
+    }
+
+    mod write_cstring {
+        use crate::ops::WriteCString;
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let string = WriteCString::new(b"id");
+            assert_eq!(string.as_bytes(), b"id");
+            assert_eq!(string.size(), Some(3));
+            let mut buffer = [0xffu8; 4];
+            assert_eq!(string.write_to(&mut buffer)?, 3);
+            assert_eq!(buffer, *b"id\0\xff");
+            assert!(matches!(
+                string.write_to(&mut [0u8; 2]),
+                Err(Error::OutputBufferTooSmall(_))
+            ));
+            Ok(())
+        }
+    }
+
+    mod mangled_pointer {
+        use crate::ops::{Endianness, Mangling, WriteMangledPointer};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let guard = Mangling::PtrMangle {
+                cookie: 0x1122_3344_5566_7788,
+            };
+            assert_eq!(
+                guard.demangle(guard.mangle(0x7f12_3456_789a)),
+                0x7f12_3456_789a
+            );
+            let custom = Mangling::Custom {
+                and_mask: 0x0000_ffff_ffff_ffff,
+                xor_mask: 0xff,
+                rotation: 8,
+            };
+            assert_eq!(custom.mangle(0xaa00_0000_0000_1234), 0x0000_0000_0012_cb00);
+            assert_eq!(custom.demangle(0x0000_0000_0012_cb00), 0x1234);
+
+            let pointer =
+                WriteMangledPointer::new(0x1000, Mangling::SafeLinking { position: 0x2000 })
+                    .with_endianness(Endianness::Big);
+            assert_eq!(pointer.pointer(), 0x1000);
+            assert_eq!(pointer.value(), 0x1002);
+            assert_eq!(pointer.size(), Some(8));
+            let mut buffer = [0u8; 8];
+            assert_eq!(pointer.write_to(&mut buffer)?, 8);
+            assert_eq!(buffer, *b"\0\0\0\0\0\0\x10\x02");
+            Ok(())
+        }
+    }
+
+    mod sled {
+        use crate::arch::Arch;
+        use crate::ops::{Endianness, Sled};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let sled = Sled::new(Arch::Aarch64, 8).with_endianness(Endianness::Big);
+            assert_eq!(sled.arch(), Arch::Aarch64);
+            assert_eq!(sled.len(), 8);
+            assert_eq!(sled.size(), Some(8));
+            let mut buffer = [0u8; 9];
+            assert_eq!(sled.write_to(&mut buffer)?, 8);
+            assert_eq!(buffer, *b"\x1f\x20\x03\xd5\x1f\x20\x03\xd5\0");
+
+            let sled = Sled::new(Arch::Arm, 6);
+            assert_eq!(sled.size(), None);
+            assert!(matches!(
+                sled.write_to(&mut buffer),
+                Err(Error::Misaligned {
+                    value: 6,
+                    alignment: 4
+                })
+            ));
+            assert!(Sled::new(Arch::X86, 0).is_empty());
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "polymorphic")]
+    mod junk {
+        use crate::arch::Arch;
+        use crate::ops::Junk;
+
+        use crate::prelude::*;
+
+        /// Returns `true` if a ModR/M byte has the same register as both
+        /// operands, with the given addressing mode.
+        fn same(modrm: u8, mode: u8) -> bool {
+            modrm >> 6 == mode && (modrm >> 3) & 7 == modrm & 7 && (mode == 3 || modrm & 7 != 4)
+        }
+
+        /// Returns the lengths of the instructions of a padding, panicking
+        /// on anything but inert instructions.
+        #[allow(clippy::single_call_fn)]
+        fn lengths(bytes: &[u8], x86_64: bool) -> impl Iterator<Item = usize> + '_ {
+            let mut offset = 0;
+            core::iter::from_fn(move || {
+                let rest = bytes.get(offset..).filter(|rest| !rest.is_empty())?;
+                let len = match *rest {
+                    [0x90, ..] => 1,
+                    [0x66, 0x90, ..] | [0xeb, 0x00, ..] => 2,
+                    [0x86 | 0x88 | 0x8a, modrm, ..] if same(modrm, 3) => 2,
+                    [0x87 | 0x89 | 0x8b, modrm, ..] if !x86_64 && same(modrm, 3) => 2,
+                    [0x66, 0x87 | 0x89 | 0x8b, modrm, ..] if same(modrm, 3) => 3,
+                    [0x48 | 0x4d, 0x87 | 0x89 | 0x8b, modrm, ..] if x86_64 && same(modrm, 3) => 3,
+                    [0x0f, 0x1f, 0x00, ..] => 3,
+                    [0x8d, modrm, 0x00, ..] if !x86_64 && same(modrm, 1) => 3,
+                    [0x0f, 0x1f, 0x40, 0x00, ..] => 4,
+                    [0x48 | 0x4d, 0x8d, modrm, 0x00, ..] if x86_64 && same(modrm, 1) => 4,
+                    [0x0f, 0x1f, 0x44, 0x00, 0x00, ..] => 5,
+                    _ => panic!("unexpected junk at {offset}: {rest:02x?}"),
+                };
+                offset += len;
+                Some(len)
+            })
+        }
+
+        #[test]
+        fn test() -> Result<()> {
+            for (arch, x86_64) in [(Arch::X86, false), (Arch::X86_64, true)] {
+                for seed in 0..32 {
+                    let junk = Junk::new(arch, 61, seed);
+                    assert_eq!(junk.size(), Some(61));
+                    let mut buffer = [0u8; 62];
+                    assert_eq!(junk.write_to(&mut buffer)?, 61);
+                    assert_eq!(buffer[61], 0);
+                    assert_eq!(lengths(&buffer[..61], x86_64).sum::<usize>(), 61);
+
+                    let mut again = [0u8; 62];
+                    Junk::new(arch, 61, seed).write_to(&mut again)?;
+                    assert_eq!(buffer, again);
+                }
+            }
+
+            let mut first = [0u8; 32];
+            let mut second = [0u8; 32];
+            Junk::new(Arch::X86, 32, 1).write_to(&mut first)?;
+            Junk::new(Arch::X86, 32, 2).write_to(&mut second)?;
+            assert_ne!(first, second);
+            Ok(())
+        }
+
+        #[test]
+        fn test_unsupported() {
+            let junk = Junk::new(Arch::Aarch64, 4, 0);
+            assert_eq!(junk.arch(), Arch::Aarch64);
+            assert_eq!(junk.seed(), 0);
+            assert!(!junk.is_empty());
+            assert!(matches!(
+                junk.write_to(&mut [0u8; 4]),
+                Err(Error::Unsupported(_))
+            ));
+        }
+    }
+
+    mod syscall_stub {
+        use crate::arch::Arch;
+        use crate::ops::{Endianness, SyscallStub};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let stub = SyscallStub::new(Arch::X86_64);
+            assert_eq!(stub.arch(), Arch::X86_64);
+            assert_eq!(stub.size(), Some(2));
+            let mut buffer = [0u8; 12];
+            assert_eq!(stub.with_return(true).write_to(&mut buffer)?, 3);
+            assert_eq!(buffer[..3], *b"\x0f\x05\xc3");
+
+            let stub = SyscallStub::new(Arch::Mips).with_return(true);
+            assert_eq!(stub.write_to(&mut buffer)?, 12);
+            assert_eq!(buffer, *b"\x0c\x01\x01\x01\x08\x00\xe0\x03\x01\x01\x20\x25");
+            assert_eq!(
+                SyscallStub::new(Arch::Mips)
+                    .with_endianness(Endianness::Big)
+                    .size(),
+                Some(4)
+            );
+
+            let mut buffer = [0u8; 8];
+            for (arch, expected) in [
+                (Arch::X86, &b"\xb8\x0b\0\0\0\xcd\x80"[..]),
+                (Arch::Arm, b"\x0b\x70\x00\xe3\x00\x00\x00\xef"),
+                (Arch::Thumb, b"\x0b\x27\x00\xdf"),
+                (Arch::Aarch64, b"\xa8\x1b\x80\xd2\x01\x00\x00\xd4"),
+                (Arch::Mips, b"\xab\x0f\x02\x24\x0c\x01\x01\x01"),
+                (Arch::Riscv64, b"\x93\x08\xd0\x0d\x73\0\0\0"),
+            ] {
+                let number = match arch {
+                    Arch::Aarch64 | Arch::Riscv64 => 221,
+                    Arch::Mips => 4011,
+                    _ => 11,
+                };
+                let stub = SyscallStub::new(arch).with_number(number);
+                assert_eq!(stub.number(), Some(number));
+                assert_eq!(stub.size(), Some(expected.len()));
+                assert_eq!(stub.write_to(&mut buffer)?, expected.len());
+                assert_eq!(buffer[..expected.len()], *expected);
+            }
+            assert!(SyscallStub::new(Arch::Thumb)
+                .with_number(0x100)
+                .write_to(&mut buffer)
+                .is_err());
+            assert_eq!(
+                SyscallStub::new(Arch::Riscv64).with_number(0x800).size(),
+                None
+            );
+            Ok(())
+        }
+    }
+
+    mod insn {
+        use crate::arch::Arch;
+        use crate::ops::{Endianness, Insn, WriteInsn};
+
+        use crate::prelude::*;
+
+        fn encode(insn: WriteInsn, buffer: &mut [u8; 8]) -> Result<&[u8]> {
+            let len = insn.write_to(&mut *buffer)?;
+            assert_eq!(insn.size(), Some(len));
+            Ok(&buffer[..len])
+        }
+
+        #[test]
+        fn test() -> Result<()> {
+            let mut buffer = [0u8; 8];
+            for (arch, insn, expected) in [
+                (Arch::X86, Insn::JmpRel32(-5), &b"\xe9\xf6\xff\xff\xff"[..]),
+                (Arch::X86_64, Insn::Int3, b"\xcc"),
+                (Arch::Arm, Insn::CallRel32(0x1000), b"\xfe\x03\x00\xeb"),
+                (Arch::Arm, Insn::JmpRel32(0), b"\xfe\xff\xff\xea"),
+                (Arch::Arm, Insn::Int3, b"\x70\x00\x20\xe1"),
+                (Arch::Thumb, Insn::CallRel32(4), b"\x00\xf0\x00\xf8"),
+                (Arch::Thumb, Insn::JmpRel32(-0x100), b"\xff\xf7\x7e\xbf"),
+                (Arch::Aarch64, Insn::CallRel32(0x40), b"\x10\x00\x00\x94"),
+                (Arch::Aarch64, Insn::Int3, b"\x00\x00\x20\xd4"),
+                (
+                    Arch::Mips,
+                    Insn::InfiniteLoop,
+                    b"\xff\xff\x00\x10\x01\x01\x20\x25",
+                ),
+                (Arch::Riscv64, Insn::CallRel32(8), b"\xef\x00\x80\x00"),
+                (Arch::Riscv64, Insn::JmpRel32(-4), b"\x6f\xf0\xdf\xff"),
+                (Arch::Riscv64, Insn::InfiniteLoop, b"\x01\xa0"),
+            ] {
+                let op = WriteInsn::new(arch, insn);
+                assert_eq!(op.arch(), arch);
+                assert_eq!(op.insn(), insn);
+                assert_eq!(encode(op, &mut buffer)?, expected);
+            }
+            assert_eq!(
+                encode(
+                    WriteInsn::new(Arch::Mips, Insn::CallRel32(0x14))
+                        .with_endianness(Endianness::Big),
+                    &mut buffer
+                )?,
+                b"\x04\x11\x00\x04\x25\x20\x01\x01"
+            );
+            assert!(matches!(
+                encode(
+                    WriteInsn::new(Arch::Aarch64, Insn::JmpRel32(6)),
+                    &mut buffer
+                ),
+                Err(Error::Misaligned {
+                    value: 6,
+                    alignment: 4
+                })
+            ));
+            assert!(matches!(
+                encode(
+                    WriteInsn::new(Arch::Riscv64, Insn::JmpRel32(0x10_0000)),
+                    &mut buffer
+                ),
+                Err(Error::IntegerOverflow)
+            ));
+            assert!(matches!(
+                encode(
+                    WriteInsn::new(Arch::X86, Insn::JmpRel32(i32::MIN)),
+                    &mut buffer
+                ),
+                Err(Error::IntegerOverflow)
+            ));
+            Ok(())
+        }
+    }
+
+    mod partial_pointer {
+        use crate::ops::{Endianness, PartialPointer};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let pointer = PartialPointer::new(0x7fff_1234_5678, 3);
+            assert_eq!(pointer.address(), 0x7fff_1234_5678);
+            assert_eq!(pointer.len(), 3);
+            assert_eq!(pointer.brute_force_bits(), 12);
+            assert_eq!(pointer.with_known_bits(28).brute_force_bits(), 0);
+            assert_eq!(PartialPointer::new(0, 1).brute_force_bits(), 0);
+
+            let mut buffer = [0xffu8; 4];
+            assert_eq!(pointer.write_to(&mut buffer)?, 3);
+            assert_eq!(buffer, *b"\x78\x56\x34\xff");
+            let pointer = pointer.with_endianness(Endianness::Big);
+            assert_eq!(pointer.write_to(&mut buffer)?, 3);
+            assert_eq!(buffer, *b"\x34\x56\x78\xff");
+
+            let pointer = PartialPointer::new(0, 9);
+            assert_eq!(pointer.size(), None);
+            assert_eq!(pointer.brute_force_bits(), 52);
+            assert!(matches!(
+                pointer.write_to(&mut [0u8; 16]),
+                Err(Error::IntegerOverflow)
+            ));
             Ok(())
         }
+    }
+
+    mod write_utf16 {
+        use crate::ops::{Endianness, WriteUtf16};
+
+        use crate::prelude::*;
 
-        #[cfg(feature = "std")]
         #[test]
         fn test() -> Result<()> {
-            {
-                let mut stream = vec![0u8; 10];
-                let advance = Advance::new(10);
-                assert_eq!(advance.write_to(&mut stream).unwrap(), 10);
-                assert_eq!(stream.len(), 10);
-                assert_eq!(stream.as_slice(), &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0,]);
-            }
-            {
-                let mut stream = vec![0u8; 9];
-                let advance = Advance::new(10);
-                let err = advance.write_to(&mut stream).unwrap_err();
-                let io_err = err.io().unwrap();
-                assert_eq!(io_err.kind(), std::io::ErrorKind::WriteZero);
-                assert_eq!(stream.len(), 9);
-                assert_eq!(stream.as_slice(), &[0, 0, 0, 0, 0, 0, 0, 0, 0,]);
-            }
+            let string = WriteUtf16::new("k\u{1f600}");
+            assert_eq!(string.as_str(), "k\u{1f600}");
+            assert_eq!(string.size(), Some(8));
+            let mut buffer = [0xffu8; 8];
+            assert_eq!(string.write_to(&mut buffer)?, 8);
+            assert_eq!(buffer, *b"k\0\x3d\xd8\x00\xde\0\0");
+
+            let big = string
+                .with_endianness(Endianness::Big)
+                .with_terminator(false);
+            assert_eq!(big.write_to(&mut buffer)?, 6);
+            assert_eq!(buffer.get(..6), Some(b"\0k\xd8\x3d\xde\x00".as_slice()));
+            assert!(matches!(
+                string.write_to(&mut [0u8; 7]),
+                Err(Error::OutputBufferTooSmall(8))
+            ));
+            assert_eq!(WriteUtf16::new("").size(), Some(2));
             Ok(())
         }
     }
 
-    mod fill {
-        use crate::ops::Fill;
+    mod write_uuid {
+        use crate::ops::{UuidLayout, WriteUuid};
 
         use crate::prelude::*;
 
-        #[cfg(feature = "std")]
         #[test]
-        fn test_io() -> Result<()> {
-            {
-                let mut stream = Vec::new();
-                let fill = Fill::new(0, 0x41);
-                assert_eq!(fill.write_to_io(&mut stream).unwrap(), 0);
-                assert_eq!(stream.len(), 0);
-            }
-            {
-                let mut stream = Vec::new();
-                let fill = Fill::new(42, 0x41);
-                assert_eq!(fill.write_to_io(&mut stream).unwrap(), 42);
-                assert_eq!(stream.len(), 42);
-                assert_eq!(
-                    String::from_utf8(stream).as_deref(),
-                    Ok("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")
-                );
+        fn test() -> Result<()> {
+            let uuid = WriteUuid::parse("6B29FC40-CA47-1067-B31D-00DD010662DA").unwrap();
+            assert_eq!(
+                uuid,
+                WriteUuid::new(0x6b29_fc40_ca47_1067_b31d_00dd_0106_62da)
+            );
+            assert_eq!(
+                WriteUuid::parse("6b29fc40ca471067b31d00dd010662da"),
+                Some(uuid)
+            );
+            assert_eq!(
+                uuid.to_bytes(),
+                *b"\x6b\x29\xfc\x40\xca\x47\x10\x67\xb3\x1d\x00\xdd\x01\x06\x62\xda"
+            );
+            let mut buffer = [0u8; 16];
+            assert_eq!(
+                uuid.with_layout(UuidLayout::Guid).write_to(&mut buffer)?,
+                16
+            );
+            assert_eq!(
+                buffer,
+                *b"\x40\xfc\x29\x6b\x47\xca\x67\x10\xb3\x1d\x00\xdd\x01\x06\x62\xda"
+            );
+            assert!(matches!(
+                uuid.write_to(&mut [0u8; 15]),
+                Err(Error::OutputBufferTooSmall(16))
+            ));
+
+            for malformed in [
+                "",
+                "6b29fc40-ca47-1067-b31d-00dd010662d",
+                "6b29fc40ca47-1067-b31d-00dd010662da-",
+                "6b29fc40-ca47-1067-b31d-00dd010662dg",
+                "{6b29fc40ca471067b31d00dd010662da",
+            ] {
+                assert_eq!(WriteUuid::parse(malformed), None);
             }
-            {
-                let mut stream = vec![0x42u8; 4];
-                let fill = Fill::new(42, 0x41);
-                assert_eq!(fill.write_to_io(&mut stream).unwrap(), 42);
-                assert_eq!(stream.len(), 46);
-                assert_eq!(
-                    String::from_utf8(stream).as_deref(),
-                    Ok("BBBBAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA")
-                );
+            Ok(())
+        }
+    }
+
+    mod write_encoded_integer {
+        use crate::ops::{Endianness, IntegerEncoding, WriteEncodedInteger};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test_encode() -> Result<()> {
+            for (value, encoded) in [(0, 0), (-1, 1), (1, 2), (-2, 3), (i64::MIN, u64::MAX)] {
+                assert_eq!(IntegerEncoding::ZigZag.encode(value)?, encoded);
             }
+            assert_eq!(IntegerEncoding::ZigZag.encode(i64::MAX)?, u64::MAX - 1);
+            assert_eq!(IntegerEncoding::Excess(3).encode(-3)?, 0);
+            assert!(IntegerEncoding::Excess(3).encode(-4).is_err());
+            assert_eq!(IntegerEncoding::Bcd.encode(0)?, 0);
+            assert_eq!(
+                IntegerEncoding::Bcd.encode(9_999_999_999_999_999)?,
+                0x9999_9999_9999_9999
+            );
+            assert!(IntegerEncoding::Bcd.encode(10_000_000_000_000_000).is_err());
+            assert!(IntegerEncoding::Bcd.encode(-1).is_err());
             Ok(())
         }
 
-        #[cfg(feature = "std")]
         #[test]
         fn test() -> Result<()> {
-            {
-                let mut stream = vec![0u8; 10];
-                let fill = Fill::new(10, 0x41);
-                assert_eq!(fill.write_to(&mut stream).unwrap(), 10);
-                assert_eq!(stream.len(), 10);
-                assert_eq!(String::from_utf8(stream).as_deref(), Ok("AAAAAAAAAA"));
-            }
+            let offset = WriteEncodedInteger::<u16>::new(-2, IntegerEncoding::Excess(0x8000))
+                .with_endianness(Endianness::Big);
+            assert_eq!(offset.size(), Some(2));
+            let mut buffer = [0u8; 2];
+            assert_eq!(offset.write_to(&mut buffer)?, 2);
+            assert_eq!(buffer, [0x7f, 0xfe]);
 
-            {
-                let mut stream = vec![0u8; 9];
-                let fill = Fill::new(10, 0x41);
-                let err = fill.write_to(&mut stream).unwrap_err();
-                let io_err = err.io().unwrap();
-                assert_eq!(io_err.kind(), std::io::ErrorKind::WriteZero);
-                assert_eq!(stream.len(), 9);
-                assert_eq!(
-                    stream.as_slice(),
-                    &[0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41, 0x41,]
-                );
-            }
+            let overflow = WriteEncodedInteger::<u8>::new(100, IntegerEncoding::Bcd);
+            assert_eq!(overflow.size(), None);
+            assert!(matches!(
+                overflow.write_to(&mut buffer),
+                Err(Error::IntegerOverflow)
+            ));
             Ok(())
         }
     }
 
-    mod integers {
-        use crate::ops::{EncodableInteger, WriteInteger};
+    mod composite {
+        use crate::ops::{AssertPosition, Fill, Placeholder, WriteBuffer, WriteInteger};
+        use crate::r#static::Shellcoder;
+        use crate::Shellcoder as _;
 
         use crate::prelude::*;
 
         #[test]
-        fn test_constructors() -> Result<()> {
-            assert_eq!(WriteInteger::new_be(1u8), WriteInteger::BigEndian(1u8));
-            assert_eq!(<_ as EncodableInteger>::n(1u8), 1);
-            assert_eq!(WriteInteger::new_le(1u8), WriteInteger::LittleEndian(1u8));
-            assert_eq!(WriteInteger::new_be(1u16), WriteInteger::BigEndian(1u16));
-            assert_eq!(<_ as EncodableInteger>::n(1u16), 2);
-            assert_eq!(WriteInteger::new_le(1u16), WriteInteger::LittleEndian(1u16));
-            assert_eq!(WriteInteger::new_be(1u32), WriteInteger::BigEndian(1u32));
-            assert_eq!(<_ as EncodableInteger>::n(1u32), 4);
-            assert_eq!(WriteInteger::new_le(1u32), WriteInteger::LittleEndian(1u32));
-            assert_eq!(WriteInteger::new_be(1u64), WriteInteger::BigEndian(1u64));
-            assert_eq!(<_ as EncodableInteger>::n(1u64), 8);
-            assert_eq!(WriteInteger::new_le(1u64), WriteInteger::LittleEndian(1u64));
+        fn test() -> Result<()> {
+            let header = (
+                WriteBuffer::new(b"ELF"),
+                WriteInteger::new_le(2u16),
+                WriteInteger::new_be(0x10u16),
+            );
+            assert_eq!(header.size(), Some(7));
+            let mut buffer = [0u8; 16];
+            assert_eq!(header.write_to(&mut buffer).unwrap(), 7);
+            assert_eq!(&buffer[..7], b"ELF\x02\0\0\x10");
+
+            let padding = [Fill::new(2, b'A'), Fill::new(2, b'B')];
+            let mut shellcoder = Shellcoder::new(&mut buffer);
+            let (n, shellcoder) = shellcoder.add_counted(header)?;
+            assert_eq!(n, 7);
+            shellcoder.push(padding)?.push(&padding[..1])?;
+            assert_eq!(shellcoder.get(), b"ELF\x02\0\0\x10AABBAA");
+
+            let mut small = [0xccu8; 10];
+            assert!(matches!(
+                (header, padding).write_to(&mut small),
+                Err(Error::OutputBufferTooSmall(11))
+            ));
+            assert_eq!(small, [0xcc; 10]);
             Ok(())
         }
 
-        #[cfg(feature = "std")]
         #[test]
-        fn test_encodable_integer_io() -> Result<()> {
-            {
-                let mut stream = Vec::new();
-                assert_eq!(
-                    WriteInteger::new_be(1u8).write_to_io(&mut stream).unwrap(),
-                    1
-                );
-                assert_eq!(stream.len(), 1);
-                assert_eq!(
-                    WriteInteger::new_le(2u8).write_to_io(&mut stream).unwrap(),
-                    1
-                );
-                assert_eq!(stream.len(), 2);
-                assert_eq!(stream.as_slice(), &[1, 2]);
-            }
+        fn test_check_at() -> Result<()> {
+            let mut buffer = [0u8; 16];
+            let mut shellcoder = Shellcoder::new(&mut buffer);
+            assert!(shellcoder
+                .push((Fill::new(2, b'A'), AssertPosition::new(5)))
+                .is_err());
+            assert!(shellcoder.push([AssertPosition::new(5)]).is_err());
+            shellcoder
+                .fill(1, b'A')?
+                .push((Fill::new(2, b'B'), AssertPosition::new(3)))?
+                .push([Fill::new(1, b'C'), Fill::new(1, b'D')])?
+                .push(&[AssertPosition::new(5)][..])?;
+            assert_eq!(shellcoder.get(), b"ABBCD");
+            Ok(())
+        }
 
-            {
-                let mut stream = vec![2u8; 1];
-                assert_eq!(
-                    WriteInteger::new_be(1u8).write_to_io(&mut stream).unwrap(),
-                    1
-                );
-                assert_eq!(stream.len(), 2);
-                assert_eq!(stream.as_slice(), &[2u8, 1u8]);
+        #[test]
+        fn test_placeholder() {
+            let mut buffer = [0u8; 16];
+            let mut shellcoder = Shellcoder::new(&mut buffer);
+            for error in [
+                shellcoder
+                    .push((Fill::new(2, b'A'), Placeholder::new("ret", 8)))
+                    .unwrap_err(),
+                shellcoder.push([Placeholder::new("ret", 8)]).unwrap_err(),
+            ] {
+                assert!(matches!(error.root(), Error::Unsupported(_)));
             }
+            assert!(shellcoder.get().is_empty());
+        }
+    }
 
-            {
-                let mut stream = Vec::new();
-                assert_eq!(
-                    WriteInteger::new_be(0xdeadu16)
-                        .write_to_io(&mut stream)
-                        .unwrap(),
-                    2
-                );
-                assert_eq!(stream.len(), 2);
-                assert_eq!(stream.as_slice(), &[0xde, 0xad]);
-            }
+    mod any_op {
+        use crate::ops::{Advance, AnyOp, Fill, WriteBuffer, WriteInteger};
 
-            {
-                let mut stream = Vec::new();
-                assert_eq!(
-                    WriteInteger::new_le(0xdeadu16)
-                        .write_to_io(&mut stream)
-                        .unwrap(),
-                    2
-                );
-                assert_eq!(stream.len(), 2);
-                assert_eq!(stream.as_slice(), &[0xad, 0xde]);
-            }
+        use crate::prelude::*;
 
-            {
-                let mut stream = Vec::new();
-                assert_eq!(
-                    WriteInteger::new_be(0xdeadbeefu32)
-                        .write_to_io(&mut stream)
-                        .unwrap(),
-                    4
-                );
-                assert_eq!(stream.len(), 4);
-                assert_eq!(stream.as_slice(), &[0xde, 0xad, 0xbe, 0xef]);
+        #[test]
+        fn test() -> Result<()> {
+            let ops = [
+                AnyOp::from(WriteInteger::new_be(0xdeadu16)),
+                AnyOp::from(Fill::new(2, b'A')),
+                AnyOp::from(WriteBuffer::new(b"sh")),
+            ];
+            let mut buffer = [0u8; 6];
+            let mut offset = 0;
+            for op in ops {
+                offset += op.write_to(&mut buffer[offset..]).unwrap();
             }
+            assert_eq!(offset, 6);
+            assert_eq!(&buffer, b"\xde\xadAAsh");
+            Ok(())
+        }
 
-            {
-                let mut stream = Vec::new();
-                assert_eq!(
-                    WriteInteger::new_le(0xdeadbeefu32)
-                        .write_to_io(&mut stream)
-                        .unwrap(),
-                    4
-                );
-                assert_eq!(stream.len(), 4);
-                assert_eq!(stream.as_slice(), &[0xef, 0xbe, 0xad, 0xde]);
+        #[test]
+        fn test_buffer_too_small() -> Result<()> {
+            let ops = [
+                (AnyOp::from(Advance::new(3)), 3),
+                (AnyOp::from(Fill::new(4, b'A')), 4),
+                (AnyOp::from(WriteInteger::new_le(0xdeadu16)), 2),
+                (AnyOp::from(WriteInteger::new_be(0xdeadbeefu32)), 4),
+                (AnyOp::from(WriteBuffer::new(b"/bin/sh")), 7),
+            ];
+            for (op, needed) in ops {
+                let mut buffer = [0xccu8; 1];
+                let error = op.write_to(&mut buffer).unwrap_err();
+                assert!(matches!(error, Error::OutputBufferTooSmall(n) if n == needed));
+                assert_eq!(buffer, [0xcc]);
             }
+            Ok(())
+        }
 
-            {
-                let mut stream = Vec::new();
-                assert_eq!(
-                    WriteInteger::new_be(0xdeadbeefcafebabeu64)
-                        .write_to_io(&mut stream)
-                        .unwrap(),
-                    8
-                );
-                assert_eq!(stream.len(), 8);
-                assert_eq!(
-                    stream.as_slice(),
-                    &[0xde, 0xad, 0xbe, 0xef, 0xca, 0xfe, 0xba, 0xbe]
-                );
-            }
+        #[cfg(feature = "json")]
+        #[test]
+        fn test_serde() -> Result<()> {
+            let op = AnyOp::from(WriteInteger::new_le(0xdeadbeefu32));
+            let json = serde_json::to_string(&op).unwrap();
+            assert_eq!(
+                json,
+                r#"{"op":"WriteU32","args":{"LittleEndian":3735928559}}"#
+            );
+            assert_eq!(serde_json::from_str::<AnyOp<'_>>(&json).unwrap(), op);
 
-            {
+            let json = serde_json::to_string(&AnyOp::from(WriteBuffer::new(b"sh"))).unwrap();
+            assert_eq!(json, r#"{"op":"WriteBuffer","args":[115,104]}"#);
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    mod arbitrary {
+        use arbitrary::{Arbitrary as _, Unstructured};
+
+        use crate::ops::{AnyOp, ARBITRARY_MAX_LEN};
+
+        use crate::prelude::*;
+
+        #[test]
+        fn test() -> Result<()> {
+            let data: Vec<u8> = (0..=u8::MAX).cycle().take(0x1000).collect();
+            let mut u = Unstructured::new(&data);
+            let mut n_ops = 0;
+            while !u.is_empty() {
+                let op = AnyOp::arbitrary(&mut u).unwrap();
+                n_ops += 1;
                 let mut stream = Vec::new();
-                assert_eq!(
-                    WriteInteger::new_le(0xdeadbeefcafebabeu64)
-                        .write_to_io(&mut stream)
-                        .unwrap(),
-                    8
-                );
-                assert_eq!(stream.len(), 8);
-                assert_eq!(
-                    stream.as_slice(),
-                    &[0xbe, 0xba, 0xfe, 0xca, 0xef, 0xbe, 0xad, 0xde]
-                );
+                let n = op.write_to_io(&mut stream).unwrap();
+                assert_eq!(n, stream.len());
+                assert!(n <= ARBITRARY_MAX_LEN.max(data.len()));
             }
+            assert!(n_ops > 1);
             Ok(())
         }
     }
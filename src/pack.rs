@@ -0,0 +1,146 @@
+//! Packing and unpacking of integers, in the style of pwntools.
+//!
+//! `pN` packs an `N`-bit integer into an array of bytes, and `uN` unpacks
+//! it. Both are little-endian, as pwntools is by default. Variants suffixed
+//! with `_le` and `_be` state the endianness, and variants suffixed with
+//! `_with` take it at runtime.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::pack::{p32, p64_be, u16_be, u32};
+//!
+//! assert_eq!(p32(0xdeadbeef), *b"\xef\xbe\xad\xde");
+//! assert_eq!(p64_be(0x4142), *b"\0\0\0\0\0\0AB");
+//! assert_eq!(u32(*b"\xef\xbe\xad\xde"), 0xdeadbeef);
+//! assert_eq!(u16_be(*b"AB"), 0x4142);
+//! ```
+
+use crate::ops::Endianness;
+
+/// Packs an 8-bit integer, for symmetry with the wider variants.
+#[inline]
+#[must_use]
+pub const fn p8(value: u8) -> [u8; 1] {
+    [value]
+}
+
+/// Unpacks an 8-bit integer, for symmetry with the wider variants.
+#[inline]
+#[must_use]
+pub const fn u8(bytes: [u8; 1]) -> u8 {
+    let [value] = bytes;
+    value
+}
+
+/// Defines the packing and unpacking functions of an integer type.
+macro_rules! pack_functions {
+    (
+        $ty:ident,
+        $n:literal,
+        $pack:ident,
+        $pack_le:ident,
+        $pack_be:ident,
+        $pack_with:ident,
+        $unpack:ident,
+        $unpack_le:ident,
+        $unpack_be:ident,
+        $unpack_with:ident
+    ) => {
+        #[doc = concat!("Packs a ", stringify!($ty), " in little endian.")]
+        #[inline]
+        #[must_use]
+        pub const fn $pack(value: $ty) -> [u8; $n] {
+            value.to_le_bytes()
+        }
+
+        #[doc = concat!("Packs a ", stringify!($ty), " in little endian.")]
+        #[inline]
+        #[must_use]
+        pub const fn $pack_le(value: $ty) -> [u8; $n] {
+            value.to_le_bytes()
+        }
+
+        #[doc = concat!("Packs a ", stringify!($ty), " in big endian.")]
+        #[inline]
+        #[must_use]
+        pub const fn $pack_be(value: $ty) -> [u8; $n] {
+            value.to_be_bytes()
+        }
+
+        #[doc = concat!("Packs a ", stringify!($ty), " with an endianness.")]
+        #[inline]
+        #[must_use]
+        pub const fn $pack_with(value: $ty, endianness: Endianness) -> [u8; $n] {
+            match endianness {
+                Endianness::Little => value.to_le_bytes(),
+                Endianness::Big => value.to_be_bytes(),
+            }
+        }
+
+        #[doc = concat!("Unpacks a ", stringify!($ty), " in little endian.")]
+        #[inline]
+        #[must_use]
+        pub const fn $unpack(bytes: [u8; $n]) -> $ty {
+            $ty::from_le_bytes(bytes)
+        }
+
+        #[doc = concat!("Unpacks a ", stringify!($ty), " in little endian.")]
+        #[inline]
+        #[must_use]
+        pub const fn $unpack_le(bytes: [u8; $n]) -> $ty {
+            $ty::from_le_bytes(bytes)
+        }
+
+        #[doc = concat!("Unpacks a ", stringify!($ty), " in big endian.")]
+        #[inline]
+        #[must_use]
+        pub const fn $unpack_be(bytes: [u8; $n]) -> $ty {
+            $ty::from_be_bytes(bytes)
+        }
+
+        #[doc = concat!("Unpacks a ", stringify!($ty), " with an endianness.")]
+        #[inline]
+        #[must_use]
+        pub const fn $unpack_with(bytes: [u8; $n], endianness: Endianness) -> $ty {
+            match endianness {
+                Endianness::Little => $ty::from_le_bytes(bytes),
+                Endianness::Big => $ty::from_be_bytes(bytes),
+            }
+        }
+    };
+}
+
+pack_functions!(u16, 2, p16, p16_le, p16_be, p16_with, u16, u16_le, u16_be, u16_with);
+pack_functions!(u32, 4, p32, p32_le, p32_be, p32_with, u32, u32_le, u32_be, u32_with);
+pack_functions!(u64, 8, p64, p64_le, p64_be, p64_with, u64, u64_le, u64_be, u64_with);
+pack_functions!(u128, 16, p128, p128_le, p128_be, p128_with, u128, u128_le, u128_be, u128_with);
+
+#[cfg(test)]
+#[allow(clippy::redundant_test_prefix)]
+mod tests {
+    use crate::ops::Endianness;
+    use crate::pack;
+
+    #[test]
+    fn test_pack() {
+        assert_eq!(pack::p8(0x41), *b"A");
+        assert_eq!(pack::u8(*b"A"), 0x41);
+        assert_eq!(pack::p16_le(0x4142), *b"BA");
+        assert_eq!(pack::p16_with(0x4142, Endianness::Big), *b"AB");
+        assert_eq!(pack::u32_le(pack::p32(0xdead_beef)), 0xdead_beef);
+        assert_eq!(
+            pack::u64_with(
+                pack::p64_with(0x0804_8000, Endianness::Big),
+                Endianness::Big
+            ),
+            0x0804_8000
+        );
+        assert_eq!(pack::p128_be(1)[15], 1);
+        assert_eq!(pack::u128(pack::p128(u128::MAX - 1)), u128::MAX - 1);
+        assert_eq!(
+            pack::u64_with(*b"\x01\0\0\0\0\0\0\0", Endianness::Little),
+            pack::u64_be(*b"\0\0\0\0\0\0\0\x01")
+        );
+    }
+}
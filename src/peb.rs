@@ -0,0 +1,466 @@
+//! Offsets of Windows thread and process environment blocks.
+//!
+//! Position-independent Windows shellcode finds the modules it needs by
+//! walking from the thread environment block (TEB) to the process
+//! environment block (PEB), then to the list of loaded modules. The offsets
+//! of the fields it walks are given here by name, for x86 and x86-64, and
+//! the loads are emitted by [`LoadPeb`] and [`LoadField`].
+//!
+//! Offsets are the ones of Windows 7 to Windows 11, which have not moved
+//! in between.
+//!
+//! # Examples
+//!
+//! Loads the first entry of the list of modules in memory order, i.e. the
+//! executable.
+//!
+//! ```rust
+//! use shellcoder::arch::Arch;
+//! use shellcoder::peb::{LoadField, LoadPeb, Peb, PebLdrData};
+//! use shellcoder::r#static::Shellcoder;
+//! use shellcoder::Shellcoder as _;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let arch = Arch::X86_64;
+//! let mut buffer = [0u8; 0x20];
+//! let mut shellcoder = Shellcoder::new(&mut buffer);
+//! shellcoder
+//!     .add(LoadPeb::new(arch))?
+//!     .add(LoadField::new(arch, Peb::Ldr.offset(arch)?))?
+//!     .add(LoadField::new(arch, PebLdrData::InMemoryOrderModuleList.offset(arch)?))?;
+//! assert_eq!(
+//!     shellcoder.get(),
+//!     b"\x65\x48\x8b\x04\x25\x60\0\0\0\x48\x8b\x40\x18\x48\x8b\x40\x20"
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::arch::Arch;
+use crate::ops::WriteBuffer;
+use crate::prelude::*;
+
+/// Returns the error of architectures other than x86 and x86-64.
+const fn unsupported() -> Error {
+    Error::Unsupported("Windows environment blocks are laid out for x86-64 and x86 only")
+}
+
+/// Returns one of two offsets, depending on the architecture.
+const fn select(arch: Arch, x86: usize, x86_64: usize) -> Result<usize> {
+    match arch {
+        Arch::X86 => Ok(x86),
+        Arch::X86_64 => Ok(x86_64),
+        Arch::Arm | Arch::Thumb | Arch::Aarch64 | Arch::Mips | Arch::Riscv64 => Err(unsupported()),
+    }
+}
+
+/// A field of the thread environment block (`TEB`), which is at `fs:0`
+/// with x86 and `gs:0` with x86-64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Teb {
+    /// `NtTib.ExceptionList`, i.e. the SEH chain of x86.
+    ExceptionList,
+
+    /// `NtTib.StackBase`.
+    StackBase,
+
+    /// `NtTib.StackLimit`.
+    StackLimit,
+
+    /// `NtTib.Self`, i.e. the linear address of the TEB.
+    SelfPointer,
+
+    /// `ClientId.UniqueProcess`, i.e. the process identifier.
+    UniqueProcess,
+
+    /// `ClientId.UniqueThread`, i.e. the thread identifier.
+    UniqueThread,
+
+    /// `ThreadLocalStoragePointer`.
+    ThreadLocalStoragePointer,
+
+    /// `ProcessEnvironmentBlock`, see [`Peb`].
+    ProcessEnvironmentBlock,
+
+    /// `LastErrorValue`.
+    LastErrorValue,
+}
+
+impl Teb {
+    /// Returns the offset of the field.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Unsupported`]: the architecture is neither x86 nor x86-64.
+    #[inline]
+    pub const fn offset(self, arch: Arch) -> Result<usize> {
+        match self {
+            Self::ExceptionList => select(arch, 0, 0),
+            Self::StackBase => select(arch, 0x04, 0x08),
+            Self::StackLimit => select(arch, 0x08, 0x10),
+            Self::SelfPointer => select(arch, 0x18, 0x30),
+            Self::UniqueProcess => select(arch, 0x20, 0x40),
+            Self::UniqueThread => select(arch, 0x24, 0x48),
+            Self::ThreadLocalStoragePointer => select(arch, 0x2c, 0x58),
+            Self::ProcessEnvironmentBlock => select(arch, 0x30, 0x60),
+            Self::LastErrorValue => select(arch, 0x34, 0x68),
+        }
+    }
+}
+
+/// A field of the process environment block (`PEB`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum Peb {
+    /// `BeingDebugged`, a byte.
+    BeingDebugged,
+
+    /// `ImageBaseAddress`, i.e. the base of the executable.
+    ImageBaseAddress,
+
+    /// `Ldr`, i.e. the loaded modules, see [`PebLdrData`].
+    Ldr,
+
+    /// `ProcessParameters`, e.g. the command line.
+    ProcessParameters,
+
+    /// `ProcessHeap`.
+    ProcessHeap,
+
+    /// `NtGlobalFlag`.
+    NtGlobalFlag,
+
+    /// `OSMajorVersion`.
+    OsMajorVersion,
+
+    /// `OSMinorVersion`.
+    OsMinorVersion,
+
+    /// `OSBuildNumber`.
+    OsBuildNumber,
+}
+
+impl Peb {
+    /// Returns the offset of the field.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Unsupported`]: the architecture is neither x86 nor x86-64.
+    #[inline]
+    pub const fn offset(self, arch: Arch) -> Result<usize> {
+        match self {
+            Self::BeingDebugged => select(arch, 0x02, 0x02),
+            Self::ImageBaseAddress => select(arch, 0x08, 0x10),
+            Self::Ldr => select(arch, 0x0c, 0x18),
+            Self::ProcessParameters => select(arch, 0x10, 0x20),
+            Self::ProcessHeap => select(arch, 0x18, 0x30),
+            Self::NtGlobalFlag => select(arch, 0x68, 0xbc),
+            Self::OsMajorVersion => select(arch, 0xa4, 0x118),
+            Self::OsMinorVersion => select(arch, 0xa8, 0x11c),
+            Self::OsBuildNumber => select(arch, 0xac, 0x120),
+        }
+    }
+}
+
+/// A field of the loaded modules of a process (`PEB_LDR_DATA`).
+///
+/// Each list links the `LIST_ENTRY` of the same name in the entries of the
+/// modules, see [`LdrDataTableEntry`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+#[allow(clippy::module_name_repetitions)]
+pub enum PebLdrData {
+    /// `InLoadOrderModuleList`.
+    InLoadOrderModuleList,
+
+    /// `InMemoryOrderModuleList`.
+    InMemoryOrderModuleList,
+
+    /// `InInitializationOrderModuleList`.
+    InInitializationOrderModuleList,
+}
+
+impl PebLdrData {
+    /// Returns the offset of the field.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Unsupported`]: the architecture is neither x86 nor x86-64.
+    #[inline]
+    pub const fn offset(self, arch: Arch) -> Result<usize> {
+        match self {
+            Self::InLoadOrderModuleList => select(arch, 0x0c, 0x10),
+            Self::InMemoryOrderModuleList => select(arch, 0x14, 0x20),
+            Self::InInitializationOrderModuleList => select(arch, 0x1c, 0x30),
+        }
+    }
+}
+
+/// A field of the entry of a loaded module (`LDR_DATA_TABLE_ENTRY`).
+///
+/// Lists point to the `LIST_ENTRY` of the next entry rather than to the
+/// entry itself: a field reached through `InMemoryOrderLinks` is at its
+/// offset minus the one of [`LdrDataTableEntry::InMemoryOrderLinks`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum LdrDataTableEntry {
+    /// `InLoadOrderLinks`.
+    InLoadOrderLinks,
+
+    /// `InMemoryOrderLinks`.
+    InMemoryOrderLinks,
+
+    /// `InInitializationOrderLinks`.
+    InInitializationOrderLinks,
+
+    /// `DllBase`, i.e. the base of the module.
+    DllBase,
+
+    /// `EntryPoint`.
+    EntryPoint,
+
+    /// `SizeOfImage`.
+    SizeOfImage,
+
+    /// `FullDllName`, a `UNICODE_STRING`.
+    FullDllName,
+
+    /// `BaseDllName`, a `UNICODE_STRING`.
+    BaseDllName,
+}
+
+impl LdrDataTableEntry {
+    /// Returns the offset of the field.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Unsupported`]: the architecture is neither x86 nor x86-64.
+    #[inline]
+    pub const fn offset(self, arch: Arch) -> Result<usize> {
+        match self {
+            Self::InLoadOrderLinks => select(arch, 0, 0),
+            Self::InMemoryOrderLinks => select(arch, 0x08, 0x10),
+            Self::InInitializationOrderLinks => select(arch, 0x10, 0x20),
+            Self::DllBase => select(arch, 0x18, 0x30),
+            Self::EntryPoint => select(arch, 0x1c, 0x38),
+            Self::SizeOfImage => select(arch, 0x20, 0x40),
+            Self::FullDllName => select(arch, 0x24, 0x48),
+            Self::BaseDllName => select(arch, 0x2c, 0x58),
+        }
+    }
+}
+
+/// An operation that writes an instruction loading the address of the PEB
+/// into the accumulator, i.e. `mov eax, fs:[0x30]` with x86 and
+/// `mov rax, gs:[0x60]` with x86-64.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(clippy::module_name_repetitions)]
+pub struct LoadPeb {
+    /// Architecture of the target.
+    arch: Arch,
+}
+
+impl LoadPeb {
+    /// Instantiates a new [`LoadPeb`].
+    #[inline]
+    #[must_use]
+    pub const fn new(arch: Arch) -> Self {
+        Self { arch }
+    }
+
+    /// Returns the architecture of the target.
+    #[inline]
+    #[must_use]
+    pub const fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns the bytes of the instruction, and its length.
+    fn bytes(self) -> Result<([u8; 9], usize)> {
+        let prefix: &[u8] = match self.arch {
+            // mov eax, fs:[offset]
+            Arch::X86 => b"\x64\xa1",
+            // mov rax, gs:[offset]
+            Arch::X86_64 => b"\x65\x48\x8b\x04\x25",
+            Arch::Arm | Arch::Thumb | Arch::Aarch64 | Arch::Mips | Arch::Riscv64 => {
+                return Err(unsupported())
+            }
+        };
+        let offset = u32::try_from(Teb::ProcessEnvironmentBlock.offset(self.arch)?)?.to_le_bytes();
+        let mut bytes = [0; 9];
+        for (slot, &byte) in bytes.iter_mut().zip(prefix.iter().chain(&offset)) {
+            *slot = byte;
+        }
+        Ok((bytes, prefix.len().saturating_add(offset.len())))
+    }
+}
+
+impl Op for LoadPeb {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer::new(bytes.get(..len).unwrap_or_default()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer::new(bytes.get(..len).unwrap_or_default()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.bytes().ok().map(|(_bytes, len)| len)
+    }
+}
+
+/// An operation that writes an instruction loading a field of the structure
+/// the accumulator points to, to walk from the PEB to the loaded modules.
+///
+/// The instruction is `mov eax, [eax + offset]` with x86 and
+/// `mov rax, [rax + offset]` with x86-64. Offsets below 0x80 are encoded on
+/// a single byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LoadField {
+    /// Architecture of the target.
+    arch: Arch,
+
+    /// Offset of the field.
+    offset: usize,
+}
+
+impl LoadField {
+    /// Instantiates a new [`LoadField`].
+    #[inline]
+    #[must_use]
+    pub const fn new(arch: Arch, offset: usize) -> Self {
+        Self { arch, offset }
+    }
+
+    /// Returns the offset of the field.
+    #[inline]
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the bytes of the instruction, and its length.
+    fn bytes(self) -> Result<([u8; 7], usize)> {
+        let rex = match self.arch {
+            Arch::X86 => None,
+            Arch::X86_64 => Some(0x48),
+            Arch::Arm | Arch::Thumb | Arch::Aarch64 | Arch::Mips | Arch::Riscv64 => {
+                return Err(unsupported())
+            }
+        };
+        let mut bytes = [0; 7];
+        let mut len = 0;
+        let mut push = |byte: u8| {
+            if let Some(slot) = bytes.get_mut(len) {
+                *slot = byte;
+            }
+            len = len.saturating_add(1);
+        };
+        if let Some(prefix) = rex {
+            push(prefix);
+        }
+        push(0x8b);
+        if let Ok(disp8) = i8::try_from(self.offset) {
+            push(0x40);
+            disp8.to_le_bytes().into_iter().for_each(&mut push);
+        } else {
+            push(0x80);
+            i32::try_from(self.offset)?
+                .to_le_bytes()
+                .into_iter()
+                .for_each(&mut push);
+        }
+        Ok((bytes, len))
+    }
+}
+
+impl Op for LoadField {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer::new(bytes.get(..len).unwrap_or_default()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer::new(bytes.get(..len).unwrap_or_default()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.bytes().ok().map(|(_bytes, len)| len)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::indexing_slicing,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::shadow_unrelated,
+    clippy::unseparated_literal_suffix
+)]
+mod tests {
+    use crate::arch::Arch;
+    use crate::peb::{LdrDataTableEntry, LoadField, LoadPeb, Peb, PebLdrData, Teb};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_offsets() -> Result<()> {
+        assert_eq!(Teb::ProcessEnvironmentBlock.offset(Arch::X86)?, 0x30);
+        assert_eq!(Teb::ProcessEnvironmentBlock.offset(Arch::X86_64)?, 0x60);
+        assert_eq!(Peb::Ldr.offset(Arch::X86)?, 0x0c);
+        assert_eq!(PebLdrData::InMemoryOrderModuleList.offset(Arch::X86)?, 0x14);
+        assert_eq!(LdrDataTableEntry::DllBase.offset(Arch::X86_64)?, 0x30);
+        assert!(matches!(
+            Peb::Ldr.offset(Arch::Aarch64),
+            Err(Error::Unsupported(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_loads() -> Result<()> {
+        let mut buffer = [0u8; 16];
+        let n = LoadPeb::new(Arch::X86).write_to(&mut buffer)?;
+        assert_eq!(&buffer[..n], b"\x64\xa1\x30\0\0\0");
+
+        // mov eax, [eax + DllBase - InMemoryOrderLinks]
+        let offset = LdrDataTableEntry::DllBase.offset(Arch::X86)?
+            - LdrDataTableEntry::InMemoryOrderLinks.offset(Arch::X86)?;
+        let n = LoadField::new(Arch::X86, offset).write_to(&mut buffer)?;
+        assert_eq!(&buffer[..n], b"\x8b\x40\x10");
+
+        let load = LoadField::new(Arch::X86_64, Peb::OsBuildNumber.offset(Arch::X86_64)?);
+        assert_eq!(load.size(), Some(7));
+        let n = load.write_to(&mut buffer)?;
+        assert_eq!(&buffer[..n], b"\x48\x8b\x80\x20\x01\0\0");
+
+        assert!(LoadPeb::new(Arch::Mips).write_to(&mut buffer).is_err());
+        assert!(LoadField::new(Arch::X86, usize::MAX)
+            .write_to(&mut buffer)
+            .is_err());
+        Ok(())
+    }
+}
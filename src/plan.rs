@@ -0,0 +1,1229 @@
+//! Plans, i.e. sequences of operations, and their compact binary encoding.
+//!
+//! Plans only hold the primitive operations covered by [`AnyOp`], i.e.
+//! [`ops::Advance`], [`ops::Fill`], [`ops::WriteInteger`] of `u8` to `u64`
+//! and [`ops::WriteBuffer`].
+//!
+//! A plan is encoded as a sequence of operations, each made of an opcode
+//! byte followed by its operands:
+//!
+//! | Opcode        | Operation                 | Operands                              |
+//! |---------------|---------------------------|---------------------------------------|
+//! | `0x01`        | [`ops::Advance`]          | length (varint)                       |
+//! | `0x02`        | [`ops::Fill`]             | length (varint), byte                 |
+//! | `0x10`-`0x13` | big-endian integer        | the encoded `u8`, `u16`, `u32`, `u64` |
+//! | `0x18`-`0x1b` | little-endian integer     | the encoded `u8`, `u16`, `u32`, `u64` |
+//! | `0x20`        | [`ops::WriteBuffer`]      | length (varint), bytes                |
+//!
+//! Varints are unsigned LEB128 integers.
+//!
+//! Decoding does not allocate: buffers are borrowed from the bytecode, and
+//! [`replay`] interprets the bytecode directly against any shellcoder.
+
+#[cfg(feature = "std")]
+use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use core::fmt;
+use core::iter::FusedIterator;
+#[cfg(feature = "std")]
+use core::ops::Range;
+use core::result::Result as CoreResult;
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use crate::alloc;
+#[cfg(feature = "std")]
+use crate::arch::Arch;
+#[cfg(feature = "std")]
+use crate::config::{Config, DebugPlacement, DebugStub, PointerWidth};
+#[cfg(feature = "std")]
+use crate::ops::Endianness;
+use crate::ops::{self, AnyOp, WriteInteger};
+use crate::prelude::*;
+#[cfg(feature = "std")]
+use crate::unpack;
+#[cfg(feature = "std")]
+use crate::{IntoOp, Shellcoder as _};
+
+/// Opcode of [`ops::Advance`].
+const OPCODE_ADVANCE: u8 = 0x01;
+
+/// Opcode of [`ops::Fill`].
+const OPCODE_FILL: u8 = 0x02;
+
+/// Opcode of big-endian `u8`, then `u16`, `u32` and `u64` up to `0x13`.
+const OPCODE_INT_BE: u8 = 0x10;
+
+/// Opcode of little-endian `u8`, then `u16`, `u32` and `u64` up to `0x1b`.
+const OPCODE_INT_LE: u8 = 0x18;
+
+/// Opcode of [`ops::WriteBuffer`].
+const OPCODE_BUFFER: u8 = 0x20;
+
+/// Decodes the operations of a bytecode, one by one.
+///
+/// Decoding stops after the first error.
+#[derive(Clone, Debug)]
+pub struct Decoder<'buf> {
+    /// The bytecode.
+    bytecode: &'buf [u8],
+
+    /// Offset of the next operation.
+    offset: usize,
+}
+
+impl<'buf> Decoder<'buf> {
+    /// Instantiates a new [`Decoder`].
+    #[inline]
+    #[must_use]
+    pub const fn new(bytecode: &'buf [u8]) -> Self {
+        Self {
+            bytecode,
+            offset: 0,
+        }
+    }
+
+    /// Returns the offset of the next operation in the bytecode.
+    #[inline]
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Reads `n` bytes.
+    fn read(&mut self, n: usize) -> Option<&'buf [u8]> {
+        let end = self.offset.checked_add(n)?;
+        let bytes = self.bytecode.get(self.offset..end)?;
+        self.offset = end;
+        Some(bytes)
+    }
+
+    /// Reads a single byte.
+    fn read_u8(&mut self) -> Option<u8> {
+        self.read(1)?.first().copied()
+    }
+
+    /// Reads `N` bytes.
+    fn read_array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.read(N)?.try_into().ok()
+    }
+
+    /// Reads a varint.
+    fn read_varint(&mut self) -> Option<usize> {
+        let mut value: usize = 0;
+        let mut shift: u32 = 0;
+        loop {
+            let byte = self.read_u8()?;
+            let bits = usize::from(byte & 0x7f);
+            value |= bits
+                .checked_shl(shift)
+                .filter(|shifted| shifted >> shift == bits)?;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+            shift = shift.checked_add(7).filter(|&next| next < usize::BITS)?;
+        }
+    }
+
+    /// Decodes the next operation.
+    fn decode(&mut self) -> Option<AnyOp<'buf>> {
+        let opcode = self.read_u8()?;
+        Some(match opcode {
+            OPCODE_ADVANCE => ops::Advance::new(self.read_varint()?).into(),
+            OPCODE_FILL => {
+                let len = self.read_varint()?;
+                ops::Fill::new(len, self.read_u8()?).into()
+            }
+            OPCODE_BUFFER => {
+                let len = self.read_varint()?;
+                ops::WriteBuffer::new(self.read(len)?).into()
+            }
+            _ => return self.decode_integer(opcode),
+        })
+    }
+
+    /// Decodes an integer.
+    fn decode_integer(&mut self, opcode: u8) -> Option<AnyOp<'buf>> {
+        let big_endian = match opcode & !0x03 {
+            OPCODE_INT_BE => true,
+            OPCODE_INT_LE => false,
+            _ => return None,
+        };
+        Some(match (opcode & 0x03, big_endian) {
+            (0, true) => WriteInteger::new_be(u8::from_be_bytes(self.read_array()?)).into(),
+            (1, true) => WriteInteger::new_be(u16::from_be_bytes(self.read_array()?)).into(),
+            (2, true) => WriteInteger::new_be(u32::from_be_bytes(self.read_array()?)).into(),
+            (_, true) => WriteInteger::new_be(u64::from_be_bytes(self.read_array()?)).into(),
+            (0, false) => WriteInteger::new_le(u8::from_le_bytes(self.read_array()?)).into(),
+            (1, false) => WriteInteger::new_le(u16::from_le_bytes(self.read_array()?)).into(),
+            (2, false) => WriteInteger::new_le(u32::from_le_bytes(self.read_array()?)).into(),
+            (_, false) => WriteInteger::new_le(u64::from_le_bytes(self.read_array()?)).into(),
+        })
+    }
+}
+
+impl<'buf> Iterator for Decoder<'buf> {
+    type Item = Result<AnyOp<'buf>>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytecode.len() {
+            return None;
+        }
+        let offset = self.offset;
+        Some(self.decode().ok_or_else(|| {
+            self.offset = self.bytecode.len();
+            Error::InvalidBytecode(offset)
+        }))
+    }
+}
+
+impl FusedIterator for Decoder<'_> {}
+
+/// Replays a bytecode against a shellcoder.
+///
+/// # Errors
+///
+///  - [`Error::InvalidBytecode`]: the bytecode is malformed. Operations
+///    that precede the faulty one have already been pushed.
+///  - Any error returned by [`crate::Shellcoder::add`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::plan;
+/// use shellcoder::r#static::Shellcoder;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 6];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// plan::replay(b"\x11\xde\xad\x02\x02A\x20\x02sh", &mut shellcoder)?;
+/// assert_eq!(shellcoder.get(), b"\xde\xadAAsh");
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub fn replay<S>(bytecode: &[u8], shellcoder: &mut S) -> Result<()>
+where
+    S: crate::Shellcoder + ?Sized,
+{
+    shellcoder.reserve(
+        Decoder::new(bytecode)
+            .map_while(CoreResult::ok)
+            .filter_map(|op| op.size())
+            .fold(0, usize::saturating_add),
+    );
+    for op in Decoder::new(bytecode) {
+        shellcoder.add(op?)?;
+    }
+    Ok(())
+}
+
+/// Encodes a varint.
+#[cfg(feature = "std")]
+fn write_varint(bytecode: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytecode.push(value.to_le_bytes().first().map_or(0, |byte| byte | 0x80));
+        value = value.wrapping_shr(7);
+    }
+    bytecode.extend(value.to_le_bytes().first());
+}
+
+/// Encodes an integer.
+#[cfg(feature = "std")]
+fn write_integer<I>(bytecode: &mut Vec<u8>, op: &WriteInteger<I>) -> Result<()>
+where
+    I: ops::EncodableInteger,
+{
+    let (opcode, value) = match *op {
+        WriteInteger::BigEndian(value) => (OPCODE_INT_BE, value),
+        WriteInteger::LittleEndian(value) => (OPCODE_INT_LE, value),
+    };
+    let width = match value.n() {
+        1 => 0,
+        2 => 1,
+        4 => 2,
+        _ => 3,
+    };
+    bytecode.push(opcode | width);
+    op.write_to_io(bytecode).map(|_| ())
+}
+
+/// Encodes an operation.
+#[allow(clippy::single_call_fn)]
+#[cfg(feature = "std")]
+fn encode(bytecode: &mut Vec<u8>, op: &AnyOp<'_>) -> Result<()> {
+    match op {
+        AnyOp::Advance(advance) => {
+            bytecode.push(OPCODE_ADVANCE);
+            write_varint(bytecode, advance.n());
+        }
+        AnyOp::Fill(fill) => {
+            bytecode.push(OPCODE_FILL);
+            write_varint(bytecode, fill.len());
+            bytecode.push(fill.chr());
+        }
+        AnyOp::WriteU8(integer) => write_integer(bytecode, integer)?,
+        AnyOp::WriteU16(integer) => write_integer(bytecode, integer)?,
+        AnyOp::WriteU32(integer) => write_integer(bytecode, integer)?,
+        AnyOp::WriteU64(integer) => write_integer(bytecode, integer)?,
+        AnyOp::WriteBuffer(buffer) => {
+            bytecode.push(OPCODE_BUFFER);
+            write_varint(bytecode, buffer.as_bytes().len());
+            bytecode.extend_from_slice(buffer.as_bytes());
+        }
+    }
+    Ok(())
+}
+
+/// Returns a label describing an operation.
+#[allow(clippy::single_call_fn)]
+#[cfg(feature = "std")]
+fn label(op: &AnyOp<'_>) -> String {
+    match op {
+        AnyOp::Advance(advance) => format!("{advance:?}"),
+        AnyOp::Fill(fill) => format!("{fill:?}"),
+        AnyOp::WriteU8(integer) => format!("{integer:?}"),
+        AnyOp::WriteU16(integer) => format!("{integer:?}"),
+        AnyOp::WriteU32(integer) => format!("{integer:?}"),
+        AnyOp::WriteU64(integer) => format!("{integer:?}"),
+        AnyOp::WriteBuffer(buffer) => format!("{buffer:?}"),
+    }
+}
+
+/// A region of a payload, i.e. the bytes written by an operation.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    /// Offsets of the bytes in the payload.
+    range: Range<usize>,
+
+    /// Index of the operation in the plan.
+    index: usize,
+
+    /// Description of the operation.
+    label: String,
+}
+
+#[cfg(feature = "std")]
+impl Region {
+    /// Returns the offsets of the bytes in the payload.
+    #[inline]
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the index of the operation in the plan.
+    #[inline]
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns a description of the operation.
+    #[inline]
+    #[must_use]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+/// The layout of a payload built from a plan, i.e. which operation wrote
+/// which bytes.
+///
+/// [`Layout`] implements [`fmt::Display`], printing one region per line.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Fill, WriteInteger};
+/// use shellcoder::plan::{Plan, Region};
+///
+/// let mut plan = Plan::new();
+/// plan.push(Fill::new(0x48, b'A'))
+///     .push(WriteInteger::new_le(0xdeadbeefu32));
+///
+/// let layout = plan.layout();
+/// assert_eq!(layout.find(0x4b).map(Region::index), Some(1));
+/// assert_eq!(
+///     layout.to_string(),
+///     "0x0000..0x0048  #0  Fill(72, 65)\n0x0048..0x004c  #1  LittleEndian(3735928559)\n",
+/// );
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// Regions, in order.
+    regions: Vec<Region>,
+}
+
+#[cfg(feature = "std")]
+impl Layout {
+    /// Returns the regions, in order.
+    #[inline]
+    #[must_use]
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Returns the region that contains the byte at `offset`, if any.
+    #[inline]
+    #[must_use]
+    pub fn find(&self, offset: usize) -> Option<&Region> {
+        let index = self
+            .regions
+            .partition_point(|region| region.range.end <= offset);
+        self.regions
+            .get(index)
+            .filter(|region| region.range.contains(&offset))
+    }
+
+    /// Returns the non-empty regions that overlap a range of offsets.
+    #[inline]
+    pub fn overlapping(&self, range: Range<usize>) -> impl Iterator<Item = &Region> + '_ {
+        let first = self
+            .regions
+            .partition_point(|region| region.range.end <= range.start);
+        self.regions
+            .get(first..)
+            .unwrap_or_default()
+            .iter()
+            .take_while(move |region| region.range.start < range.end)
+            .filter(|region| !region.range.is_empty())
+    }
+
+    /// Fixes up the regions once `len` bytes have been inserted at
+    /// `offset`, see [`crate::alloc::Shellcoder::insert_at`]. [`Recorder`]
+    /// does it on its own.
+    ///
+    /// Regions that start at or after `offset` are moved, and a region that
+    /// contains `offset` grows.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::alloc::Shellcoder;
+    /// use shellcoder::ops::{Fill, WriteBuffer};
+    /// use shellcoder::plan::{Plan, Region};
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut plan = Plan::new();
+    /// plan.push(Fill::new(8, b'A'))
+    ///     .push(WriteBuffer::new(b"/bin/sh\0"));
+    ///
+    /// let mut shellcoder = Shellcoder::new();
+    /// let mut layout = plan.apply_with_layout(&mut shellcoder)?;
+    /// shellcoder.insert_at(0, WriteBuffer::new(b"HDR\0"))?;
+    /// layout.shift(0, 4);
+    /// assert_eq!(layout.find(12).map(Region::index), Some(1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn shift(&mut self, offset: usize, len: usize) {
+        for Region { range, .. } in &mut self.regions {
+            if range.start >= offset {
+                range.start = range.start.saturating_add(len);
+            }
+            if range.end > offset || range.end < range.start {
+                range.end = range.end.saturating_add(len);
+            }
+        }
+    }
+
+    /// Appends the region of an operation.
+    fn push(&mut self, label: String, start: usize, len: usize) {
+        self.regions.push(Region {
+            range: start..start.saturating_add(len),
+            index: self.regions.len(),
+            label,
+        });
+    }
+
+    /// Inserts the region of an operation whose `len` bytes have been
+    /// inserted at `offset`, moving the regions that follow.
+    fn insert(&mut self, label: String, offset: usize, len: usize) {
+        self.shift(offset, len);
+        let position = self
+            .regions
+            .partition_point(|region| region.range.start < offset);
+        self.regions.insert(
+            position,
+            Region {
+                range: offset..offset.saturating_add(len),
+                index: self.regions.len(),
+                label,
+            },
+        );
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Layout {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for region in &self.regions {
+            writeln!(
+                fmt,
+                "{:#06x}..{:#06x}  #{:<2} {}",
+                region.range.start, region.range.end, region.index, region.label
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Hints for decomposing a payload into a plan, see
+/// [`Plan::from_bytes_heuristic`].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hints {
+    /// Minimum length of a run of the same byte to be a fill.
+    min_fill_len: usize,
+
+    /// Minimum length of a run of printable characters to be a string.
+    min_string_len: usize,
+
+    /// Addresses that pointers may point to, if any.
+    pointers: Option<Range<u64>>,
+
+    /// Configuration giving the width and the endianness of pointers.
+    config: Config,
+}
+
+#[cfg(feature = "std")]
+impl Default for Hints {
+    /// Runs of at least 4 bytes, and no pointers.
+    #[inline]
+    fn default() -> Self {
+        Self {
+            min_fill_len: 4,
+            min_string_len: 4,
+            pointers: None,
+            config: Config::default(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Hints {
+    /// Instantiates new default [`Hints`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the minimum length of a run of the same byte to be a fill.
+    #[inline]
+    #[must_use]
+    pub const fn with_min_fill_len(mut self, len: usize) -> Self {
+        self.min_fill_len = len;
+        self
+    }
+
+    /// Sets the minimum length of a run of printable characters to be a
+    /// string.
+    #[inline]
+    #[must_use]
+    pub const fn with_min_string_len(mut self, len: usize) -> Self {
+        self.min_string_len = len;
+        self
+    }
+
+    /// Sets the addresses that pointers may point to, e.g. the range of a
+    /// loaded image. Aligned integers within this range are pointers.
+    #[inline]
+    #[must_use]
+    pub const fn with_pointers(mut self, range: Range<u64>) -> Self {
+        self.pointers = Some(range);
+        self
+    }
+
+    /// Sets the width and the endianness of pointers, from a configuration.
+    #[inline]
+    #[must_use]
+    pub const fn with_config(mut self, config: &Config) -> Self {
+        self.config = *config;
+        self
+    }
+
+    /// Returns the pointer at the start of `bytes`, if any.
+    fn pointer<'buf>(&self, bytes: &[u8]) -> Option<(AnyOp<'buf>, usize)> {
+        let range = self.pointers.as_ref()?;
+        let value = unpack::read_ptr(bytes, &self.config).filter(|value| range.contains(value))?;
+        let endianness = self.config.endianness();
+        let op = match self.config.pointer_width() {
+            PointerWidth::Bits16 => {
+                AnyOp::from(WriteInteger::new(u16::try_from(value).ok()?, endianness))
+            }
+            PointerWidth::Bits32 => {
+                WriteInteger::new(u32::try_from(value).ok()?, endianness).into()
+            }
+            PointerWidth::Bits64 => WriteInteger::new(value, endianness).into(),
+        };
+        Some((op, self.config.pointer_width().bytes()))
+    }
+
+    /// Returns `true` if a pointer is expected at `offset`.
+    fn is_aligned(&self, offset: usize) -> bool {
+        offset.checked_rem(self.config.pointer_width().bytes()) == Some(0)
+    }
+
+    /// Returns the fill at the start of `bytes`, at `offset` in the payload,
+    /// if any. The fill stops before a pointer, e.g. zeroes followed by a
+    /// pointer whose least significant byte is zero.
+    fn fill<'buf>(&self, bytes: &[u8], offset: usize) -> Option<(AnyOp<'buf>, usize)> {
+        let &first = bytes.first()?;
+        let run = bytes.iter().take_while(|&&byte| byte == first).count();
+        let len = (1..run)
+            .find(|&index| {
+                self.is_aligned(offset.saturating_add(index))
+                    && bytes
+                        .get(index..)
+                        .and_then(|rest| self.pointer(rest))
+                        .is_some()
+            })
+            .unwrap_or(run);
+        (len >= self.min_fill_len.max(1)).then(|| (ops::Fill::new(len, first).into(), len))
+    }
+
+    /// Returns the string at the start of `bytes`, including its null
+    /// terminator, if any.
+    fn string<'buf>(&self, bytes: &'buf [u8]) -> Option<(AnyOp<'buf>, usize)> {
+        let printable = bytes
+            .iter()
+            .take_while(|&&byte| byte.is_ascii_graphic() || byte == b' ')
+            .count();
+        if printable < self.min_string_len.max(1) {
+            return None;
+        }
+        let len = if bytes.get(printable) == Some(&0) {
+            printable.saturating_add(1)
+        } else {
+            printable
+        };
+        Some((ops::WriteBuffer::new(bytes.get(..len)?).into(), len))
+    }
+}
+
+/// A plan, i.e. a sequence of operations.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::alloc::Shellcoder;
+/// use shellcoder::ops::{Fill, WriteBuffer, WriteInteger};
+/// use shellcoder::plan::Plan;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut plan = Plan::new();
+/// plan.push(WriteInteger::new_le(0xdeadbeefu32))
+///     .push(Fill::new(4, b'A'))
+///     .push(WriteBuffer::new(b"/bin/sh\0"));
+///
+/// let bytecode = plan.to_bytes()?;
+/// assert_eq!(Plan::from_bytes(&bytecode)?, plan);
+///
+/// let mut shellcoder = Shellcoder::new();
+/// plan.apply(&mut shellcoder)?;
+/// assert_eq!(shellcoder.as_bytes(), b"\xef\xbe\xad\xdeAAAA/bin/sh\0");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Plan<'buf> {
+    /// Operations, in order.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    ops: Vec<AnyOp<'buf>>,
+}
+
+#[cfg(feature = "std")]
+impl<'buf> Plan<'buf> {
+    /// Instantiates a new empty [`Plan`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an operation.
+    #[inline]
+    pub fn push(&mut self, op: impl Into<AnyOp<'buf>>) -> &mut Self {
+        self.ops.push(op.into());
+        self
+    }
+
+    /// Returns the operations.
+    #[inline]
+    #[must_use]
+    pub fn ops(&self) -> &[AnyOp<'buf>] {
+        &self.ops
+    }
+
+    /// Decodes a plan from its bytecode. Buffers are borrowed from the
+    /// bytecode.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidBytecode`]: the bytecode is malformed.
+    #[inline]
+    pub fn from_bytes(bytecode: &'buf [u8]) -> Result<Self> {
+        Decoder::new(bytecode)
+            .collect::<Result<_>>()
+            .map(|ops| Self { ops })
+    }
+
+    /// Decomposes a payload into a plan, on a best-effort basis, e.g. to
+    /// turn a handmade payload into a maintainable one.
+    ///
+    /// The payload is split into aligned pointers within the range given by
+    /// the hints, runs of the same byte, and printable strings, in this
+    /// order of precedence. Remaining bytes are kept as buffers. Applying
+    /// the plan writes the payload back.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::ops::{Fill, WriteBuffer, WriteInteger};
+    /// use shellcoder::plan::{Hints, Plan};
+    ///
+    /// let payload = b"AAAAAAAA\x36\x11\x40\0\0\0\0\0/bin/sh\0\x90\xcc";
+    /// let hints = Hints::new().with_pointers(0x40_0000..0x50_0000);
+    /// let plan = Plan::from_bytes_heuristic(payload, &hints);
+    /// assert_eq!(
+    ///     plan.ops(),
+    ///     [
+    ///         Fill::new(8, b'A').into(),
+    ///         WriteInteger::new_le(0x40_1136_u64).into(),
+    ///         WriteBuffer::new(b"/bin/sh\0").into(),
+    ///         WriteBuffer::new(b"\x90\xcc").into(),
+    ///     ]
+    /// );
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn from_bytes_heuristic(payload: &'buf [u8], hints: &Hints) -> Self {
+        let mut plan = Self::new();
+        let mut raw: usize = 0;
+        let mut offset: usize = 0;
+        while let Some(rest) = payload.get(offset..).filter(|rest| !rest.is_empty()) {
+            let recognized = hints
+                .is_aligned(offset)
+                .then(|| hints.pointer(rest))
+                .flatten()
+                .or_else(|| hints.fill(rest, offset))
+                .or_else(|| hints.string(rest));
+            if let Some((op, len)) = recognized {
+                if let Some(bytes) = payload.get(raw..offset).filter(|bytes| !bytes.is_empty()) {
+                    plan.push(ops::WriteBuffer::new(bytes));
+                }
+                plan.push(op);
+                offset = offset.saturating_add(len);
+                raw = offset;
+            } else {
+                offset = offset.saturating_add(1);
+            }
+        }
+        if let Some(bytes) = payload.get(raw..).filter(|bytes| !bytes.is_empty()) {
+            plan.push(ops::WriteBuffer::new(bytes));
+        }
+        plan
+    }
+
+    /// Encodes the plan into bytecode.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::Io`]: an I/O error occurred.
+    #[inline]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytecode = Vec::new();
+        for op in &self.ops {
+            encode(&mut bytecode, op)?;
+        }
+        Ok(bytecode)
+    }
+
+    /// Applies the plan to a shellcoder.
+    ///
+    /// Capacity for the whole plan is reserved up front, see
+    /// [`crate::Shellcoder::reserve`].
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by [`crate::Shellcoder::add`].
+    #[inline]
+    pub fn apply<S>(&self, shellcoder: &mut S) -> Result<()>
+    where
+        S: crate::Shellcoder + ?Sized,
+    {
+        shellcoder.reserve(Op::size(self).unwrap_or_default());
+        shellcoder.extend(&self.ops).map(|_| ())
+    }
+
+    /// Returns the layout of the payload, assuming operations are written
+    /// contiguously.
+    #[inline]
+    #[must_use]
+    pub fn layout(&self) -> Layout {
+        let mut layout = Layout::default();
+        let mut offset: usize = 0;
+        for op in &self.ops {
+            let len = op.size().unwrap_or_default();
+            layout.push(label(op), offset, len);
+            offset = offset.saturating_add(len);
+        }
+        layout
+    }
+
+    /// Applies the plan to a shellcoder, and returns the layout of what
+    /// has actually been written, e.g. including the padding added by the
+    /// shellcoder's configuration.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by [`crate::Shellcoder::add`].
+    #[inline]
+    pub fn apply_with_layout<S>(&self, shellcoder: &mut S) -> Result<Layout>
+    where
+        S: crate::Shellcoder + ?Sized,
+    {
+        shellcoder.reserve(Op::size(self).unwrap_or_default());
+        let mut layout = Layout::default();
+        for op in &self.ops {
+            let (n, next) = shellcoder.add_counted::<AnyOp<'_>>(op)?;
+            layout.push(label(op), next.len().saturating_sub(n), n);
+        }
+        Ok(layout)
+    }
+}
+
+/// A plan is an operation that writes all its operations, in order.
+#[cfg(feature = "std")]
+impl Op for Plan<'_> {
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.ops.as_slice().write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.ops.as_slice().write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.ops.as_slice().size()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'buf, O> FromIterator<O> for Plan<'buf>
+where
+    O: Into<AnyOp<'buf>>,
+{
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = O>>(iter: T) -> Self {
+        Self {
+            ops: iter.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// A shellcoder that records the layout of what it writes, i.e. which
+/// operation wrote which bytes, see [`Layout`].
+///
+/// Operations are written to an [`alloc::Shellcoder`], and labelled with
+/// their debug representation. Unlike [`alloc::Shellcoder::insert_at`],
+/// [`Recorder::insert_at`] keeps the layout up to date.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Fill, Placeholder, WriteBuffer};
+/// use shellcoder::plan::{Recorder, Region};
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut recorder = Recorder::new();
+/// recorder
+///     .add(Fill::new(8, b'A'))?
+///     .add(Placeholder::new("ret", 8))?;
+///
+/// // There turns out to be a 4-byte header.
+/// recorder.insert_at(0, WriteBuffer::new(b"HDR\0"))?;
+/// assert_eq!(recorder.layout().find(12).map(Region::index), Some(1));
+/// assert_eq!(recorder.layout().find(0).map(Region::index), Some(2));
+///
+/// recorder.patch_placeholder("ret", 0x40_1136_u64)?;
+/// assert_eq!(&recorder.as_bytes()[12..], b"\x36\x11\x40\0\0\0\0\0");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recorder {
+    /// The shellcoder operations are written to.
+    shellcoder: alloc::Shellcoder,
+
+    /// The layout of what has been written.
+    layout: Layout,
+}
+
+#[cfg(feature = "std")]
+impl Recorder {
+    /// Instantiates a new [`Recorder`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the configuration, see [`crate::config::ShellcoderBuilder`].
+    #[inline]
+    #[must_use]
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.shellcoder = self.shellcoder.with_config(config);
+        self
+    }
+
+    /// Returns the layout of what has been written.
+    #[inline]
+    #[must_use]
+    pub const fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Returns the shellcoder operations are written to.
+    #[inline]
+    #[must_use]
+    pub const fn get_ref(&self) -> &alloc::Shellcoder {
+        &self.shellcoder
+    }
+
+    /// Returns the shellcode.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        self.shellcoder.as_bytes()
+    }
+
+    /// Consumes the [`Recorder`] and returns the shellcoder operations have
+    /// been written to, and the layout of what has been written.
+    #[inline]
+    #[must_use]
+    pub fn into_parts(self) -> (alloc::Shellcoder, Layout) {
+        (self.shellcoder, self.layout)
+    }
+
+    /// Inserts an operation at `offset`, shifting the bytes that follow,
+    /// see [`alloc::Shellcoder::insert_at`].
+    ///
+    /// The regions and the placeholders that follow are moved, and the
+    /// operation is recorded as a new region.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::Overlap`]: `offset` is within the region of an
+    ///    operation.
+    ///  - Any error returned by [`alloc::Shellcoder::insert_at`].
+    #[inline]
+    pub fn insert_at(&mut self, offset: usize, op: impl Op) -> Result<&mut Self> {
+        if let Some(region) = self
+            .layout
+            .find(offset)
+            .filter(|region| region.range.start < offset)
+        {
+            return Err(Error::Overlap {
+                offset,
+                previous: region.range.start,
+            });
+        }
+        let label = format!("{op:?}");
+        let len = self.shellcoder.len();
+        self.shellcoder.insert_at(offset, op)?;
+        self.layout
+            .insert(label, offset, self.shellcoder.len().saturating_sub(len));
+        Ok(self)
+    }
+
+    /// Overwrites a placeholder with a value, see
+    /// [`alloc::Shellcoder::patch_placeholder`]. The layout is unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by [`alloc::Shellcoder::patch_placeholder`].
+    #[inline]
+    pub fn patch_placeholder(&mut self, name: &str, value: impl IntoOp) -> Result<&mut Self> {
+        self.shellcoder.patch_placeholder(name, value)?;
+        Ok(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl crate::Shellcoder for Recorder {
+    #[inline]
+    fn add<O>(&mut self, op: impl Borrow<O>) -> Result<&mut Self>
+    where
+        O: Op,
+    {
+        self.add_counted(op).map(|(_, shellcoder)| shellcoder)
+    }
+
+    #[inline]
+    fn add_counted<O>(&mut self, op: impl Borrow<O>) -> Result<(usize, &mut Self)>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let (n, shellcoder) = self.shellcoder.add_counted::<O>(operation)?;
+        let start = shellcoder.len().saturating_sub(n);
+        self.layout.push(format!("{operation:?}"), start, n);
+        Ok((n, self))
+    }
+
+    #[inline]
+    fn try_add<O>(&self, op: impl Borrow<O>) -> Result<()>
+    where
+        O: Op,
+    {
+        self.shellcoder.try_add(op)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.shellcoder.len()
+    }
+
+    #[inline]
+    fn position(&self) -> usize {
+        self.shellcoder.position()
+    }
+
+    #[inline]
+    fn endianness(&self) -> Endianness {
+        self.shellcoder.endianness()
+    }
+
+    #[inline]
+    fn pointer_width(&self) -> PointerWidth {
+        self.shellcoder.pointer_width()
+    }
+
+    #[inline]
+    fn arch(&self) -> Option<Arch> {
+        self.shellcoder.arch()
+    }
+
+    #[inline]
+    fn debug(&self) -> Option<(DebugStub, DebugPlacement)> {
+        self.shellcoder.debug()
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.shellcoder.reserve(additional);
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::absolute_paths,
+    clippy::indexing_slicing,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::unnecessary_wraps,
+    clippy::unreadable_literal,
+    clippy::unseparated_literal_suffix,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used
+)]
+mod tests {
+    #[cfg(feature = "std")]
+    use crate::config::{PointerWidth, ShellcoderBuilder};
+    use crate::ops::{Advance, WriteBuffer, WriteInteger};
+    #[cfg(feature = "std")]
+    use crate::ops::{AnyOp, Fill};
+    #[cfg(feature = "std")]
+    use crate::plan::Region;
+    use crate::plan::{replay, Decoder};
+    #[cfg(feature = "std")]
+    use crate::plan::{Hints, Plan, Recorder};
+    #[cfg(feature = "std")]
+    use crate::Shellcoder as _;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_decoder() -> Result<()> {
+        let mut decoder = Decoder::new(b"\x01\x80\x01\x1a\xef\xbe\xad\xde\x20\x01A\xff");
+        assert_eq!(decoder.next().unwrap().unwrap(), Advance::new(0x80).into());
+        assert_eq!(
+            decoder.next().unwrap().unwrap(),
+            WriteInteger::new_le(0xdeadbeefu32).into()
+        );
+        assert_eq!(
+            decoder.next().unwrap().unwrap(),
+            WriteBuffer::new(b"A").into()
+        );
+        assert!(matches!(
+            decoder.next(),
+            Some(Err(Error::InvalidBytecode(11)))
+        ));
+        assert!(decoder.next().is_none());
+
+        assert!(matches!(
+            Decoder::new(b"\x02\x01").next(),
+            Some(Err(Error::InvalidBytecode(0)))
+        ));
+        assert!(matches!(
+            Decoder::new(b"\x01\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01").next(),
+            Some(Err(Error::InvalidBytecode(0)))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay() -> Result<()> {
+        let mut buffer = [0u8; 8];
+        let mut shellcoder = crate::r#static::Shellcoder::new(&mut buffer);
+        replay(b"\x02\x03B\x11\xde\xad\x01\x01", &mut shellcoder).unwrap();
+        assert_eq!(shellcoder.get(), b"BBB\xde\xad\0");
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_replay_huge_fill() {
+        let mut shellcoder = crate::alloc::Shellcoder::new();
+        let bytecode = b"\x02\xff\xff\xff\xff\xff\xff\xff\xff\xff\x01\x41";
+        assert!(replay(bytecode, &mut shellcoder).is_err());
+        assert!(shellcoder.as_bytes().is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_plan() -> Result<()> {
+        let plan = [
+            AnyOp::from(Fill::new(300, 0x90)),
+            WriteInteger::new_be(0x4142u16).into(),
+            WriteInteger::new_le(1u64).into(),
+            WriteInteger::new_be(2u8).into(),
+            WriteBuffer::new(b"sh").into(),
+        ]
+        .into_iter()
+        .collect::<Plan<'_>>();
+        let bytecode = plan.to_bytes().unwrap();
+        assert_eq!(
+            bytecode,
+            b"\x02\xac\x02\x90\x11AB\x1b\x01\0\0\0\0\0\0\0\x10\x02\x20\x02sh"
+        );
+        assert_eq!(Plan::from_bytes(&bytecode).unwrap(), plan);
+        assert_eq!(plan.size(), Some(313));
+        let mut shellcoder = crate::alloc::Shellcoder::new();
+        plan.apply(&mut shellcoder).unwrap();
+        assert_eq!(shellcoder.as_bytes().len(), 313);
+        let mut other = crate::alloc::Shellcoder::new();
+        other.push(&plan)?.push(&plan)?;
+        assert_eq!(other.as_bytes(), shellcoder.as_bytes().repeat(2));
+        let mut buffer = [0u8; 312];
+        assert!(matches!(
+            plan.write_to(&mut buffer),
+            Err(Error::OutputBufferTooSmall(313))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_bytes_heuristic() -> Result<()> {
+        let payload = b"\x90\0\0\0\0\x80\x04\x08\xf8\xf6\xf4\xf2AB";
+        let config = *ShellcoderBuilder::new()
+            .pointer_width(PointerWidth::Bits32)
+            .config();
+        let hints = Hints::new()
+            .with_pointers(0x0804_8000..0x0805_0000)
+            .with_min_fill_len(3)
+            .with_min_string_len(2)
+            .with_config(&config);
+        let plan = Plan::from_bytes_heuristic(payload, &hints);
+        assert_eq!(
+            plan.ops(),
+            [
+                AnyOp::from(WriteBuffer::new(b"\x90")),
+                Fill::new(3, 0).into(),
+                WriteInteger::new_le(0x0804_8000_u32).into(),
+                WriteBuffer::new(b"\xf8\xf6\xf4\xf2").into(),
+                WriteBuffer::new(b"AB").into(),
+            ]
+        );
+        let mut shellcoder = crate::alloc::Shellcoder::new();
+        plan.apply(&mut shellcoder)?;
+        assert_eq!(shellcoder.as_bytes(), payload);
+        assert!(Plan::from_bytes_heuristic(b"", &hints).ops().is_empty());
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_layout() -> Result<()> {
+        let plan = [
+            AnyOp::from(WriteInteger::new_be(0x41u8)),
+            Fill::new(0, b'B').into(),
+            WriteBuffer::new(b"sh").into(),
+        ]
+        .into_iter()
+        .collect::<Plan<'_>>();
+        let layout = plan.layout();
+        assert_eq!(layout.regions().len(), 3);
+        assert_eq!(layout.regions()[2].range(), 1..3);
+        assert_eq!(layout.find(0).map(Region::label), Some("BigEndian(65)"));
+        assert_eq!(layout.find(1).map(Region::index), Some(2));
+        assert!(layout.find(3).is_none());
+
+        let mut shellcoder = ShellcoderBuilder::new().alignment(4).build_alloc();
+        let applied = plan.apply_with_layout(&mut shellcoder)?;
+        assert_eq!(applied.regions()[2].range(), 4..8);
+        assert_eq!(applied.find(3).map(Region::index), Some(0));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_recorder() -> Result<()> {
+        let config = *ShellcoderBuilder::new().alignment(2).config();
+        let mut recorder = Recorder::new().with_config(config);
+        recorder.push(b"AAAA")?.int_le(0x4242_u16)?.push(b"C")?;
+        recorder.insert_at(4, WriteBuffer::new(b"D"))?;
+        assert_eq!(recorder.as_bytes(), b"AAAAD\0BBC\0");
+        assert_eq!(
+            recorder.layout().to_string(),
+            "0x0000..0x0004  #0  WriteBuffer([65, 65, 65, 65])\n\
+             0x0004..0x0006  #3  WriteBuffer([68])\n\
+             0x0006..0x0008  #1  LittleEndian(16962)\n\
+             0x0008..0x000a  #2  WriteBuffer([67])\n"
+        );
+        assert!(matches!(
+            recorder.insert_at(7, WriteBuffer::new(b"E")),
+            Err(Error::Overlap {
+                offset: 7,
+                previous: 6
+            })
+        ));
+        recorder.insert_at(10, Fill::new(1, b'F'))?;
+        let (shellcoder, layout) = recorder.into_parts();
+        assert_eq!(shellcoder.as_bytes(), b"AAAAD\0BBC\0F\0");
+        assert_eq!(layout.find(11).map(Region::index), Some(4));
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+//! Python bindings.
+//!
+//! This module exposes the [`crate::alloc::Shellcoder`] and the operations
+//! of [`crate::ops`] to Python through [`pyo3`], e.g. using `maturin`:
+//!
+//! ```shell
+//! $ maturin develop --features python,pyo3/extension-module
+//! ```
+//!
+//! ```python
+//! from shellcoder import Fill, Shellcoder, WriteInteger
+//!
+//! shellcoder = Shellcoder()
+//! shellcoder.int_le(0xdeadbeef, 8).add(Fill(8, 0x41))
+//! payload = bytes(shellcoder)
+//! ```
+
+use pyo3::exceptions::{PyKeyError, PyOSError, PyOverflowError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::error::ErrorKind;
+use crate::ops::Endianness;
+use crate::prelude::*;
+use crate::Shellcoder as _;
+use crate::{alloc, ops};
+
+impl From<Error> for PyErr {
+    #[inline]
+    fn from(error: Error) -> Self {
+        let message = error.report();
+        match error.kind() {
+            ErrorKind::IntegerOverflow => PyOverflowError::new_err(message),
+            ErrorKind::Io => PyOSError::new_err(message),
+            ErrorKind::UnknownSymbol => PyKeyError::new_err(message),
+            ErrorKind::OutputBufferTooSmall
+            | ErrorKind::MaxLengthExceeded
+            | ErrorKind::BadByteFound
+            | ErrorKind::InvalidBytecode
+            | ErrorKind::InvalidRecipe
+            | ErrorKind::UnexpectedPosition
+            | ErrorKind::Overlap
+            | ErrorKind::InvalidSymbolTable
+            | ErrorKind::Misaligned
+            | ErrorKind::UnknownArch
+            | ErrorKind::Unsupported => PyValueError::new_err(message),
+        }
+    }
+}
+
+/// Pushes an integer of a given width in bytes.
+fn push_int(
+    shellcoder: &mut alloc::Shellcoder,
+    value: u64,
+    width: usize,
+    little_endian: bool,
+) -> Result<&mut alloc::Shellcoder> {
+    let endianness = if little_endian {
+        Endianness::Little
+    } else {
+        Endianness::Big
+    };
+    match width {
+        1 => shellcoder.int_with(u8::try_from(value)?, endianness),
+        2 => shellcoder.int_with(u16::try_from(value)?, endianness),
+        4 => shellcoder.int_with(u32::try_from(value)?, endianness),
+        8 => shellcoder.int_with(value, endianness),
+        _ => Err(Error::IntegerOverflow),
+    }
+}
+
+/// Instantiates a new [`WriteInteger`], checking that the integer fits.
+const fn write_integer(value: u64, width: usize, little_endian: bool) -> Result<WriteInteger> {
+    let max = match width {
+        1 => 0xff,
+        2 => 0xffff,
+        4 => 0xffff_ffff,
+        8 => u64::MAX,
+        _ => return Err(Error::IntegerOverflow),
+    };
+    if value > max {
+        return Err(Error::IntegerOverflow);
+    }
+    Ok(WriteInteger {
+        value,
+        width,
+        little_endian,
+    })
+}
+
+/// See [`ops::Advance`].
+#[pyclass(frozen, module = "shellcoder")]
+#[derive(Clone, Copy, Debug)]
+pub struct Advance(ops::Advance);
+
+#[pymethods]
+impl Advance {
+    /// Instantiates a new [`Advance`] to move the cursor ahead by n bytes.
+    #[new]
+    #[inline]
+    #[must_use]
+    pub const fn new(n: usize) -> Self {
+        Self(ops::Advance::new(n))
+    }
+}
+
+/// See [`ops::Fill`].
+#[pyclass(frozen, module = "shellcoder")]
+#[derive(Clone, Copy, Debug)]
+pub struct Fill(ops::Fill);
+
+#[pymethods]
+impl Fill {
+    /// Instantiates a new [`Fill`].
+    #[new]
+    #[inline]
+    #[must_use]
+    pub const fn new(len: usize, chr: u8) -> Self {
+        Self(ops::Fill::new(len, chr))
+    }
+}
+
+/// See [`ops::WriteInteger`].
+///
+/// Python integers are not sized, thus the width in bytes of the integer
+/// is given explicitly, i.e. 1, 2, 4 or 8.
+#[pyclass(frozen, module = "shellcoder")]
+#[derive(Clone, Copy, Debug)]
+pub struct WriteInteger {
+    /// The integer's value.
+    value: u64,
+
+    /// Width in bytes.
+    width: usize,
+
+    /// Whether the integer is encoded in little-endian.
+    little_endian: bool,
+}
+
+#[pymethods]
+impl WriteInteger {
+    /// Instantiates a new [`WriteInteger`] to write a big-endian encoded
+    /// integer.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: the integer does not fit in `width`
+    /// bytes.
+    #[staticmethod]
+    #[inline]
+    pub const fn be(value: u64, width: usize) -> Result<Self> {
+        write_integer(value, width, false)
+    }
+
+    /// Instantiates a new [`WriteInteger`] to write a little-endian encoded
+    /// integer.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: the integer does not fit in `width`
+    /// bytes.
+    #[staticmethod]
+    #[inline]
+    pub const fn le(value: u64, width: usize) -> Result<Self> {
+        write_integer(value, width, true)
+    }
+}
+
+/// See [`ops::WriteBuffer`].
+///
+/// The buffer is copied, as Python objects cannot be borrowed.
+#[pyclass(frozen, module = "shellcoder")]
+#[derive(Clone, Debug)]
+pub struct WriteBuffer(Vec<u8>);
+
+#[pymethods]
+impl WriteBuffer {
+    /// Instantiates a new [`WriteBuffer`].
+    #[new]
+    #[inline]
+    #[must_use]
+    pub const fn new(buffer: Vec<u8>) -> Self {
+        Self(buffer)
+    }
+}
+
+/// Any operation exposed to Python.
+#[derive(Clone, Debug, FromPyObject)]
+#[non_exhaustive]
+pub enum AnyOp {
+    /// See [`Advance`].
+    Advance(Advance),
+
+    /// See [`Fill`].
+    Fill(Fill),
+
+    /// See [`WriteInteger`].
+    WriteInteger(WriteInteger),
+
+    /// See [`WriteBuffer`].
+    WriteBuffer(WriteBuffer),
+}
+
+/// A shellcoder backed by a dynamic buffer, exposed to Python.
+#[pyclass(module = "shellcoder")]
+#[derive(Clone, Debug, Default)]
+pub struct Shellcoder(alloc::Shellcoder);
+
+#[pymethods]
+impl Shellcoder {
+    /// Instantiates a new shellcoder, optionally with a maximum length in
+    /// bytes.
+    #[new]
+    #[pyo3(signature = (max_len = None))]
+    #[inline]
+    #[must_use]
+    pub fn new(max_len: Option<usize>) -> Self {
+        Self(max_len.map_or_else(alloc::Shellcoder::new, alloc::Shellcoder::new_with_max_len))
+    }
+
+    /// Pushes an operation, i.e. an [`Advance`], a [`Fill`], a
+    /// [`WriteInteger`] or a [`WriteBuffer`].
+    ///
+    /// # Errors
+    ///
+    /// The maximum length is exceeded.
+    #[inline]
+    pub fn add(mut slf: PyRefMut<'_, Self>, op: AnyOp) -> Result<PyRefMut<'_, Self>> {
+        let shellcoder = &mut slf.0;
+        match op {
+            AnyOp::Advance(Advance(advance)) => shellcoder.add(advance),
+            AnyOp::Fill(Fill(fill)) => shellcoder.add(fill),
+            AnyOp::WriteInteger(integer) => push_int(
+                shellcoder,
+                integer.value,
+                integer.width,
+                integer.little_endian,
+            ),
+            AnyOp::WriteBuffer(WriteBuffer(bytes)) => shellcoder.push_buffer(bytes),
+        }?;
+        Ok(slf)
+    }
+
+    /// Pushes an integer of `width` bytes (1, 2, 4 or 8) in little endian.
+    ///
+    /// # Errors
+    ///
+    /// The integer does not fit in `width` bytes, or the maximum length is
+    /// exceeded.
+    #[inline]
+    pub fn int_le(
+        mut slf: PyRefMut<'_, Self>,
+        value: u64,
+        width: usize,
+    ) -> Result<PyRefMut<'_, Self>> {
+        push_int(&mut slf.0, value, width, true)?;
+        Ok(slf)
+    }
+
+    /// Pushes an integer of `width` bytes (1, 2, 4 or 8) in big endian.
+    ///
+    /// # Errors
+    ///
+    /// The integer does not fit in `width` bytes, or the maximum length is
+    /// exceeded.
+    #[inline]
+    pub fn int_be(
+        mut slf: PyRefMut<'_, Self>,
+        value: u64,
+        width: usize,
+    ) -> Result<PyRefMut<'_, Self>> {
+        push_int(&mut slf.0, value, width, false)?;
+        Ok(slf)
+    }
+
+    /// Fills with `len` bytes of value `chr`.
+    ///
+    /// # Errors
+    ///
+    /// The maximum length is exceeded.
+    #[inline]
+    pub fn fill(mut slf: PyRefMut<'_, Self>, len: usize, chr: u8) -> Result<PyRefMut<'_, Self>> {
+        slf.0.fill(len, chr)?;
+        Ok(slf)
+    }
+
+    /// Advances the cursor by `n` bytes, filling the gap with zeroes.
+    ///
+    /// # Errors
+    ///
+    /// The maximum length is exceeded.
+    #[inline]
+    pub fn advance(mut slf: PyRefMut<'_, Self>, n: usize) -> Result<PyRefMut<'_, Self>> {
+        slf.0.advance(n)?;
+        Ok(slf)
+    }
+
+    /// Pushes a buffer.
+    ///
+    /// # Errors
+    ///
+    /// The maximum length is exceeded.
+    #[inline]
+    pub fn push_buffer(mut slf: PyRefMut<'_, Self>, buffer: Vec<u8>) -> Result<PyRefMut<'_, Self>> {
+        slf.0.push_buffer(buffer)?;
+        Ok(slf)
+    }
+
+    /// Returns the number of bytes written so far.
+    #[inline]
+    #[must_use]
+    pub fn __len__(&self) -> usize {
+        self.0.as_bytes().len()
+    }
+
+    /// Returns a copy of the payload.
+    #[inline]
+    #[must_use]
+    pub fn __bytes__<'py>(&self, py: Python<'py>) -> Bound<'py, PyBytes> {
+        PyBytes::new_bound(py, self.0.as_bytes())
+    }
+}
+
+/// The `shellcoder` Python module.
+///
+/// # Errors
+///
+/// A class cannot be added to the module.
+// `#[pymodule]` rebinds `module`.
+#[allow(clippy::shadow_reuse)]
+#[pymodule]
+#[inline]
+pub fn shellcoder(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<Shellcoder>()?;
+    module.add_class::<Advance>()?;
+    module.add_class::<Fill>()?;
+    module.add_class::<WriteInteger>()?;
+    module.add_class::<WriteBuffer>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::shadow_unrelated,
+    clippy::unnecessary_wraps,
+    clippy::unwrap_used
+)]
+mod tests {
+    use pyo3::exceptions::PyOverflowError;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    use pyo3::wrap_pymodule;
+
+    use crate::python::shellcoder;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_python() -> Result<()> {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let locals = PyDict::new_bound(py);
+            locals
+                .set_item("shellcoder", wrap_pymodule!(shellcoder)(py))
+                .unwrap();
+            py.run_bound(
+                r#"
+sc = shellcoder.Shellcoder()
+sc.int_le(0xdead, 2).add(shellcoder.WriteInteger.be(0xbeef, 4))
+sc.add(shellcoder.Fill(2, 0x41)).add(shellcoder.Advance(1))
+sc.add(shellcoder.WriteBuffer(b"sh"))
+assert len(sc) == 11
+assert bytes(sc) == b"\xad\xde\0\0\xbe\xefAA\0sh"
+"#,
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+            let error = py
+                .run_bound("shellcoder.WriteInteger.le(0x100, 1)", None, Some(&locals))
+                .unwrap_err();
+            assert!(error.is_instance_of::<PyOverflowError>(py));
+            let error = py
+                .run_bound(
+                    "shellcoder.Shellcoder(max_len=1).fill(2, 0)",
+                    None,
+                    Some(&locals),
+                )
+                .unwrap_err();
+            assert!(!error.is_instance_of::<PyOverflowError>(py));
+            assert!(error.value_bound(py).to_string().ends_with(
+                "at offset 0x0: maximum length exceeded: 0x2 byte(s) for a limit of 0x1"
+            ));
+        });
+        Ok(())
+    }
+}
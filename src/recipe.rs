@@ -0,0 +1,337 @@
+//! Recipes: payload layouts described in data files.
+//!
+//! A recipe is a list of operations that can be deserialized from JSON
+//! (feature `json`), YAML (feature `yaml`) or TOML (feature `toml`), and then
+//! applied to any [`crate::Shellcoder`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "json")]
+//! # {
+//! use shellcoder::alloc::Shellcoder;
+//! use shellcoder::recipe::Recipe;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let recipe = Recipe::from_json(r#"{
+//!     "ops": [
+//!         { "op": "int", "type": "u32", "value": 3735928559, "endianness": "little" },
+//!         { "op": "advance", "len": 4 },
+//!         { "op": "fill", "len": 2, "byte": 65 },
+//!         { "op": "buffer", "bytes": [47, 98, 105, 110] }
+//!     ]
+//! }"#)?;
+//!
+//! let mut shellcoder = Shellcoder::new();
+//! recipe.apply(&mut shellcoder)?;
+//! assert_eq!(shellcoder.as_bytes(), b"\xef\xbe\xad\xde\0\0\0\0AA/bin");
+//! # Ok(())
+//! # }
+//! # main().unwrap();
+//! # }
+//! ```
+
+use crate::ops;
+use crate::prelude::*;
+
+/// Endianness of an integer in a recipe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Endianness {
+    /// Big-endian.
+    Big,
+
+    /// Little-endian.
+    Little,
+}
+
+impl From<Endianness> for ops::Endianness {
+    #[inline]
+    fn from(endianness: Endianness) -> Self {
+        match endianness {
+            Endianness::Big => Self::Big,
+            Endianness::Little => Self::Little,
+        }
+    }
+}
+
+/// An integer in a recipe, with its type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Integer {
+    /// An 8-bit integer.
+    U8(u8),
+
+    /// A 16-bit integer.
+    U16(u16),
+
+    /// A 32-bit integer.
+    U32(u32),
+
+    /// A 64-bit integer.
+    U64(u64),
+}
+
+/// A single operation of a recipe.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum Step {
+    /// See [`ops::Advance`].
+    Advance {
+        /// Number of bytes to skip.
+        len: usize,
+    },
+
+    /// See [`ops::Fill`].
+    Fill {
+        /// Number of bytes to write.
+        len: usize,
+
+        /// Value of the bytes.
+        byte: u8,
+    },
+
+    /// See [`ops::RandomFill`].
+    Random {
+        /// Number of bytes to write.
+        len: usize,
+
+        /// Seed of the generator.
+        seed: u64,
+    },
+
+    /// See [`ops::WriteInteger`].
+    Int {
+        /// The integer.
+        #[serde(flatten)]
+        value: Integer,
+
+        /// Its endianness.
+        endianness: Endianness,
+    },
+
+    /// See [`ops::WriteBuffer`].
+    Buffer {
+        /// Bytes to write.
+        bytes: Vec<u8>,
+    },
+}
+
+impl Step {
+    /// Applies the step to a shellcoder.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by [`crate::Shellcoder::add`].
+    #[inline]
+    pub fn apply<S>(&self, shellcoder: &mut S) -> Result<()>
+    where
+        S: crate::Shellcoder + ?Sized,
+    {
+        match self {
+            Self::Advance { len } => shellcoder.add(ops::Advance::new(*len)),
+            Self::Fill { len, byte } => shellcoder.add(ops::Fill::new(*len, *byte)),
+            Self::Random { len, seed } => shellcoder.add(ops::RandomFill::new(*len, *seed)),
+            Self::Int { value, endianness } => match *value {
+                Integer::U8(i) => shellcoder.int_with(i, (*endianness).into()),
+                Integer::U16(i) => shellcoder.int_with(i, (*endianness).into()),
+                Integer::U32(i) => shellcoder.int_with(i, (*endianness).into()),
+                Integer::U64(i) => shellcoder.int_with(i, (*endianness).into()),
+            },
+            Self::Buffer { bytes } => shellcoder.push_buffer(bytes),
+        }
+        .map(|_| ())
+    }
+}
+
+/// A recipe, i.e. a list of operations.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Recipe {
+    /// Operations, in order.
+    ops: Vec<Step>,
+}
+
+impl Recipe {
+    /// Instantiates a new [`Recipe`] from a list of operations.
+    #[inline]
+    #[must_use]
+    pub fn new(ops: impl IntoIterator<Item = Step>) -> Self {
+        Self {
+            ops: ops.into_iter().collect(),
+        }
+    }
+
+    /// Returns the operations of the recipe.
+    #[inline]
+    #[must_use]
+    pub fn ops(&self) -> &[Step] {
+        &self.ops
+    }
+
+    /// Parses a recipe from JSON.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidRecipe`]: the recipe is not valid.
+    #[cfg(feature = "json")]
+    #[inline]
+    pub fn from_json(recipe: &str) -> Result<Self> {
+        serde_json::from_str(recipe).map_err(Error::invalid_recipe)
+    }
+
+    /// Parses a recipe from YAML.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidRecipe`]: the recipe is not valid.
+    #[cfg(feature = "yaml")]
+    #[inline]
+    pub fn from_yaml(recipe: &str) -> Result<Self> {
+        serde_yaml::from_str(recipe).map_err(Error::invalid_recipe)
+    }
+
+    /// Parses a recipe from TOML.
+    ///
+    /// Operations are described as an array of tables named `ops`.
+    /// Note that TOML integers are signed 64-bit integers, thus `u64` values
+    /// above `i64::MAX` cannot be expressed.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidRecipe`]: the recipe is not valid.
+    #[cfg(feature = "toml")]
+    #[inline]
+    pub fn from_toml(recipe: &str) -> Result<Self> {
+        toml::from_str(recipe).map_err(Error::invalid_recipe)
+    }
+
+    /// Applies all the operations of the recipe to a shellcoder.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by [`crate::Shellcoder::add`].
+    #[inline]
+    pub fn apply<S>(&self, shellcoder: &mut S) -> Result<()>
+    where
+        S: crate::Shellcoder + ?Sized,
+    {
+        self.ops.iter().try_for_each(|step| step.apply(shellcoder))
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used
+)]
+mod tests {
+    #[cfg(feature = "json")]
+    use std::error::Error as _;
+
+    use crate::alloc::Shellcoder;
+    use crate::recipe::{Endianness, Integer, Recipe, Step};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_apply() -> Result<()> {
+        let recipe = Recipe::new(vec![
+            Step::Int {
+                value: Integer::U16(0xdead),
+                endianness: Endianness::Big,
+            },
+            Step::Fill { len: 2, byte: 0x41 },
+            Step::Advance { len: 1 },
+            Step::Buffer {
+                bytes: b"sh".to_vec(),
+            },
+            Step::Random { len: 2, seed: 0 },
+        ]);
+        let mut shellcoder = Shellcoder::new();
+        recipe.apply(&mut shellcoder).unwrap();
+        assert_eq!(shellcoder.as_bytes(), b"\xde\xadAA\0sh\xaf\xcd");
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() -> Result<()> {
+        let recipe = Recipe::from_json(
+            r#"{"ops": [
+                {"op": "int", "type": "u64", "value": 1, "endianness": "little"},
+                {"op": "fill", "len": 3, "byte": 66}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            recipe.ops(),
+            &[
+                Step::Int {
+                    value: Integer::U64(1),
+                    endianness: Endianness::Little,
+                },
+                Step::Fill { len: 3, byte: 0x42 },
+            ]
+        );
+
+        let err = Recipe::from_json(r#"{"ops": [{"op": "jump"}]}"#).unwrap_err();
+        assert!(matches!(err, Error::InvalidRecipe(_)));
+        assert_eq!(err.to_string(), "invalid recipe");
+        assert!(err
+            .source()
+            .is_some_and(|source| source.to_string().contains("unknown variant `jump`")));
+        Ok(())
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml() -> Result<()> {
+        let recipe = Recipe::from_yaml(
+            "ops:\n  - op: advance\n    len: 2\n  - op: int\n    type: u8\n    value: 255\n    endianness: big\n",
+        )
+        .unwrap();
+        let mut shellcoder = Shellcoder::new();
+        recipe.apply(&mut shellcoder).unwrap();
+        assert_eq!(shellcoder.as_bytes(), b"\0\0\xff");
+
+        let err = Recipe::from_yaml(
+            "ops:\n  - op: int\n    type: u8\n    value: 256\n    endianness: big\n",
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::InvalidRecipe(_)));
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml() -> Result<()> {
+        let recipe = Recipe::from_toml(
+            r#"
+            [[ops]]
+            op = "int"
+            type = "u32"
+            value = 0xdeadbeef
+            endianness = "big"
+
+            [[ops]]
+            op = "buffer"
+            bytes = [0x2f, 0x73, 0x68]
+            "#,
+        )
+        .unwrap();
+        let mut shellcoder = Shellcoder::new();
+        recipe.apply(&mut shellcoder).unwrap();
+        assert_eq!(shellcoder.as_bytes(), b"\xde\xad\xbe\xef/sh");
+
+        let err = Recipe::from_toml("ops = 1").unwrap_err();
+        assert!(matches!(err, Error::InvalidRecipe(_)));
+        Ok(())
+    }
+}
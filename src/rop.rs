@@ -0,0 +1,1412 @@
+//! Return-oriented programming.
+//!
+//! Gadgets are looked up by their instructions, e.g. `pop rdi; ret`, in a
+//! [`Catalog`] imported from the output of `ROPgadget` or `ropper`, so that
+//! chains are built against the binary at hand instead of hand-copied
+//! addresses. A [`Chain`] keeps the meaning of each of its slots, so that
+//! it can be reviewed as a listing rather than as raw words.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::alloc::Shellcoder;
+//! use shellcoder::rop::Catalog;
+//! use shellcoder::Shellcoder as _;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let catalog = Catalog::from_text("\
+//!     Gadgets information\n\
+//!     ============================================================\n\
+//!     0x000000000040116a : pop rdi ; ret\n\
+//!     0x0000000000401016 : ret\n\
+//!     \n\
+//!     Unique gadgets found: 2\n\
+//! ");
+//!
+//! let mut shellcoder = Shellcoder::new();
+//! shellcoder
+//!     .ptr(catalog.resolve("pop rdi; ret")?)?
+//!     .ptr(0x404060)?
+//!     .ptr(catalog.resolve("ret")?)?;
+//! assert_eq!(&shellcoder.as_bytes()[..8], b"\x6a\x11\x40\0\0\0\0\0");
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc_crate::collections::{btree_map, BTreeMap};
+use core::fmt;
+use std::io;
+
+use crate::config::{Config, PointerWidth};
+use crate::pack;
+use crate::prelude::*;
+
+/// A catalog of gadgets, mapping their instructions to their offsets from
+/// a base.
+///
+/// Instructions are normalized, so that `pop rdi ; ret`, `pop rdi; ret;`
+/// and `POP RDI; RET` are the same gadget. When several gadgets have the
+/// same instructions, the lowest offset is kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Catalog {
+    /// Runtime base the offsets are relative to.
+    base: u64,
+
+    /// Offsets, by normalized instructions.
+    gadgets: BTreeMap<String, u64>,
+}
+
+impl Catalog {
+    /// Instantiates a new empty [`Catalog`], based at 0.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the text output of `ROPgadget` or `ropper`.
+    ///
+    /// Gadgets are lines of the form `0x401016 : ret` (`ROPgadget`) or
+    /// `0x401016: ret;` (`ropper`). Any other line, e.g. a header, is skipped.
+    #[inline]
+    #[must_use]
+    pub fn from_text(text: &str) -> Self {
+        let mut catalog = Self::new();
+        for (offset, instructions) in text.lines().filter_map(parse_line) {
+            catalog.insert(instructions, offset);
+        }
+        catalog
+    }
+
+    /// Sets the runtime base the offsets are relative to, e.g. for a
+    /// position-independent binary.
+    #[inline]
+    #[must_use]
+    pub const fn with_base(mut self, base: u64) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Sets the runtime base the offsets are relative to, e.g. once it has
+    /// been leaked.
+    #[inline]
+    pub fn set_base(&mut self, base: u64) -> &mut Self {
+        self.base = base;
+        self
+    }
+
+    /// Returns the runtime base the offsets are relative to.
+    #[inline]
+    #[must_use]
+    pub const fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Inserts a gadget, unless a gadget with the same instructions has a
+    /// lower offset.
+    #[inline]
+    pub fn insert(&mut self, instructions: &str, offset: u64) -> &mut Self {
+        let previous = self
+            .gadgets
+            .entry(normalize(instructions))
+            .or_insert(offset);
+        *previous = offset.min(*previous);
+        self
+    }
+
+    /// Returns the offset of a gadget.
+    #[inline]
+    #[must_use]
+    pub fn offset(&self, instructions: &str) -> Option<u64> {
+        self.gadgets.get(&normalize(instructions)).copied()
+    }
+
+    /// Returns the address of a gadget, i.e. its offset from the base.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::UnknownSymbol`]: the gadget is not in the catalog.
+    ///  - [`Error::IntegerOverflow`]: the address overflows.
+    #[inline]
+    pub fn resolve(&self, instructions: &str) -> Result<u64> {
+        self.offset(instructions)
+            .ok_or_else(|| Error::UnknownSymbol(normalize(instructions)))?
+            .checked_add(self.base)
+            .ok_or(Error::IntegerOverflow)
+    }
+
+    /// Returns the number of gadgets.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.gadgets.len()
+    }
+
+    /// Returns `true` if there is no gadget.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.gadgets.is_empty()
+    }
+
+    /// Returns the gadget with the lowest offset that pops `count`
+    /// registers, then returns, e.g. `pop esi; pop edi; ret` for 2.
+    ///
+    /// The gadget is returned with its normalized instructions and its
+    /// offset.
+    #[inline]
+    #[must_use]
+    pub fn pops(&self, count: usize) -> Option<(&str, u64)> {
+        self.gadgets
+            .iter()
+            .filter(|(instructions, _offset)| {
+                popped(instructions).map_or(false, |popped| popped.len() == count)
+            })
+            .min_by_key(|(_instructions, &offset)| offset)
+            .map(|(instructions, &offset)| (instructions.as_str(), offset))
+    }
+
+    /// Returns the gadget that pops a register first, then as few other
+    /// registers as possible, then returns, e.g. `pop rsi; pop r15; ret`
+    /// for `rsi`. Among such gadgets, the one with the lowest offset is
+    /// returned.
+    ///
+    /// The gadget is returned with its normalized instructions, its offset
+    /// and the number of other registers it pops.
+    #[inline]
+    #[must_use]
+    pub fn pop(&self, register: &str) -> Option<(&str, u64, usize)> {
+        let normalized = register.trim().to_lowercase();
+        self.gadgets
+            .iter()
+            .filter_map(|(instructions, &offset)| {
+                let popped = popped(instructions)?;
+                let (first, others) = popped.split_first()?;
+                (*first == normalized).then(|| (instructions.as_str(), offset, others.len()))
+            })
+            .min_by_key(|&(_instructions, offset, others)| (others, offset))
+    }
+
+    /// Returns an iterator over the normalized instructions of the gadgets
+    /// and their offsets, sorted by instructions.
+    #[inline]
+    pub fn iter(&self) -> btree_map::Iter<'_, String, u64> {
+        self.gadgets.iter()
+    }
+}
+
+impl<'catalog> IntoIterator for &'catalog Catalog {
+    type Item = (&'catalog String, &'catalog u64);
+    type IntoIter = btree_map::Iter<'catalog, String, u64>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Meaning of a slot of a [`Chain`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Slot {
+    /// A gadget, with its normalized instructions.
+    Gadget(String),
+
+    /// An argument, e.g. popped by the previous gadget.
+    Argument,
+
+    /// A named address, e.g. a function.
+    Address(String),
+
+    /// Padding, e.g. for a register popped by a gadget but unused.
+    Padding,
+}
+
+/// A ROP chain, i.e. stack slots holding gadgets and their arguments.
+///
+/// Slots are as wide as the pointers of the configuration, and are written
+/// with its endianness.
+///
+/// By default, slots are contiguous, as they are popped from the stack by
+/// `ret` instructions. Jump-oriented programming chains are laid out in a
+/// dispatch table instead, walked by a dispatcher gadget such as
+/// `add rdx, 0x10; jmp qword ptr [rdx]`: slots are then spaced by the
+/// stride of the dispatcher, see [`Chain::with_stride`].
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+/// use shellcoder::rop::{Catalog, Chain};
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut catalog = Catalog::new();
+/// catalog.insert("pop ebx; ret", 0x0804_901e);
+/// let config = *ShellcoderBuilder::new()
+///     .pointer_width(PointerWidth::Bits32)
+///     .config();
+///
+/// let mut chain = Chain::new(&catalog, &config);
+/// chain
+///     .address("exit", 0xf7c3_ebd0)
+///     .gadget("pop ebx; ret")?
+///     .arg(0);
+/// assert_eq!(chain.to_bytes()?, b"\xd0\xeb\xc3\xf7\x1e\x90\x04\x08\0\0\0\0");
+/// assert_eq!(
+///     chain.listing().to_string(),
+///     "0x0000  0xf7c3ebd0  exit\n\
+///      0x0004  0x0804901e  pop ebx; ret\n\
+///      0x0008  0x00000000    argument\n",
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chain<'catalog> {
+    /// Catalog the gadgets are resolved from.
+    catalog: &'catalog Catalog,
+
+    /// Configuration, for the width and the endianness of slots.
+    config: Config,
+
+    /// Distance between the starts of two slots, if they are not
+    /// contiguous.
+    stride: Option<usize>,
+
+    /// Alignment of the stack when functions are entered, if any.
+    stack_alignment: Option<usize>,
+
+    /// Values of the slots, and their meaning.
+    slots: Vec<(u64, Slot)>,
+}
+
+impl<'catalog> Chain<'catalog> {
+    /// Instantiates a new empty [`Chain`], resolving gadgets from a catalog.
+    #[inline]
+    #[must_use]
+    pub const fn new(catalog: &'catalog Catalog, config: &Config) -> Self {
+        Self {
+            catalog,
+            config: *config,
+            stride: None,
+            stack_alignment: None,
+            slots: Vec::new(),
+        }
+    }
+
+    /// Spaces the slots by `stride` bytes, as a dispatch table walked by a
+    /// dispatcher gadget. Gaps between slots are filled with zeroes.
+    ///
+    /// A stride smaller than a pointer is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::config::ShellcoderBuilder;
+    /// use shellcoder::rop::{Catalog, Chain};
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut catalog = Catalog::new();
+    /// catalog
+    ///     .insert("add rdx, 0x10; jmp qword ptr [rdx]", 0x401000)
+    ///     .insert("pop rdi; jmp rcx", 0x401010)
+    ///     .insert("xor eax, eax; jmp rcx", 0x401020);
+    /// let config = *ShellcoderBuilder::new().config();
+    ///
+    /// // rcx holds the dispatcher, and rdx walks the dispatch table.
+    /// let dispatcher = catalog.resolve("add rdx, 0x10; jmp qword ptr [rdx]")?;
+    /// let mut table = Chain::new(&catalog, &config).with_stride(0x10);
+    /// table
+    ///     .gadget("xor eax, eax; jmp rcx")?
+    ///     .gadget("pop rdi; jmp rcx")?;
+    /// assert_eq!(dispatcher, 0x401000);
+    /// assert_eq!(table.to_bytes()?.len(), 0x18);
+    /// assert_eq!(table.to_bytes()?[0x10..0x18], 0x401010_u64.to_le_bytes());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_stride(mut self, stride: usize) -> Self {
+        self.stride = Some(stride);
+        self
+    }
+
+    /// Aligns the stack on `alignment` bytes when functions called with
+    /// [`Chain::call_sysv`] or [`Chain::call_cdecl`] are entered, as if
+    /// they had been called, by inserting `ret` gadgets before them when
+    /// needed.
+    ///
+    /// Some ABIs require it, e.g. 16 bytes with System V on `x86_64`, where
+    /// `system` crashes on a misaligned `movaps` otherwise. The chain is
+    /// assumed to start at a saved return address, which the ABI aligns
+    /// that way. [`Chain::call_win64`] always aligns the stack on 16 bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::config::ShellcoderBuilder;
+    /// use shellcoder::rop::{Catalog, Chain};
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let catalog = Catalog::from_text("\
+    ///     0x401016 : ret\n\
+    ///     0x401203 : pop rdi ; ret\n\
+    /// ");
+    /// let config = *ShellcoderBuilder::new().config();
+    ///
+    /// let mut chain = Chain::new(&catalog, &config).with_stack_alignment(16);
+    /// chain.call_sysv("system", 0x7ffff7c50d70, &[0x7ffff7dd8678])?;
+    /// assert_eq!(
+    ///     chain.listing().to_string(),
+    ///     "0x0000  0x0000000000401016  ret\n\
+    ///      0x0008  0x0000000000401203  pop rdi; ret\n\
+    ///      0x0010  0x00007ffff7dd8678    argument\n\
+    ///      0x0018  0x00007ffff7c50d70  system\n",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn with_stack_alignment(mut self, alignment: usize) -> Self {
+        self.stack_alignment = Some(alignment);
+        self
+    }
+
+    /// Returns the distance between the starts of two slots.
+    #[inline]
+    #[must_use]
+    pub fn stride(&self) -> usize {
+        let width = self.config.pointer_width().bytes();
+        self.stride.map_or(width, |stride| stride.max(width))
+    }
+
+    /// Appends a gadget.
+    ///
+    /// # Errors
+    ///
+    /// See [`Catalog::resolve`].
+    #[inline]
+    pub fn gadget(&mut self, instructions: &str) -> Result<&mut Self> {
+        let address = self.catalog.resolve(instructions)?;
+        self.slots
+            .push((address, Slot::Gadget(normalize(instructions))));
+        Ok(self)
+    }
+
+    /// Appends an argument.
+    #[inline]
+    pub fn arg(&mut self, value: u64) -> &mut Self {
+        self.slots.push((value, Slot::Argument));
+        self
+    }
+
+    /// Appends a named address, e.g. a function resolved from a
+    /// [`crate::symbols::Table`].
+    #[inline]
+    pub fn address(&mut self, name: impl Into<String>, address: u64) -> &mut Self {
+        self.slots.push((address, Slot::Address(name.into())));
+        self
+    }
+
+    /// Appends a call to a function, with arguments passed on the stack as
+    /// the `cdecl` calling convention expects them on 32-bit x86.
+    ///
+    /// The function returns to a gadget popping as many registers as there
+    /// are arguments, so that further calls can be chained. Without
+    /// arguments, it returns directly to the next slot.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::UnknownSymbol`]: the catalog has no gadget popping as many
+    /// registers as there are arguments, see [`Catalog::pops`], or aligning
+    /// the stack, see [`Chain::with_stack_alignment`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+    /// use shellcoder::rop::{Catalog, Chain};
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let catalog = Catalog::from_text("\
+    ///     0x0804901e : pop ebx ; ret\n\
+    ///     0x08049301 : pop esi ; pop edi ; ret\n\
+    /// ");
+    /// let config = *ShellcoderBuilder::new()
+    ///     .pointer_width(PointerWidth::Bits32)
+    ///     .config();
+    ///
+    /// let mut chain = Chain::new(&catalog, &config);
+    /// chain
+    ///     .call_cdecl("setuid", 0xf7c7_e1a0, &[0])?
+    ///     .call_cdecl("system", 0xf7c4_c8e0, &[0x0804_c030])?;
+    /// assert_eq!(
+    ///     chain.listing().to_string(),
+    ///     "0x0000  0xf7c7e1a0  setuid\n\
+    ///      0x0004  0x0804901e  pop ebx; ret\n\
+    ///      0x0008  0x00000000    argument\n\
+    ///      0x000c  0xf7c4c8e0  system\n\
+    ///      0x0010  0x0804901e  pop ebx; ret\n\
+    ///      0x0014  0x0804c030    argument\n",
+    /// );
+    /// assert!(chain.call_cdecl("execve", 0xf7c9_a0f0, &[0, 0, 0]).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn call_cdecl(
+        &mut self,
+        name: impl Into<String>,
+        function: u64,
+        args: &[u64],
+    ) -> Result<&mut Self> {
+        let cleanup = if args.is_empty() {
+            None
+        } else {
+            let (instructions, offset) = self.catalog.pops(args.len()).ok_or_else(|| {
+                let mut pops = "pop; ".repeat(args.len());
+                pops.push_str("ret");
+                Error::UnknownSymbol(pops)
+            })?;
+            Some(self.gadget_slot(instructions, offset)?)
+        };
+        if let Some(alignment) = self.stack_alignment {
+            let mut rets = self.alignment_slots(0, alignment)?;
+            self.slots.append(&mut rets);
+        }
+        self.address(name, function);
+        self.slots.extend(cleanup);
+        for &arg in args {
+            self.arg(arg);
+        }
+        Ok(self)
+    }
+
+    /// Appends a call to a function, with arguments passed in registers as
+    /// the System V calling convention expects them on `x86_64`, i.e. in
+    /// `rdi`, `rsi`, `rdx`, `rcx`, `r8` and `r9`.
+    ///
+    /// Each argument is popped by a gadget from the catalog, see
+    /// [`Catalog::pop`]. Other registers popped by the same gadget are
+    /// padded with zeroes.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::MaxLengthExceeded`]: there are more than 6 arguments.
+    ///  - [`Error::UnknownSymbol`]: the catalog has no gadget popping the
+    ///    register of an argument, or aligning the stack, see
+    ///    [`Chain::with_stack_alignment`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::config::ShellcoderBuilder;
+    /// use shellcoder::rop::{Catalog, Chain};
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let catalog = Catalog::from_text("\
+    ///     0x401203 : pop rdi ; ret\n\
+    ///     0x401201 : pop rsi ; pop r15 ; ret\n\
+    /// ");
+    /// let config = *ShellcoderBuilder::new().config();
+    ///
+    /// let mut chain = Chain::new(&catalog, &config);
+    /// chain.call_sysv("puts", 0x401030, &[0x404018])?;
+    /// chain.call_sysv("main", 0x401136, &[1, 0x7ffe_3000])?;
+    /// assert_eq!(
+    ///     chain.listing().to_string(),
+    ///     "0x0000  0x0000000000401203  pop rdi; ret\n\
+    ///      0x0008  0x0000000000404018    argument\n\
+    ///      0x0010  0x0000000000401030  puts\n\
+    ///      0x0018  0x0000000000401203  pop rdi; ret\n\
+    ///      0x0020  0x0000000000000001    argument\n\
+    ///      0x0028  0x0000000000401201  pop rsi; pop r15; ret\n\
+    ///      0x0030  0x000000007ffe3000    argument\n\
+    ///      0x0038  0x0000000000000000    padding\n\
+    ///      0x0040  0x0000000000401136  main\n",
+    /// );
+    /// assert!(chain.call_sysv("read", 0x401040, &[0, 0x404100, 0x100]).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn call_sysv(
+        &mut self,
+        name: impl Into<String>,
+        function: u64,
+        args: &[u64],
+    ) -> Result<&mut Self> {
+        let mut slots = self.register_slots(args, &SYSV_REGISTERS)?;
+        if let Some(alignment) = self.stack_alignment {
+            let rets = self.alignment_slots(slots.len(), alignment)?;
+            slots.splice(0..0, rets);
+        }
+        self.slots.append(&mut slots);
+        Ok(self.address(name, function))
+    }
+
+    /// Appends a call to a function, with arguments passed in registers as
+    /// the Windows x64 calling convention expects them, i.e. in `rcx`,
+    /// `rdx`, `r8` and `r9`.
+    ///
+    /// Each argument is popped by a gadget from the catalog, see
+    /// [`Catalog::pop`]. The function returns to a gadget skipping the
+    /// 0x20-byte shadow space it owns above its return address, i.e.
+    /// popping 4 registers or `add rsp, 0x20; ret`, so that further calls
+    /// can be chained.
+    ///
+    /// The stack must be 16-byte aligned when the function is entered, as
+    /// if it had been called. Assuming that the chain starts at a saved
+    /// return address, which the ABI aligns that way, a `ret` gadget is
+    /// inserted first when the function would be misaligned.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::MaxLengthExceeded`]: there are more than 4 arguments.
+    ///  - [`Error::UnknownSymbol`]: the catalog has no gadget popping the
+    ///    register of an argument, skipping the shadow space or aligning
+    ///    the stack.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::config::ShellcoderBuilder;
+    /// use shellcoder::rop::{Catalog, Chain};
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let catalog = Catalog::from_text("\
+    ///     0x140001010: pop rcx; ret;\n\
+    ///     0x140001020: add rsp, 0x20; ret;\n\
+    ///     0x140001030: ret;\n\
+    /// ");
+    /// let config = *ShellcoderBuilder::new().config();
+    ///
+    /// let mut chain = Chain::new(&catalog, &config);
+    /// chain.call_win64("WinExec", 0x7ffb_2d5e_1000, &[0x1_4000_3000])?;
+    /// assert_eq!(
+    ///     chain.listing().to_string(),
+    ///     "0x0000  0x0000000140001030  ret\n\
+    ///      0x0008  0x0000000140001010  pop rcx; ret\n\
+    ///      0x0010  0x0000000140003000    argument\n\
+    ///      0x0018  0x00007ffb2d5e1000  WinExec\n\
+    ///      0x0020  0x0000000140001020  add rsp, 0x20; ret\n\
+    ///      0x0028  0x0000000000000000    padding\n\
+    ///      0x0030  0x0000000000000000    padding\n\
+    ///      0x0038  0x0000000000000000    padding\n\
+    ///      0x0040  0x0000000000000000    padding\n",
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn call_win64(
+        &mut self,
+        name: impl Into<String>,
+        function: u64,
+        args: &[u64],
+    ) -> Result<&mut Self> {
+        let mut slots = self.register_slots(args, &WIN64_REGISTERS)?;
+        let (instructions, offset) = self
+            .catalog
+            .pops(WIN64_SHADOW_SLOTS)
+            .or_else(|| {
+                self.catalog
+                    .offset(WIN64_SKIP_SHADOW)
+                    .map(|offset| (WIN64_SKIP_SHADOW, offset))
+            })
+            .ok_or_else(|| Error::UnknownSymbol(WIN64_SKIP_SHADOW.to_owned()))?;
+        let cleanup = self.gadget_slot(instructions, offset)?;
+
+        let rets = self.alignment_slots(slots.len(), STACK_ALIGNMENT)?;
+        slots.splice(0..0, rets);
+
+        self.slots.append(&mut slots);
+        self.address(name, function);
+        self.slots.push(cleanup);
+        for _ in 0..WIN64_SHADOW_SLOTS {
+            self.pad(0);
+        }
+        Ok(self)
+    }
+
+    /// Appends a padding slot.
+    #[inline]
+    pub fn pad(&mut self, value: u64) -> &mut Self {
+        self.slots.push((value, Slot::Padding));
+        self
+    }
+
+    /// Returns the slots popping arguments into registers.
+    fn register_slots(&self, args: &[u64], registers: &[&str]) -> Result<Vec<(u64, Slot)>> {
+        if args.len() > registers.len() {
+            return Err(Error::MaxLengthExceeded {
+                limit: registers.len(),
+                attempted: args.len(),
+            });
+        }
+        let mut slots = Vec::new();
+        for (&arg, register) in args.iter().zip(registers) {
+            let (instructions, offset, others) = self
+                .catalog
+                .pop(register)
+                .ok_or_else(|| Error::UnknownSymbol(format!("pop {register}; ret")))?;
+            slots.push(self.gadget_slot(instructions, offset)?);
+            slots.push((arg, Slot::Argument));
+            slots.resize(slots.len().saturating_add(others), (0, Slot::Padding));
+        }
+        Ok(slots)
+    }
+
+    /// Returns the slots of `ret` gadgets to insert before `count` slots
+    /// and a function, so that the function is entered with a stack aligned
+    /// on `alignment` bytes.
+    fn alignment_slots(&self, count: usize, alignment: usize) -> Result<Vec<(u64, Slot)>> {
+        // The function is entered with the stack pointer on the slot that
+        // follows it, which must be aligned.
+        let stride = self.stride();
+        let mut entry = self
+            .len()
+            .saturating_add(count)
+            .saturating_add(1)
+            .saturating_mul(stride);
+        let mut slots = Vec::new();
+        while entry.checked_rem(alignment).map_or(false, |rem| rem != 0) {
+            let ret = self
+                .catalog
+                .offset("ret")
+                .ok_or_else(|| Error::UnknownSymbol("ret".to_owned()))?;
+            slots.push(self.gadget_slot("ret", ret)?);
+            entry = entry.saturating_add(stride);
+        }
+        Ok(slots)
+    }
+
+    /// Returns the slot of a gadget of the catalog.
+    fn gadget_slot(&self, instructions: &str, offset: u64) -> Result<(u64, Slot)> {
+        let address = offset
+            .checked_add(self.catalog.base())
+            .ok_or(Error::IntegerOverflow)?;
+        Ok((address, Slot::Gadget(instructions.to_owned())))
+    }
+
+    /// Returns the values of the slots, and their meaning.
+    #[inline]
+    #[must_use]
+    pub fn slots(&self) -> &[(u64, Slot)] {
+        &self.slots
+    }
+
+    /// Returns the number of slots.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns `true` if there is no slot.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Returns a human-readable listing of the slots: their offset, their
+    /// value and their meaning.
+    #[inline]
+    #[must_use]
+    pub const fn listing(&self) -> Listing<'_, 'catalog> {
+        Listing { chain: self }
+    }
+
+    /// Returns the bytes of the chain.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: a value does not fit in a pointer.
+    #[inline]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let gap = self
+            .stride()
+            .saturating_sub(self.config.pointer_width().bytes());
+        let mut bytes = Vec::with_capacity(self.size().unwrap_or_default());
+        for (index, &(value, _)) in self.slots.iter().enumerate() {
+            if index != 0 {
+                bytes.resize(bytes.len().saturating_add(gap), 0);
+            }
+            push_ptr(&mut bytes, value, &self.config)?;
+        }
+        Ok(bytes)
+    }
+}
+
+impl Op for Chain<'_> {
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let bytes = self.to_bytes()?;
+        stream.write_all(&bytes)?;
+        Ok(bytes.len())
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        copy_to(&self.to_bytes()?, out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        let gaps = self
+            .slots
+            .len()
+            .saturating_sub(1)
+            .checked_mul(self.stride())?;
+        if self.slots.is_empty() {
+            Some(0)
+        } else {
+            gaps.checked_add(self.config.pointer_width().bytes())
+        }
+    }
+}
+
+/// A human-readable listing of a [`Chain`], see [`Chain::listing`].
+#[derive(Clone, Copy, Debug)]
+pub struct Listing<'chain, 'catalog> {
+    /// The chain.
+    chain: &'chain Chain<'catalog>,
+}
+
+impl fmt::Display for Listing<'_, '_> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.chain.config.pointer_width().bytes();
+        // Two hexadecimal digits per byte, and the `0x` prefix.
+        let digits = width.saturating_mul(2).saturating_add(2);
+        let mut offset: usize = 0;
+        for (value, slot) in &self.chain.slots {
+            write!(fmt, "{offset:#06x}  {value:#0digits$x}  ")?;
+            match slot {
+                Slot::Gadget(instructions) => writeln!(fmt, "{instructions}")?,
+                Slot::Argument => writeln!(fmt, "  argument")?,
+                Slot::Address(name) => writeln!(fmt, "{name}")?,
+                Slot::Padding => writeln!(fmt, "  padding")?,
+            }
+            offset = offset.saturating_add(self.chain.stride());
+        }
+        Ok(())
+    }
+}
+
+/// A classic saved-frame overwrite: padding up to the saved frame pointer,
+/// a fake frame pointer, a return address and arguments.
+///
+/// Arguments are laid out as the calling convention of the target expects
+/// them when the function at the return address is entered:
+///
+///  - with 32-bit (or narrower) pointers, as with `cdecl`, they follow the
+///    return address of that function, see
+///    [`SavedFrame::with_continuation`];
+///  - with 64-bit pointers, arguments are passed in registers, thus they
+///    directly follow the return address, to be popped by the gadget it
+///    points to, e.g. `pop rdi; ret`.
+///
+/// This way, the same exploit source serves 32-bit and 64-bit builds of a
+/// target.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+/// use shellcoder::rop::SavedFrame;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let config = *ShellcoderBuilder::new()
+///     .pointer_width(PointerWidth::Bits32)
+///     .config();
+/// let frame = SavedFrame::new(&config, 6)
+///     .with_return_address(0x0804_9186)
+///     .with_args(vec![0x0804_c030]);
+/// assert_eq!(frame.return_address_offset(), 10);
+/// assert_eq!(frame.args_offset(), 18);
+/// assert_eq!(
+///     frame.to_bytes()?,
+///     b"AAAAAAAAAA\x86\x91\x04\x08\0\0\0\0\x30\xc0\x04\x08"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SavedFrame {
+    /// Configuration, for the width and the endianness of pointers.
+    config: Config,
+
+    /// Offset of the saved frame pointer.
+    offset: usize,
+
+    /// Byte that fills the padding.
+    filler: u8,
+
+    /// Fake frame pointer, if any.
+    frame_pointer: Option<u64>,
+
+    /// Return address.
+    return_address: u64,
+
+    /// Return address of the function at the return address, for targets
+    /// passing arguments on the stack.
+    continuation: u64,
+
+    /// Arguments.
+    args: Vec<u64>,
+}
+
+impl SavedFrame {
+    /// Instantiates a new [`SavedFrame`], whose saved frame pointer is at
+    /// `offset` from the start of the overflowed buffer.
+    ///
+    /// The padding is filled with `A`, as is the frame pointer until
+    /// [`SavedFrame::with_frame_pointer`] is called.
+    #[inline]
+    #[must_use]
+    pub const fn new(config: &Config, offset: usize) -> Self {
+        Self {
+            config: *config,
+            offset,
+            filler: b'A',
+            frame_pointer: None,
+            return_address: 0,
+            continuation: 0,
+            args: Vec::new(),
+        }
+    }
+
+    /// Sets the byte that fills the padding.
+    #[inline]
+    #[must_use]
+    pub const fn with_filler(mut self, filler: u8) -> Self {
+        self.filler = filler;
+        self
+    }
+
+    /// Sets the fake frame pointer, e.g. for a stack pivot through
+    /// `leave; ret`.
+    #[inline]
+    #[must_use]
+    pub const fn with_frame_pointer(mut self, frame_pointer: u64) -> Self {
+        self.frame_pointer = Some(frame_pointer);
+        self
+    }
+
+    /// Sets the return address.
+    #[inline]
+    #[must_use]
+    pub const fn with_return_address(mut self, return_address: u64) -> Self {
+        self.return_address = return_address;
+        self
+    }
+
+    /// Sets the address the function at the return address returns to,
+    /// for targets passing arguments on the stack. It defaults to 0, and is
+    /// ignored with 64-bit pointers.
+    #[inline]
+    #[must_use]
+    pub const fn with_continuation(mut self, continuation: u64) -> Self {
+        self.continuation = continuation;
+        self
+    }
+
+    /// Sets the arguments.
+    #[inline]
+    #[must_use]
+    pub fn with_args(mut self, args: Vec<u64>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Returns the offset of the saved frame pointer.
+    #[inline]
+    #[must_use]
+    pub const fn frame_pointer_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the offset of the return address.
+    #[inline]
+    #[must_use]
+    pub const fn return_address_offset(&self) -> usize {
+        self.offset
+            .saturating_add(self.config.pointer_width().bytes())
+    }
+
+    /// Returns the offset of the first argument.
+    #[inline]
+    #[must_use]
+    pub const fn args_offset(&self) -> usize {
+        let width = self.config.pointer_width().bytes();
+        let args_offset = self.return_address_offset().saturating_add(width);
+        if self.has_continuation() {
+            args_offset.saturating_add(width)
+        } else {
+            args_offset
+        }
+    }
+
+    /// Returns the bytes of the overwrite.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: a value does not fit in a pointer.
+    #[inline]
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let width = self.config.pointer_width().bytes();
+        let mut bytes = Vec::with_capacity(self.size().unwrap_or_default());
+        bytes.resize(self.offset, self.filler);
+        if let Some(frame_pointer) = self.frame_pointer {
+            push_ptr(&mut bytes, frame_pointer, &self.config)?;
+        } else {
+            bytes.resize(self.offset.saturating_add(width), self.filler);
+        }
+        push_ptr(&mut bytes, self.return_address, &self.config)?;
+        if self.has_continuation() {
+            push_ptr(&mut bytes, self.continuation, &self.config)?;
+        }
+        for &arg in &self.args {
+            push_ptr(&mut bytes, arg, &self.config)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Returns `true` if arguments are passed on the stack, after the
+    /// return address of the function.
+    const fn has_continuation(&self) -> bool {
+        !matches!(self.config.pointer_width(), PointerWidth::Bits64)
+    }
+}
+
+impl Op for SavedFrame {
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let bytes = self.to_bytes()?;
+        stream.write_all(&bytes)?;
+        Ok(bytes.len())
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        copy_to(&self.to_bytes()?, out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.args
+            .len()
+            .checked_mul(self.config.pointer_width().bytes())?
+            .checked_add(self.args_offset())
+    }
+}
+
+/// Registers of the arguments of the System V calling convention on
+/// `x86_64`, in order.
+const SYSV_REGISTERS: [&str; 6] = ["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+
+/// Registers of the arguments of the Windows x64 calling convention, in
+/// order.
+const WIN64_REGISTERS: [&str; 4] = ["rcx", "rdx", "r8", "r9"];
+
+/// Number of slots of the shadow space of the Windows x64 calling
+/// convention, i.e. 0x20 bytes.
+const WIN64_SHADOW_SLOTS: usize = 4;
+
+/// Gadget skipping the shadow space of the Windows x64 calling convention.
+const WIN64_SKIP_SHADOW: &str = "add rsp, 0x20; ret";
+
+/// Alignment of the stack pointer when a function is called, on `x86_64`.
+const STACK_ALIGNMENT: usize = 16;
+
+/// Returns the registers popped by a gadget made of `pop` instructions
+/// followed by `ret`, if it is such a gadget.
+fn popped(instructions: &str) -> Option<Vec<&str>> {
+    let pops = if instructions == "ret" {
+        ""
+    } else {
+        instructions.strip_suffix("; ret")?
+    };
+    pops.split("; ")
+        .filter(|pop| !pop.is_empty())
+        .map(|pop| {
+            pop.strip_prefix("pop ")
+                .filter(|operand| !operand.contains(|chr: char| chr.is_whitespace() || chr == '['))
+        })
+        .collect()
+}
+
+/// Appends a pointer, using the endianness and the pointer width of a
+/// configuration.
+fn push_ptr(bytes: &mut Vec<u8>, value: u64, config: &Config) -> Result<()> {
+    let endianness = config.endianness();
+    match config.pointer_width() {
+        PointerWidth::Bits16 => bytes.extend(pack::p16_with(u16::try_from(value)?, endianness)),
+        PointerWidth::Bits32 => bytes.extend(pack::p32_with(u32::try_from(value)?, endianness)),
+        PointerWidth::Bits64 => bytes.extend(pack::p64_with(value, endianness)),
+    }
+    Ok(())
+}
+
+/// Copies bytes to the start of a buffer.
+fn copy_to(bytes: &[u8], mut out: impl AsMut<[u8]>) -> Result<usize> {
+    out.as_mut()
+        .get_mut(..bytes.len())
+        .ok_or_else(|| Error::buffer_too_small(bytes.len()))?
+        .copy_from_slice(bytes);
+    Ok(bytes.len())
+}
+
+/// Parses a line of `ROPgadget` or `ropper` output into an offset and
+/// instructions.
+#[allow(clippy::single_call_fn)]
+fn parse_line(line: &str) -> Option<(u64, &str)> {
+    let (hex, instructions) = line.trim().split_once(':')?;
+    let offset = u64::from_str_radix(hex.trim().strip_prefix("0x")?, 16).ok()?;
+    (!instructions.trim().is_empty()).then(|| (offset, instructions))
+}
+
+/// Normalizes instructions: lowercase, single spaces, `, ` between operands
+/// and `; ` between instructions, without a trailing `;`.
+fn normalize(instructions: &str) -> String {
+    instructions
+        .split(';')
+        .map(|instruction| {
+            instruction
+                .split(',')
+                .map(|operand| {
+                    operand
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                        .to_lowercase()
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .filter(|instruction| !instruction.is_empty())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::indexing_slicing,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::shadow_reuse,
+    clippy::shadow_unrelated
+)]
+mod tests {
+    use crate::alloc::Shellcoder;
+    use crate::config::{PointerWidth, ShellcoderBuilder};
+    use crate::error::ErrorKind;
+    use crate::ops::Endianness;
+    use crate::rop::{Catalog, Chain, SavedFrame, Slot};
+    use crate::Shellcoder as _;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_catalog() -> Result<()> {
+        let catalog = Catalog::from_text(
+            "[INFO] Load gadgets for section: LOAD\n\
+             [LOAD] loading... 100%\n\
+             \n\
+             Gadgets\n\
+             =======\n\
+             \n\
+             0x0000000000001172: mov rax,  qword ptr [rbp - 8]; ret;\n\
+             0x000000000000116a: pop rdi; ret;\n\
+             0x0000000000001016: ret;\n\
+             0x0000000000001010: ret;\n\
+             \n\
+             4 gadgets found\n",
+        )
+        .with_base(0x5555_5555_4000);
+        assert_eq!(catalog.len(), 3);
+        assert_eq!(catalog.offset("RET"), Some(0x1010));
+        assert_eq!(
+            catalog.resolve("mov rax,qword ptr [rbp - 8] ; ret")?,
+            0x5555_5555_5172
+        );
+        assert!(catalog
+            .iter()
+            .map(|(instructions, _offset)| instructions)
+            .eq(["mov rax, qword ptr [rbp - 8]; ret", "pop rdi; ret", "ret"]));
+        assert!(matches!(
+            catalog.resolve("pop rsi ;ret"),
+            Err(Error::UnknownSymbol(gadget)) if gadget == "pop rsi; ret"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_chain() -> Result<()> {
+        let mut catalog = Catalog::new();
+        catalog
+            .insert("pop rdi ; ret", 0x1_0000)
+            .insert("ret", 0xff);
+        let config = *ShellcoderBuilder::new()
+            .endianness(Endianness::Big)
+            .pointer_width(PointerWidth::Bits16)
+            .config();
+
+        let mut chain = Chain::new(&catalog, &config);
+        assert!(chain.is_empty());
+        assert!(chain.gadget("pop rsi; ret").is_err());
+        chain.gadget("ret")?.pad(0x4141).arg(0x1234);
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.slots()[1], (0x4141, Slot::Padding));
+        assert_eq!(chain.to_bytes()?, b"\0\xffAA\x12\x34");
+        assert_eq!(
+            chain.listing().to_string(),
+            "0x0000  0x00ff  ret\n\
+             0x0002  0x4141    padding\n\
+             0x0004  0x1234    argument\n"
+        );
+
+        let mut shellcoder = Shellcoder::new();
+        shellcoder.push(&chain)?;
+        assert_eq!(shellcoder.as_bytes(), b"\0\xffAA\x12\x34");
+
+        chain.gadget("pop rdi; ret")?;
+        assert_eq!(chain.size(), Some(8));
+        assert!(matches!(chain.to_bytes(), Err(Error::IntegerOverflow)));
+        assert!(shellcoder.push(&chain).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispatch_table() -> Result<()> {
+        let catalog = Catalog::new();
+        let config = *ShellcoderBuilder::new()
+            .pointer_width(PointerWidth::Bits32)
+            .config();
+
+        let table = Chain::new(&catalog, &config).with_stride(2);
+        assert_eq!(table.stride(), 4);
+        assert_eq!(table.size(), Some(0));
+        let mut table = table.with_stride(6);
+        table.address("a", 0x4141_4141).address("b", 0x4242_4242);
+        assert_eq!(table.size(), Some(10));
+        assert_eq!(table.to_bytes()?, b"AAAA\0\0BBBB");
+        assert_eq!(
+            table.listing().to_string(),
+            "0x0000  0x41414141  a\n\
+             0x0006  0x42424242  b\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_saved_frame() -> Result<()> {
+        let config = *ShellcoderBuilder::new().config();
+        let frame = SavedFrame::new(&config, 2)
+            .with_filler(0)
+            .with_frame_pointer(0x4242)
+            .with_return_address(0x40_116a)
+            .with_continuation(0xdead)
+            .with_args(vec![1]);
+        assert_eq!(frame.return_address_offset(), 10);
+        assert_eq!(frame.args_offset(), 18);
+        assert_eq!(frame.size(), Some(26));
+        assert_eq!(
+            frame.to_bytes()?,
+            b"\0\0BB\0\0\0\0\0\0\x6a\x11\x40\0\0\0\0\0\x01\0\0\0\0\0\0\0"
+        );
+
+        let config = *ShellcoderBuilder::new()
+            .pointer_width(PointerWidth::Bits16)
+            .config();
+        let frame = SavedFrame::new(&config, 0).with_return_address(0x1_0000);
+        assert_eq!(frame.size(), Some(6));
+        let mut shellcoder = Shellcoder::new();
+        assert!(matches!(
+            shellcoder.push(&frame).map_err(|error| error.kind()),
+            Err(ErrorKind::IntegerOverflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_cdecl() -> Result<()> {
+        let catalog = Catalog::from_text(
+            "0x20: pop eax; pop dword ptr [ecx]; ret;\n\
+             0x30: pop ebp; pop esi; ret;\n\
+             0x10: pop ebx; pop esi; ret;\n\
+             0x40: pop ebx; pop esi; pop edi; jmp eax;\n",
+        )
+        .with_base(0x100);
+        assert_eq!(catalog.pops(2), Some(("pop ebx; pop esi; ret", 0x10)));
+        assert_eq!(catalog.pops(0), None);
+        assert_eq!(catalog.pops(3), None);
+
+        let config = *ShellcoderBuilder::new()
+            .pointer_width(PointerWidth::Bits32)
+            .config();
+        let mut chain = Chain::new(&catalog, &config);
+        chain
+            .call_cdecl("f", 0x1000, &[])?
+            .call_cdecl("g", 0x2000, &[1, 2])?;
+        assert_eq!(
+            chain.slots(),
+            [
+                (0x1000, Slot::Address("f".to_owned())),
+                (0x2000, Slot::Address("g".to_owned())),
+                (0x110, Slot::Gadget("pop ebx; pop esi; ret".to_owned())),
+                (1, Slot::Argument),
+                (2, Slot::Argument),
+            ]
+        );
+        assert!(matches!(
+            chain.call_cdecl("h", 0x3000, &[1]),
+            Err(Error::UnknownSymbol(pops)) if pops == "pop; ret"
+        ));
+        assert_eq!(chain.len(), 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_sysv() -> Result<()> {
+        let catalog = Catalog::from_text(
+            "0x10: pop rdi; pop rbp; ret;\n\
+             0x20: pop rdi; ret;\n\
+             0x30: pop rdi; pop rsi; ret;\n\
+             0x40: pop rsi; pop r15; ret;\n\
+             0x50: pop rdx; pop rbx; pop rbp; ret;\n\
+             0x60: pop rcx; mov eax, 1; ret;\n",
+        );
+        assert_eq!(catalog.pop(" RDI"), Some(("pop rdi; ret", 0x20, 0)));
+        assert_eq!(catalog.pop("rsi"), Some(("pop rsi; pop r15; ret", 0x40, 1)));
+        assert_eq!(catalog.pop("rcx"), None);
+
+        let config = *ShellcoderBuilder::new().config();
+        let mut chain = Chain::new(&catalog, &config);
+        chain.call_sysv("f", 0x1000, &[])?;
+        chain.call_sysv("g", 0x2000, &[1, 2, 3])?;
+        assert_eq!(chain.len(), 11);
+        assert_eq!(chain.slots()[7], (3, Slot::Argument));
+        assert_eq!(chain.slots()[10], (0x2000, Slot::Address("g".to_owned())));
+
+        assert!(matches!(
+            chain.call_sysv("h", 0x3000, &[1, 2, 3, 4]),
+            Err(Error::UnknownSymbol(gadget)) if gadget == "pop rcx; ret"
+        ));
+        assert!(matches!(
+            chain.call_sysv("h", 0x3000, &[0; 7]),
+            Err(Error::MaxLengthExceeded {
+                limit: 6,
+                attempted: 7
+            })
+        ));
+        assert_eq!(chain.len(), 11);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stack_alignment() -> Result<()> {
+        let catalog = Catalog::from_text(
+            "0x10: pop rdi; ret;\n\
+             0x20: ret;\n",
+        );
+        let config = *ShellcoderBuilder::new().config();
+        let mut chain = Chain::new(&catalog, &config).with_stack_alignment(16);
+        chain
+            .call_sysv("f", 0x1000, &[1])?
+            .call_sysv("g", 0x2000, &[])?
+            .call_sysv("h", 0x3000, &[])?;
+        // Each function returns to the next one one slot too far.
+        assert_eq!(chain.len(), 8);
+        assert_eq!(chain.slots()[0], (0x20, Slot::Gadget("ret".to_owned())));
+        assert_eq!(chain.slots()[3], (0x1000, Slot::Address("f".to_owned())));
+        assert_eq!(chain.slots()[5], (0x2000, Slot::Address("g".to_owned())));
+        assert_eq!(chain.slots()[7], (0x3000, Slot::Address("h".to_owned())));
+
+        let config = *ShellcoderBuilder::new()
+            .pointer_width(PointerWidth::Bits32)
+            .config();
+        let mut chain = Chain::new(&catalog, &config).with_stack_alignment(16);
+        chain
+            .call_cdecl("f", 0x1000, &[1])?
+            .call_cdecl("g", 0x2000, &[])?;
+        assert_eq!(
+            chain.slots(),
+            [
+                (0x20, Slot::Gadget("ret".to_owned())),
+                (0x20, Slot::Gadget("ret".to_owned())),
+                (0x20, Slot::Gadget("ret".to_owned())),
+                (0x1000, Slot::Address("f".to_owned())),
+                (0x10, Slot::Gadget("pop rdi; ret".to_owned())),
+                (1, Slot::Argument),
+                (0x20, Slot::Gadget("ret".to_owned())),
+                (0x2000, Slot::Address("g".to_owned())),
+            ]
+        );
+
+        let catalog = Catalog::new();
+        let mut chain = Chain::new(&catalog, &config).with_stack_alignment(8);
+        assert!(matches!(
+            chain.call_cdecl("f", 0x1000, &[]),
+            Err(Error::UnknownSymbol(gadget)) if gadget == "ret"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_call_win64() -> Result<()> {
+        let catalog = Catalog::from_text(
+            "0x10: pop rcx; ret;\n\
+             0x20: pop rdx; pop r12; ret;\n\
+             0x30: pop rax; pop rbx; pop rbp; pop rdi; ret;\n",
+        );
+        let config = *ShellcoderBuilder::new().config();
+        let mut chain = Chain::new(&catalog, &config);
+        chain.call_win64("f", 0x1000, &[1, 2])?;
+        assert_eq!(chain.len(), 11);
+        assert_eq!(chain.slots()[5], (0x1000, Slot::Address("f".to_owned())));
+        assert_eq!(
+            chain.slots()[6],
+            (
+                0x30,
+                Slot::Gadget("pop rax; pop rbx; pop rbp; pop rdi; ret".to_owned())
+            )
+        );
+
+        // The next function would be misaligned, and there is no `ret`.
+        assert!(matches!(
+            chain.call_win64("g", 0x2000, &[1, 2]),
+            Err(Error::UnknownSymbol(gadget)) if gadget == "ret"
+        ));
+        chain.call_win64("g", 0x2000, &[1])?;
+        assert_eq!(chain.slots()[13], (0x2000, Slot::Address("g".to_owned())));
+        assert!(matches!(
+            chain.call_win64("h", 0x3000, &[0; 5]),
+            Err(Error::MaxLengthExceeded {
+                limit: 4,
+                attempted: 5
+            })
+        ));
+
+        let catalog = Catalog::new();
+        let mut chain = Chain::new(&catalog, &config);
+        assert!(matches!(
+            chain.call_win64("f", 0x1000, &[]),
+            Err(Error::UnknownSymbol(gadget)) if gadget == "add rsp, 0x20; ret"
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+//! Multi-stage payloads.
+//!
+//! A [`Stages`] is a sequence of [`Stage`], e.g. a small stage0 that reads
+//! a bigger stage1 from the wire. Each stage is encoded using a
+//! [`Format`], then framed, i.e. preceded by an optional marker and an
+//! optional length prefix. Stages are then either concatenated into a
+//! single blob, or kept as separate [`Artifact`]s. Both share the same
+//! metadata.
+
+use core::fmt;
+
+use crate::analysis::{self, Digest};
+use crate::format::Format;
+use crate::ops::Endianness;
+use crate::prelude::*;
+
+/// A stage of a multi-stage payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stage {
+    /// Name of the stage.
+    name: String,
+
+    /// Bytes of the stage, before encoding and framing.
+    bytes: Vec<u8>,
+
+    /// Encoding of the stage.
+    format: Format,
+
+    /// Marker preceding the stage, if any.
+    marker: Option<Vec<u8>>,
+
+    /// Endianness of the length prefix, if any.
+    length_prefix: Option<Endianness>,
+}
+
+impl Stage {
+    /// Instantiates a new [`Stage`] from its bytes, without encoding nor
+    /// framing.
+    #[inline]
+    #[must_use]
+    pub fn new(name: impl Into<String>, bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            name: name.into(),
+            bytes: bytes.into(),
+            format: Format::Raw,
+            marker: None,
+            length_prefix: None,
+        }
+    }
+
+    /// Instantiates a new [`Stage`] from the bytes written by an operation,
+    /// e.g. a [`crate::plan::Plan`] applied to a shellcoder, or a
+    /// compressed or encrypted operation.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by the operation.
+    #[inline]
+    pub fn from_op(name: impl Into<String>, op: &impl Op) -> Result<Self> {
+        let mut bytes = Vec::with_capacity(op.size().unwrap_or_default());
+        op.write_to_io(&mut bytes)?;
+        Ok(Self::new(name, bytes))
+    }
+
+    /// Sets the encoding of the stage, e.g. [`Format::Base64`] for a stage
+    /// that goes through a text-only channel.
+    #[inline]
+    #[must_use]
+    pub const fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Precedes the stage with a marker, e.g. an egg that stage0 looks for.
+    #[inline]
+    #[must_use]
+    pub fn with_marker(mut self, marker: impl Into<Vec<u8>>) -> Self {
+        self.marker = Some(marker.into());
+        self
+    }
+
+    /// Precedes the encoded stage with its length, as a 32-bit integer. The
+    /// length prefix follows the marker, if any.
+    #[inline]
+    #[must_use]
+    pub const fn with_length_prefix(mut self, endianness: Endianness) -> Self {
+        self.length_prefix = Some(endianness);
+        self
+    }
+
+    /// Returns the name of the stage.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the bytes of the stage, before encoding and framing.
+    #[inline]
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the encoding of the stage.
+    #[inline]
+    #[must_use]
+    pub const fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Returns the encoded stage, framed by its marker and length prefix.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::IntegerOverflow`]: the length of the encoded stage does
+    ///    not fit in 32 bits.
+    ///  - Any error returned by [`Format::write_to_io`].
+    #[inline]
+    pub fn framed(&self) -> Result<Vec<u8>> {
+        let mut encoded = Vec::with_capacity(self.bytes.len());
+        self.format.write_to_io(&self.bytes, &mut encoded)?;
+        let mut framed = self.marker.clone().unwrap_or_default();
+        match self.length_prefix {
+            Some(Endianness::Little) => {
+                framed.extend_from_slice(&u32::try_from(encoded.len())?.to_le_bytes());
+            }
+            Some(Endianness::Big) => {
+                framed.extend_from_slice(&u32::try_from(encoded.len())?.to_be_bytes());
+            }
+            None => {}
+        }
+        framed.extend_from_slice(&encoded);
+        Ok(framed)
+    }
+}
+
+/// A stage that has been encoded and framed, see [`Stages::artifacts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Artifact {
+    /// Name of the stage.
+    name: String,
+
+    /// Offset of the stage in the concatenated blob.
+    offset: usize,
+
+    /// Bytes of the framed stage.
+    bytes: Vec<u8>,
+
+    /// Hashes of the framed stage.
+    digest: Digest,
+}
+
+impl Artifact {
+    /// Returns the name of the stage.
+    #[inline]
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the offset of the stage in the concatenated blob.
+    #[inline]
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the bytes of the framed stage.
+    #[inline]
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consumes the artifact and returns the bytes of the framed stage.
+    #[inline]
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the hashes of the framed stage.
+    #[inline]
+    #[must_use]
+    pub const fn digest(&self) -> &Digest {
+        &self.digest
+    }
+}
+
+/// [`Artifact`] is displayed as its range in the concatenated blob, its
+/// name and its SHA-256.
+impl fmt::Display for Artifact {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{:#06x}..{:#06x}  {}  {}",
+            self.offset,
+            self.offset.saturating_add(self.bytes.len()),
+            self.name,
+            self.digest
+        )
+    }
+}
+
+/// A multi-stage payload, i.e. a sequence of stages.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::ops::{Endianness, Fill};
+/// use shellcoder::stages::{Stage, Stages};
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut stages = Stages::new();
+/// stages
+///     .push(Stage::new("stage0", b"\x90\x90\xcc"))
+///     .push(
+///         Stage::from_op("stage1", &Fill::new(4, b'A'))?
+///             .with_marker(b"w00t")
+///             .with_length_prefix(Endianness::Little),
+///     );
+///
+/// let blob = stages.blob()?;
+/// assert_eq!(blob, b"\x90\x90\xccw00t\x04\0\0\0AAAA");
+///
+/// let artifacts = stages.artifacts()?;
+/// assert_eq!(artifacts[1].offset(), 3);
+/// assert_eq!(artifacts[1].bytes(), &blob[3..]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stages {
+    /// Stages, in order.
+    stages: Vec<Stage>,
+}
+
+impl Stages {
+    /// Instantiates a new empty [`Stages`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a stage.
+    #[inline]
+    pub fn push(&mut self, stage: Stage) -> &mut Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Returns the stages.
+    #[inline]
+    #[must_use]
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// Encodes and frames each stage, see [`Stage::framed`].
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by [`Stage::framed`].
+    #[inline]
+    pub fn artifacts(&self) -> Result<Vec<Artifact>> {
+        let mut offset: usize = 0;
+        self.stages
+            .iter()
+            .map(|stage| {
+                let bytes = stage.framed()?;
+                let artifact = Artifact {
+                    name: stage.name.clone(),
+                    offset,
+                    digest: analysis::digest(&bytes),
+                    bytes,
+                };
+                offset = offset.saturating_add(artifact.bytes.len());
+                Ok(artifact)
+            })
+            .collect()
+    }
+
+    /// Encodes and frames each stage, then concatenates them.
+    ///
+    /// # Errors
+    ///
+    /// Any error returned by [`Stage::framed`].
+    #[inline]
+    pub fn blob(&self) -> Result<Vec<u8>> {
+        self.stages.iter().try_fold(Vec::new(), |mut blob, stage| {
+            blob.extend_from_slice(&stage.framed()?);
+            Ok(blob)
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::indexing_slicing,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix
+)]
+mod tests {
+    use crate::format::Format;
+    use crate::ops::Endianness;
+    use crate::stages::{Stage, Stages};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_stages() -> Result<()> {
+        let mut stages = Stages::new();
+        stages
+            .push(Stage::new("stage0", b"AB".as_slice()).with_length_prefix(Endianness::Big))
+            .push(
+                Stage::new("stage1", b"/bin/sh".as_slice())
+                    .with_format(Format::Base64)
+                    .with_marker(b"\xde\xad".as_slice()),
+            );
+        assert_eq!(stages.stages()[1].bytes(), b"/bin/sh");
+
+        let blob = stages.blob()?;
+        assert_eq!(blob, b"\0\0\0\x02AB\xde\xadL2Jpbi9zaA==\n");
+
+        let artifacts = stages.artifacts()?;
+        assert_eq!(
+            artifacts
+                .iter()
+                .flat_map(|artifact| artifact.bytes().to_vec())
+                .collect::<Vec<_>>(),
+            blob
+        );
+        assert_eq!(artifacts[1].offset(), 6);
+        assert_eq!(artifacts[1].name(), "stage1");
+        assert_eq!(
+            artifacts[1].to_string(),
+            format!("0x0006..0x0015  stage1  {}", artifacts[1].digest())
+        );
+        assert!(Stages::new().blob()?.is_empty());
+        Ok(())
+    }
+}
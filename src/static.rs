@@ -2,56 +2,518 @@
 
 use core::borrow::Borrow;
 use core::mem;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::io;
 
+use crate::analysis::{self, Digest, Occurrences};
+use crate::arch::Arch;
+use crate::config::{Config, DebugPlacement, DebugStub, PointerWidth};
+use crate::ops::Endianness;
 use crate::prelude::*;
+use crate::trace;
+use crate::transform::{Reverse, SwapPairs, Transform};
+use crate::Snapshot;
+
+#[cfg(feature = "zeroize")]
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// A shellcoder backed by a static buffer.
+///
+/// Fields are the whole buffer, the number of bytes that have been
+/// written, the number of operations that have been pushed, and the
+/// configuration.
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
-pub struct Shellcoder<'buf>(&'buf mut [u8], usize);
+pub struct Shellcoder<'buf>(&'buf mut [u8], usize, usize, Config);
 
 impl<'buf> Shellcoder<'buf> {
     /// Instantiates a new shellcoder.
     #[inline]
     #[must_use]
     pub fn new(buffer: &'buf mut [u8]) -> Self {
-        Self(buffer, 0)
+        Self(buffer, 0, 0, Config::default())
+    }
+
+    /// Sets the configuration, see [`crate::config::ShellcoderBuilder`].
+    #[inline]
+    #[must_use]
+    pub const fn with_config(mut self, config: Config) -> Self {
+        self.3 = config;
+        self
+    }
+
+    /// Sets the default endianness, see [`crate::Shellcoder::endianness`].
+    #[inline]
+    #[must_use]
+    pub const fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.3 = self.3.with_endianness(endianness);
+        self
+    }
+
+    /// Returns the configuration.
+    #[inline]
+    #[must_use]
+    pub const fn config(&self) -> &Config {
+        &self.3
     }
 
     /// Returns the shellcode.
     #[inline]
     #[must_use]
-    pub fn get(&self) -> &'buf [u8] {
-        // SAFETY:
-        //
-        // We are sure that [`self.1`] is not going to overflow the buffer,
-        // cause we test it in [`Shellcoder::add`].
-        let effective = unsafe { self.0.get_unchecked(..self.1) };
+    pub fn get(&self) -> &[u8] {
+        self.0.get(..self.1).unwrap_or_default()
+    }
+
+    /// Returns the total capacity of the buffer, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns the number of bytes that can still be written to the buffer.
+    ///
+    /// If a maximum length is configured, it is taken into account.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 8];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.int_le(0xdeadbeef_u32)?;
+    /// assert_eq!(shellcoder.capacity(), 8);
+    /// if shellcoder.remaining() >= 4 {
+    ///     shellcoder.fill(4, b'A')?;
+    /// }
+    /// assert_eq!(shellcoder.remaining(), 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        let capacity = self
+            .3
+            .max_len()
+            .map_or(self.0.len(), |limit| limit.min(self.0.len()));
+        capacity.saturating_sub(self.1)
+    }
+
+    /// Returns the current state of the shellcoder, see [`Self::restore`].
+    #[inline]
+    #[must_use]
+    pub const fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.1, self.2)
+    }
+
+    /// Rewinds the cursor to a snapshot.
+    ///
+    /// Bytes written after the snapshot are discarded. Transforms of
+    /// regions preceding the snapshot, e.g. [`Self::reverse`], are not
+    /// undone.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::UnexpectedPosition`]: the shellcoder has been rewound past
+    /// the snapshot, e.g. by restoring an earlier one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 8];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.fill(4, b'A')?;
+    /// let prefix = shellcoder.snapshot();
+    /// shellcoder.int_le(0xdeadbeef_u32)?;
+    /// shellcoder.restore(prefix)?.fill(4, b'B')?;
+    /// assert_eq!(shellcoder.get(), b"AAAABBBB");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<&mut Self> {
+        snapshot.check_restore(self.1)?;
+        self.1 = snapshot.len();
+        self.2 = snapshot.n_ops();
+        Ok(self)
+    }
+
+    /// Returns an iterator over the offsets of a subsequence in the
+    /// shellcode, see [`analysis::occurrences`].
+    #[inline]
+    #[must_use]
+    pub fn find<'needle>(&self, needle: &'needle [u8]) -> Occurrences<'_, 'needle> {
+        analysis::occurrences(self.get(), needle)
+    }
+
+    /// Returns the hashes of the shellcode, see [`analysis::digest`].
+    #[inline]
+    #[must_use]
+    pub fn digest(&self) -> Digest {
+        analysis::digest(self.get())
+    }
 
-        // SAFETY:
-        //
-        // [`std::slice::get`] and [`std::slice::get_mut`] does not propagate
-        // the right lifetime.
-        // In this bit of code, we are sure that lifetimes match.
-        unsafe { mem::transmute(effective) }
+    /// Transforms a region of the shellcode, right away. See
+    /// [`crate::transform::Transforms`] to transform regions once the
+    /// shellcode is complete.
+    ///
+    /// The transformed region is checked against the bad bytes of the
+    /// configuration. Without the `std` feature, the region is transformed
+    /// in place before being checked, thus it is left transformed on
+    /// failure. Otherwise, the shellcode is left untouched.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::OutputBufferTooSmall`]: the region is out of the
+    ///    shellcode.
+    ///  - [`Error::BadByteFound`]: the transformed region contains a bad
+    ///    byte.
+    #[inline]
+    pub fn transform(
+        &mut self,
+        range: Range<usize>,
+        transform: &(impl Transform + ?Sized),
+    ) -> Result<&mut Self> {
+        let start = range.start;
+        #[cfg(feature = "std")]
+        {
+            let mut bytes = self.region_mut(range.clone())?.to_vec();
+            transform.apply(&mut bytes);
+            self.3.bad_bytes().check(&bytes, start)?;
+            self.region_mut(range)?.copy_from_slice(&bytes);
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            transform.apply(self.region_mut(range.clone())?);
+            let region = self.get().get(range).unwrap_or_default();
+            self.3.bad_bytes().check(region, start)?;
+        }
+        Ok(self)
+    }
+
+    /// Reverses the order of the bytes of a region of the shellcode, see
+    /// [`Reverse`].
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`]: the region is out of the shellcode.
+    #[inline]
+    pub fn reverse(&mut self, range: Range<usize>) -> Result<&mut Self> {
+        self.transform(range, &Reverse)
+    }
+
+    /// Swaps the bytes of each pair of a region of the shellcode, see
+    /// [`SwapPairs`].
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`]: the region is out of the shellcode.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 8];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.int_be(0xdeadbeef_u32)?.swap_pairs(0..4)?;
+    /// assert_eq!(shellcoder.get(), b"\xad\xde\xef\xbe");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn swap_pairs(&mut self, range: Range<usize>) -> Result<&mut Self> {
+        self.transform(range, &SwapPairs)
+    }
+
+    /// Returns a region of the shellcode.
+    fn region_mut(&mut self, range: Range<usize>) -> Result<&mut [u8]> {
+        let end = range.end;
+        self.0
+            .get_mut(..self.1)
+            .and_then(|shellcode| shellcode.get_mut(range))
+            .ok_or_else(|| Error::buffer_too_small(end))
+    }
+
+    /// Consumes the shellcoder and returns the shellcode, borrowed for the
+    /// whole lifetime of the buffer.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::r#static::Shellcoder;
+    /// use shellcoder::Shellcoder as _;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let mut buffer = [0u8; 8];
+    /// let mut shellcoder = Shellcoder::new(&mut buffer);
+    /// shellcoder.fill(2, b'A')?.int_be(0x4243_u16)?;
+    /// let shellcode = shellcoder.into_bytes();
+    /// assert_eq!(shellcode, b"AABC");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn into_bytes(mut self) -> &'buf mut [u8] {
+        let offset = self.1.min(self.0.len());
+        mem::take(&mut self.0).split_at_mut(offset).0
+    }
+
+    /// Overwrites the whole buffer with zeroes and rewinds the cursor, e.g.
+    /// once a payload containing credentials has been delivered.
+    ///
+    /// This includes bytes past the cursor, e.g. after a
+    /// [`Self::restore`].
+    #[cfg(feature = "zeroize")]
+    #[inline]
+    pub fn clear_sensitive(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Zeroize for Shellcoder<'_> {
+    #[inline]
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+        self.1 = 0;
+        self.2 = 0;
+    }
+}
+
+/// The whole buffer is overwritten with zeroes when the shellcoder is
+/// dropped, see [`Shellcoder::into_bytes`] to keep the shellcode.
+#[cfg(feature = "zeroize")]
+impl Drop for Shellcoder<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.zeroize();
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl ZeroizeOnDrop for Shellcoder<'_> {}
+
 impl crate::Shellcoder for Shellcoder<'_> {
     #[inline]
     fn add<O>(&mut self, op: impl Borrow<O>) -> Result<&mut Self>
     where
         O: Op,
     {
-        let n = op.borrow().write_to(&mut self.0)?;
-        self.0 =
-        // SAFETY:
-        //
-        // [`std::slice::get`] and [`std::slice::get_mut`] does not propagate
-        // the right lifetime.
-        // In this bit of code, we are sure that lifetimes match.
-            unsafe { mem::transmute(self.0.get_mut(n..).ok_or_else(|| Error::buffer_too_small(n))?) };
-        self.1 = self.1.checked_add(n).ok_or(Error::IntegerOverflow)?;
-        Ok(self)
+        self.add_counted(op).map(|(_, shellcoder)| shellcoder)
+    }
+
+    #[inline]
+    fn add_counted<O>(&mut self, op: impl Borrow<O>) -> Result<(usize, &mut Self)>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let index = self.2;
+        let start = self.1;
+        let config = &self.3;
+        let buffer = self.0.get_mut(start..).unwrap_or_default();
+        let (stub, n) = config
+            .write_entry_stub(start, &mut *buffer)
+            .and_then(|stub| {
+                let offset = start.saturating_add(stub);
+                let remaining = buffer.get_mut(stub..).unwrap_or_default();
+                let n = operation
+                    .check_at(config.position(offset))
+                    .and_then(|()| operation.write_to(&mut *remaining))?;
+                let padded = n.saturating_add(config.padding(offset.saturating_add(n)));
+                let written = remaining
+                    .get_mut(..padded)
+                    .ok_or_else(|| Error::buffer_too_small(padded))?;
+                written.get_mut(n..).unwrap_or_default().fill(0);
+                config.check(offset, written)?;
+                Ok((stub, padded))
+            })
+            .map_err(|error| {
+                trace::failed(operation, index, start, &error);
+                error.with_op(operation, index, start)
+            })?;
+        let offset = start.saturating_add(stub);
+        trace::applied(operation, index, offset, n);
+        self.1 = offset.checked_add(n).ok_or(Error::IntegerOverflow)?;
+        self.2 = index.saturating_add(1);
+        Ok((n, self))
+    }
+
+    /// Checks that an operation fits in the remaining buffer and satisfies
+    /// the configuration, e.g. its maximum length or its bad bytes.
+    ///
+    /// Without the `std` feature, only operations that provide a
+    /// [`Op::size`] hint can be checked, and bad bytes are not checked.
+    #[inline]
+    fn try_add<O>(&self, op: impl Borrow<O>) -> Result<()>
+    where
+        O: Op,
+    {
+        let operation = op.borrow();
+        let start = self.1;
+        let stub = self.3.write_entry_stub(start, &mut [0; 8]);
+        let offset = stub.as_ref().map_or(start, |n| start.saturating_add(*n));
+        #[cfg(feature = "std")]
+        let size = if self.3.bad_bytes().is_empty() {
+            operation.size().map_or_else(
+                || operation.write_to_io(&mut io::sink()).map(Some),
+                |n| Ok(Some(n)),
+            )
+        } else {
+            self.3
+                .render(operation, offset)
+                .map(|bytes| Some(bytes.len()))
+        };
+        #[cfg(not(feature = "std"))]
+        let size = Ok(operation.size());
+        stub.and_then(|_stub| operation.check_at(self.3.position(offset)))
+            .and(size)
+            .and_then(|hint| {
+                hint.map_or(Ok(()), |n| {
+                    let padded = n.saturating_add(self.3.padding(offset.saturating_add(n)));
+                    if padded > self.0.len().saturating_sub(offset) {
+                        Err(Error::buffer_too_small(padded))
+                    } else {
+                        self.3.check_len(offset, padded)
+                    }
+                })
+            })
+            .map_err(|error| error.with_op(operation, self.2, start))
+    }
+
+    #[inline]
+    fn position(&self) -> usize {
+        self.3.position(self.1)
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.1
+    }
+
+    #[inline]
+    fn endianness(&self) -> Endianness {
+        self.3.endianness()
+    }
+
+    #[inline]
+    fn pointer_width(&self) -> PointerWidth {
+        self.3.pointer_width()
+    }
+
+    #[inline]
+    fn arch(&self) -> Option<Arch> {
+        self.3.arch()
+    }
+
+    #[inline]
+    fn debug(&self) -> Option<(DebugStub, DebugPlacement)> {
+        self.3.debug()
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::assertions_on_result_states,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::unseparated_literal_suffix
+)]
+mod tests {
+    use crate::config::ShellcoderBuilder;
+    use crate::r#static::Shellcoder;
+    use crate::transform::{Add, Xor};
+    use crate::{Shellcoder as _, Snapshot};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_transform_bad_bytes() -> Result<()> {
+        let mut buffer = [0u8; 8];
+        let mut shellcoder = ShellcoderBuilder::new()
+            .bad_bytes(b"\0".iter().copied().collect())
+            .build_static(&mut buffer);
+        shellcoder.push(b"AB")?.transform(0..2, &Add::new(1))?;
+        assert_eq!(shellcoder.get(), b"BC");
+        assert!(matches!(
+            shellcoder.transform(0..2, &Xor::new(b"BC")),
+            Err(Error::BadByteFound { .. })
+        ));
+        #[cfg(feature = "std")]
+        assert_eq!(shellcoder.get(), b"BC");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cursor() -> Result<()> {
+        let mut buffer = [0u8; 6];
+        let mut shellcoder = Shellcoder::new(&mut buffer);
+        shellcoder.int_le(0x4241_u16)?.advance(1)?;
+        assert_eq!(shellcoder.get(), b"AB\0");
+        assert!(shellcoder.push_buffer(b"CDEF").is_err());
+        assert_eq!(shellcoder.get(), b"AB\0");
+        shellcoder.push_buffer(b"CDE")?;
+        assert!(shellcoder.advance(1).is_err());
+        assert!(shellcoder.find(b"C").eq([3]));
+        assert_eq!(shellcoder.remaining(), 0);
+        assert_eq!(shellcoder.capacity(), 6);
+        let snapshot = shellcoder.snapshot();
+        assert_eq!(snapshot.len(), 6);
+        shellcoder.restore(Snapshot::new(2, 1))?.push_buffer(b"C")?;
+        assert!(shellcoder.restore(snapshot).is_err());
+        assert_eq!(shellcoder.snapshot(), Snapshot::new(3, 2));
+        assert_eq!(shellcoder.into_bytes(), b"ABC");
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_op_index() -> Result<()> {
+        let mut buffer = [0u8; 4];
+        let mut shellcoder = Shellcoder::new(&mut buffer);
+        shellcoder.fill(2, b'A')?;
+        assert!(shellcoder.assert_at(0).is_err());
+        assert!(shellcoder.fill(4, b'B').is_err());
+        assert_eq!(shellcoder.snapshot(), Snapshot::new(2, 1));
+        shellcoder.fill(2, b'B')?;
+        assert_eq!(shellcoder.snapshot(), Snapshot::new(4, 2));
+        Ok(())
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_clear_sensitive() -> Result<()> {
+        let mut buffer = [0xccu8; 6];
+        let mut shellcoder = Shellcoder::new(&mut buffer);
+        let snapshot = shellcoder.snapshot();
+        shellcoder.fill(4, b'A')?.restore(snapshot)?;
+        shellcoder.clear_sensitive();
+        assert!(shellcoder.is_empty());
+        shellcoder.fill(1, b'B')?;
+        assert_eq!(shellcoder.into_bytes(), b"B");
+        assert_eq!(buffer, *b"B\0\0\0\0\0");
+
+        let mut dropped = Shellcoder::new(&mut buffer);
+        dropped.fill(2, b'C')?;
+        drop(dropped);
+        assert_eq!(buffer, [0; 6]);
+        Ok(())
     }
 }
@@ -0,0 +1,123 @@
+//! [`proptest`] strategies for operations.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use proptest::prelude::*;
+//! use shellcoder::alloc::Shellcoder;
+//! use shellcoder::strategies;
+//! use shellcoder::Shellcoder as _;
+//!
+//! proptest!(|(ops in strategies::ops(0..16))| {
+//!     let mut shellcoder = Shellcoder::new();
+//!     for op in ops {
+//!         shellcoder.add(op).unwrap();
+//!     }
+//! });
+//! ```
+
+use ::proptest::collection::{self, SizeRange};
+use ::proptest::prelude::{any, prop_oneof, Arbitrary, Just, Strategy};
+
+use crate::ops::{Advance, AnyOp, EncodableInteger, Fill, WriteBuffer, WriteInteger};
+
+/// Maximum length generated for operations that take a length.
+pub const MAX_LEN: usize = 0x100;
+
+/// Bytes that buffers generated by [`write_buffer`] are borrowed from.
+///
+/// It contains bytes that are usually of interest when writing payloads,
+/// such as null bytes, newlines or opcodes.
+static BUFFER_POOL: &[u8] = b"\x00\x01\x7f\x80\xfe\xff\n\r\t /bin/sh\x00AAAABBBB\xde\xad\xbe\xef\
+    \xca\xfe\xba\xbe%s%n%p\x90\x90\x90\x90\xcc\xcc\xc3\x0f\x05\xcd\x80";
+
+/// Returns a strategy for [`Advance`], up to [`MAX_LEN`] bytes.
+#[inline]
+pub fn advance() -> impl Strategy<Value = Advance> {
+    (0..=MAX_LEN).prop_map(Advance::new)
+}
+
+/// Returns a strategy for [`Fill`], up to [`MAX_LEN`] bytes.
+#[inline]
+pub fn fill() -> impl Strategy<Value = Fill> {
+    (0..=MAX_LEN, any::<u8>()).prop_map(|(len, chr)| Fill::new(len, chr))
+}
+
+/// Returns a strategy for [`WriteInteger`], in both endiannesses.
+#[inline]
+pub fn write_integer<I>() -> impl Strategy<Value = WriteInteger<I>>
+where
+    I: EncodableInteger + Arbitrary,
+{
+    prop_oneof![
+        any::<I>().prop_map(WriteInteger::new_be),
+        any::<I>().prop_map(WriteInteger::new_le),
+    ]
+}
+
+/// Returns a strategy for [`WriteBuffer`].
+///
+/// Buffers are borrowed from a static pool of bytes, thus they are small.
+#[inline]
+pub fn write_buffer() -> impl Strategy<Value = WriteBuffer<'static>> {
+    (0..=BUFFER_POOL.len())
+        .prop_flat_map(|start| (Just(start), start..=BUFFER_POOL.len()))
+        .prop_map(|(start, end)| WriteBuffer::new(BUFFER_POOL.get(start..end).unwrap_or_default()))
+}
+
+/// Returns a strategy for any [`AnyOp`], i.e. one of the primitive
+/// operations it covers.
+#[inline]
+pub fn any_op() -> impl Strategy<Value = AnyOp<'static>> {
+    prop_oneof![
+        advance().prop_map(AnyOp::from),
+        fill().prop_map(AnyOp::from),
+        write_integer::<u8>().prop_map(AnyOp::from),
+        write_integer::<u16>().prop_map(AnyOp::from),
+        write_integer::<u32>().prop_map(AnyOp::from),
+        write_integer::<u64>().prop_map(AnyOp::from),
+        write_buffer().prop_map(AnyOp::from),
+    ]
+}
+
+/// Returns a strategy for sequences of operations, whose number is within
+/// `size`.
+#[inline]
+pub fn ops(size: impl Into<SizeRange>) -> impl Strategy<Value = Vec<AnyOp<'static>>> {
+    collection::vec(any_op(), size)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::arithmetic_side_effects,
+    clippy::redundant_test_prefix,
+    clippy::unwrap_used
+)]
+mod tests {
+    use ::proptest::prelude::*;
+
+    use crate::alloc;
+    use crate::io;
+    use crate::strategies::ops;
+    use crate::Shellcoder as _;
+
+    use crate::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_backends_agree(ops in ops(0..32)) {
+            let mut shellcoder = alloc::Shellcoder::new();
+            let mut stream = Vec::new();
+            let mut io_shellcoder = io::Shellcoder::new(&mut stream);
+            let mut len = 0;
+            for op in ops {
+                let mut buffer = Vec::new();
+                len += op.write_to_io(&mut buffer).unwrap();
+                shellcoder.add(op).unwrap();
+                io_shellcoder.add(op).unwrap();
+            }
+            prop_assert_eq!(shellcoder.as_bytes().len(), len);
+            prop_assert_eq!(shellcoder.as_bytes(), stream.as_slice());
+        }
+    }
+}
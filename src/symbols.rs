@@ -0,0 +1,318 @@
+//! Symbol offset tables.
+//!
+//! Offsets of functions and gadgets are usually exported from a
+//! disassembler (IDA, Ghidra, ropper...) rather than copied by hand. A
+//! [`Table`] loads them from CSV, or JSON (feature `json`), and resolves
+//! them against the runtime base of the module once it has been leaked, so
+//! that exploit code reads `table.resolve("system")?` instead of a magic
+//! number.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::alloc::Shellcoder;
+//! use shellcoder::symbols::Table;
+//! use shellcoder::Shellcoder as _;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let mut table = Table::from_csv("\
+//!     name,offset\n\
+//!     system,0x50d70\n\
+//!     binsh,0x1d8678\n\
+//! ")?;
+//! table.set_base(0x7f00_0000_0000);
+//!
+//! let mut shellcoder = Shellcoder::new();
+//! shellcoder
+//!     .ptr(table.resolve("binsh")?)?
+//!     .ptr(table.resolve("system")?)?;
+//! assert_eq!(&shellcoder.as_bytes()[8..], b"\x70\x0d\x05\0\0\x7f\0\0");
+//! assert!(table.resolve("execve").is_err());
+//! # Ok(())
+//! # }
+//! ```
+
+use alloc_crate::collections::{btree_map, BTreeMap};
+#[cfg(feature = "json")]
+use core::{fmt, result};
+
+#[cfg(feature = "json")]
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+
+use crate::prelude::*;
+
+/// A table of symbols, mapping names to offsets from a base.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Table {
+    /// Runtime base the offsets are relative to.
+    base: u64,
+
+    /// Offsets, by name.
+    symbols: BTreeMap<String, u64>,
+}
+
+impl Table {
+    /// Instantiates a new empty [`Table`], based at 0.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a table from CSV.
+    ///
+    /// Each line holds a name and an offset, separated by a comma. Offsets
+    /// are decimal, or hexadecimal when prefixed with `0x`. Fields may be
+    /// quoted, and extra fields are ignored. Empty lines, lines starting
+    /// with `#` and a header line, i.e. a first line that is not an entry,
+    /// are skipped.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidSymbolTable`]: a line is not a valid entry.
+    #[inline]
+    pub fn from_csv(csv: &str) -> Result<Self> {
+        let mut table = Self::new();
+        let mut header = true;
+        for (index, line) in csv.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split(',').map(|field| field.trim().trim_matches('"'));
+            let entry = fields
+                .next()
+                .filter(|name| !name.is_empty())
+                .zip(fields.next().and_then(parse_offset));
+            match entry {
+                Some((name, offset)) => {
+                    table.insert(name, offset);
+                }
+                None if header => {}
+                None => return Err(Error::InvalidSymbolTable(index.saturating_add(1))),
+            }
+            header = false;
+        }
+        Ok(table)
+    }
+
+    /// Parses a table from a JSON object mapping names to offsets.
+    ///
+    /// Offsets are integers, or strings holding a decimal or a
+    /// `0x`-prefixed hexadecimal integer.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidSymbolTable`]: the object is not valid.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use shellcoder::symbols::Table;
+    /// # use shellcoder::Result;
+    ///
+    /// # pub fn main() -> Result<()> {
+    /// let table = Table::from_json(r#"{ "main": 4464, "win": "0x1189" }"#)?;
+    /// assert_eq!(table.offset("win"), Some(0x1189));
+    /// assert!(Table::from_json(r#"{ "main": "start" }"#).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    #[inline]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let symbols = serde_json::from_str::<BTreeMap<String, Offset>>(json)
+            .map_err(|error| Error::InvalidSymbolTable(error.line()))?;
+        Ok(Self {
+            base: 0,
+            symbols: symbols
+                .into_iter()
+                .map(|(name, Offset(offset))| (name, offset))
+                .collect(),
+        })
+    }
+
+    /// Sets the runtime base the offsets are relative to.
+    #[inline]
+    #[must_use]
+    pub const fn with_base(mut self, base: u64) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Sets the runtime base the offsets are relative to, e.g. once it has
+    /// been leaked.
+    #[inline]
+    pub fn set_base(&mut self, base: u64) -> &mut Self {
+        self.base = base;
+        self
+    }
+
+    /// Returns the runtime base the offsets are relative to.
+    #[inline]
+    #[must_use]
+    pub const fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Inserts a symbol, replacing any symbol with the same name.
+    #[inline]
+    pub fn insert(&mut self, name: impl Into<String>, offset: u64) -> &mut Self {
+        self.symbols.insert(name.into(), offset);
+        self
+    }
+
+    /// Returns the offset of a symbol.
+    #[inline]
+    #[must_use]
+    pub fn offset(&self, name: &str) -> Option<u64> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Returns the address of a symbol, i.e. its offset from the base.
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::UnknownSymbol`]: the symbol is not in the table.
+    ///  - [`Error::IntegerOverflow`]: the address overflows.
+    #[inline]
+    pub fn resolve(&self, name: &str) -> Result<u64> {
+        self.offset(name)
+            .ok_or_else(|| Error::UnknownSymbol(name.to_owned()))?
+            .checked_add(self.base)
+            .ok_or(Error::IntegerOverflow)
+    }
+
+    /// Returns the number of symbols.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns `true` if there is no symbol.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Returns an iterator over the symbols and their offsets, sorted by
+    /// name.
+    #[inline]
+    pub fn iter(&self) -> btree_map::Iter<'_, String, u64> {
+        self.symbols.iter()
+    }
+}
+
+impl<'table> IntoIterator for &'table Table {
+    type Item = (&'table String, &'table u64);
+    type IntoIter = btree_map::Iter<'table, String, u64>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Parses an offset, either decimal or `0x`-prefixed hexadecimal.
+#[allow(clippy::single_call_fn)]
+fn parse_offset(offset: &str) -> Option<u64> {
+    offset
+        .strip_prefix("0x")
+        .or_else(|| offset.strip_prefix("0X"))
+        .map_or_else(
+            || offset.parse().ok(),
+            |hex| u64::from_str_radix(hex, 16).ok(),
+        )
+}
+
+/// An offset in a JSON table, either an integer or a string.
+#[cfg(feature = "json")]
+struct Offset(u64);
+
+#[cfg(feature = "json")]
+impl<'de> Deserialize<'de> for Offset {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// Visitor of integers and strings.
+        struct OffsetVisitor;
+
+        impl Visitor<'_> for OffsetVisitor {
+            type Value = Offset;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                fmt.write_str("an offset")
+            }
+
+            fn visit_u64<E>(self, offset: u64) -> result::Result<Offset, E> {
+                Ok(Offset(offset))
+            }
+
+            fn visit_str<E>(self, offset: &str) -> result::Result<Offset, E>
+            where
+                E: de::Error,
+            {
+                parse_offset(offset)
+                    .map(Offset)
+                    .ok_or_else(|| E::invalid_value(Unexpected::Str(offset), &self))
+            }
+        }
+
+        deserializer.deserialize_any(OffsetVisitor)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic_in_result_fn, clippy::redundant_test_prefix)]
+mod tests {
+    use crate::symbols::Table;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_csv() -> Result<()> {
+        let mut table = Table::from_csv(
+            "# exported from ghidra\n\
+             \"Name\",\"Location\"\n\
+             \n\
+             \"main\",\"0x1040\",\"Function\"\n\
+             win , 4464\n",
+        )?;
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.resolve("main")?, 0x1040);
+        table.set_base(0x5555_5555_0000).insert("main", 0x1050);
+        assert_eq!(table.resolve("main")?, 0x5555_5555_1050);
+        assert_eq!(table.resolve("win")?, 0x5555_5555_1170);
+        assert!(table.iter().map(|(name, _offset)| name).eq(["main", "win"]));
+
+        assert!(matches!(
+            table.resolve("system"),
+            Err(Error::UnknownSymbol(name)) if name == "system"
+        ));
+        assert!(matches!(
+            table.with_base(u64::MAX).resolve("win"),
+            Err(Error::IntegerOverflow)
+        ));
+        assert!(matches!(
+            Table::from_csv("main,0x10\nwin\n"),
+            Err(Error::InvalidSymbolTable(2))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() -> Result<()> {
+        let table = Table::from_json("{\n\"a\": 1,\n\"b\": \"0x10\"\n}")?.with_base(0x100);
+        assert_eq!(table.resolve("b")?, 0x110);
+        assert!(matches!(
+            Table::from_json("{\n\"a\": 1,\n\"b\": -1\n}"),
+            Err(Error::InvalidSymbolTable(3))
+        ));
+        Ok(())
+    }
+}
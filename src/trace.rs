@@ -0,0 +1,56 @@
+//! Instrumentation of operations, using [`tracing`](https://docs.rs/tracing).
+//!
+//! With the `tracing` feature, backends emit an event for each operation
+//! they apply, with target `shellcoder`:
+//!
+//!  - at level `TRACE` when the operation has been written, with its
+//!    index, offset, size, and debug representation;
+//!  - at level `DEBUG` when the operation failed, with the error.
+//!
+//! Without the feature, these functions do nothing.
+
+#![allow(clippy::redundant_pub_crate)]
+// Without `std`, the static backend is the only one recording operations.
+#![cfg_attr(not(feature = "std"), allow(clippy::single_call_fn))]
+
+use core::fmt;
+
+use crate::prelude::*;
+
+/// Records that an operation has been written.
+#[cfg(feature = "tracing")]
+#[inline]
+pub(crate) fn applied(op: &impl fmt::Debug, index: usize, offset: usize, size: usize) {
+    tracing::trace!(
+        target: "shellcoder",
+        index,
+        offset,
+        size,
+        op = ?op,
+        "operation applied"
+    );
+}
+
+/// Records that an operation has been written.
+#[cfg(not(feature = "tracing"))]
+#[inline]
+pub(crate) const fn applied(_op: &impl fmt::Debug, _index: usize, _offset: usize, _size: usize) {}
+
+/// Records that an operation failed.
+#[cfg(feature = "tracing")]
+#[inline]
+pub(crate) fn failed(op: &impl fmt::Debug, index: usize, offset: usize, error: &Error) {
+    tracing::debug!(
+        target: "shellcoder",
+        index,
+        offset,
+        op = ?op,
+        error = %error,
+        "operation failed"
+    );
+}
+
+/// Records that an operation failed.
+#[cfg(not(feature = "tracing"))]
+#[inline]
+pub(crate) const fn failed(_op: &impl fmt::Debug, _index: usize, _offset: usize, _error: &Error) {}
@@ -0,0 +1,342 @@
+//! Transforms of regions of a payload, applied after they have been
+//! written.
+//!
+//! Some targets consume data in an unusual order, e.g. word-swapped on odd
+//! embedded platforms, and some payloads must be obfuscated. Payloads are
+//! built in their natural, readable layout, then their regions are
+//! transformed, either right away, see
+//! [`crate::alloc::Shellcoder::transform`], or once the payload is
+//! complete, see [`Transforms`].
+
+use core::fmt;
+#[cfg(feature = "std")]
+use core::ops::Range;
+
+#[cfg(feature = "std")]
+use crate::prelude::*;
+
+/// A transform of a region of a payload.
+pub trait Transform: fmt::Debug {
+    /// Transforms the bytes of a region, in place.
+    fn apply(&self, bytes: &mut [u8]);
+}
+
+impl<T> Transform for &T
+where
+    T: Transform + ?Sized,
+{
+    #[inline]
+    fn apply(&self, bytes: &mut [u8]) {
+        (**self).apply(bytes);
+    }
+}
+
+/// Reverses the order of the bytes of a region.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::transform::{Reverse, Transform as _};
+///
+/// let mut bytes = *b"ABCDE";
+/// Reverse.apply(&mut bytes);
+/// assert_eq!(&bytes, b"EDCBA");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+// Constructed by name, like any unit struct.
+#[allow(clippy::exhaustive_structs)]
+pub struct Reverse;
+
+impl Transform for Reverse {
+    #[inline]
+    fn apply(&self, bytes: &mut [u8]) {
+        bytes.reverse();
+    }
+}
+
+/// Swaps the bytes of each pair of a region, i.e. `AB CD` becomes `BA DC`.
+/// A trailing odd byte is left untouched.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::transform::{SwapPairs, Transform as _};
+///
+/// let mut bytes = *b"ABCDE";
+/// SwapPairs.apply(&mut bytes);
+/// assert_eq!(&bytes, b"BADCE");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+// Constructed by name, like any unit struct.
+#[allow(clippy::exhaustive_structs)]
+pub struct SwapPairs;
+
+impl Transform for SwapPairs {
+    #[inline]
+    fn apply(&self, bytes: &mut [u8]) {
+        for pair in bytes.chunks_exact_mut(2) {
+            pair.reverse();
+        }
+    }
+}
+
+/// XORs the bytes of a region with a repeating key, starting with the first
+/// byte of the key at the start of the region. An empty key leaves the
+/// region untouched.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::transform::{Transform as _, Xor};
+///
+/// let mut bytes = *b"ABCDE";
+/// Xor::new(b"\x01\x02").apply(&mut bytes);
+/// assert_eq!(&bytes, b"@@BFD");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Xor<K>(K);
+
+impl<K> Xor<K>
+where
+    K: AsRef<[u8]>,
+{
+    /// Instantiates a new [`Xor`] with a key.
+    #[inline]
+    #[must_use]
+    pub const fn new(key: K) -> Self {
+        Self(key)
+    }
+
+    /// Returns the key.
+    #[inline]
+    #[must_use]
+    pub fn key(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<K> Transform for Xor<K>
+where
+    K: AsRef<[u8]> + fmt::Debug,
+{
+    #[inline]
+    fn apply(&self, bytes: &mut [u8]) {
+        for (byte, key_byte) in bytes.iter_mut().zip(self.0.as_ref().iter().cycle()) {
+            *byte ^= key_byte;
+        }
+    }
+}
+
+/// Adds a constant to each byte of a region, wrapping around on overflow.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::transform::{Add, Transform as _};
+///
+/// let mut bytes = *b"\x00\x41\xff";
+/// Add::new(1).apply(&mut bytes);
+/// assert_eq!(&bytes, b"\x01\x42\x00");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Add(u8);
+
+impl Add {
+    /// Instantiates a new [`Add`] with a constant.
+    #[inline]
+    #[must_use]
+    pub const fn new(constant: u8) -> Self {
+        Self(constant)
+    }
+}
+
+impl Transform for Add {
+    #[inline]
+    fn apply(&self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte = byte.wrapping_add(self.0);
+        }
+    }
+}
+
+/// Rotates the bits of each byte of a region to the left.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::transform::{RotateLeft, Transform as _};
+///
+/// let mut bytes = *b"\x81\x0f";
+/// RotateLeft::new(1).apply(&mut bytes);
+/// assert_eq!(&bytes, b"\x03\x1e");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RotateLeft(u32);
+
+impl RotateLeft {
+    /// Instantiates a new [`RotateLeft`] by a number of bits.
+    #[inline]
+    #[must_use]
+    pub const fn new(bits: u32) -> Self {
+        Self(bits)
+    }
+}
+
+impl Transform for RotateLeft {
+    #[inline]
+    fn apply(&self, bytes: &mut [u8]) {
+        for byte in bytes {
+            *byte = byte.rotate_left(self.0);
+        }
+    }
+}
+
+/// Transforms of regions, applied once the payload is complete.
+///
+/// This keeps the payload readable while it is built, e.g. to debug its
+/// layout, and emits the transformed bytes at the end. Transforms are
+/// applied in the order they have been pushed.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::alloc::Shellcoder;
+/// use shellcoder::transform::{Transforms, Xor};
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut shellcoder = Shellcoder::new();
+/// let mut transforms = Transforms::new();
+/// shellcoder.push(b"\xeb\x10")?;
+/// let start = shellcoder.len();
+/// shellcoder.push(b"/bin/sh\0")?;
+/// transforms.push(start..shellcoder.len(), Xor::new([0x20]));
+///
+/// assert_eq!(shellcoder.as_bytes(), b"\xeb\x10/bin/sh\0");
+/// let mut payload = shellcoder.into_bytes();
+/// transforms.apply(&mut payload)?;
+/// assert_eq!(payload, b"\xeb\x10\x0fBIN\x0fSH\x20");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct Transforms<'transform> {
+    /// Regions and their transforms, in order.
+    transforms: Vec<(Range<usize>, Box<dyn Transform + 'transform>)>,
+}
+
+#[cfg(feature = "std")]
+impl<'transform> Transforms<'transform> {
+    /// Instantiates a new empty [`Transforms`].
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the transform of a region.
+    #[inline]
+    pub fn push(
+        &mut self,
+        range: Range<usize>,
+        transform: impl Transform + 'transform,
+    ) -> &mut Self {
+        self.transforms.push((range, Box::new(transform)));
+        self
+    }
+
+    /// Returns the number of transforms.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    /// Returns `true` if there is no transform.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// Applies the transforms to a payload.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::OutputBufferTooSmall`]: a region is out of the payload.
+    /// The payload is left untouched.
+    #[inline]
+    pub fn apply(&self, payload: &mut [u8]) -> Result<()> {
+        if let Some(end) = self
+            .transforms
+            .iter()
+            .map(|(range, _transform)| range)
+            .find(|range| payload.get(Range::clone(range)).is_none())
+            .map(|range| range.end)
+        {
+            return Err(Error::buffer_too_small(end));
+        }
+        for (range, transform) in &self.transforms {
+            if let Some(region) = payload.get_mut(range.clone()) {
+                transform.apply(region);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps
+)]
+mod tests {
+    use crate::transform::{Add, Reverse, RotateLeft, SwapPairs, Transform as _, Xor};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_transforms() -> Result<()> {
+        let mut bytes = *b"\x01\x02\x03\x04\x05";
+        Xor::new(b"").apply(&mut bytes);
+        Reverse.apply(&mut bytes[..0]);
+        SwapPairs.apply(&mut bytes[..1]);
+        assert_eq!(bytes, *b"\x01\x02\x03\x04\x05");
+
+        Xor::new([0xff; 2]).apply(&mut bytes);
+        assert_eq!(bytes, *b"\xfe\xfd\xfc\xfb\xfa");
+        Add::new(0x10).apply(&mut bytes);
+        assert_eq!(bytes, *b"\x0e\x0d\x0c\x0b\x0a");
+        RotateLeft::new(12).apply(&mut bytes);
+        assert_eq!(bytes, *b"\xe0\xd0\xc0\xb0\xa0");
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_deferred() -> Result<()> {
+        use crate::transform::Transforms;
+
+        let key = vec![0x41];
+        let mut transforms = Transforms::new();
+        transforms
+            .push(0..4, Reverse)
+            .push(2..4, Xor::new(key.as_slice()));
+        assert_eq!(transforms.len(), 2);
+
+        let mut payload = *b"ABCD";
+        transforms.apply(&mut payload)?;
+        assert_eq!(payload, *b"DC\x03\x00");
+
+        transforms.push(3..5, SwapPairs);
+        assert!(matches!(
+            transforms.apply(&mut payload),
+            Err(Error::OutputBufferTooSmall(5))
+        ));
+        assert_eq!(payload, *b"DC\x03\x00");
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+//! Parsing of leaked data.
+//!
+//! Unlike [`crate::pack`], which converts fixed-size arrays, these read
+//! from the start of slices as they come out of a target, and follow the
+//! endianness and pointer width of a [`Config`], so that leaks are parsed
+//! with the same settings the payload is built with.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::config::{PointerWidth, ShellcoderBuilder};
+//! use shellcoder::unpack;
+//!
+//! let config = *ShellcoderBuilder::new()
+//!     .pointer_width(PointerWidth::Bits32)
+//!     .config();
+//! let leak = b"\x00\x80\x04\x08\x10\x80\x04\x08\n";
+//! assert_eq!(unpack::read_ptr(leak, &config), Some(0x0804_8000));
+//! assert!(unpack::pointers(leak, &config).eq([0x0804_8000, 0x0804_8010]));
+//! assert_eq!(unpack::u64_partial_le(b"\x10\x32\x54\x76\xff\x7f"), Some(0x7fff_7654_3210));
+//! ```
+
+use core::slice::ChunksExact;
+
+use crate::config::{Config, PointerWidth};
+use crate::ops::Endianness;
+use crate::pack;
+
+/// Defines the functions reading an integer type from the start of a slice.
+macro_rules! unpack_functions {
+    ($ty:ident, $n:literal, $read_le:ident, $read_be:ident, $le:ident, $be:ident) => {
+        #[doc = concat!("Reads a ", stringify!($ty), " in little endian from the start of a slice.")]
+        #[inline]
+        #[must_use]
+        pub fn $read_le(bytes: &[u8]) -> Option<$ty> {
+            Some(pack::$le(bytes.get(..$n)?.try_into().ok()?))
+        }
+
+        #[doc = concat!("Reads a ", stringify!($ty), " in big endian from the start of a slice.")]
+        #[inline]
+        #[must_use]
+        pub fn $read_be(bytes: &[u8]) -> Option<$ty> {
+            Some(pack::$be(bytes.get(..$n)?.try_into().ok()?))
+        }
+    };
+}
+
+unpack_functions!(u16, 2, u16_le, u16_be, u16_le, u16_be);
+unpack_functions!(u32, 4, u32_le, u32_be, u32_le, u32_be);
+unpack_functions!(u64, 8, u64_le, u64_be, u64_le, u64_be);
+
+/// Reads a little-endian [`u64`] from a slice of at most 8 bytes, as if it
+/// was padded with zeroes, e.g. an address leaked by a string function that
+/// stopped at its first null byte.
+#[inline]
+#[must_use]
+pub fn u64_partial_le(bytes: &[u8]) -> Option<u64> {
+    let mut padded = [0; 8];
+    padded.get_mut(..bytes.len())?.copy_from_slice(bytes);
+    Some(u64::from_le_bytes(padded))
+}
+
+/// Reads a pointer from the start of a slice, using the endianness and the
+/// pointer width of a configuration.
+#[inline]
+#[must_use]
+pub fn read_ptr(bytes: &[u8], config: &Config) -> Option<u64> {
+    match (config.pointer_width(), config.endianness()) {
+        (PointerWidth::Bits16, Endianness::Little) => u16_le(bytes).map(u64::from),
+        (PointerWidth::Bits16, Endianness::Big) => u16_be(bytes).map(u64::from),
+        (PointerWidth::Bits32, Endianness::Little) => u32_le(bytes).map(u64::from),
+        (PointerWidth::Bits32, Endianness::Big) => u32_be(bytes).map(u64::from),
+        (PointerWidth::Bits64, Endianness::Little) => u64_le(bytes),
+        (PointerWidth::Bits64, Endianness::Big) => u64_be(bytes),
+    }
+}
+
+/// An iterator over the pointers of a slice, see [`pointers`].
+#[derive(Clone, Debug)]
+pub struct Pointers<'buf, 'config> {
+    /// Pointer-wide chunks of the slice.
+    chunks: ChunksExact<'buf, u8>,
+
+    /// The configuration.
+    config: &'config Config,
+}
+
+impl Pointers<'_, '_> {
+    /// Returns the trailing bytes that do not make a whole pointer.
+    #[inline]
+    #[must_use]
+    pub fn remainder(&self) -> &[u8] {
+        self.chunks.remainder()
+    }
+}
+
+impl Iterator for Pointers<'_, '_> {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<u64> {
+        read_ptr(self.chunks.next()?, self.config)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chunks.size_hint()
+    }
+}
+
+/// Returns an iterator over the pointers of a slice, e.g. a leaked stack
+/// frame, using the endianness and the pointer width of a configuration.
+///
+/// Trailing bytes that do not make a whole pointer are ignored, see
+/// [`Pointers::remainder`].
+#[inline]
+#[must_use]
+pub fn pointers<'buf, 'config>(
+    bytes: &'buf [u8],
+    config: &'config Config,
+) -> Pointers<'buf, 'config> {
+    Pointers {
+        chunks: bytes.chunks_exact(config.pointer_width().bytes()),
+        config,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::redundant_test_prefix, clippy::shadow_unrelated)]
+mod tests {
+    use crate::config::{PointerWidth, ShellcoderBuilder};
+    use crate::ops::Endianness;
+    use crate::unpack;
+
+    #[test]
+    fn test_unpack() {
+        assert_eq!(unpack::u16_be(b"ABC"), Some(0x4142));
+        assert_eq!(unpack::u32_le(b"ABC"), None);
+        assert_eq!(unpack::u64_be(b"\0\0\0\0\0\0\0\x01"), Some(1));
+        assert_eq!(unpack::u64_partial_le(b""), Some(0));
+        assert_eq!(unpack::u64_partial_le(&[0xff; 9]), None);
+
+        let config = *ShellcoderBuilder::new()
+            .endianness(Endianness::Big)
+            .pointer_width(PointerWidth::Bits16)
+            .config();
+        let mut pointers = unpack::pointers(b"\x12\x34\x56\x78\x9a", &config);
+        assert_eq!(pointers.size_hint(), (2, Some(2)));
+        assert_eq!(pointers.next(), Some(0x1234));
+        assert_eq!(pointers.remainder(), b"\x9a");
+        assert_eq!(unpack::read_ptr(b"\x56", &config), None);
+
+        let config = *ShellcoderBuilder::new().config();
+        assert_eq!(
+            unpack::read_ptr(b"\x10\x32\x54\x76\xff\x7f\0\0", &config),
+            Some(0x7fff_7654_3210)
+        );
+    }
+}
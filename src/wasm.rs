@@ -0,0 +1,177 @@
+//! WebAssembly bindings.
+//!
+//! This module exposes the [`crate::alloc::Shellcoder`] to JavaScript
+//! through [`wasm_bindgen`](mod@wasm_bindgen), e.g. using `wasm-pack`:
+//!
+//! ```shell
+//! $ wasm-pack build --target web -- --features wasm
+//! ```
+//!
+//! ```js
+//! const shellcoder = new Shellcoder();
+//! shellcoder.intLe(0xdeadbeefn, 8);
+//! shellcoder.fill(8, 0x41);
+//! const payload = shellcoder.bytes();
+//! ```
+
+use core::result::Result as CoreResult;
+
+use wasm_bindgen::prelude::{wasm_bindgen, JsError};
+
+use crate::alloc;
+use crate::ops::Endianness;
+use crate::prelude::*;
+use crate::Shellcoder as _;
+
+/// Converts a result into a JavaScript result.
+fn js_result<T>(result: Result<T>) -> CoreResult<(), JsError> {
+    result
+        .map(|_| ())
+        .map_err(|error| JsError::new(&error.report()))
+}
+
+/// Pushes an integer of a given width in bytes.
+fn push_int(
+    shellcoder: &mut alloc::Shellcoder,
+    value: u64,
+    width: usize,
+    little_endian: bool,
+) -> Result<&mut alloc::Shellcoder> {
+    let endianness = if little_endian {
+        Endianness::Little
+    } else {
+        Endianness::Big
+    };
+    match width {
+        1 => shellcoder.int_with(u8::try_from(value)?, endianness),
+        2 => shellcoder.int_with(u16::try_from(value)?, endianness),
+        4 => shellcoder.int_with(u32::try_from(value)?, endianness),
+        8 => shellcoder.int_with(value, endianness),
+        _ => Err(Error::IntegerOverflow),
+    }
+}
+
+/// A shellcoder backed by a dynamic buffer, exposed to JavaScript.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Shellcoder(alloc::Shellcoder);
+
+// `#[inline]` is ignored on functions exported by `wasm_bindgen`.
+#[allow(clippy::missing_inline_in_public_items)]
+#[wasm_bindgen]
+impl Shellcoder {
+    /// Instantiates a new shellcoder.
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Instantiates a new shellcoder with a maximum length in bytes.
+    #[wasm_bindgen(js_name = withMaxLen)]
+    #[must_use]
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self(alloc::Shellcoder::new_with_max_len(max_len))
+    }
+
+    /// Pushes an integer of `width` bytes (1, 2, 4 or 8) in little endian.
+    ///
+    /// # Errors
+    ///
+    /// The integer does not fit in `width` bytes, or the maximum length is
+    /// exceeded.
+    #[wasm_bindgen(js_name = intLe)]
+    pub fn int_le(&mut self, value: u64, width: usize) -> CoreResult<(), JsError> {
+        js_result(push_int(&mut self.0, value, width, true))
+    }
+
+    /// Pushes an integer of `width` bytes (1, 2, 4 or 8) in big endian.
+    ///
+    /// # Errors
+    ///
+    /// The integer does not fit in `width` bytes, or the maximum length is
+    /// exceeded.
+    #[wasm_bindgen(js_name = intBe)]
+    pub fn int_be(&mut self, value: u64, width: usize) -> CoreResult<(), JsError> {
+        js_result(push_int(&mut self.0, value, width, false))
+    }
+
+    /// Fills with `len` bytes of value `chr`.
+    ///
+    /// # Errors
+    ///
+    /// The maximum length is exceeded.
+    pub fn fill(&mut self, len: usize, chr: u8) -> CoreResult<(), JsError> {
+        js_result(self.0.fill(len, chr))
+    }
+
+    /// Advances the cursor by `n` bytes, filling the gap with zeroes.
+    ///
+    /// # Errors
+    ///
+    /// The maximum length is exceeded.
+    pub fn advance(&mut self, n: usize) -> CoreResult<(), JsError> {
+        js_result(self.0.advance(n))
+    }
+
+    /// Pushes a buffer.
+    ///
+    /// # Errors
+    ///
+    /// The maximum length is exceeded.
+    #[wasm_bindgen(js_name = pushBuffer)]
+    pub fn push_buffer(&mut self, buffer: &[u8]) -> CoreResult<(), JsError> {
+        js_result(self.0.push_buffer(buffer))
+    }
+
+    /// Returns the number of bytes written so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.as_bytes().len()
+    }
+
+    /// Returns `true` if nothing has been written yet.
+    #[wasm_bindgen(js_name = isEmpty)]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.as_bytes().is_empty()
+    }
+
+    /// Returns a copy of the payload.
+    #[must_use]
+    pub fn bytes(&self) -> Vec<u8> {
+        self.0.as_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::unnecessary_wraps,
+    clippy::unwrap_in_result,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::wasm::{push_int, Shellcoder};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_shellcoder() -> Result<()> {
+        let mut shellcoder = Shellcoder::new();
+        assert!(shellcoder.is_empty());
+        shellcoder.int_le(0xdead, 2).unwrap();
+        shellcoder.int_be(0xbeef, 4).unwrap();
+        shellcoder.fill(2, b'A').unwrap();
+        shellcoder.advance(1).unwrap();
+        shellcoder.push_buffer(b"sh").unwrap();
+        assert_eq!(shellcoder.len(), 11);
+        assert_eq!(shellcoder.bytes(), b"\xad\xde\0\0\xbe\xefAA\0sh");
+        assert!(matches!(
+            push_int(&mut shellcoder.0, 0x100, 1, true),
+            Err(Error::IntegerOverflow)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+//! Build watermarks.
+//!
+//! [`Watermark`] is an operation that appends a compact metadata record to
+//! a payload: a build identifier, a timestamp and an operator tag, behind a
+//! magic prefix. Payloads that turn up later, e.g. in logs or crash dumps,
+//! can then be traced back to the run that produced them, see [`find`].
+//!
+//! A record is laid out as follows, integers being little-endian:
+//!
+//!  - the magic, [`WATERMARK_MAGIC`],
+//!  - the version of the layout, one byte, see [`WATERMARK_SCHEMA`],
+//!  - the build identifier, 16 bytes,
+//!  - the timestamp, 8 bytes,
+//!  - the length of the tag, one byte, and the tag.
+
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::ops::{WriteBuffer, WriteInteger};
+use crate::prelude::*;
+use crate::unpack;
+
+/// Magic prefix of watermarks.
+pub const WATERMARK_MAGIC: [u8; 4] = *b"SCWM";
+
+/// Version of the layout of watermarks written by this crate.
+pub const WATERMARK_SCHEMA: u8 = 1;
+
+/// Maximum length of the operator tag of a watermark.
+pub const WATERMARK_TAG_MAX_LEN: usize = 0xff;
+
+/// Length of a watermark with an empty tag.
+const HEADER_LEN: usize = 30;
+
+/// Operations writing a watermark, see [`Watermark`].
+type Ops<'watermark, 'tag> = (
+    WriteBuffer<'static>,
+    WriteInteger<u8>,
+    WriteBuffer<'watermark>,
+    WriteInteger<u64>,
+    WriteInteger<u8>,
+    WriteBuffer<'tag>,
+);
+
+/// An operation that writes a watermark, i.e. a metadata record tracing a
+/// payload back to its build.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::r#static::Shellcoder;
+/// use shellcoder::watermark::{self, Watermark};
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// let mut buffer = [0u8; 64];
+/// let mut shellcoder = Shellcoder::new(&mut buffer);
+/// shellcoder
+///     .push_buffer(b"\xcc\xcc")?
+///     .add(Watermark::new([0x42; 16], 1_700_000_000, b"op-7"))?;
+///
+/// let (offset, watermark) = watermark::find(shellcoder.get()).unwrap();
+/// assert_eq!(offset, 2);
+/// assert_eq!(watermark.build_id(), &[0x42; 16]);
+/// assert_eq!(watermark.timestamp(), 1_700_000_000);
+/// assert_eq!(watermark.tag(), b"op-7");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Watermark<'tag> {
+    /// Version of the layout.
+    schema: u8,
+
+    /// Identifier of the build, e.g. a UUID or a commit hash.
+    build_id: [u8; 16],
+
+    /// Time of the build, e.g. in seconds since the Unix epoch.
+    timestamp: u64,
+
+    /// Tag of the operator.
+    tag: &'tag [u8],
+}
+
+impl<'tag> Watermark<'tag> {
+    /// Instantiates a new [`Watermark`], with the current layout.
+    #[inline]
+    #[must_use]
+    pub fn new(
+        build_id: [u8; 16],
+        timestamp: u64,
+        tag: &'tag (impl AsRef<[u8]> + ?Sized + 'tag),
+    ) -> Self {
+        Self {
+            schema: WATERMARK_SCHEMA,
+            build_id,
+            timestamp,
+            tag: tag.as_ref(),
+        }
+    }
+
+    /// Parses a watermark at the start of a slice.
+    ///
+    /// Returns `None` if the slice does not start with a watermark, or if
+    /// the layout of the watermark is unknown.
+    #[inline]
+    #[must_use]
+    pub fn parse(bytes: &'tag [u8]) -> Option<Self> {
+        let record = bytes.strip_prefix(&WATERMARK_MAGIC)?;
+        let (&schema, fields) = record.split_first()?;
+        if schema != WATERMARK_SCHEMA {
+            return None;
+        }
+        let build_id = fields.get(..16)?.try_into().ok()?;
+        let timestamp = unpack::u64_le(fields.get(16..)?)?;
+        let (&len, tag) = fields.get(24..)?.split_first()?;
+        Some(Self {
+            schema,
+            build_id,
+            timestamp,
+            tag: tag.get(..usize::from(len))?,
+        })
+    }
+
+    /// Returns the version of the layout.
+    #[inline]
+    #[must_use]
+    pub const fn schema(&self) -> u8 {
+        self.schema
+    }
+
+    /// Returns the identifier of the build.
+    #[inline]
+    #[must_use]
+    pub const fn build_id(&self) -> &[u8; 16] {
+        &self.build_id
+    }
+
+    /// Returns the time of the build.
+    #[inline]
+    #[must_use]
+    pub const fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the tag of the operator.
+    #[inline]
+    #[must_use]
+    pub const fn tag(&self) -> &'tag [u8] {
+        self.tag
+    }
+
+    /// Returns the operations writing the watermark.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::MaxLengthExceeded`]: the tag is too long.
+    fn ops(&self) -> Result<Ops<'_, 'tag>> {
+        let len = u8::try_from(self.tag.len()).map_err(|_error| Error::MaxLengthExceeded {
+            limit: WATERMARK_TAG_MAX_LEN,
+            attempted: self.tag.len(),
+        })?;
+        Ok((
+            WriteBuffer::new(&WATERMARK_MAGIC),
+            WriteInteger::new_le(self.schema),
+            WriteBuffer::new(&self.build_id),
+            WriteInteger::new_le(self.timestamp),
+            WriteInteger::new_le(len),
+            WriteBuffer::new(self.tag),
+        ))
+    }
+}
+
+impl Op for Watermark<'_> {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        self.ops()?.write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        self.ops()?.write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.ops().ok()?;
+        HEADER_LEN.checked_add(self.tag.len())
+    }
+}
+
+/// Finds the last watermark of a payload, and returns its offset.
+///
+/// Watermarks are usually trailers: the last one wins, so that a watermark
+/// embedded in an earlier stage does not hide the one of the payload.
+#[inline]
+#[must_use]
+pub fn find(payload: &[u8]) -> Option<(usize, Watermark<'_>)> {
+    payload
+        .windows(WATERMARK_MAGIC.len())
+        .enumerate()
+        .rev()
+        .filter(|(_, window)| *window == WATERMARK_MAGIC)
+        .find_map(|(offset, _)| Some((offset, Watermark::parse(payload.get(offset..)?)?)))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::shadow_unrelated,
+    clippy::unseparated_literal_suffix,
+    clippy::unwrap_used
+)]
+mod tests {
+    use crate::watermark::{self, Watermark, WATERMARK_TAG_MAX_LEN};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test() -> Result<()> {
+        let watermark = Watermark::new([0xaa; 16], 0x0102_0304_0506_0708, "red");
+        assert_eq!(watermark.schema(), 1);
+        assert_eq!(watermark.size(), Some(33));
+        let mut buffer = [0xffu8; 34];
+        assert_eq!(watermark.write_to(&mut buffer)?, 33);
+        assert_eq!(&buffer[..5], b"SCWM\x01");
+        assert_eq!(buffer[5..21], [0xaa; 16]);
+        assert_eq!(&buffer[21..29], b"\x08\x07\x06\x05\x04\x03\x02\x01");
+        assert_eq!(&buffer[29..], b"\x03red\xff");
+        assert_eq!(Watermark::parse(&buffer), Some(watermark));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find() -> Result<()> {
+        let mut buffer = [0x90u8; 80];
+        Watermark::new([1; 16], 1, "a").write_to(&mut buffer[2..])?;
+        Watermark::new([2; 16], 2, "b").write_to(&mut buffer[40..])?;
+        let (offset, last) = watermark::find(&buffer).unwrap();
+        assert_eq!(offset, 40);
+        assert_eq!(last.build_id(), &[2; 16]);
+
+        // A truncated watermark is skipped.
+        let (offset, first) = watermark::find(&buffer[..60]).unwrap();
+        assert_eq!(offset, 2);
+        assert_eq!(first.tag(), b"a");
+
+        assert_eq!(watermark::find(b"SCWM\x02"), None);
+        assert_eq!(watermark::find(&[0x90; 8]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_too_long() {
+        let tag = [b'A'; WATERMARK_TAG_MAX_LEN + 1];
+        let watermark = Watermark::new([0; 16], 0, &tag);
+        assert_eq!(watermark.size(), None);
+        assert!(matches!(
+            watermark.write_to(&mut [0u8; 0x200]),
+            Err(Error::MaxLengthExceeded {
+                limit: 255,
+                attempted: 256
+            })
+        ));
+    }
+}
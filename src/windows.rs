@@ -0,0 +1,852 @@
+//! Forged Windows structures.
+//!
+//! Thread hijacking and exception-based techniques, e.g. `NtContinue` or
+//! `SetThreadContext`, take whole structures rather than a few registers,
+//! and use-after-free exploits replace objects with fake ones whose
+//! vtables they control, see [`FakeObject`]. Their layouts are laid out
+//! here by field name, so that exploit code does not carry tables of magic
+//! offsets.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use shellcoder::arch::Arch;
+//! use shellcoder::r#static::Shellcoder;
+//! use shellcoder::windows::{Context, ContextRegister, CONTEXT_AMD64_LEN};
+//! use shellcoder::Shellcoder as _;
+//! # use shellcoder::Result;
+//!
+//! # pub fn main() -> Result<()> {
+//! let context = Context::new(Arch::X86_64)
+//!     .with_register(ContextRegister::Rcx, 0x1_4000_3000)
+//!     .with_register(ContextRegister::Rsp, 0xb8_f000)
+//!     .with_register(ContextRegister::Rip, 0x7ffa_1234_5678);
+//! // CONTEXT_AMD64 | CONTEXT_CONTROL | CONTEXT_INTEGER
+//! assert_eq!(context.flags(), 0x10_0003);
+//!
+//! let mut buffer = [0u8; CONTEXT_AMD64_LEN];
+//! let mut shellcoder = Shellcoder::new(&mut buffer);
+//! shellcoder.add(context)?;
+//! assert_eq!(shellcoder.get()[0x30..0x34], *b"\x03\0\x10\0");
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "std")]
+use std::io;
+
+#[cfg(feature = "std")]
+use core::iter;
+
+use crate::arch::Arch;
+#[cfg(feature = "std")]
+use crate::config::PointerWidth;
+#[cfg(feature = "std")]
+use crate::ops::Endianness;
+use crate::ops::WriteBuffer;
+#[cfg(feature = "std")]
+use crate::pack;
+use crate::prelude::*;
+
+/// Length of a `CONTEXT` record on x86-64.
+pub const CONTEXT_AMD64_LEN: usize = 0x4d0;
+
+/// Length of a `CONTEXT` record on x86.
+pub const CONTEXT_I386_LEN: usize = 0x2cc;
+
+/// `ContextFlags` of x86-64 records (`CONTEXT_AMD64`).
+pub const CONTEXT_AMD64: u32 = 0x10_0000;
+
+/// `ContextFlags` of x86 records (`CONTEXT_i386`).
+pub const CONTEXT_I386: u32 = 0x1_0000;
+
+/// `ContextFlags` of the instruction and stack pointers, the flags and the
+/// code and stack segments (`CONTEXT_CONTROL`).
+pub const CONTEXT_CONTROL: u32 = 0x1;
+
+/// `ContextFlags` of the general-purpose registers (`CONTEXT_INTEGER`).
+pub const CONTEXT_INTEGER: u32 = 0x2;
+
+/// `ContextFlags` of the data segments (`CONTEXT_SEGMENTS`).
+pub const CONTEXT_SEGMENTS: u32 = 0x4;
+
+/// `ContextFlags` of the floating-point state (`CONTEXT_FLOATING_POINT`).
+pub const CONTEXT_FLOATING_POINT: u32 = 0x8;
+
+/// `ContextFlags` of the debug registers (`CONTEXT_DEBUG_REGISTERS`).
+pub const CONTEXT_DEBUG_REGISTERS: u32 = 0x10;
+
+/// Methods of `IUnknown`, which start the vtables of all COM interfaces,
+/// see [`FakeObject::set_names`].
+pub const IUNKNOWN: [&str; 3] = ["QueryInterface", "AddRef", "Release"];
+
+/// Initial SSE control and status register, i.e. all exceptions masked.
+const MXCSR_DEFAULT: u32 = 0x1f80;
+
+/// Offsets of `MxCsr` and `FltSave.MxCsr` in x86-64 records.
+const CONTEXT_AMD64_MXCSR: [usize; 2] = [0x34, 0x118];
+
+/// A register of a `CONTEXT` record, see [`Context`].
+///
+/// With x86, registers are the 32-bit ones, e.g. [`ContextRegister::Rax`]
+/// is `Eax`, and `r8` to `r15` do not exist.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
+pub enum ContextRegister {
+    /// `Rax`, or `Eax`.
+    Rax,
+
+    /// `Rcx`, or `Ecx`.
+    Rcx,
+
+    /// `Rdx`, or `Edx`.
+    Rdx,
+
+    /// `Rbx`, or `Ebx`.
+    Rbx,
+
+    /// `Rsp`, or `Esp`.
+    Rsp,
+
+    /// `Rbp`, or `Ebp`.
+    Rbp,
+
+    /// `Rsi`, or `Esi`.
+    Rsi,
+
+    /// `Rdi`, or `Edi`.
+    Rdi,
+
+    /// `R8`.
+    R8,
+
+    /// `R9`.
+    R9,
+
+    /// `R10`.
+    R10,
+
+    /// `R11`.
+    R11,
+
+    /// `R12`.
+    R12,
+
+    /// `R13`.
+    R13,
+
+    /// `R14`.
+    R14,
+
+    /// `R15`.
+    R15,
+
+    /// `Rip`, or `Eip`.
+    Rip,
+
+    /// `EFlags`.
+    EFlags,
+
+    /// `SegCs`.
+    SegCs,
+
+    /// `SegDs`.
+    SegDs,
+
+    /// `SegEs`.
+    SegEs,
+
+    /// `SegFs`.
+    SegFs,
+
+    /// `SegGs`.
+    SegGs,
+
+    /// `SegSs`.
+    SegSs,
+
+    /// `Dr0`.
+    Dr0,
+
+    /// `Dr1`.
+    Dr1,
+
+    /// `Dr2`.
+    Dr2,
+
+    /// `Dr3`.
+    Dr3,
+
+    /// `Dr6`.
+    Dr6,
+
+    /// `Dr7`.
+    Dr7,
+}
+
+impl ContextRegister {
+    /// Registers, in the order of [`Context`].
+    pub const ALL: [Self; 30] = [
+        Self::Rax,
+        Self::Rcx,
+        Self::Rdx,
+        Self::Rbx,
+        Self::Rsp,
+        Self::Rbp,
+        Self::Rsi,
+        Self::Rdi,
+        Self::R8,
+        Self::R9,
+        Self::R10,
+        Self::R11,
+        Self::R12,
+        Self::R13,
+        Self::R14,
+        Self::R15,
+        Self::Rip,
+        Self::EFlags,
+        Self::SegCs,
+        Self::SegDs,
+        Self::SegEs,
+        Self::SegFs,
+        Self::SegGs,
+        Self::SegSs,
+        Self::Dr0,
+        Self::Dr1,
+        Self::Dr2,
+        Self::Dr3,
+        Self::Dr6,
+        Self::Dr7,
+    ];
+
+    /// Returns the offset of the register in a record of an architecture,
+    /// or [`None`] if it does not have it.
+    #[inline]
+    #[must_use]
+    pub const fn offset(self, arch: Arch) -> Option<usize> {
+        match arch {
+            Arch::X86_64 => Some(self.offset_amd64()),
+            Arch::X86 => self.offset_i386(),
+            Arch::Arm | Arch::Thumb | Arch::Aarch64 | Arch::Mips | Arch::Riscv64 => None,
+        }
+    }
+
+    /// Returns the width of the register in a record of an architecture,
+    /// in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn width(self, arch: Arch) -> usize {
+        match (arch, self) {
+            (
+                Arch::X86_64,
+                Self::SegCs | Self::SegDs | Self::SegEs | Self::SegFs | Self::SegGs | Self::SegSs,
+            ) => 2,
+            (Arch::X86_64, Self::EFlags) | (Arch::X86, _) => 4,
+            _ => 8,
+        }
+    }
+
+    /// Returns the `ContextFlags` the register belongs to, e.g.
+    /// [`CONTEXT_CONTROL`].
+    #[inline]
+    #[must_use]
+    pub const fn flags(self, arch: Arch) -> u32 {
+        match self {
+            // The frame pointer is a control register with x86 only.
+            Self::Rbp if matches!(arch, Arch::X86) => CONTEXT_CONTROL,
+            Self::Rsp | Self::Rip | Self::EFlags | Self::SegCs | Self::SegSs => CONTEXT_CONTROL,
+            Self::SegDs | Self::SegEs | Self::SegFs | Self::SegGs => CONTEXT_SEGMENTS,
+            Self::Dr0 | Self::Dr1 | Self::Dr2 | Self::Dr3 | Self::Dr6 | Self::Dr7 => {
+                CONTEXT_DEBUG_REGISTERS
+            }
+            Self::Rax
+            | Self::Rcx
+            | Self::Rdx
+            | Self::Rbx
+            | Self::Rbp
+            | Self::Rsi
+            | Self::Rdi
+            | Self::R8
+            | Self::R9
+            | Self::R10
+            | Self::R11
+            | Self::R12
+            | Self::R13
+            | Self::R14
+            | Self::R15 => CONTEXT_INTEGER,
+        }
+    }
+
+    /// Returns the offset of the register in a x86-64 record.
+    const fn offset_amd64(self) -> usize {
+        match self {
+            Self::SegCs => 0x38,
+            Self::SegDs => 0x3a,
+            Self::SegEs => 0x3c,
+            Self::SegFs => 0x3e,
+            Self::SegGs => 0x40,
+            Self::SegSs => 0x42,
+            Self::EFlags => 0x44,
+            Self::Dr0 => 0x48,
+            Self::Dr1 => 0x50,
+            Self::Dr2 => 0x58,
+            Self::Dr3 => 0x60,
+            Self::Dr6 => 0x68,
+            Self::Dr7 => 0x70,
+            Self::Rax => 0x78,
+            Self::Rcx => 0x80,
+            Self::Rdx => 0x88,
+            Self::Rbx => 0x90,
+            Self::Rsp => 0x98,
+            Self::Rbp => 0xa0,
+            Self::Rsi => 0xa8,
+            Self::Rdi => 0xb0,
+            Self::R8 => 0xb8,
+            Self::R9 => 0xc0,
+            Self::R10 => 0xc8,
+            Self::R11 => 0xd0,
+            Self::R12 => 0xd8,
+            Self::R13 => 0xe0,
+            Self::R14 => 0xe8,
+            Self::R15 => 0xf0,
+            Self::Rip => 0xf8,
+        }
+    }
+
+    /// Returns the offset of the register in a x86 record.
+    const fn offset_i386(self) -> Option<usize> {
+        match self {
+            Self::Dr0 => Some(0x04),
+            Self::Dr1 => Some(0x08),
+            Self::Dr2 => Some(0x0c),
+            Self::Dr3 => Some(0x10),
+            Self::Dr6 => Some(0x14),
+            Self::Dr7 => Some(0x18),
+            Self::SegGs => Some(0x8c),
+            Self::SegFs => Some(0x90),
+            Self::SegEs => Some(0x94),
+            Self::SegDs => Some(0x98),
+            Self::Rdi => Some(0x9c),
+            Self::Rsi => Some(0xa0),
+            Self::Rbx => Some(0xa4),
+            Self::Rdx => Some(0xa8),
+            Self::Rcx => Some(0xac),
+            Self::Rax => Some(0xb0),
+            Self::Rbp => Some(0xb4),
+            Self::Rip => Some(0xb8),
+            Self::SegCs => Some(0xbc),
+            Self::EFlags => Some(0xc0),
+            Self::Rsp => Some(0xc4),
+            Self::SegSs => Some(0xc8),
+            Self::R8
+            | Self::R9
+            | Self::R10
+            | Self::R11
+            | Self::R12
+            | Self::R13
+            | Self::R14
+            | Self::R15 => None,
+        }
+    }
+
+    /// Returns the index of the register in [`ContextRegister::ALL`].
+    fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|&register| register == self)
+            .unwrap_or_default()
+    }
+}
+
+/// An operation that writes a Windows `CONTEXT` record, for x86-64 or x86,
+/// e.g. for `NtContinue`.
+///
+/// `ContextFlags` covers the registers that have been set, see
+/// [`ContextRegister::flags`], and the floating-point state of x86-64
+/// records masks all exceptions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Context {
+    /// Architecture of the target.
+    arch: Arch,
+
+    /// Registers, in the order of [`ContextRegister::ALL`].
+    registers: [u64; 30],
+
+    /// `ContextFlags`, but the architecture.
+    flags: u32,
+
+    /// Whether a register that does not exist on the architecture has
+    /// been set.
+    invalid: bool,
+}
+
+impl Context {
+    /// Instantiates a new [`Context`], with null registers, for x86-64 or
+    /// x86.
+    #[inline]
+    #[must_use]
+    pub const fn new(arch: Arch) -> Self {
+        Self {
+            arch,
+            registers: [0; 30],
+            flags: 0,
+            invalid: false,
+        }
+    }
+
+    /// Sets a register, and the `ContextFlags` it belongs to.
+    #[inline]
+    #[must_use]
+    pub fn with_register(mut self, register: ContextRegister, value: u64) -> Self {
+        if let Some(slot) = self.registers.get_mut(register.index()) {
+            *slot = value;
+        }
+        self.flags |= register.flags(self.arch);
+        self.invalid |= register.offset(self.arch).is_none();
+        self
+    }
+
+    /// Adds `ContextFlags`, e.g. [`CONTEXT_FLOATING_POINT`].
+    #[inline]
+    #[must_use]
+    pub const fn with_flags(mut self, flags: u32) -> Self {
+        self.flags |= flags;
+        self
+    }
+
+    /// Returns a register.
+    #[inline]
+    #[must_use]
+    pub fn register(&self, register: ContextRegister) -> u64 {
+        self.registers
+            .get(register.index())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns `ContextFlags`, including the architecture.
+    #[inline]
+    #[must_use]
+    pub const fn flags(&self) -> u32 {
+        match self.arch {
+            Arch::X86 => CONTEXT_I386 | self.flags,
+            Arch::X86_64 | Arch::Arm | Arch::Thumb | Arch::Aarch64 | Arch::Mips | Arch::Riscv64 => {
+                CONTEXT_AMD64 | self.flags
+            }
+        }
+    }
+
+    /// Returns the architecture of the target.
+    #[inline]
+    #[must_use]
+    pub const fn arch(&self) -> Arch {
+        self.arch
+    }
+
+    /// Returns the length of the record.
+    const fn len(self) -> Result<usize> {
+        match self.arch {
+            Arch::X86_64 => Ok(CONTEXT_AMD64_LEN),
+            Arch::X86 => Ok(CONTEXT_I386_LEN),
+            Arch::Arm | Arch::Thumb | Arch::Aarch64 | Arch::Mips | Arch::Riscv64 => Err(
+                Error::Unsupported("CONTEXT records are laid out for x86-64 and x86 only"),
+            ),
+        }
+    }
+
+    /// Returns the bytes of the record, and its length.
+    fn bytes(&self) -> Result<([u8; CONTEXT_AMD64_LEN], usize)> {
+        let len = self.len()?;
+        if self.invalid {
+            return Err(Error::Unsupported("r8 to r15 do not exist on x86"));
+        }
+        let mut bytes = [0; CONTEXT_AMD64_LEN];
+        let flags_offset = if matches!(self.arch, Arch::X86) {
+            0
+        } else {
+            for offset in CONTEXT_AMD64_MXCSR {
+                put(&mut bytes, offset, &MXCSR_DEFAULT.to_le_bytes());
+            }
+            0x30
+        };
+        put(&mut bytes, flags_offset, &self.flags().to_le_bytes());
+        for (register, value) in ContextRegister::ALL.iter().zip(self.registers) {
+            let width = register.width(self.arch);
+            if let Some(offset) = register.offset(self.arch) {
+                let value_bytes = value.to_le_bytes();
+                if value_bytes.iter().skip(width).any(|&byte| byte != 0) {
+                    return Err(Error::IntegerOverflow);
+                }
+                put(
+                    &mut bytes,
+                    offset,
+                    value_bytes.get(..width).unwrap_or_default(),
+                );
+            }
+        }
+        Ok((bytes, len))
+    }
+}
+
+impl Op for Context {
+    #[cfg(feature = "std")]
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer::new(bytes.get(..len).unwrap_or_default()).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        let (bytes, len) = self.bytes()?;
+        WriteBuffer::new(bytes.get(..len).unwrap_or_default()).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        self.len().ok()
+    }
+}
+
+/// A fake object whose first field points to a vtable, e.g. a COM object,
+/// to replace a freed object in a use-after-free.
+///
+/// The object is made of the vtable pointer and of its other fields, and
+/// is followed by the vtable, unless it is elsewhere, see
+/// [`FakeObject::set_vtable_address`]. Slots of the vtable are assigned by
+/// index, or by name once their names are known, see
+/// [`FakeObject::set_names`]. Pointers are little-endian.
+///
+/// # Examples
+///
+/// ```rust
+/// use shellcoder::alloc::Shellcoder;
+/// use shellcoder::config::PointerWidth;
+/// use shellcoder::windows::{FakeObject, IUNKNOWN};
+/// use shellcoder::Shellcoder as _;
+/// # use shellcoder::Result;
+///
+/// # pub fn main() -> Result<()> {
+/// // The object replaces one freed at 0x1d0040, whose `Release` is called.
+/// let mut object = FakeObject::new(PointerWidth::Bits32, 0x1d_0040, 3);
+/// object
+///     .set_names(&IUNKNOWN)
+///     .push_field(1)
+///     .set_method("Release", 0x6a41_2d5c)?;
+///
+/// assert!(object.set_method("Invoke", 0).is_err());
+///
+/// let mut shellcoder = Shellcoder::new();
+/// shellcoder.add(object)?;
+/// assert_eq!(
+///     shellcoder.as_bytes(),
+///     b"\x48\0\x1d\0\x01\0\0\0\0\0\0\0\0\0\0\0\x5c\x2d\x41\x6a"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FakeObject {
+    /// Width of pointers.
+    pointer_width: PointerWidth,
+
+    /// Address the object is written at.
+    address: u64,
+
+    /// Address of the vtable, if it does not follow the object.
+    vtable: Option<u64>,
+
+    /// Fields following the vtable pointer.
+    fields: Vec<u64>,
+
+    /// Slots of the vtable.
+    slots: Vec<u64>,
+
+    /// Names of the first slots of the vtable.
+    names: Vec<String>,
+}
+
+#[cfg(feature = "std")]
+impl FakeObject {
+    /// Instantiates a new [`FakeObject`] written at `address`, with a vtable
+    /// of `len` null slots.
+    #[inline]
+    #[must_use]
+    pub fn new(pointer_width: PointerWidth, address: u64, len: usize) -> Self {
+        Self {
+            pointer_width,
+            address,
+            vtable: None,
+            fields: Vec::new(),
+            slots: vec![0; len],
+            names: Vec::new(),
+        }
+    }
+
+    /// Sets the address of the vtable, e.g. a vtable sprayed elsewhere, so
+    /// that it is not written after the object.
+    #[inline]
+    pub fn set_vtable_address(&mut self, address: u64) -> &mut Self {
+        self.vtable = Some(address);
+        self
+    }
+
+    /// Appends a field to the object, after the vtable pointer and the
+    /// fields appended before, e.g. a reference count.
+    #[inline]
+    pub fn push_field(&mut self, value: u64) -> &mut Self {
+        self.fields.push(value);
+        self
+    }
+
+    /// Names the first slots of the vtable, in order, e.g. [`IUNKNOWN`]
+    /// followed by the methods of the interface.
+    #[inline]
+    pub fn set_names(&mut self, names: &[&str]) -> &mut Self {
+        self.names = names.iter().map(|&name| name.to_owned()).collect();
+        self
+    }
+
+    /// Assigns a slot of the vtable.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::MaxLengthExceeded`]: the slot is past the end of the
+    /// vtable.
+    #[inline]
+    pub fn set_slot(&mut self, index: usize, target: u64) -> Result<&mut Self> {
+        let len = self.slots.len();
+        let slot = self
+            .slots
+            .get_mut(index)
+            .ok_or_else(|| Error::MaxLengthExceeded {
+                limit: len,
+                attempted: index.saturating_add(1),
+            })?;
+        *slot = target;
+        Ok(self)
+    }
+
+    /// Assigns a slot of the vtable by name, see [`FakeObject::set_names`].
+    ///
+    /// # Errors
+    ///
+    ///  - [`Error::UnknownSymbol`]: no slot has this name.
+    ///  - Any error returned by [`FakeObject::set_slot`].
+    #[inline]
+    pub fn set_method(&mut self, name: &str, target: u64) -> Result<&mut Self> {
+        let index = self
+            .names
+            .iter()
+            .position(|known| known == name)
+            .ok_or_else(|| Error::UnknownSymbol(name.to_owned()))?;
+        self.set_slot(index, target)
+    }
+
+    /// Returns the slots of the vtable.
+    #[inline]
+    #[must_use]
+    pub fn slots(&self) -> &[u64] {
+        &self.slots
+    }
+
+    /// Returns the address of the vtable.
+    ///
+    /// # Errors
+    ///
+    /// [`Error::IntegerOverflow`]: the vtable follows the object past the
+    /// end of the address space.
+    #[inline]
+    pub fn vtable_address(&self) -> Result<u64> {
+        self.vtable.map_or_else(
+            || {
+                let len = u64::try_from(self.fields.len().saturating_add(1))?;
+                let width = u64::try_from(self.pointer_width.bytes())?;
+                len.checked_mul(width)
+                    .and_then(|offset| self.address.checked_add(offset))
+                    .ok_or(Error::IntegerOverflow)
+            },
+            Ok,
+        )
+    }
+
+    /// Returns the bytes of the object, followed by the vtable if it is
+    /// inline.
+    fn bytes(&self) -> Result<Vec<u8>> {
+        let inline = if self.vtable.is_some() {
+            &[][..]
+        } else {
+            &self.slots
+        };
+        let pointers = iter::once(self.vtable_address()?)
+            .chain(self.fields.iter().copied())
+            .chain(inline.iter().copied());
+        let mut bytes = Vec::new();
+        for pointer in pointers {
+            match self.pointer_width {
+                PointerWidth::Bits16 => {
+                    bytes.extend(pack::p16_with(u16::try_from(pointer)?, Endianness::Little));
+                }
+                PointerWidth::Bits32 => {
+                    bytes.extend(pack::p32_with(u32::try_from(pointer)?, Endianness::Little));
+                }
+                PointerWidth::Bits64 => bytes.extend(pack::p64_with(pointer, Endianness::Little)),
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Op for FakeObject {
+    #[inline]
+    fn write_to_io(&self, stream: &mut dyn io::Write) -> Result<usize> {
+        WriteBuffer::new(&self.bytes()?).write_to_io(stream)
+    }
+
+    #[inline]
+    fn write_to(&self, out: impl AsMut<[u8]>) -> Result<usize> {
+        WriteBuffer::new(&self.bytes()?).write_to(out)
+    }
+
+    #[inline]
+    fn size(&self) -> Option<usize> {
+        let inline = if self.vtable.is_some() {
+            0
+        } else {
+            self.slots.len()
+        };
+        self.fields
+            .len()
+            .checked_add(1)?
+            .checked_add(inline)?
+            .checked_mul(self.pointer_width.bytes())
+    }
+}
+
+/// Copies `value` at `offset` in `bytes`, if it fits.
+fn put(bytes: &mut [u8], offset: usize, value: &[u8]) {
+    if let Some(slot) = bytes.get_mut(offset..offset.saturating_add(value.len())) {
+        slot.copy_from_slice(value);
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::separated_literal_suffix,
+    clippy::unseparated_literal_suffix
+)]
+mod tests {
+    use crate::arch::Arch;
+    #[cfg(feature = "std")]
+    use crate::config::PointerWidth;
+    use crate::windows::{
+        Context, ContextRegister, CONTEXT_AMD64_LEN, CONTEXT_DEBUG_REGISTERS,
+        CONTEXT_FLOATING_POINT, CONTEXT_I386_LEN,
+    };
+    #[cfg(feature = "std")]
+    use crate::windows::{FakeObject, IUNKNOWN};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_context_amd64() -> Result<()> {
+        let context = Context::new(Arch::X86_64)
+            .with_register(ContextRegister::R15, 0x4141_4141_4141_4141)
+            .with_register(ContextRegister::SegCs, 0x33)
+            .with_register(ContextRegister::EFlags, 0x202)
+            .with_flags(CONTEXT_FLOATING_POINT);
+        assert_eq!(context.flags(), 0x10_000b);
+
+        let mut buffer = [0xffu8; CONTEXT_AMD64_LEN];
+        assert_eq!(context.write_to(&mut buffer)?, CONTEXT_AMD64_LEN);
+        assert!(buffer[..0x30].iter().all(|&byte| byte == 0));
+        assert_eq!(buffer[0x30..0x38], *b"\x0b\0\x10\0\x80\x1f\0\0");
+        assert_eq!(buffer[0x38..0x3a], *b"\x33\0");
+        assert_eq!(buffer[0x44..0x48], *b"\x02\x02\0\0");
+        assert_eq!(buffer[0xf0..0xf8], *b"AAAAAAAA");
+        assert_eq!(buffer[0x118..0x11c], *b"\x80\x1f\0\0");
+
+        assert!(matches!(
+            context
+                .with_register(ContextRegister::SegSs, 0x1_0000)
+                .write_to(&mut buffer),
+            Err(Error::IntegerOverflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_context_i386() -> Result<()> {
+        let context = Context::new(Arch::X86)
+            .with_register(ContextRegister::Rbp, 0x0019_ff00)
+            .with_register(ContextRegister::Rax, 0x4141_4141)
+            .with_register(ContextRegister::Dr7, 0x1);
+        assert_eq!(context.flags(), 0x1_0013);
+        assert_eq!(
+            context.flags() & CONTEXT_DEBUG_REGISTERS,
+            CONTEXT_DEBUG_REGISTERS
+        );
+        assert_eq!(context.size(), Some(CONTEXT_I386_LEN));
+
+        let mut buffer = [0xffu8; CONTEXT_I386_LEN];
+        assert_eq!(context.write_to(&mut buffer)?, CONTEXT_I386_LEN);
+        assert_eq!(buffer[..4], *b"\x13\0\x01\0");
+        assert_eq!(buffer[0x18..0x1c], *b"\x01\0\0\0");
+        assert_eq!(buffer[0xb0..0xb8], *b"AAAA\0\xff\x19\0");
+
+        assert!(matches!(
+            context
+                .with_register(ContextRegister::R8, 0)
+                .write_to(&mut buffer),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            Context::new(Arch::Aarch64).write_to(&mut buffer),
+            Err(Error::Unsupported(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_fake_object() -> Result<()> {
+        let mut object = FakeObject::new(PointerWidth::Bits64, 0x1_0000, 4);
+        object
+            .set_names(&IUNKNOWN)
+            .set_method("AddRef", 0x7ffa_0000_1000)?
+            .set_slot(3, 0x7ffa_0000_2000)?;
+        assert_eq!(object.vtable_address()?, 0x1_0008);
+        assert_eq!(object.size(), Some(0x28));
+
+        let mut buffer = [0u8; 0x28];
+        assert_eq!(object.write_to(&mut buffer)?, 0x28);
+        assert_eq!(buffer[..8], 0x1_0008_u64.to_le_bytes());
+        assert_eq!(buffer[0x10..0x18], 0x7ffa_0000_1000_u64.to_le_bytes());
+        assert_eq!(buffer[0x20..], 0x7ffa_0000_2000_u64.to_le_bytes());
+
+        object.set_vtable_address(0x2_0000);
+        assert_eq!(object.size(), Some(8));
+        assert_eq!(object.write_to(&mut buffer)?, 8);
+        assert_eq!(buffer[..8], 0x2_0000_u64.to_le_bytes());
+
+        assert!(matches!(
+            object.set_slot(4, 0),
+            Err(Error::MaxLengthExceeded {
+                limit: 4,
+                attempted: 5
+            })
+        ));
+        assert!(matches!(
+            object.set_method("Invoke", 0),
+            Err(Error::UnknownSymbol(name)) if name == "Invoke"
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+//! Tests of the `shellcoder` command.
+
+#![cfg(feature = "cli")]
+#![allow(
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::tests_outside_test_module,
+    clippy::unwrap_used
+)]
+
+use std::env;
+use std::fs;
+use std::io;
+use std::process::{self, Command};
+
+/// Path to the `shellcoder` command.
+const SHELLCODER: &str = env!("CARGO_BIN_EXE_shellcoder");
+
+#[test]
+fn test_help() -> io::Result<()> {
+    for flag in ["-h", "--help"] {
+        let output = Command::new(SHELLCODER).arg(flag).output()?;
+        assert!(output.status.success());
+        assert!(output.stdout.starts_with(b"usage: shellcoder"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_recipe() -> io::Result<()> {
+    let recipe = env::temp_dir().join(format!("shellcoder-cli-{}.json", process::id()));
+    fs::write(
+        &recipe,
+        r#"{"ops": [{"op": "fill", "len": 3, "byte": 66}]}"#,
+    )?;
+    let result = Command::new(SHELLCODER)
+        .args(["-f", "hex"])
+        .arg(&recipe)
+        .output();
+    fs::remove_file(&recipe)?;
+    let output = result?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"424242\n");
+    Ok(())
+}
+
+#[test]
+fn test_invalid_recipe() -> io::Result<()> {
+    let recipe = env::temp_dir().join(format!("shellcoder-cli-{}-invalid.json", process::id()));
+    fs::write(&recipe, r#"{"ops": [{"op": "jump"}]}"#)?;
+    let result = Command::new(SHELLCODER).arg(&recipe).output();
+    fs::remove_file(&recipe)?;
+    let output = result?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(": invalid recipe: unknown variant `jump`"));
+    Ok(())
+}
+
+#[test]
+fn test_unexpected_argument() -> io::Result<()> {
+    let output = Command::new(SHELLCODER).arg("--jump").output()?;
+    assert!(!output.status.success());
+    assert!(output.stderr.starts_with(b"unexpected argument `--jump`"));
+    Ok(())
+}
@@ -0,0 +1,44 @@
+//! Tests of a shellcoder implemented outside of the crate.
+
+#![cfg(feature = "std")]
+#![allow(
+    clippy::assertions_on_result_states,
+    clippy::panic_in_result_fn,
+    clippy::redundant_test_prefix,
+    clippy::tests_outside_test_module
+)]
+
+use core::borrow::Borrow;
+
+use shellcoder::ops::{AssertPosition, Fill};
+use shellcoder::{Op, Result, Shellcoder};
+
+/// A shellcoder implementing only the required methods.
+#[derive(Debug, Default)]
+struct Minimal(Vec<u8>);
+
+impl Shellcoder for Minimal {
+    fn add<O>(&mut self, op: impl Borrow<O>) -> Result<&mut Self>
+    where
+        O: Op,
+    {
+        op.borrow().write_to_io(&mut self.0)?;
+        Ok(self)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[test]
+fn test_defaults() -> Result<()> {
+    let mut shellcoder = Minimal::default();
+    let (n, next) = shellcoder.fill(2, b'A')?.add_counted(Fill::new(3, b'B'))?;
+    assert_eq!(n, 3);
+    assert_eq!(next.position(), 5);
+    assert_eq!(shellcoder.0, b"AABBB");
+    assert!(shellcoder.try_add(AssertPosition::new(5)).is_ok());
+    assert!(shellcoder.try_add(AssertPosition::new(4)).is_err());
+    Ok(())
+}